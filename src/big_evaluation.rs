@@ -0,0 +1,167 @@
+use crate::big_number::bits;
+use crate::{ExprHandle, Expression};
+use num::traits::Pow;
+use num::{BigInt, BigRational, Integer, One, Signed, ToPrimitive, Zero};
+
+/// The largest bit length of the numerators and denominators [`Expression::evaluate_big`]
+/// computes, about 20,000 decimal digits, so that `(99!)!` or `9^9^9` fail quickly instead of
+/// exhausting time and memory.
+const MAX_BITS: u64 = 1 << 16;
+
+/// `x`, unless its bit length exceeds [`MAX_BITS`].
+#[inline]
+fn within(x: BigRational) -> Option<BigRational> {
+    (bits(&x) <= MAX_BITS).then_some(x)
+}
+
+/// Whether `n!`, and so `!n`, surely has more than [`MAX_BITS`] bits.
+#[inline]
+fn factorial_too_long(n: u64) -> bool {
+    // log2(n!) > n * (log2(n) - log2(e)), from Stirling's formula.
+    n as f64 * ((n as f64).log2() - std::f64::consts::LOG2_E) > MAX_BITS as f64
+}
+
+pub(crate) fn try_sqrt(x: &BigInt) -> Option<BigInt> {
+    if x.is_negative() {
+        return None;
+    }
+    let y = x.sqrt();
+    if &(&y * &y) == x {
+        Some(y)
+    } else {
+        None
+    }
+}
+
+impl Expression {
+    /// Evaluates the expression exactly with arbitrary-precision rationals.
+    ///
+    /// Returns `None` if some subexpression is irrational (e.g. `sqrt(2)*sqrt(8)`), if a
+    /// factorial, subfactorial, modulo, gcd or lcm is applied to a non-integer, if an exponent
+    /// does not fit in an `i32`, or if some value has a numerator or denominator of more than
+    /// 65,536 bits. Factorials and powers that surely exceed that are not computed.
+    pub fn evaluate_big(&self) -> Option<BigRational> {
+        match self {
+            Expression::Number(x) => Some(BigRational::from_integer((*x).into())),
+            Expression::Negate(x) => Some(-x.evaluate_big()?),
+            Expression::Add(x, y) => within(x.evaluate_big()? + y.evaluate_big()?),
+            Expression::Subtract(x, y) => within(x.evaluate_big()? - y.evaluate_big()?),
+            Expression::Multiply(x, y) => within(x.evaluate_big()? * y.evaluate_big()?),
+            Expression::Divide(x, y) => {
+                let y = y.evaluate_big()?;
+                if y.is_zero() {
+                    None
+                } else {
+                    within(x.evaluate_big()? / y)
+                }
+            }
+            Expression::Power(x, y) => {
+                let x = x.evaluate_big()?;
+                let y = y.evaluate_big()?;
+                if !y.is_integer() || (x.is_zero() && y.is_negative()) {
+                    return None;
+                }
+                let exponent = y.to_integer().to_i32()?;
+                // The bit length less one is a lower bound on the binary logarithm.
+                if bits(&x).saturating_sub(1) * u64::from(exponent.unsigned_abs()) > MAX_BITS {
+                    return None;
+                }
+                within(x.pow(exponent))
+            }
+            Expression::Modulo(x, y) => {
+                let x = x.evaluate_big()?;
+                let y = y.evaluate_big()?;
+                if !x.is_integer() || !y.is_integer() || !y.is_positive() {
+                    return None;
+                }
+                Some(BigRational::from_integer(
+                    x.to_integer().mod_floor(&y.to_integer()),
+                ))
+            }
+            Expression::Gcd(x, y) | Expression::Lcm(x, y) => {
+                let x = x.evaluate_big()?;
+                let y = y.evaluate_big()?;
+                if !x.is_integer() || !y.is_integer() {
+                    return None;
+                }
+                let (x, y) = (x.to_integer(), y.to_integer());
+                Some(BigRational::from_integer(if self.is_gcd() {
+                    x.gcd(&y)
+                } else {
+                    x.lcm(&y)
+                }))
+            }
+            Expression::Sqrt(x, order) => {
+                let mut x = x.evaluate_big()?;
+                for _ in 0..*order {
+                    x = BigRational::new(try_sqrt(x.numer())?, try_sqrt(x.denom())?);
+                }
+                Some(x)
+            }
+            Expression::Factorial(x) => {
+                let x = x.evaluate_big()?;
+                if !x.is_integer() || x.is_negative() {
+                    return None;
+                }
+                let n = x.to_integer().to_u64()?;
+                if factorial_too_long(n) {
+                    return None;
+                }
+                within(BigRational::from_integer(
+                    (2..=n).fold(BigInt::one(), |product, k| product * k),
+                ))
+            }
+            Expression::Subfactorial(x) => {
+                let x = x.evaluate_big()?;
+                if !x.is_integer() || x.is_negative() {
+                    return None;
+                }
+                let n = x.to_integer().to_u64()?;
+                if factorial_too_long(n) {
+                    return None;
+                }
+                within(BigRational::from_integer((1..=n).fold(
+                    BigInt::one(),
+                    |d, k| {
+                        if k % 2 == 0 {
+                            d * k + 1
+                        } else {
+                            d * k - 1
+                        }
+                    },
+                )))
+            }
+        }
+    }
+}
+
+impl ExprHandle {
+    #[inline]
+    pub fn evaluate_big(&self) -> Option<BigRational> {
+        self.as_expression().evaluate_big()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Expression;
+    use num::BigRational;
+
+    fn evaluate(input: &str) -> Option<BigRational> {
+        Expression::parse(input).unwrap().evaluate_big()
+    }
+
+    #[test]
+    fn evaluates_beyond_i64() {
+        let value = evaluate("(5*5)!/(5+5)").unwrap();
+        assert_eq!(value.to_string(), "1551121004333098598400000");
+        assert_eq!(evaluate("(4!)!/(4!-4)!").unwrap().to_string(), "255024");
+    }
+
+    #[test]
+    fn gives_up_on_huge_values() {
+        for input in ["(99!)!", "9^9^9", "!(999!)", "(9^9)!", "9^(9^9)/9^(9^9)"] {
+            assert_eq!(evaluate(input), None, "{input}");
+        }
+    }
+}
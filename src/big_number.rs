@@ -0,0 +1,290 @@
+use num::rational::ParseRatioError;
+use num::{BigRational, Num, One, Signed, Zero};
+use rustc_hash::FxHashMap;
+use std::any::Any;
+use std::cell::RefCell;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::ops::{Add, Div, Mul, Neg, Rem, Sub};
+use std::rc::Rc;
+
+/// The values of the live [`BigNumber`] handles of a thread, each stored once.
+#[derive(Default)]
+struct Store {
+    values: Vec<Rc<BigRational>>,
+    indices: FxHashMap<Rc<BigRational>, usize>,
+    /// The values before this were made while no solver held the store, such as targets, and
+    /// are kept for the life of the thread. Those after it are freed with the last solver.
+    kept: usize,
+    /// Bumped whenever the values are freed, so that older handles are told apart.
+    generation: u32,
+    /// The solvers holding the values allocated.
+    holders: usize,
+}
+
+/// The generation of the handles of kept values, which no generation of freed values reaches.
+const KEPT: u32 = u32::MAX;
+
+thread_local! {
+    static STORE: RefCell<Store> = RefCell::new(Store::default());
+}
+
+/// Keeps the values of the thread allocated while a solver holds it, and frees them all when the
+/// last one is dropped.
+struct StoreHold;
+
+impl Drop for StoreHold {
+    fn drop(&mut self) {
+        // The store is gone already if the thread is exiting.
+        let _ = STORE.try_with(|store| {
+            let mut store = store.borrow_mut();
+            store.holders -= 1;
+            if store.holders == 0 {
+                let kept = store.kept;
+                store.values.truncate(kept);
+                store.values.shrink_to_fit();
+                store.indices.retain(|_, index| *index < kept);
+                store.indices.shrink_to_fit();
+                store.generation = (store.generation + 1) % KEPT;
+            }
+        });
+    }
+}
+
+/// Holds the values of the thread for a [`Solver`](crate::Solver) of [`BigNumber`].
+pub(crate) fn hold_store() -> Rc<dyn Any> {
+    STORE.with(|store| store.borrow_mut().holders += 1);
+    Rc::new(StoreHold)
+}
+
+/// The bit length of the larger of the numerator and denominator of `x`.
+#[inline]
+pub(crate) fn bits(x: &BigRational) -> u64 {
+    x.numer().bits().max(x.denom().bits())
+}
+
+/// An arbitrary-precision rational, for exhaustive searches whose values exceed 128 bits.
+///
+/// [`Number`](crate::Number) domains are `Copy`, so this is a handle to a [`BigRational`] stored
+/// once per thread. Values made while no solver of this domain lives on the thread, such as the
+/// targets passed to them, stay valid for the life of the thread. Values made during the life
+/// of a solver, such as its states, are freed with the last one, after which their handles must
+/// not be used.
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub struct BigNumber {
+    index: usize,
+    generation: u32,
+    /// Handles index the store of the thread that made them.
+    _thread: PhantomData<Rc<()>>,
+}
+
+impl BigNumber {
+    pub fn new(value: BigRational) -> Self {
+        STORE.with(|store| {
+            let mut store = store.borrow_mut();
+            let index = match store.indices.get(&value) {
+                Some(&index) => index,
+                None => {
+                    let index = store.values.len();
+                    let value = Rc::new(value);
+                    store.values.push(value.clone());
+                    store.indices.insert(value, index);
+                    if store.holders == 0 {
+                        store.kept = store.values.len();
+                    }
+                    index
+                }
+            };
+            Self {
+                index,
+                generation: if index < store.kept {
+                    KEPT
+                } else {
+                    store.generation
+                },
+                _thread: PhantomData,
+            }
+        })
+    }
+
+    /// Like [`new`](Self::new), unless the bit length of `value` exceeds `max_digits`, in which
+    /// case nothing is stored. Solvers make their candidates with this, so that those out of
+    /// range do not fill the store.
+    pub(crate) fn new_within(value: BigRational, max_digits: usize) -> Option<Self> {
+        (bits(&value) <= max_digits as u64).then(|| Self::new(value))
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the value was freed since the handle was made.
+    pub fn get(&self) -> Rc<BigRational> {
+        STORE.with(|store| {
+            let store = store.borrow();
+            assert!(
+                self.generation == KEPT || self.generation == store.generation,
+                "BigNumber used after the solvers holding its value were dropped"
+            );
+            store.values[self.index].clone()
+        })
+    }
+
+    /// The bit length of the larger of the numerator and denominator.
+    #[inline]
+    pub fn bits(&self) -> u64 {
+        bits(&self.get())
+    }
+
+    #[inline]
+    pub fn is_integer(&self) -> bool {
+        self.get().is_integer()
+    }
+}
+
+/// Equal values share one index, but are hashed by value so that hashes do not depend on the
+/// order values were made in.
+impl Hash for BigNumber {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.get().hash(state);
+    }
+}
+
+impl fmt::Display for BigNumber {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = self.get();
+        if value.is_integer() {
+            write!(f, "{}", value.numer())
+        } else {
+            write!(f, "{}/{}", value.numer(), value.denom())
+        }
+    }
+}
+
+impl Signed for BigNumber {
+    #[inline]
+    fn abs(&self) -> Self {
+        Self::new(self.get().abs())
+    }
+
+    #[inline]
+    fn abs_sub(&self, other: &Self) -> Self {
+        Self::new(self.get().abs_sub(&other.get()))
+    }
+
+    #[inline]
+    fn signum(&self) -> Self {
+        Self::new(self.get().signum())
+    }
+
+    #[inline]
+    fn is_positive(&self) -> bool {
+        self.get().is_positive()
+    }
+
+    #[inline]
+    fn is_negative(&self) -> bool {
+        self.get().is_negative()
+    }
+}
+
+impl Num for BigNumber {
+    type FromStrRadixErr = ParseRatioError;
+
+    /// Parses an integer `a` or a fraction `a/b`, which need not be in lowest terms.
+    #[inline]
+    fn from_str_radix(str: &str, radix: u32) -> Result<Self, Self::FromStrRadixErr> {
+        BigRational::from_str_radix(str, radix).map(Self::new)
+    }
+}
+
+impl Zero for BigNumber {
+    #[inline]
+    fn zero() -> Self {
+        Self::new(BigRational::zero())
+    }
+
+    #[inline]
+    fn is_zero(&self) -> bool {
+        self.get().is_zero()
+    }
+}
+
+impl One for BigNumber {
+    #[inline]
+    fn one() -> Self {
+        Self::new(BigRational::one())
+    }
+
+    #[inline]
+    fn is_one(&self) -> bool {
+        self.get().is_one()
+    }
+}
+
+#[opimps::impl_uni_ops(Neg)]
+#[inline]
+fn neg(self: BigNumber) -> BigNumber {
+    BigNumber::new(-&*self.get())
+}
+
+#[opimps::impl_ops(Add)]
+#[inline]
+fn add(self: BigNumber, rhs: BigNumber) -> BigNumber {
+    BigNumber::new(&*self.get() + &*rhs.get())
+}
+
+#[opimps::impl_ops(Sub)]
+#[inline]
+fn sub(self: BigNumber, rhs: BigNumber) -> BigNumber {
+    BigNumber::new(&*self.get() - &*rhs.get())
+}
+
+#[opimps::impl_ops(Mul)]
+#[inline]
+fn mul(self: BigNumber, rhs: BigNumber) -> BigNumber {
+    BigNumber::new(&*self.get() * &*rhs.get())
+}
+
+#[opimps::impl_ops(Div)]
+#[inline]
+fn div(self: BigNumber, rhs: BigNumber) -> BigNumber {
+    BigNumber::new(&*self.get() / &*rhs.get())
+}
+
+#[opimps::impl_ops(Rem)]
+#[inline]
+fn rem(self: BigNumber, rhs: BigNumber) -> BigNumber {
+    BigNumber::new(&*self.get() % &*rhs.get())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BigNumber;
+    use crate::{Limits, Solver};
+
+    fn solver() -> Solver<BigNumber> {
+        Solver::new(3, Limits::recommended_rational())
+    }
+
+    #[test]
+    fn keeps_values_made_outside_solvers() {
+        let target = BigNumber::from(2023);
+        let mut solver_a = solver();
+        assert!(solver_a.solve(target, Some(2)).is_none());
+        drop(solver_a);
+        let mut solver_b = solver();
+        assert!(solver_b.solve(target, Some(2)).is_none());
+        assert_eq!(target.to_string(), "2023");
+    }
+
+    #[test]
+    #[should_panic(expected = "BigNumber used after the solvers")]
+    fn frees_values_made_by_solvers() {
+        let mut solver = solver();
+        solver.exhaust_to_depth(2);
+        let (&value, _) = solver.states_at_depth(2).next().unwrap();
+        drop(solver);
+        value.get();
+    }
+}
@@ -0,0 +1,104 @@
+use std::ops::{BitAnd, BitOr, BitXor};
+
+/// A fixed-size set of integers in `[1, len]`, one bit per integer.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Bitmap {
+    len: u64,
+    words: Vec<u64>,
+}
+
+impl Bitmap {
+    pub fn new(len: u64) -> Self {
+        Self {
+            len,
+            words: vec![0; len.div_ceil(64) as usize],
+        }
+    }
+
+    #[inline]
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[inline]
+    pub fn insert(&mut self, x: u64) -> bool {
+        if x == 0 || x > self.len {
+            return false;
+        }
+        let (word, bit) = (((x - 1) / 64) as usize, (x - 1) % 64);
+        let inserted = self.words[word] & (1 << bit) == 0;
+        self.words[word] |= 1 << bit;
+        inserted
+    }
+
+    #[inline]
+    pub fn contains(&self, x: u64) -> bool {
+        x != 0 && x <= self.len && self.words[((x - 1) / 64) as usize] & (1 << ((x - 1) % 64)) != 0
+    }
+
+    #[inline]
+    pub fn count(&self) -> u64 {
+        self.words.iter().map(|word| word.count_ones() as u64).sum()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = u64> + '_ {
+        (1..=self.len).filter(|&x| self.contains(x))
+    }
+
+    #[inline]
+    pub fn as_words(&self) -> &[u64] {
+        &self.words
+    }
+
+    pub fn from_words(len: u64, mut words: Vec<u64>) -> Self {
+        words.resize(len.div_ceil(64) as usize, 0);
+        if !len.is_multiple_of(64) {
+            if let Some(last) = words.last_mut() {
+                *last &= (1 << (len % 64)) - 1;
+            }
+        }
+        Self { len, words }
+    }
+
+    fn zip_with(&self, rhs: &Self, f: impl Fn(u64, u64) -> u64) -> Self {
+        let len = u64::max(self.len, rhs.len);
+        let words = (0..len.div_ceil(64) as usize)
+            .map(|i| {
+                f(
+                    self.words.get(i).copied().unwrap_or(0),
+                    rhs.words.get(i).copied().unwrap_or(0),
+                )
+            })
+            .collect();
+        Self { len, words }
+    }
+}
+
+impl BitOr for &Bitmap {
+    type Output = Bitmap;
+
+    fn bitor(self, rhs: Self) -> Bitmap {
+        self.zip_with(rhs, |x, y| x | y)
+    }
+}
+
+impl BitAnd for &Bitmap {
+    type Output = Bitmap;
+
+    fn bitand(self, rhs: Self) -> Bitmap {
+        self.zip_with(rhs, |x, y| x & y)
+    }
+}
+
+impl BitXor for &Bitmap {
+    type Output = Bitmap;
+
+    fn bitxor(self, rhs: Self) -> Bitmap {
+        self.zip_with(rhs, |x, y| x ^ y)
+    }
+}
@@ -0,0 +1,111 @@
+use crate::{ExprHandle, Expression};
+use std::rc::Rc;
+
+impl Expression {
+    /// A normal form shared by trivially equivalent expressions, meant as a deduplication key.
+    ///
+    /// Sums and differences are flattened into `(p1+p2+...)-(q1+q2+...)`, and products and
+    /// quotients into `(p1*p2*...)/(q1*q2*...)`, with the operands of each side in a fixed order.
+    /// So `a-(b-c)` and `c+a-b` share a canonical form, and so do `x*y` and `y*x`. The result
+    /// evaluates to the same value but may use more operations than the original.
+    pub fn canonical(&self) -> Rc<Expression> {
+        match self {
+            Expression::Number(x) => Expression::from_number(*x),
+            Expression::Add(..) | Expression::Subtract(..) | Expression::Negate(_) => {
+                let mut terms = (vec![], vec![]);
+                collect_terms(self, true, &mut terms);
+                let (positive, negative) = terms;
+                match (
+                    sorted_fold(positive, Expression::Add),
+                    sorted_fold(negative, Expression::Add),
+                ) {
+                    (Some(p), Some(q)) => Rc::new(Expression::Subtract(p, q)),
+                    (Some(p), None) => p,
+                    (None, Some(q)) => Rc::new(Expression::Negate(q)),
+                    (None, None) => unreachable!(),
+                }
+            }
+            Expression::Multiply(..) | Expression::Divide(..) => {
+                let mut factors = (vec![], vec![]);
+                collect_factors(self, true, &mut factors);
+                let (numerator, denominator) = factors;
+                let numerator = sorted_fold(numerator, Expression::Multiply)
+                    .unwrap_or_else(|| Expression::from_number(1));
+                match sorted_fold(denominator, Expression::Multiply) {
+                    Some(denominator) => Rc::new(Expression::Divide(numerator, denominator)),
+                    None => numerator,
+                }
+            }
+            Expression::Power(x, y) => Rc::new(Expression::Power(x.canonical(), y.canonical())),
+            Expression::Modulo(x, y) => Rc::new(Expression::Modulo(x.canonical(), y.canonical())),
+            Expression::Gcd(x, y) | Expression::Lcm(x, y) => {
+                let (x, y) = ordered(x.canonical(), y.canonical());
+                Rc::new(if self.is_gcd() {
+                    Expression::Gcd(x, y)
+                } else {
+                    Expression::Lcm(x, y)
+                })
+            }
+            Expression::Sqrt(x, order) => Rc::new(Expression::Sqrt(x.canonical(), *order)),
+            Expression::Factorial(x) => Rc::new(Expression::Factorial(x.canonical())),
+            Expression::Subfactorial(x) => Rc::new(Expression::Subfactorial(x.canonical())),
+        }
+    }
+}
+
+type Sides = (Vec<Rc<Expression>>, Vec<Rc<Expression>>);
+
+fn collect_terms(expression: &Expression, positive: bool, terms: &mut Sides) {
+    match expression {
+        Expression::Add(x, y) => {
+            collect_terms(x, positive, terms);
+            collect_terms(y, positive, terms);
+        }
+        Expression::Subtract(x, y) => {
+            collect_terms(x, positive, terms);
+            collect_terms(y, !positive, terms);
+        }
+        Expression::Negate(x) => collect_terms(x, !positive, terms),
+        _ if positive => terms.0.push(expression.canonical()),
+        _ => terms.1.push(expression.canonical()),
+    }
+}
+
+fn collect_factors(expression: &Expression, numerator: bool, factors: &mut Sides) {
+    match expression {
+        Expression::Multiply(x, y) => {
+            collect_factors(x, numerator, factors);
+            collect_factors(y, numerator, factors);
+        }
+        Expression::Divide(x, y) => {
+            collect_factors(x, numerator, factors);
+            collect_factors(y, !numerator, factors);
+        }
+        _ if numerator => factors.0.push(expression.canonical()),
+        _ => factors.1.push(expression.canonical()),
+    }
+}
+
+/// Sorts operands by their encoding and joins them left to right with `operation`.
+fn sorted_fold(
+    mut operands: Vec<Rc<Expression>>,
+    operation: fn(Rc<Expression>, Rc<Expression>) -> Expression,
+) -> Option<Rc<Expression>> {
+    operands.sort_by_cached_key(|x| x.encode());
+    operands.into_iter().reduce(|x, y| Rc::new(operation(x, y)))
+}
+
+fn ordered(x: Rc<Expression>, y: Rc<Expression>) -> (Rc<Expression>, Rc<Expression>) {
+    if x.encode() <= y.encode() {
+        (x, y)
+    } else {
+        (y, x)
+    }
+}
+
+impl ExprHandle {
+    #[inline]
+    pub fn canonical(&self) -> ExprHandle {
+        ExprHandle::new(self.as_expression().canonical())
+    }
+}
@@ -0,0 +1,380 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use tchisla_solver::{Limits, MAX_QUADRATIC_POWER};
+
+/// The names of the configuration files looked up when `--config` is not given, in order.
+const FILE_NAMES: &[&str] = &["tchisla.toml", "tchisla.json"];
+
+const BOOLEAN_KEYS: &[&str] = &[
+    "latex",
+    "verbose",
+    "approximate",
+    "modular-pruning",
+    "goal-lookup",
+    "dead-state-pruning",
+    "bloom-filter",
+    "subfactorial",
+    "modulo",
+    "gcd-lcm",
+    "negation",
+    "optimize",
+    "quiet",
+    "tui",
+    "histogram",
+    "decompose",
+    "split",
+];
+
+/// Keys whose value is a non-negative integer, the seconds of `--deadline` and the MiB of
+/// `--memory-budget`.
+const INTEGER_KEYS: &[&str] = &["deadline", "memory-budget"];
+
+const STRING_KEYS: &[&str] = &["log", "output", "trace", "replay", "cache-dir"];
+
+const CHOICE_KEYS: &[(&str, &[&str])] = &[
+    (
+        "backend",
+        &["integral", "rational", "quadratic", "progressive"],
+    ),
+    ("format", &["ascii", "unicode", "latex", "json", "csv"]),
+    ("color", &["auto", "always", "never"]),
+    ("stage-order", &["default", "target-first"]),
+];
+
+/// The solver sections of a `[limits.<n>]` table.
+pub const LIMIT_SECTIONS: &[&str] = &["integral", "rational", "quadratic", "radical"];
+
+/// The fields of a limits section, each also a command-line flag.
+pub const LIMIT_KEYS: &[&str] = &[
+    "max-digits",
+    "max-factorial",
+    "max-quadratic-power",
+    "max-radical-power",
+    "max-subfactorial",
+];
+
+#[derive(Clone, Debug, PartialEq)]
+enum Value {
+    Boolean(bool),
+    Integer(i64),
+    String(String),
+}
+
+/// Settings read from a TOML or JSON file, as a flat map from dotted keys such as `modulo`,
+/// `limits.rational.max-digits` or `limits.7.rational.max-digits` to values.
+///
+/// Only the part of TOML the settings need is read: tables, comments, and bare keys with
+/// string, integer or boolean values. JSON nests objects where TOML has tables, so
+/// `{"limits": {"7": {"rational": {"max-digits": 20}}}}` sets `limits.7.rational.max-digits`.
+/// Top-level keys are named after the command-line flags, and every key is checked, so that a
+/// typo is reported instead of silently ignored.
+#[derive(Clone, Default)]
+pub struct Config {
+    values: BTreeMap<String, Value>,
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Syntax {
+        line: usize,
+        message: String,
+    },
+    Json(serde_json::Error),
+    /// A setting of a JSON file, which has no lines to point at.
+    Setting(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Io(error) => write!(f, "{error}"),
+            ConfigError::Syntax { line, message } => write!(f, "line {line}: {message}"),
+            ConfigError::Json(error) => write!(f, "{error}"),
+            ConfigError::Setting(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl Config {
+    /// `tchisla.toml` or `tchisla.json` in the working directory, or else in the `tchisla`
+    /// directory of the user configuration directory, whichever exists first.
+    pub fn default_path() -> Option<PathBuf> {
+        let config_home = env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")));
+        let directories = [
+            Some(PathBuf::new()),
+            config_home.map(|home| home.join("tchisla")),
+        ];
+        directories
+            .into_iter()
+            .flatten()
+            .flat_map(|directory| FILE_NAMES.iter().map(move |name| directory.join(name)))
+            .find(|path| path.is_file())
+    }
+
+    /// Reads a JSON file if its extension is `.json`, and TOML otherwise.
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let input = fs::read_to_string(path).map_err(ConfigError::Io)?;
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            Self::parse_json(&input)
+        } else {
+            Self::parse(&input)
+        }
+    }
+
+    pub fn parse_json(input: &str) -> Result<Self, ConfigError> {
+        let mut config = Config::default();
+        let json: serde_json::Value = serde_json::from_str(input).map_err(ConfigError::Json)?;
+        let serde_json::Value::Object(settings) = json else {
+            return Err(ConfigError::Setting("expected an object".to_string()));
+        };
+        let mut tables = vec![(String::new(), settings)];
+        while let Some((table, settings)) = tables.pop() {
+            for (key, value) in settings {
+                let key = table.clone() + &key;
+                let value = match value {
+                    serde_json::Value::Object(settings) => {
+                        tables.push((key + ".", settings));
+                        continue;
+                    }
+                    serde_json::Value::Bool(value) => Value::Boolean(value),
+                    serde_json::Value::Number(value) => match value.as_i64() {
+                        Some(value) => Value::Integer(value),
+                        None => {
+                            return Err(ConfigError::Setting(format!("`{key}`: invalid value")))
+                        }
+                    },
+                    serde_json::Value::String(value) => Value::String(value),
+                    _ => return Err(ConfigError::Setting(format!("`{key}`: invalid value"))),
+                };
+                check(&key, &value).map_err(ConfigError::Setting)?;
+                if config.values.insert(key.clone(), value).is_some() {
+                    return Err(ConfigError::Setting(format!("`{key}` is set twice")));
+                }
+            }
+        }
+        Ok(config)
+    }
+
+    pub fn parse(input: &str) -> Result<Self, ConfigError> {
+        let mut config = Config::default();
+        let mut table = String::new();
+        for (index, line) in input.lines().enumerate() {
+            let error = |message: String| ConfigError::Syntax {
+                line: index + 1,
+                message,
+            };
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(header) = line.strip_prefix('[') {
+                let header = header
+                    .strip_suffix(']')
+                    .ok_or_else(|| error("unterminated table header".to_string()))?;
+                let parts: Vec<_> = header.split('.').map(str::trim).collect();
+                if !parts.iter().all(|part| is_bare_key(part)) {
+                    return Err(error(format!("invalid table name `{header}`")));
+                }
+                table = parts.join(".") + ".";
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| error(format!("expected `key = value`, found `{line}`")))?;
+            let key = key.trim();
+            if !is_bare_key(key) {
+                return Err(error(format!("invalid key `{key}`")));
+            }
+            let key = table.clone() + key;
+            let value = parse_value(value.trim()).ok_or_else(|| error("invalid value".into()))?;
+            check(&key, &value).map_err(error)?;
+            if config.values.insert(key.clone(), value).is_some() {
+                return Err(error(format!("`{key}` is set twice")));
+            }
+        }
+        Ok(config)
+    }
+
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        match self.values.get(key)? {
+            Value::Boolean(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn get_int(&self, key: &str) -> Option<i64> {
+        match self.values.get(key)? {
+            Value::Integer(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        match self.values.get(key)? {
+            Value::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// `limits` with the fields set in `[limits.<section>]` replaced, and then those set in
+    /// `[limits.<n>.<section>]`.
+    pub fn limits(&self, n: i64, section: &str, mut limits: Limits) -> Limits {
+        for table in [format!("limits.{section}"), format!("limits.{n}.{section}")] {
+            for field in LIMIT_KEYS {
+                if let Some(value) = self.get_int(&format!("{table}.{field}")) {
+                    set_limit(&mut limits, field, value);
+                }
+            }
+        }
+        limits
+    }
+}
+
+/// Sets the field of `limits` that the limit key `field` names.
+pub fn set_limit(limits: &mut Limits, field: &str, value: i64) {
+    match field {
+        "max-digits" => limits.max_digits = value as usize,
+        "max-factorial" => limits.max_factorial = value,
+        "max-quadratic-power" => limits.max_quadratic_power = value as u8,
+        "max-radical-power" => limits.max_radical_power = value as u16,
+        "max-subfactorial" => limits.max_subfactorial = value,
+        _ => unreachable!("unknown limit `{field}`"),
+    }
+}
+
+/// The largest value of the limit key `field` the solvers of `section` handle. Their numbers are
+/// all made of `i64`s: integral states are compared with `1 << max_digits` and their sums take
+/// one more digit, rational sums and products take twice the digits plus one, and quadratic
+/// and radical quotients may further pick up a factor of `2 * 3 * 5 * 7`. Factorials and
+/// subfactorials are taken below their limits, and 20 is the last that fits.
+fn max_limit(section: &str, field: &str) -> i64 {
+    match (section, field) {
+        ("integral", "max-digits") => 61,
+        ("rational", "max-digits") => 31,
+        (_, "max-digits") => 27,
+        (_, "max-factorial" | "max-subfactorial") => 21,
+        (_, "max-quadratic-power") => MAX_QUADRATIC_POWER as i64,
+        _ => u8::MAX as i64,
+    }
+}
+
+/// Why `value` is out of the range of the limit key `field` in `section`, if it is.
+pub fn check_limit(section: &str, field: &str, value: i64) -> Result<(), String> {
+    let max = max_limit(section, field);
+    (0..=max)
+        .contains(&value)
+        .then_some(())
+        .ok_or_else(|| format!("an integer between 0 and {max}"))
+}
+
+fn is_bare_key(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// The line up to a `#` outside of strings.
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+    for (position, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..position],
+            _ => {}
+        }
+    }
+    line
+}
+
+fn parse_value(input: &str) -> Option<Value> {
+    match input {
+        "true" => return Some(Value::Boolean(true)),
+        "false" => return Some(Value::Boolean(false)),
+        _ => {}
+    }
+    if let Some(string) = input.strip_prefix('"') {
+        let mut value = String::new();
+        let mut chars = string.chars();
+        loop {
+            match chars.next()? {
+                '"' => break,
+                '\\' => value.push(match chars.next()? {
+                    'n' => '\n',
+                    't' => '\t',
+                    c @ ('"' | '\\') => c,
+                    _ => return None,
+                }),
+                c => value.push(c),
+            }
+        }
+        return chars
+            .as_str()
+            .trim()
+            .is_empty()
+            .then_some(Value::String(value));
+    }
+    input.replace('_', "").parse().ok().map(Value::Integer)
+}
+
+/// Checks that `key` is a setting and `value` has its type.
+fn check(key: &str, value: &Value) -> Result<(), String> {
+    let expected = if BOOLEAN_KEYS.contains(&key) {
+        matches!(value, Value::Boolean(_))
+            .then_some(())
+            .ok_or("a boolean")
+    } else if INTEGER_KEYS.contains(&key) {
+        matches!(value, Value::Integer(value) if *value >= 0)
+            .then_some(())
+            .ok_or("a non-negative integer")
+    } else if STRING_KEYS.contains(&key) {
+        matches!(value, Value::String(_))
+            .then_some(())
+            .ok_or("a string")
+    } else if let Some((_, choices)) = CHOICE_KEYS.iter().find(|(name, _)| *name == key) {
+        match value {
+            Value::String(value) if choices.contains(&value.as_str()) => Ok(()),
+            _ => return Err(format!("`{key}` must be one of {}", choices.join(", "))),
+        }
+    } else if is_limit_key(key) {
+        let mut parts = key.rsplit('.');
+        let (field, section) = (parts.next().unwrap(), parts.next().unwrap());
+        return match value {
+            Value::Integer(value) => check_limit(section, field, *value),
+            _ => Err("an integer".to_string()),
+        }
+        .map_err(|expected| format!("`{key}` must be {expected}"));
+    } else {
+        return Err(format!("unknown setting `{key}`"));
+    };
+    expected.map_err(|expected| format!("`{key}` must be {expected}"))
+}
+
+/// Whether `key` is `limits.<section>.<field>` or `limits.<n>.<section>.<field>`.
+fn is_limit_key(key: &str) -> bool {
+    let parts: Vec<_> = key.split('.').collect();
+    match parts.as_slice() {
+        ["limits", section, field] => {
+            LIMIT_SECTIONS.contains(section) && LIMIT_KEYS.contains(field)
+        }
+        ["limits", n, section, field] => {
+            n.parse::<i64>().is_ok()
+                && LIMIT_SECTIONS.contains(section)
+                && LIMIT_KEYS.contains(field)
+        }
+        _ => false,
+    }
+}
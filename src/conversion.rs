@@ -0,0 +1,242 @@
+#[cfg(feature = "bigint")]
+use crate::BigNumber;
+use crate::{
+    Domain, GenericIntegralQuadratic, GenericRationalQuadratic, PrimeBasis, Radical, Rational,
+    Rational128, PRIMES,
+};
+use num::rational::Rational64;
+#[cfg(feature = "bigint")]
+use num::BigRational;
+use std::fmt;
+
+/// The error of a narrowing conversion between number domains, such as `Rational` into `i64`,
+/// when the target domain lacks the value. Widening conversions are `From` and never fail.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct OutOfDomainError {
+    /// The domain the value was converted into.
+    pub domain: Domain,
+}
+
+impl fmt::Display for OutOfDomainError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "value is not in the {} domain", self.domain.as_str())
+    }
+}
+
+impl From<i64> for Rational {
+    #[inline]
+    fn from(value: i64) -> Self {
+        Self::new_raw(value, 1)
+    }
+}
+
+impl From<Rational64> for Rational {
+    #[inline]
+    fn from(value: Rational64) -> Self {
+        Self::new(*value.numer(), *value.denom())
+    }
+}
+
+impl From<Rational> for Rational64 {
+    #[inline]
+    fn from(value: Rational) -> Self {
+        Rational64::new_raw(value.numerator(), value.denominator())
+    }
+}
+
+impl TryFrom<Rational> for i64 {
+    type Error = OutOfDomainError;
+
+    #[inline]
+    fn try_from(value: Rational) -> Result<Self, Self::Error> {
+        if value.is_integer() {
+            Ok(value.numerator())
+        } else {
+            Err(OutOfDomainError {
+                domain: Domain::Integral,
+            })
+        }
+    }
+}
+
+impl From<i64> for Rational128 {
+    #[inline]
+    fn from(value: i64) -> Self {
+        Self::new_raw(value.into(), 1)
+    }
+}
+
+impl From<Rational> for Rational128 {
+    #[inline]
+    fn from(value: Rational) -> Self {
+        Self::new_raw(value.numerator().into(), value.denominator().into())
+    }
+}
+
+#[cfg(feature = "bigint")]
+impl From<i64> for BigNumber {
+    #[inline]
+    fn from(value: i64) -> Self {
+        Self::new(BigRational::from_integer(value.into()))
+    }
+}
+
+#[cfg(feature = "bigint")]
+impl From<Rational> for BigNumber {
+    #[inline]
+    fn from(value: Rational) -> Self {
+        Self::new(BigRational::new_raw(
+            value.numerator().into(),
+            value.denominator().into(),
+        ))
+    }
+}
+
+impl From<i64> for Radical {
+    #[inline]
+    fn from(value: i64) -> Self {
+        Rational::from(value).into()
+    }
+}
+
+impl From<Rational> for Radical {
+    #[inline]
+    fn from(value: Rational) -> Self {
+        Radical::from_exponents(value, [0; PRIMES.len()])
+    }
+}
+
+impl<B: PrimeBasis> From<i64> for GenericIntegralQuadratic<B> {
+    #[inline]
+    fn from(value: i64) -> Self {
+        Self {
+            integral_part: value,
+            quadratic_part: B::Powers::default(),
+            quadratic_power: 0,
+        }
+    }
+}
+
+impl<B: PrimeBasis> From<i64> for GenericRationalQuadratic<B> {
+    #[inline]
+    fn from(value: i64) -> Self {
+        Rational::from(value).into()
+    }
+}
+
+impl<B: PrimeBasis> From<Rational> for GenericRationalQuadratic<B> {
+    #[inline]
+    fn from(value: Rational) -> Self {
+        Self {
+            rational_part: value,
+            quadratic_part: B::Powers::default(),
+            quadratic_power: 0,
+        }
+    }
+}
+
+impl<B: PrimeBasis> From<Rational64> for GenericRationalQuadratic<B> {
+    #[inline]
+    fn from(value: Rational64) -> Self {
+        Rational::from(value).into()
+    }
+}
+
+impl<B: PrimeBasis> From<GenericIntegralQuadratic<B>> for GenericRationalQuadratic<B> {
+    #[inline]
+    fn from(value: GenericIntegralQuadratic<B>) -> Self {
+        Self {
+            rational_part: value.integral_part.into(),
+            quadratic_part: value.quadratic_part,
+            quadratic_power: value.quadratic_power,
+        }
+    }
+}
+
+impl<B: PrimeBasis> TryFrom<Rational> for GenericIntegralQuadratic<B> {
+    type Error = OutOfDomainError;
+
+    #[inline]
+    fn try_from(value: Rational) -> Result<Self, Self::Error> {
+        i64::try_from(value)
+            .map(Self::from)
+            .map_err(|_| OutOfDomainError {
+                domain: Domain::IntegralQuadratic,
+            })
+    }
+}
+
+impl<B: PrimeBasis> TryFrom<GenericIntegralQuadratic<B>> for i64 {
+    type Error = OutOfDomainError;
+
+    #[inline]
+    fn try_from(value: GenericIntegralQuadratic<B>) -> Result<Self, Self::Error> {
+        if value.quadratic_power == 0 {
+            Ok(value.integral_part)
+        } else {
+            Err(OutOfDomainError {
+                domain: Domain::Integral,
+            })
+        }
+    }
+}
+
+impl<B: PrimeBasis> TryFrom<GenericIntegralQuadratic<B>> for Rational {
+    type Error = OutOfDomainError;
+
+    #[inline]
+    fn try_from(value: GenericIntegralQuadratic<B>) -> Result<Self, Self::Error> {
+        i64::try_from(value)
+            .map(Rational::from)
+            .map_err(|_| OutOfDomainError {
+                domain: Domain::Rational,
+            })
+    }
+}
+
+impl<B: PrimeBasis> TryFrom<GenericRationalQuadratic<B>> for Rational {
+    type Error = OutOfDomainError;
+
+    #[inline]
+    fn try_from(value: GenericRationalQuadratic<B>) -> Result<Self, Self::Error> {
+        if value.quadratic_power == 0 {
+            Ok(value.rational_part)
+        } else {
+            Err(OutOfDomainError {
+                domain: Domain::Rational,
+            })
+        }
+    }
+}
+
+impl<B: PrimeBasis> TryFrom<GenericRationalQuadratic<B>> for i64 {
+    type Error = OutOfDomainError;
+
+    #[inline]
+    fn try_from(value: GenericRationalQuadratic<B>) -> Result<Self, Self::Error> {
+        Rational::try_from(value)
+            .and_then(i64::try_from)
+            .map_err(|_| OutOfDomainError {
+                domain: Domain::Integral,
+            })
+    }
+}
+
+impl<B: PrimeBasis> TryFrom<GenericRationalQuadratic<B>> for GenericIntegralQuadratic<B> {
+    type Error = OutOfDomainError;
+
+    #[inline]
+    fn try_from(value: GenericRationalQuadratic<B>) -> Result<Self, Self::Error> {
+        if value.rational_part.is_integer() {
+            Ok(Self {
+                integral_part: value.rational_part.numerator(),
+                quadratic_part: value.quadratic_part,
+                quadratic_power: value.quadratic_power,
+            })
+        } else {
+            Err(OutOfDomainError {
+                domain: Domain::IntegralQuadratic,
+            })
+        }
+    }
+}
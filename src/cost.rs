@@ -0,0 +1,89 @@
+use crate::{Limits, Number, Solver};
+use serde::{Deserialize, Serialize};
+
+/// Depths [`estimate_cost`] searches for real before extrapolating, cheap for every `n`.
+const SAMPLE_DEPTH: usize = 4;
+
+/// The predicted work of one depth of a search.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct DepthCost {
+    pub depth: usize,
+    /// States first reached at this depth.
+    pub states: u64,
+    /// Pairs of states from two depths summing to this one, each tried with every binary
+    /// operation.
+    pub pairs: u64,
+    /// A rough estimate of the bytes held by the states of this depth.
+    pub memory: u64,
+    /// Whether the depth was searched rather than extrapolated.
+    pub measured: bool,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct CostEstimate {
+    /// One entry per depth, from depth 1.
+    pub depths: Vec<DepthCost>,
+}
+
+impl CostEstimate {
+    pub fn total_states(&self) -> u64 {
+        self.depths.iter().map(|depth| depth.states).sum()
+    }
+
+    pub fn total_pairs(&self) -> u64 {
+        self.depths.iter().map(|depth| depth.pairs).sum()
+    }
+
+    pub fn total_memory(&self) -> u64 {
+        self.depths.iter().map(|depth| depth.memory).sum()
+    }
+}
+
+/// Predicts the states and pair combinations of every depth up to `depth` for a search in the
+/// domain `T`, by searching the first few depths and extrapolating their growth geometrically.
+///
+/// State counts grow somewhat slower than geometrically at large depths, as the limits reject
+/// more values, so the extrapolated figures are an upper estimate of the order of magnitude.
+pub fn estimate_cost<T: Number>(n: i64, limits: Limits, depth: usize) -> CostEstimate {
+    let mut solver = Solver::<T>::new(n, limits);
+    let sampled = depth.min(SAMPLE_DEPTH);
+    solver.exhaust_to_depth(sampled);
+    let mut states: Vec<u64> = (1..=sampled)
+        .map(|digits| solver.states_at_depth(digits).count() as u64)
+        .collect();
+    let total = solver.counters().0.max(1);
+    let bytes_per_state = (solver.memory_estimate() / total) as u64;
+    let growth = match states.as_slice() {
+        [.., previous, last] if *previous > 0 => *last as f64 / *previous as f64,
+        _ => 1.0,
+    };
+    while states.len() < depth {
+        let last = *states.last().unwrap_or(&1) as f64;
+        states.push((last * growth).min(u64::MAX as f64) as u64);
+    }
+    let depths = (1..=depth)
+        .map(|digits| DepthCost {
+            depth: digits,
+            states: states[digits - 1],
+            pairs: pairs(&states, digits),
+            memory: states[digits - 1].saturating_mul(bytes_per_state),
+            measured: digits <= sampled,
+        })
+        .collect();
+    CostEstimate { depths }
+}
+
+/// The pairs of states from depths `d1 <= d2` with `d1 + d2 == digits`, where `states[d - 1]`
+/// counts the states of depth `d`.
+fn pairs(states: &[u64], digits: usize) -> u64 {
+    (1..=digits / 2)
+        .map(|d1| {
+            let (x, y) = (states[d1 - 1], states[digits - d1 - 1]);
+            if d1 == digits - d1 {
+                x.saturating_mul(x.saturating_add(1)) / 2
+            } else {
+                x.saturating_mul(y)
+            }
+        })
+        .fold(0u64, u64::saturating_add)
+}
@@ -0,0 +1,76 @@
+use super::{format_digit_set, Options};
+use std::io::{self, Write};
+use tchisla_solver::{Progress, Solution};
+
+/// A live view of a progressive search on stderr, redrawn in place on every progress event and
+/// solution.
+pub struct Dashboard {
+    title: String,
+    progress: Option<Progress>,
+    best: Option<String>,
+    lines: usize,
+}
+
+impl Dashboard {
+    pub fn new(options: &Options) -> Self {
+        Self {
+            title: format!("{} # {}", options.target, format_digit_set(options)),
+            progress: None,
+            best: None,
+            lines: 0,
+        }
+    }
+
+    pub fn update_progress(&mut self, progress: &Progress) {
+        self.progress = Some(*progress);
+        self.draw();
+    }
+
+    pub fn update_solution(&mut self, solution: &Solution) {
+        self.best = Some(format!("{}: {}", solution.digits(), solution));
+        self.draw();
+    }
+
+    fn draw(&mut self) {
+        let mut lines = vec![self.title.clone()];
+        if let Some(progress) = &self.progress {
+            let elapsed = progress.elapsed.map_or(String::new(), |elapsed| {
+                format!("  {:.1}s", elapsed.as_secs_f64())
+            });
+            lines.push(format!(
+                "depth {:>2}  {}{elapsed}",
+                progress.depth,
+                progress.phase.as_str().replace('_', " ")
+            ));
+            lines.push(format!(
+                "states    integral {}  full integral {}  rational {}  quadratic {}",
+                progress.integral_states,
+                progress.full_integral_states,
+                progress.rational_states,
+                progress.quadratic_states
+            ));
+            lines.push(format!(
+                "memory    ~{:.1} MiB",
+                progress.memory as f64 / (1 << 20) as f64
+            ));
+        }
+        lines.push(format!(
+            "best      {}",
+            self.best.as_deref().unwrap_or("none yet")
+        ));
+        let mut stderr = io::stderr().lock();
+        let mut frame = String::new();
+        if self.lines > 0 {
+            frame.push_str(&format!("\x1b[{}A", self.lines));
+        }
+        for line in &lines {
+            frame.push_str(&format!("\x1b[2K{line}\n"));
+        }
+        // Leftovers of a taller previous frame.
+        frame.push_str("\x1b[J");
+        // A closed or redirected stderr only loses the view.
+        let _ = stderr.write_all(frame.as_bytes());
+        let _ = stderr.flush();
+        self.lines = lines.len();
+    }
+}
@@ -0,0 +1,124 @@
+use crate::{Domain, Limits, ReusableSolver};
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+
+/// Depth of the table [`estimate_difficulty`] builds, cheap for every `n`.
+const BASE_DEPTH: usize = 3;
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct DifficultyEstimate {
+    /// The predicted minimal digit count.
+    pub digits: usize,
+    /// No solution within the solver limits uses fewer digits.
+    pub lower_bound: usize,
+    /// The digits of a solution known to exist, if one was found by combining table values.
+    pub upper_bound: Option<usize>,
+}
+
+/// Predicts the minimal digit count of `target # n` from a small table of cheap values, without
+/// a full search.
+pub fn estimate_difficulty(n: i64, target: i64) -> DifficultyEstimate {
+    let mut solver = ReusableSolver::new(
+        n,
+        Limits::recommended(n, Domain::Integral),
+        Limits::recommended(n, Domain::Rational),
+        Limits::recommended(n, Domain::RationalQuadratic),
+    );
+    solver.exhaust_to_depth(BASE_DEPTH);
+    solver.estimate_difficulty(target)
+}
+
+impl ReusableSolver {
+    /// Predicts the minimal digit count of `target` from the states searched so far. Deeper
+    /// tables give tighter bounds.
+    pub fn estimate_difficulty(&self, target: i64) -> DifficultyEstimate {
+        if let Some(solution) = self.get_solution(&target) {
+            return DifficultyEstimate {
+                digits: solution.digits(),
+                lower_bound: solution.digits(),
+                upper_bound: Some(solution.digits()),
+            };
+        }
+        let depth = self.depth_searched();
+        let lower_bound = depth + 1;
+        let table: FxHashMap<i64, usize> = self.integer_digit_counts().collect();
+        let mut upper_bound: Option<usize> = None;
+        let mut prediction = usize::MAX;
+        for (&x, &x_digits) in &table {
+            for y in partners(x, target) {
+                let candidate = match table.get(&y) {
+                    Some(&y_digits) => {
+                        let digits = x_digits + y_digits;
+                        upper_bound = Some(upper_bound.map_or(digits, |bound| bound.min(digits)));
+                        digits
+                    }
+                    None => x_digits + rough_digits(y, depth),
+                };
+                prediction = prediction.min(candidate);
+            }
+        }
+        let prediction = prediction.max(lower_bound);
+        DifficultyEstimate {
+            digits: upper_bound.map_or(prediction, |bound| prediction.min(bound)),
+            lower_bound,
+            upper_bound,
+        }
+    }
+}
+
+/// Positive values `y` such that `x` and `y` combine into `target` with one arithmetic
+/// operation. Any `x` and `target` are accepted, zero and those whose combinations overflow
+/// included.
+fn partners(x: i64, target: i64) -> impl Iterator<Item = i64> {
+    [
+        target.checked_sub(x),
+        x.checked_sub(target),
+        x.checked_add(target),
+        (target.checked_rem(x) == Some(0)).then(|| target / x),
+        (x.checked_rem(target) == Some(0)).then(|| x / target),
+        x.checked_mul(target),
+    ]
+    .into_iter()
+    .flatten()
+    .filter(|&y| y > 0)
+}
+
+/// A guess at the digits of a value absent from a table complete up to `depth` digits: at
+/// least one more, plus more for large or non-smooth values.
+fn rough_digits(y: i64, depth: usize) -> usize {
+    let magnitude = (y as f64).log10() / 1.3;
+    let roughness = if has_prime_factor_above_100(y) { 1 } else { 0 };
+    depth + 1 + magnitude as usize + roughness
+}
+
+/// Whether positive `x` is left above 1 once every factor up to 100 is divided out.
+fn has_prime_factor_above_100(mut x: i64) -> bool {
+    for p in 2..=100 {
+        while x % p == 0 {
+            x /= p;
+        }
+    }
+    x > 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{estimate_difficulty, partners};
+
+    #[test]
+    fn accepts_any_target() {
+        for target in [0, -1, i64::MIN, i64::MAX] {
+            estimate_difficulty(3, target);
+        }
+        for (x, target) in [(0, 0), (-1, i64::MIN), (i64::MAX, i64::MAX), (3, i64::MIN)] {
+            assert!(partners(x, target).all(|y| y > 0));
+        }
+    }
+
+    #[test]
+    fn estimates_known_values_exactly() {
+        let estimate = estimate_difficulty(3, 9);
+        assert_eq!(estimate.digits, 2);
+        assert_eq!(estimate.upper_bound, Some(2));
+    }
+}
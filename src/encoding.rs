@@ -0,0 +1,232 @@
+use crate::{ExprHandle, Expression};
+use std::rc::Rc;
+
+const NUMBER: u8 = 0;
+const NEGATE: u8 = 1;
+const ADD: u8 = 2;
+const SUBTRACT: u8 = 3;
+const MULTIPLY: u8 = 4;
+const DIVIDE: u8 = 5;
+const POWER: u8 = 6;
+const SQRT: u8 = 7;
+const FACTORIAL: u8 = 8;
+const SUBFACTORIAL: u8 = 9;
+const MODULO: u8 = 10;
+const GCD: u8 = 11;
+const LCM: u8 = 12;
+
+/// The deepest square root nesting decoded. The powers of `i128` and `BigNumber` take square
+/// roots of 2^k-th powers for exponents up to 2^30, beyond `MAX_QUADRATIC_POWER`, but no more
+/// than 64 can come from an exponent that fits in 64 bits.
+const MAX_SQRT_ORDER: u64 = 64;
+
+#[derive(Debug)]
+pub struct DecodeExpressionError {}
+
+pub(crate) fn write_varint(bytes: &mut Vec<u8>, mut x: u64) {
+    while x >= 0x80 {
+        bytes.push((x as u8) | 0x80);
+        x >>= 7;
+    }
+    bytes.push(x as u8);
+}
+
+pub(crate) fn read_varint(
+    bytes: &mut impl Iterator<Item = u8>,
+) -> Result<u64, DecodeExpressionError> {
+    let mut x = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = bytes.next().ok_or(DecodeExpressionError {})?;
+        x |= ((byte & 0x7f) as u64) << shift;
+        if byte < 0x80 {
+            return Ok(x);
+        }
+    }
+    Err(DecodeExpressionError {})
+}
+
+fn encode_into(expression: &Expression, bytes: &mut Vec<u8>) {
+    match expression {
+        Expression::Number(x) => {
+            bytes.push(NUMBER);
+            write_varint(bytes, ((x << 1) ^ (x >> 63)) as u64);
+        }
+        Expression::Negate(x) => {
+            encode_into(x, bytes);
+            bytes.push(NEGATE);
+        }
+        Expression::Add(x, y) => {
+            encode_into(x, bytes);
+            encode_into(y, bytes);
+            bytes.push(ADD);
+        }
+        Expression::Subtract(x, y) => {
+            encode_into(x, bytes);
+            encode_into(y, bytes);
+            bytes.push(SUBTRACT);
+        }
+        Expression::Multiply(x, y) => {
+            encode_into(x, bytes);
+            encode_into(y, bytes);
+            bytes.push(MULTIPLY);
+        }
+        Expression::Divide(x, y) => {
+            encode_into(x, bytes);
+            encode_into(y, bytes);
+            bytes.push(DIVIDE);
+        }
+        Expression::Power(x, y) => {
+            encode_into(x, bytes);
+            encode_into(y, bytes);
+            bytes.push(POWER);
+        }
+        Expression::Modulo(x, y) => {
+            encode_into(x, bytes);
+            encode_into(y, bytes);
+            bytes.push(MODULO);
+        }
+        Expression::Gcd(x, y) => {
+            encode_into(x, bytes);
+            encode_into(y, bytes);
+            bytes.push(GCD);
+        }
+        Expression::Lcm(x, y) => {
+            encode_into(x, bytes);
+            encode_into(y, bytes);
+            bytes.push(LCM);
+        }
+        Expression::Sqrt(x, order) => {
+            encode_into(x, bytes);
+            bytes.push(SQRT);
+            write_varint(bytes, *order as u64);
+        }
+        Expression::Factorial(x) => {
+            encode_into(x, bytes);
+            bytes.push(FACTORIAL);
+        }
+        Expression::Subfactorial(x) => {
+            encode_into(x, bytes);
+            bytes.push(SUBFACTORIAL);
+        }
+    }
+}
+
+impl Expression {
+    /// Encodes the expression in postfix order, one opcode byte per node, with number literals
+    /// and sqrt orders following their opcode as LEB128 varints (zigzag for literals).
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        encode_into(self, &mut bytes);
+        bytes
+    }
+
+    /// Decodes the bytes of [`encode`](Self::encode), rejecting truncated or trailing input and
+    /// square roots nested more than 64 deep.
+    pub fn decode(bytes: &[u8]) -> Result<Rc<Expression>, DecodeExpressionError> {
+        let mut bytes = bytes.iter().copied();
+        let mut stack: Vec<Rc<Expression>> = vec![];
+        while let Some(opcode) = bytes.next() {
+            let expression = match opcode {
+                NUMBER => {
+                    let x = read_varint(&mut bytes)?;
+                    Expression::Number(((x >> 1) as i64) ^ -((x & 1) as i64))
+                }
+                NEGATE | SQRT | FACTORIAL | SUBFACTORIAL => {
+                    let x = stack.pop().ok_or(DecodeExpressionError {})?;
+                    match opcode {
+                        NEGATE => Expression::Negate(x),
+                        SQRT => {
+                            let order = read_varint(&mut bytes)?;
+                            if order > MAX_SQRT_ORDER {
+                                return Err(DecodeExpressionError {});
+                            }
+                            Expression::Sqrt(x, order as usize)
+                        }
+                        FACTORIAL => Expression::Factorial(x),
+                        _ => Expression::Subfactorial(x),
+                    }
+                }
+                ADD | SUBTRACT | MULTIPLY | DIVIDE | POWER | MODULO | GCD | LCM => {
+                    let y = stack.pop().ok_or(DecodeExpressionError {})?;
+                    let x = stack.pop().ok_or(DecodeExpressionError {})?;
+                    match opcode {
+                        ADD => Expression::Add(x, y),
+                        SUBTRACT => Expression::Subtract(x, y),
+                        MULTIPLY => Expression::Multiply(x, y),
+                        DIVIDE => Expression::Divide(x, y),
+                        POWER => Expression::Power(x, y),
+                        MODULO => Expression::Modulo(x, y),
+                        GCD => Expression::Gcd(x, y),
+                        _ => Expression::Lcm(x, y),
+                    }
+                }
+                _ => return Err(DecodeExpressionError {}),
+            };
+            stack.push(Rc::new(expression));
+        }
+        match (stack.pop(), stack.is_empty()) {
+            (Some(expression), true) => Ok(expression),
+            _ => Err(DecodeExpressionError {}),
+        }
+    }
+}
+
+impl ExprHandle {
+    #[inline]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.as_expression().encode()
+    }
+
+    #[inline]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeExpressionError> {
+        Ok(ExprHandle::new(Expression::decode(bytes)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Expression;
+
+    #[test]
+    fn round_trips_every_node() {
+        let inputs = [
+            "-3+3-3*3/3",
+            "3^(3!)!",
+            "sqrt(sqrt(3))*!4",
+            "gcd(12,18) mod lcm(4,6)",
+        ];
+        for input in inputs {
+            let expression = Expression::parse(input).unwrap();
+            assert_eq!(
+                Expression::decode(&expression.encode()).unwrap(),
+                expression
+            );
+        }
+        for x in [0, 1, -1, 127, 128, i64::MAX, i64::MIN] {
+            let expression = Expression::from_number(x);
+            assert_eq!(
+                Expression::decode(&expression.encode()).unwrap(),
+                expression
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_bytes() {
+        let bytes = Expression::parse("3+sqrt(3)").unwrap().encode();
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        let inputs = [
+            vec![],
+            bytes[..bytes.len() - 1].to_vec(),
+            trailing,
+            vec![0, 6, 13],
+            vec![0, 6, 7, 65],
+            vec![0, 0x80],
+            vec![2],
+        ];
+        for input in inputs {
+            assert!(Expression::decode(&input).is_err(), "{input:?}");
+        }
+    }
+}
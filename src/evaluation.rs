@@ -0,0 +1,459 @@
+use crate::number_theory::try_sqrt;
+use crate::{
+    Expression, IntegralQuadratic, Number, Radical, Rational, RationalQuadratic,
+    MAX_QUADRATIC_POWER,
+};
+use num::rational::Rational64;
+use num::traits::{CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, Inv};
+use num::{Integer, One, Signed, Zero};
+use std::fmt;
+
+/// The binary digits allowed in the powers of custom domains, which have no limits to bound them.
+const MAX_DIGITS: usize = 64;
+
+/// Why [`Expression::try_evaluate_rational`] has no value.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum EvaluationError {
+    /// Some subexpression is irrational, though the whole need not be, as `sqrt(2)*sqrt(8)`.
+    Irrational,
+    /// Some subexpression is rational but does not fit in 64 bits.
+    Overflow,
+    /// Some operation is undefined for its operands, such as a division by zero, a fractional
+    /// exponent, or a factorial of a negative or non-integer value.
+    Undefined,
+}
+
+impl fmt::Display for EvaluationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EvaluationError::Irrational => write!(f, "irrational"),
+            EvaluationError::Overflow => write!(f, "overflows 64 bits"),
+            EvaluationError::Undefined => write!(f, "undefined"),
+        }
+    }
+}
+
+impl Expression {
+    /// Evaluates the expression exactly with 64-bit rationals.
+    ///
+    /// Returns `None` if some subexpression is irrational, overflows, or applies an integer
+    /// operation (factorial, subfactorial, modulo, gcd, lcm) to a negative or non-integer value.
+    /// [`try_evaluate_rational`](Self::try_evaluate_rational) tells these apart.
+    #[inline]
+    pub fn evaluate_rational(&self) -> Option<Rational> {
+        self.try_evaluate_rational().ok()
+    }
+
+    /// Evaluates the expression exactly with 64-bit rationals, or says why it cannot.
+    ///
+    /// The error is that of the first subexpression, in evaluation order, without a value.
+    pub fn try_evaluate_rational(&self) -> Result<Rational, EvaluationError> {
+        evaluate(self).map(Rational::from)
+    }
+
+    /// Evaluates the expression exactly in the domain of `T`.
+    ///
+    /// The domains of this crate evaluate with [`evaluate_quadratic`](Self::evaluate_quadratic)
+    /// and return `None` unless the value lies in the domain of `T`, so intermediate values may
+    /// lie outside it: `sqrt(2)*sqrt(8)` is 4 as an `i64`. Custom domains evaluate with the
+    /// operations of [`Number`] the solver searches them with, and return `None` as soon as one
+    /// of them does.
+    #[inline]
+    pub fn evaluate<T: Number>(&self) -> Option<T> {
+        T::evaluate(self)
+    }
+
+    /// Evaluates the expression exactly with 64-bit rationals and nested square roots.
+    ///
+    /// Returns `None` if some subexpression overflows, nests square roots deeper than
+    /// [`MAX_QUADRATIC_POWER`], adds or subtracts values with different radicals, raises to an
+    /// exponent that is not an integer of magnitude at most 64, or applies an integer operation
+    /// to a negative or non-integer value.
+    pub fn evaluate_quadratic(&self) -> Option<RationalQuadratic> {
+        match self {
+            Expression::Number(x) => Some((*x).into()),
+            Expression::Negate(x) => Some(-x.evaluate_quadratic()?),
+            Expression::Add(x, y) => {
+                quadratic_add(x.evaluate_quadratic()?, y.evaluate_quadratic()?)
+            }
+            Expression::Subtract(x, y) => {
+                quadratic_add(x.evaluate_quadratic()?, -y.evaluate_quadratic()?)
+            }
+            Expression::Multiply(x, y) => {
+                quadratic_multiply(x.evaluate_quadratic()?, y.evaluate_quadratic()?)
+            }
+            Expression::Divide(x, y) => {
+                quadratic_divide(x.evaluate_quadratic()?, y.evaluate_quadratic()?)
+            }
+            Expression::Power(x, y) => {
+                let x = x.evaluate_quadratic()?;
+                let exponent = y.evaluate_quadratic()?.to_int()?;
+                if exponent.abs() > 64 {
+                    return None;
+                }
+                let mut result = RationalQuadratic::one();
+                for _ in 0..exponent.abs() {
+                    result = quadratic_multiply(result, x)?;
+                }
+                if exponent < 0 {
+                    quadratic_divide(RationalQuadratic::one(), result)
+                } else {
+                    Some(result)
+                }
+            }
+            Expression::Sqrt(x, order) => {
+                let mut x = x.evaluate_quadratic()?;
+                for _ in 0..*order {
+                    if x.quadratic_power() >= MAX_QUADRATIC_POWER {
+                        return None;
+                    }
+                    x = x.try_sqrt()?;
+                }
+                Some(x)
+            }
+            _ => evaluate_integral(self, &|x: &Expression| {
+                x.evaluate_quadratic()
+                    .and_then(|x| x.to_int())
+                    .filter(|x| !x.is_negative())
+                    .ok_or(EvaluationError::Undefined)
+            })
+            .ok()
+            .map(RationalQuadratic::from),
+        }
+    }
+}
+
+/// Exact evaluation in a domain, specialized for the domains of this crate.
+trait Evaluate: Sized {
+    fn evaluate(expression: &Expression) -> Option<Self>;
+}
+
+impl<T: Number> Evaluate for T {
+    default fn evaluate(expression: &Expression) -> Option<T> {
+        match expression {
+            Expression::Number(x) => Some((*x).into()),
+            Expression::Negate(x) => Some(-T::evaluate(x)?),
+            Expression::Add(x, y) => Some(T::evaluate(x)? + T::evaluate(y)?),
+            Expression::Subtract(x, y) => Some(T::evaluate(x)? - T::evaluate(y)?),
+            Expression::Multiply(x, y) => Some(T::evaluate(x)? * T::evaluate(y)?),
+            Expression::Divide(x, y) => {
+                let y = T::evaluate(y)?;
+                if y.is_zero() {
+                    return None;
+                }
+                T::evaluate(x)?.checked_divide(y)
+            }
+            Expression::Power(x, y) => T::evaluate(x)?.checked_power(T::evaluate(y)?, MAX_DIGITS),
+            Expression::Sqrt(x, order) => {
+                let mut x = T::evaluate(x)?;
+                for _ in 0..*order {
+                    x = x.checked_sqrt()?;
+                }
+                Some(x)
+            }
+            _ => evaluate_integral(expression, &|x: &Expression| {
+                T::evaluate(x)
+                    .and_then(|x| x.to_int())
+                    .filter(|x| !x.is_negative())
+                    .ok_or(EvaluationError::Undefined)
+            })
+            .ok()
+            .map(T::from),
+        }
+    }
+}
+
+impl Evaluate for i64 {
+    #[inline]
+    fn evaluate(expression: &Expression) -> Option<i64> {
+        i64::try_from(expression.evaluate_quadratic()?).ok()
+    }
+}
+
+impl Evaluate for Rational {
+    #[inline]
+    fn evaluate(expression: &Expression) -> Option<Rational> {
+        Rational::try_from(expression.evaluate_quadratic()?).ok()
+    }
+}
+
+impl Evaluate for IntegralQuadratic {
+    #[inline]
+    fn evaluate(expression: &Expression) -> Option<IntegralQuadratic> {
+        IntegralQuadratic::try_from(expression.evaluate_quadratic()?).ok()
+    }
+}
+
+impl Evaluate for RationalQuadratic {
+    #[inline]
+    fn evaluate(expression: &Expression) -> Option<RationalQuadratic> {
+        expression.evaluate_quadratic()
+    }
+}
+
+/// Sums of terms with different radical parts are not radicals, so they are not evaluated.
+impl Evaluate for Radical {
+    fn evaluate(expression: &Expression) -> Option<Radical> {
+        match expression {
+            Expression::Number(x) => Some((*x).into()),
+            Expression::Negate(x) => Some(-Radical::evaluate(x)?),
+            Expression::Add(x, y) | Expression::Subtract(x, y) => {
+                let x = Radical::evaluate(x)?;
+                let y = Radical::evaluate(y)?;
+                if !x.is_zero() && !y.is_zero() && x.radical_part() != y.radical_part() {
+                    return None;
+                }
+                Some(if expression.is_add() { x + y } else { x - y })
+            }
+            Expression::Multiply(x, y) => Some(Radical::evaluate(x)? * Radical::evaluate(y)?),
+            Expression::Divide(x, y) => {
+                let y = Radical::evaluate(y)?;
+                if y.is_zero() {
+                    return None;
+                }
+                Radical::evaluate(x)?.checked_divide(y)
+            }
+            Expression::Power(x, y) => {
+                Radical::evaluate(x)?.checked_power(Radical::evaluate(y)?, MAX_DIGITS)
+            }
+            Expression::Sqrt(x, order) => {
+                let mut x = Radical::evaluate(x)?;
+                for _ in 0..*order {
+                    x = x.checked_sqrt()?;
+                }
+                Some(x)
+            }
+            _ => evaluate_integral(expression, &|x: &Expression| {
+                Radical::evaluate(x)
+                    .and_then(|x| x.to_int())
+                    .filter(|x| !x.is_negative())
+                    .ok_or(EvaluationError::Undefined)
+            })
+            .ok()
+            .map(Radical::from),
+        }
+    }
+}
+
+fn evaluate(expression: &Expression) -> Result<Rational64, EvaluationError> {
+    match expression {
+        Expression::Number(x) => Ok((*x).into()),
+        Expression::Negate(x) => Ok(-evaluate(x)?),
+        Expression::Add(x, y) => evaluate(x)?
+            .checked_add(&evaluate(y)?)
+            .ok_or(EvaluationError::Overflow),
+        Expression::Subtract(x, y) => evaluate(x)?
+            .checked_sub(&evaluate(y)?)
+            .ok_or(EvaluationError::Overflow),
+        Expression::Multiply(x, y) => evaluate(x)?
+            .checked_mul(&evaluate(y)?)
+            .ok_or(EvaluationError::Overflow),
+        Expression::Divide(x, y) => {
+            let x = evaluate(x)?;
+            let y = evaluate(y)?;
+            if y.is_zero() {
+                return Err(EvaluationError::Undefined);
+            }
+            x.checked_div(&y).ok_or(EvaluationError::Overflow)
+        }
+        Expression::Power(x, y) => {
+            let x = evaluate(x)?;
+            let y = evaluate(y)?;
+            if !y.is_integer() || (x.is_zero() && y.is_negative()) {
+                return Err(EvaluationError::Undefined);
+            }
+            // By squaring, so that large powers of 0, 1 and -1 are computed and other large
+            // powers overflow quickly.
+            let mut exponent = y.to_integer().unsigned_abs();
+            let mut base = x;
+            let mut result = Rational64::one();
+            while exponent > 0 {
+                if exponent % 2 == 1 {
+                    result = result.checked_mul(&base).ok_or(EvaluationError::Overflow)?;
+                }
+                exponent /= 2;
+                if exponent > 0 {
+                    base = base.checked_mul(&base).ok_or(EvaluationError::Overflow)?;
+                }
+            }
+            if y.is_negative() {
+                Rational64::one()
+                    .checked_div(&result)
+                    .ok_or(EvaluationError::Overflow)
+            } else {
+                Ok(result)
+            }
+        }
+        Expression::Sqrt(x, order) => {
+            let mut x = evaluate(x)?;
+            for _ in 0..*order {
+                if x.is_negative() {
+                    return Err(EvaluationError::Undefined);
+                }
+                x = Rational64::new(
+                    try_sqrt(*x.numer()).ok_or(EvaluationError::Irrational)?,
+                    try_sqrt(*x.denom()).ok_or(EvaluationError::Irrational)?,
+                );
+            }
+            Ok(x)
+        }
+        _ => evaluate_integral(expression, &evaluate_natural).map(Rational64::from_integer),
+    }
+}
+
+fn evaluate_natural(expression: &Expression) -> Result<i64, EvaluationError> {
+    let x = evaluate(expression)?;
+    if x.is_integer() && !x.is_negative() {
+        Ok(x.to_integer())
+    } else {
+        Err(EvaluationError::Undefined)
+    }
+}
+
+/// Evaluates a factorial, subfactorial, modulo, gcd or lcm, with `natural` evaluating its
+/// operands to non-negative integers.
+fn evaluate_integral(
+    expression: &Expression,
+    natural: &impl Fn(&Expression) -> Result<i64, EvaluationError>,
+) -> Result<i64, EvaluationError> {
+    match expression {
+        Expression::Factorial(x) => {
+            let x = natural(x)?;
+            (2..=x)
+                .try_fold(1i64, |product, k| product.checked_mul(k))
+                .ok_or(EvaluationError::Overflow)
+        }
+        Expression::Subfactorial(x) => {
+            let x = natural(x)?;
+            (1..=x)
+                .try_fold(1i64, |d, k| {
+                    d.checked_mul(k)?
+                        .checked_add(if k % 2 == 0 { 1 } else { -1 })
+                })
+                .ok_or(EvaluationError::Overflow)
+        }
+        Expression::Modulo(x, y) => {
+            let x = natural(x)?;
+            let y = natural(y)?;
+            if y.is_zero() {
+                return Err(EvaluationError::Undefined);
+            }
+            Ok(x.mod_floor(&y))
+        }
+        Expression::Gcd(x, y) => Ok(natural(x)?.gcd(&natural(y)?)),
+        Expression::Lcm(x, y) => {
+            let x = natural(x)?;
+            let y = natural(y)?;
+            if x.is_zero() || y.is_zero() {
+                return Err(EvaluationError::Undefined);
+            }
+            (x / x.gcd(&y))
+                .checked_mul(y)
+                .ok_or(EvaluationError::Overflow)
+        }
+        _ => unreachable!("{expression:?} is not an integral operation"),
+    }
+}
+
+/// `x` times the rational `factor`.
+fn scale(x: RationalQuadratic, factor: Rational) -> Option<RationalQuadratic> {
+    let rational_part = Rational64::from(x.rational_part())
+        .checked_mul(&factor.into())
+        .map(Rational::from)?;
+    Some(if rational_part.is_zero() {
+        RationalQuadratic::zero()
+    } else {
+        RationalQuadratic { rational_part, ..x }
+    })
+}
+
+/// The radicals of `x`, with a rational part of 1. Multiplying or dividing radicals only carries
+/// a few small primes into the rational part, so it cannot overflow.
+#[inline]
+fn radical(x: RationalQuadratic) -> RationalQuadratic {
+    RationalQuadratic {
+        rational_part: Rational::one(),
+        ..x
+    }
+}
+
+fn quadratic_add(x: RationalQuadratic, y: RationalQuadratic) -> Option<RationalQuadratic> {
+    if x.is_zero() {
+        return Some(y);
+    } else if y.is_zero() {
+        return Some(x);
+    } else if x.quadratic_power() != y.quadratic_power() || x.quadratic_part() != y.quadratic_part()
+    {
+        return None;
+    }
+    let rational_part = Rational64::from(x.rational_part())
+        .checked_add(&y.rational_part().into())
+        .map(Rational::from)?;
+    Some(if rational_part.is_zero() {
+        RationalQuadratic::zero()
+    } else {
+        RationalQuadratic { rational_part, ..x }
+    })
+}
+
+fn quadratic_multiply(x: RationalQuadratic, y: RationalQuadratic) -> Option<RationalQuadratic> {
+    if x.is_zero() || y.is_zero() {
+        return Some(RationalQuadratic::zero());
+    }
+    scale(
+        scale(radical(x) * radical(y), x.rational_part())?,
+        y.rational_part(),
+    )
+}
+
+fn quadratic_divide(x: RationalQuadratic, y: RationalQuadratic) -> Option<RationalQuadratic> {
+    if y.is_zero() {
+        return None;
+    } else if x.is_zero() {
+        return Some(x);
+    }
+    scale(
+        scale(radical(x) / radical(y), x.rational_part())?,
+        y.rational_part().inv(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{EvaluationError, Expression, Rational};
+
+    fn evaluate(input: &str) -> Result<Rational, EvaluationError> {
+        Expression::parse(input).unwrap().try_evaluate_rational()
+    }
+
+    #[test]
+    fn evaluates_exactly() {
+        assert!(evaluate("3/(3!*3)") == Ok(Rational::new(1, 6)));
+        assert!(evaluate("(3!)!^3!") == Ok(139314069504000000.into()));
+        assert!(evaluate("1^9^9") == Ok(1.into()));
+        assert!(evaluate("(3/2)^-2") == Ok(Rational::new(4, 9)));
+        assert!(evaluate("sqrt(9/4)") == Ok(Rational::new(3, 2)));
+    }
+
+    #[test]
+    fn tells_why_values_are_missing() {
+        let inputs = [
+            ("sqrt(2)*sqrt(8)", EvaluationError::Irrational),
+            ("sqrt(2)+sqrt(3)", EvaluationError::Irrational),
+            ("9^99", EvaluationError::Overflow),
+            ("(3!)!!", EvaluationError::Overflow),
+            ("3/(3-3)", EvaluationError::Undefined),
+            ("0^-1", EvaluationError::Undefined),
+            ("4^(1/2)", EvaluationError::Undefined),
+            ("(1/2)!", EvaluationError::Undefined),
+            ("3 mod 0", EvaluationError::Undefined),
+        ];
+        for (input, error) in inputs {
+            assert_eq!(
+                evaluate(input).map(|x| x.to_string()),
+                Err(error),
+                "{input}"
+            );
+        }
+    }
+}
@@ -1,6 +1,15 @@
+use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::rc::Rc;
 
+/// An expression tree, with subexpressions shared between the trees built from them.
+///
+/// Nodes carry no cached metadata: metrics such as
+/// [`digit_count`](Self::digit_count) and [`operator_count`](Self::operator_count) walk the
+/// tree on each call. The enum is serialized as is into record tables and persisted states, so
+/// a cached field would change both formats. The search keeps the digit count of each state
+/// alongside it instead, and only solutions are measured whole.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 pub enum Expression {
     Number(i64),
     Negate(Rc<Expression>),
@@ -9,8 +18,12 @@ pub enum Expression {
     Multiply(Rc<Expression>, Rc<Expression>),
     Divide(Rc<Expression>, Rc<Expression>),
     Power(Rc<Expression>, Rc<Expression>),
+    Modulo(Rc<Expression>, Rc<Expression>),
+    Gcd(Rc<Expression>, Rc<Expression>),
+    Lcm(Rc<Expression>, Rc<Expression>),
     Sqrt(Rc<Expression>, usize),
     Factorial(Rc<Expression>),
+    Subfactorial(Rc<Expression>),
 }
 
 impl Expression {
@@ -105,6 +118,45 @@ impl Expression {
         matches!(self, Expression::Power(_, _))
     }
 
+    #[inline]
+    pub fn to_modulo(&self) -> Option<(&Rc<Expression>, &Rc<Expression>)> {
+        match self {
+            Expression::Modulo(x, y) => Some((x, y)),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn is_modulo(&self) -> bool {
+        matches!(self, Expression::Modulo(_, _))
+    }
+
+    #[inline]
+    pub fn to_gcd(&self) -> Option<(&Rc<Expression>, &Rc<Expression>)> {
+        match self {
+            Expression::Gcd(x, y) => Some((x, y)),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn is_gcd(&self) -> bool {
+        matches!(self, Expression::Gcd(_, _))
+    }
+
+    #[inline]
+    pub fn to_lcm(&self) -> Option<(&Rc<Expression>, &Rc<Expression>)> {
+        match self {
+            Expression::Lcm(x, y) => Some((x, y)),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn is_lcm(&self) -> bool {
+        matches!(self, Expression::Lcm(_, _))
+    }
+
     #[inline]
     pub fn to_sqrt(&self) -> Option<(&Rc<Expression>, &usize)> {
         match self {
@@ -131,6 +183,56 @@ impl Expression {
         matches!(self, Expression::Factorial(_))
     }
 
+    #[inline]
+    pub fn to_subfactorial(&self) -> Option<&Rc<Expression>> {
+        match self {
+            Expression::Subfactorial(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn is_subfactorial(&self) -> bool {
+        matches!(self, Expression::Subfactorial(_))
+    }
+
+    /// Evaluates the expression in floating point. Returns NaN for factorials, subfactorials,
+    /// gcds and lcms of non-integers.
+    pub fn approximate(&self) -> f64 {
+        match self {
+            Expression::Number(x) => *x as f64,
+            Expression::Negate(x) => -x.approximate(),
+            Expression::Add(x, y) => x.approximate() + y.approximate(),
+            Expression::Subtract(x, y) => x.approximate() - y.approximate(),
+            Expression::Multiply(x, y) => x.approximate() * y.approximate(),
+            Expression::Divide(x, y) => x.approximate() / y.approximate(),
+            Expression::Power(x, y) => x.approximate().powf(y.approximate()),
+            Expression::Modulo(x, y) => x.approximate().rem_euclid(y.approximate()),
+            Expression::Gcd(x, y) => approximate_gcd(x.approximate(), y.approximate()),
+            Expression::Lcm(x, y) => {
+                let (x, y) = (x.approximate(), y.approximate());
+                x / approximate_gcd(x, y) * y
+            }
+            Expression::Sqrt(x, order) => x.approximate().powf(0.5f64.powi(*order as i32)),
+            Expression::Factorial(x) => {
+                let x = x.approximate();
+                if x < 0.0 || (x - x.round()).abs() > 1e-9 {
+                    f64::NAN
+                } else {
+                    (2..=x.round() as u64).map(|k| k as f64).product()
+                }
+            }
+            Expression::Subfactorial(x) => {
+                let x = x.approximate();
+                if x < 0.0 || (x - x.round()).abs() > 1e-9 {
+                    f64::NAN
+                } else {
+                    (1..=x.round() as u64).fold(1.0, |d, k| d * k as f64 + (-1f64).powi(k as i32))
+                }
+            }
+        }
+    }
+
     #[inline]
     fn precedence(&self) -> i32 {
         match self {
@@ -141,12 +243,27 @@ impl Expression {
             Expression::Multiply(_, _) => 3,
             Expression::Divide(_, _) => 3,
             Expression::Power(_, _) => 4,
+            Expression::Modulo(_, _) => 3,
+            Expression::Gcd(_, _) => 6,
+            Expression::Lcm(_, _) => 6,
             Expression::Sqrt(_, _) => 5,
             Expression::Factorial(_) => 6,
+            Expression::Subfactorial(_) => 6,
         }
     }
 }
 
+fn approximate_gcd(mut x: f64, mut y: f64) -> f64 {
+    if (x - x.round()).abs() > 1e-9 || (y - y.round()).abs() > 1e-9 {
+        return f64::NAN;
+    }
+    (x, y) = (x.round().abs(), y.round().abs());
+    while y != 0.0 {
+        (x, y) = (y, x % y);
+    }
+    x
+}
+
 fn fmt_binary(
     f: &mut fmt::Formatter,
     x: &Rc<Expression>,
@@ -185,6 +302,11 @@ impl fmt::Display for Expression {
             Expression::Multiply(x, y) => fmt_binary(f, x, y, "*", self.precedence(), true, false),
             Expression::Divide(x, y) => fmt_binary(f, x, y, "/", self.precedence(), false, false),
             Expression::Power(x, y) => fmt_binary(f, x, y, "^", self.precedence(), false, true),
+            Expression::Modulo(x, y) => {
+                fmt_binary(f, x, y, " mod ", self.precedence(), false, false)
+            }
+            Expression::Gcd(x, y) => write!(f, "gcd({x},{y})"),
+            Expression::Lcm(x, y) => write!(f, "lcm({x},{y})"),
             Expression::Sqrt(x, order) => {
                 write!(f, "{}{x}{}", "sqrt(".repeat(*order), ")".repeat(*order))
             }
@@ -195,6 +317,13 @@ impl fmt::Display for Expression {
                     write!(f, "({x})!")
                 }
             }
+            Expression::Subfactorial(x) => {
+                if x.is_number() {
+                    write!(f, "!{x}")
+                } else {
+                    write!(f, "!({x})")
+                }
+            }
         }
     }
 }
@@ -224,7 +353,88 @@ fn fmt_latex_binary(
     lhs + operator + &rhs
 }
 
+fn add_unicode_parens(x: String) -> String {
+    "(".to_string() + &x + ")"
+}
+
+fn fmt_unicode_binary(
+    x: &Rc<Expression>,
+    y: &Rc<Expression>,
+    operator: &str,
+    precedence: i32,
+    abelian: bool,
+    rtl: bool,
+) -> String {
+    let lhs = if x.precedence() < precedence || (x.precedence() == precedence && rtl && !abelian) {
+        add_unicode_parens(x.to_unicode_string())
+    } else {
+        x.to_unicode_string()
+    };
+    let rhs = if y.precedence() < precedence || (y.precedence() == precedence && !rtl && !abelian) {
+        add_unicode_parens(y.to_unicode_string())
+    } else {
+        y.to_unicode_string()
+    };
+    lhs + operator + &rhs
+}
+
 impl Expression {
+    pub fn to_unicode_string(&self) -> String {
+        match self {
+            Expression::Number(x) => x.to_string(),
+            Expression::Negate(x) => {
+                if x.is_add() || x.is_subtract() {
+                    "\u{2212}".to_string() + &add_unicode_parens(x.to_unicode_string())
+                } else {
+                    "\u{2212}".to_string() + &x.to_unicode_string()
+                }
+            }
+            Expression::Add(x, y) => fmt_unicode_binary(x, y, "+", self.precedence(), true, false),
+            Expression::Subtract(x, y) => {
+                fmt_unicode_binary(x, y, "\u{2212}", self.precedence(), false, false)
+            }
+            Expression::Multiply(x, y) => {
+                fmt_unicode_binary(x, y, "\u{d7}", self.precedence(), true, false)
+            }
+            Expression::Divide(x, y) => {
+                fmt_unicode_binary(x, y, "\u{f7}", self.precedence(), false, false)
+            }
+            Expression::Power(x, y) => {
+                fmt_unicode_binary(x, y, "^", self.precedence(), false, true)
+            }
+            Expression::Modulo(x, y) => {
+                fmt_unicode_binary(x, y, " mod ", self.precedence(), false, false)
+            }
+            Expression::Gcd(x, y) => {
+                format!("gcd({}, {})", x.to_unicode_string(), y.to_unicode_string())
+            }
+            Expression::Lcm(x, y) => {
+                format!("lcm({}, {})", x.to_unicode_string(), y.to_unicode_string())
+            }
+            Expression::Sqrt(x, order) => {
+                if x.is_number() || x.is_factorial() || x.is_subfactorial() {
+                    "\u{221a}".repeat(*order) + &x.to_unicode_string()
+                } else {
+                    "\u{221a}".repeat(*order) + &add_unicode_parens(x.to_unicode_string())
+                }
+            }
+            Expression::Factorial(x) => {
+                if x.is_number() {
+                    x.to_unicode_string() + "!"
+                } else {
+                    add_unicode_parens(x.to_unicode_string()) + "!"
+                }
+            }
+            Expression::Subfactorial(x) => {
+                if x.is_number() {
+                    "!".to_string() + &x.to_unicode_string()
+                } else {
+                    "!".to_string() + &add_unicode_parens(x.to_unicode_string())
+                }
+            }
+        }
+    }
+
     pub fn to_latex_string(&self) -> String {
         match self {
             Expression::Number(x) => x.to_string(),
@@ -256,6 +466,19 @@ impl Expression {
                 },
                 y.to_latex_string()
             ),
+            Expression::Modulo(x, y) => {
+                fmt_latex_binary(x, y, "\\bmod ", self.precedence(), false, false)
+            }
+            Expression::Gcd(x, y) => format!(
+                "\\gcd\\left({}, {}\\right)",
+                x.to_latex_string(),
+                y.to_latex_string()
+            ),
+            Expression::Lcm(x, y) => format!(
+                "\\operatorname{{lcm}}\\left({}, {}\\right)",
+                x.to_latex_string(),
+                y.to_latex_string()
+            ),
             Expression::Sqrt(x, order) => {
                 "\\sqrt{".repeat(*order)
                     + x.to_latex_string().as_str()
@@ -268,14 +491,118 @@ impl Expression {
                     add_latex_parens(x.to_latex_string()) + "!"
                 }
             }
+            Expression::Subfactorial(x) => {
+                if x.is_number() {
+                    "{!}".to_string() + &x.to_latex_string()
+                } else {
+                    "{!}".to_string() + &add_latex_parens(x.to_latex_string())
+                }
+            }
         }
     }
 
-    pub fn from_number(x: i64) -> Rc<Expression> {
+    /// Renders the expression as a tree with one node per line, operands below their operation:
+    ///
+    /// ```text
+    /// +
+    /// ├── sqrt(x)
+    /// │   └── 4
+    /// └── x!
+    ///     └── 4
+    /// ```
+    pub fn to_tree_string(&self) -> String {
+        self.to_tree_string_with(Expression::tree_label)
+    }
+
+    /// Like [`to_tree_string`](Self::to_tree_string), with the line of every node given by
+    /// `label`, for instance to annotate subexpressions with their values.
+    pub fn to_tree_string_with(&self, mut label: impl FnMut(&Expression) -> String) -> String {
+        let mut output = String::new();
+        self.write_tree(&mut label, "", None, &mut output);
+        output
+    }
+
+    /// Writes the line of this node after `prefix`, where `last` tells whether the node is the
+    /// last operand of its parent, or `None` for the root, then the lines of its operands.
+    fn write_tree(
+        &self,
+        label: &mut impl FnMut(&Expression) -> String,
+        prefix: &str,
+        last: Option<bool>,
+        output: &mut String,
+    ) {
+        let (connector, indent) = match last {
+            None => ("", ""),
+            Some(false) => ("\u{251c}\u{2500}\u{2500} ", "\u{2502}   "),
+            Some(true) => ("\u{2514}\u{2500}\u{2500} ", "    "),
+        };
+        output.push_str(prefix);
+        output.push_str(connector);
+        output.push_str(&label(self));
+        output.push('\n');
+        let prefix = prefix.to_string() + indent;
+        let operands = self.operands();
+        for (index, operand) in operands.iter().enumerate() {
+            operand.write_tree(label, &prefix, Some(index + 1 == operands.len()), output);
+        }
+    }
+
+    fn tree_label(&self) -> String {
+        match self {
+            Expression::Number(x) => x.to_string(),
+            Expression::Negate(_) => "-x".to_string(),
+            Expression::Add(_, _) => "+".to_string(),
+            Expression::Subtract(_, _) => "-".to_string(),
+            Expression::Multiply(_, _) => "*".to_string(),
+            Expression::Divide(_, _) => "/".to_string(),
+            Expression::Power(_, _) => "^".to_string(),
+            Expression::Modulo(_, _) => "mod".to_string(),
+            Expression::Gcd(_, _) => "gcd".to_string(),
+            Expression::Lcm(_, _) => "lcm".to_string(),
+            Expression::Sqrt(_, 1) => "sqrt(x)".to_string(),
+            Expression::Sqrt(_, order) => format!("sqrt^{order}(x)"),
+            Expression::Factorial(_) => "x!".to_string(),
+            Expression::Subfactorial(_) => "!x".to_string(),
+        }
+    }
+
+    fn operands(&self) -> Vec<&Expression> {
+        match self {
+            Expression::Number(_) => vec![],
+            Expression::Negate(x)
+            | Expression::Sqrt(x, _)
+            | Expression::Factorial(x)
+            | Expression::Subfactorial(x) => vec![x],
+            Expression::Add(x, y)
+            | Expression::Subtract(x, y)
+            | Expression::Multiply(x, y)
+            | Expression::Divide(x, y)
+            | Expression::Power(x, y)
+            | Expression::Modulo(x, y)
+            | Expression::Gcd(x, y)
+            | Expression::Lcm(x, y) => vec![x, y],
+        }
+    }
+
+    /// Iterates over the expression and all of its subexpressions in pre-order: every node
+    /// comes before its operands, and left operands before right ones.
+    #[inline]
+    pub fn iter_subexpressions(&self) -> Nodes<'_> {
+        Nodes { stack: vec![self] }
+    }
+
+    /// Iterates over the operation nodes in the same order as
+    /// [`iter_subexpressions`](Self::iter_subexpressions), skipping the numbers.
+    #[inline]
+    pub fn iter_operations(&self) -> impl Iterator<Item = &Expression> {
+        self.iter_subexpressions().filter(|node| !node.is_number())
+    }
+
+    pub(crate) fn from_number(x: i64) -> Rc<Expression> {
         Rc::new(Expression::Number(x))
     }
 
-    pub fn from_negate(x: Rc<Expression>) -> Rc<Expression> {
+    pub(crate) fn from_negate(x: Rc<Expression>) -> Rc<Expression> {
         if let Some((y, z)) = x.to_subtract() {
             Rc::new(Expression::Subtract(z.clone(), y.clone()))
         } else {
@@ -283,7 +610,7 @@ impl Expression {
         }
     }
 
-    pub fn from_add(x: Rc<Expression>, y: Rc<Expression>) -> Rc<Expression> {
+    pub(crate) fn from_add(x: Rc<Expression>, y: Rc<Expression>) -> Rc<Expression> {
         let x0 = x.to_subtract();
         let y0 = y.to_subtract();
         if let (Some(x), Some(y)) = (x0, y0) {
@@ -311,7 +638,7 @@ impl Expression {
         }
     }
 
-    pub fn from_subtract(x: Rc<Expression>, y: Rc<Expression>) -> Rc<Expression> {
+    pub(crate) fn from_subtract(x: Rc<Expression>, y: Rc<Expression>) -> Rc<Expression> {
         if let Some((y1, y2)) = y.to_subtract() {
             Expression::from_add(x, Rc::new(Expression::Subtract(y2.clone(), y1.clone())))
         } else if let Some((x1, x2)) = x.to_subtract() {
@@ -324,7 +651,7 @@ impl Expression {
         }
     }
 
-    pub fn from_multiply(x: Rc<Expression>, y: Rc<Expression>) -> Rc<Expression> {
+    pub(crate) fn from_multiply(x: Rc<Expression>, y: Rc<Expression>) -> Rc<Expression> {
         let x0 = x.to_divide();
         let y0 = y.to_divide();
         if let (Some(x), Some(y)) = (x0, y0) {
@@ -352,7 +679,7 @@ impl Expression {
         }
     }
 
-    pub fn from_divide(x: Rc<Expression>, y: Rc<Expression>) -> Rc<Expression> {
+    pub(crate) fn from_divide(x: Rc<Expression>, y: Rc<Expression>) -> Rc<Expression> {
         if let Some((y1, y2)) = y.to_divide() {
             Expression::from_multiply(x, Rc::new(Expression::Divide(y2.clone(), y1.clone())))
         } else if let Some((x1, x2)) = x.to_divide() {
@@ -365,7 +692,7 @@ impl Expression {
         }
     }
 
-    pub fn from_power(x: Rc<Expression>, y: Rc<Expression>) -> Rc<Expression> {
+    pub(crate) fn from_power(x: Rc<Expression>, y: Rc<Expression>) -> Rc<Expression> {
         if let Some((x1, x2)) = x.to_power() {
             Rc::new(Expression::Power(
                 x1.clone(),
@@ -381,7 +708,19 @@ impl Expression {
         }
     }
 
-    pub fn from_sqrt(x: Rc<Expression>, order: usize) -> Rc<Expression> {
+    pub(crate) fn from_modulo(x: Rc<Expression>, y: Rc<Expression>) -> Rc<Expression> {
+        Rc::new(Expression::Modulo(x, y))
+    }
+
+    pub(crate) fn from_gcd(x: Rc<Expression>, y: Rc<Expression>) -> Rc<Expression> {
+        Rc::new(Expression::Gcd(x, y))
+    }
+
+    pub(crate) fn from_lcm(x: Rc<Expression>, y: Rc<Expression>) -> Rc<Expression> {
+        Rc::new(Expression::Lcm(x, y))
+    }
+
+    pub(crate) fn from_sqrt(x: Rc<Expression>, order: usize) -> Rc<Expression> {
         if order == 0 {
             x
         } else if let Some((y, z)) = x.to_sqrt() {
@@ -401,7 +740,98 @@ impl Expression {
         }
     }
 
-    pub fn from_factorial(x: Rc<Expression>) -> Rc<Expression> {
+    pub(crate) fn from_factorial(x: Rc<Expression>) -> Rc<Expression> {
         Rc::new(Expression::Factorial(x))
     }
+
+    pub(crate) fn from_subfactorial(x: Rc<Expression>) -> Rc<Expression> {
+        Rc::new(Expression::Subfactorial(x))
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct ExprHandle(Rc<Expression>);
+
+impl ExprHandle {
+    #[inline]
+    pub(crate) fn new(expression: Rc<Expression>) -> Self {
+        Self(expression)
+    }
+
+    #[inline]
+    pub(crate) fn into_inner(self) -> Rc<Expression> {
+        self.0
+    }
+
+    #[inline]
+    pub fn as_expression(&self) -> &Expression {
+        &self.0
+    }
+
+    #[inline]
+    pub fn to_latex_string(&self) -> String {
+        self.0.to_latex_string()
+    }
+
+    #[inline]
+    pub fn to_tree_string(&self) -> String {
+        self.0.to_tree_string()
+    }
+
+    #[inline]
+    pub fn approximate(&self) -> f64 {
+        self.0.approximate()
+    }
+
+    /// Iterates over every node of the expression tree in pre-order.
+    #[inline]
+    pub fn nodes(&self) -> Nodes<'_> {
+        self.0.iter_subexpressions()
+    }
+
+    #[inline]
+    pub fn iter_operations(&self) -> impl Iterator<Item = &Expression> {
+        self.0.iter_operations()
+    }
+}
+
+impl fmt::Display for ExprHandle {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+pub struct Nodes<'a> {
+    stack: Vec<&'a Expression>,
+}
+
+impl<'a> Iterator for Nodes<'a> {
+    type Item = &'a Expression;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        match node {
+            Expression::Number(_) => {}
+            Expression::Negate(x)
+            | Expression::Sqrt(x, _)
+            | Expression::Factorial(x)
+            | Expression::Subfactorial(x) => {
+                self.stack.push(x);
+            }
+            Expression::Add(x, y)
+            | Expression::Subtract(x, y)
+            | Expression::Multiply(x, y)
+            | Expression::Divide(x, y)
+            | Expression::Power(x, y)
+            | Expression::Modulo(x, y)
+            | Expression::Gcd(x, y)
+            | Expression::Lcm(x, y) => {
+                self.stack.push(y);
+                self.stack.push(x);
+            }
+        }
+        Some(node)
+    }
 }
@@ -0,0 +1,46 @@
+use crate::{Domain, Expression, Limits, ParseExpressionError, ProgressiveSolver, Solution};
+use std::fmt;
+
+/// Why [`solve_expression_target`] could not search.
+#[derive(Debug)]
+pub enum ExpressionTargetError {
+    Parse(ParseExpressionError),
+    /// The expression has no exact value, such as `sqrt(2)+sqrt(3)` or `(1/2)!`.
+    Inexact,
+}
+
+impl fmt::Display for ExpressionTargetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExpressionTargetError::Parse(error) => write!(f, "{error}"),
+            ExpressionTargetError::Inexact => write!(f, "target has no exact value"),
+        }
+    }
+}
+
+impl From<ParseExpressionError> for ExpressionTargetError {
+    #[inline]
+    fn from(error: ParseExpressionError) -> Self {
+        ExpressionTargetError::Parse(error)
+    }
+}
+
+/// Solves the target `expression` evaluates to exactly, such as 43 for `3!*7+1`, with the
+/// recommended limits of every domain, and returns the best solution.
+pub fn solve_expression_target(
+    expression: &str,
+    n: i64,
+) -> Result<Option<Solution>, ExpressionTargetError> {
+    let target = Expression::parse(expression)?
+        .evaluate_quadratic()
+        .ok_or(ExpressionTargetError::Inexact)?;
+    let mut solver = ProgressiveSolver::new_quadratic(
+        n,
+        target,
+        None,
+        Limits::recommended(n, Domain::Integral),
+        Limits::recommended(n, Domain::Rational),
+        Limits::recommended(n, Domain::RationalQuadratic),
+    );
+    Ok(solver.run())
+}
@@ -1,18 +1,66 @@
 #![feature(min_specialization)]
+#[cfg(feature = "bigint")]
+mod big_evaluation;
+#[cfg(feature = "bigint")]
+mod big_number;
+mod bitmap;
+mod canonical;
+mod conversion;
+mod cost;
+mod difficulty;
+mod encoding;
+mod evaluation;
 mod expression;
+mod expression_target;
 mod number;
 mod number_theory;
+mod optimizer;
+mod parser;
 mod progressive_solver;
 mod quadratic;
+mod radical;
 mod rational;
+mod rational128;
+mod record_table;
 mod reusable_solver;
+mod sampler;
+mod score;
+mod solution;
 mod solver;
+mod stats;
+#[cfg(feature = "sync")]
+mod sync;
 mod wasm;
 
-pub use expression::Expression;
-pub use number::Number;
-pub use progressive_solver::ProgressiveSolver;
-pub use quadratic::{IntegralQuadratic, RationalQuadratic, PRIMES};
-pub use rational::Rational;
+#[cfg(feature = "bigint")]
+pub use big_number::BigNumber;
+pub use bitmap::Bitmap;
+pub use conversion::OutOfDomainError;
+pub use cost::{estimate_cost, CostEstimate, DepthCost};
+pub use difficulty::{estimate_difficulty, DifficultyEstimate};
+pub use encoding::DecodeExpressionError;
+pub use evaluation::EvaluationError;
+pub use expression::{ExprHandle, Expression, Nodes};
+pub use expression_target::{solve_expression_target, ExpressionTargetError};
+pub use number::{Number, MAX_I128_DIGITS, MAX_RATIONAL128_DIGITS};
+pub use parser::ParseExpressionError;
+pub use progressive_solver::{IntoSolutions, ProgressiveSolver, SolverIterator};
+pub use quadratic::{
+    GenericIntegralQuadratic, GenericRationalQuadratic, IntegralQuadratic, ParseQuadraticError,
+    PrimeBasis, RationalQuadratic, SmallPrimes, MAX_QUADRATIC_POWER, PRIMES,
+};
+pub use radical::Radical;
+pub use rational::{ParseRationalError, Rational};
+pub use rational128::Rational128;
+pub use record_table::{Problem, RecordDiff, RecordTable};
 pub use reusable_solver::ReusableSolver;
-pub use solver::{Limits, Solver};
+pub use sampler::{SampleReport, Sampler};
+pub use score::{Board, Score};
+pub use solution::{Domain, Format, Solution, SolveOutcome};
+pub use solver::{
+    DecodeStateError, FileStateStore, Limits, MemoryStateStore, Operation, Rejection,
+    RejectionReason, SolveIter, Solver, Stage, StageOrder, StateCodec, StateStore,
+};
+pub use stats::{MagnitudeHistogram, Progress, ProgressEvent, SearchPhase, SearchStats};
+#[cfg(feature = "sync")]
+pub use sync::{fetch_records, FetchError};
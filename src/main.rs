@@ -1,56 +1,1593 @@
+mod config;
+#[cfg(feature = "tui")]
+mod dashboard;
+
+use config::{check_limit, set_limit, Config, LIMIT_KEYS, LIMIT_SECTIONS};
+#[cfg(feature = "tui")]
+use dashboard::Dashboard;
+#[cfg(feature = "tui")]
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tchisla_solver::*;
 
-fn parse_problem() -> Option<(i64, i64, bool)> {
-    let args: Vec<String> = env::args().collect();
-    if args.len() < 2 {
-        return None;
+#[derive(Clone, Copy, PartialEq)]
+enum Backend {
+    Integral,
+    Rational,
+    Quadratic,
+    Progressive,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ColorMode {
+    /// Colors when writing to a terminal and `NO_COLOR` is not set.
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Clone)]
+struct Options {
+    n: i64,
+    /// The digits literals are written with: `n` alone unless a digit set is given.
+    digit_set: Vec<i64>,
+    /// Integral for the integral and progressive backends, rational for the rational one.
+    target: RationalQuadratic,
+    backend: Backend,
+    format: Format,
+    /// Print the LaTeX of each solution after the expression in the text formats.
+    latex: bool,
+    verbose: bool,
+    approximate: bool,
+    modular_pruning: bool,
+    goal_lookup: bool,
+    dead_state_pruning: bool,
+    bloom_filter: bool,
+    subfactorial: bool,
+    modulo: bool,
+    gcd_lcm: bool,
+    negation: bool,
+    stage_order: StageOrder,
+    optimize: bool,
+    log: Option<String>,
+    output: Option<String>,
+    /// Where to stream every state the search inserts.
+    trace: Option<String>,
+    quiet: bool,
+    tui: bool,
+    color: ColorMode,
+    dry_run: bool,
+    /// Print the magnitude histograms of the searched depths to stderr after the search.
+    histogram: bool,
+    /// The soft time limit of non-progressive searches.
+    deadline: Option<Duration>,
+    /// The bytes the states of each domain may take before the search stops.
+    memory_budget: Option<usize>,
+    /// Bound integral searches by a solution built from pieces of the target.
+    decompose: bool,
+    /// Alternate integral searches with a top-down split of the target.
+    split: bool,
+    /// Known solutions, one per line, to warm-start progressive searches from.
+    replay: Option<String>,
+    /// Where progressive searches keep their tables between runs.
+    cache_dir: Option<String>,
+    /// Per-`n` limits from the configuration file.
+    config: Config,
+    /// Limits given by flags, as the section they apply to, or `None` for every section, the
+    /// field and the value.
+    limit_flags: Vec<(Option<&'static str>, &'static str, i64)>,
+    /// A file of problems, one `<target>#<n>` per line, or `-` for stdin, solved in place of the
+    /// problem on the command line.
+    batch: Option<String>,
+}
+
+const USAGE: &str = "usage: tchisla_solver <target>#<n>[,<digit>...]|--batch <file> \
+    [--config <file>] \
+    [--backend integral|rational|quadratic|progressive] \
+    [--format ascii|unicode|latex|json|csv] [--latex] \
+    [--verbose] [--approximate] [--modular-pruning] [--goal-lookup] \
+    [--dead-state-pruning] [--bloom-filter] [--subfactorial] [--modulo] \
+    [--gcd-lcm] [--negation] [--stage-order default|target-first] \
+    [--optimize] [--log <file>] \
+    [--output <file>] [--trace <file>] [--quiet] [--tui] \
+    [--color auto|always|never] [--dry-run] [--histogram] \
+    [--deadline <seconds>] [--memory-budget <MiB>] [--decompose] [--split] \
+    [--replay <file>] \
+    [--[integral-|rational-|quadratic-|radical-]max-digits|max-factorial|\
+    max-quadratic-power|max-radical-power|max-subfactorial <value>]
+       tchisla_solver explain <expression> <n>
+       tchisla_solver sample <n> <copies> [<count>] [<seed>]
+       tchisla_solver approximate <value>|pi|e <n> [<tolerance>] [<max-depth>]
+       tchisla_solver score <records.json>
+       tchisla_solver sync <records.json> <url>
+       tchisla_solver completions bash|zsh|fish";
+
+/// Parses `<target>#<n>`, or `<target>#<d1>,<d2>,...` for a digit set, whose first digit
+/// stands for `n`.
+fn parse_problem(problem: &str) -> Option<(Vec<i64>, RationalQuadratic)> {
+    let index = problem.rfind('#')?;
+    let target = problem[..index].parse().ok()?;
+    let digits: Vec<i64> = problem[(index + 1)..]
+        .split(',')
+        .map(|digit| digit.parse().ok())
+        .collect::<Option<_>>()?;
+    let is_digit_set = digits.len() > 1;
+    (!is_digit_set || digits.iter().all(|digit| (1..=9).contains(digit)))
+        .then_some((digits, target))
+}
+
+fn parse_backend(backend: &str) -> Option<Backend> {
+    match backend {
+        "integral" => Some(Backend::Integral),
+        "rational" => Some(Backend::Rational),
+        "quadratic" => Some(Backend::Quadratic),
+        "progressive" => Some(Backend::Progressive),
+        _ => None,
+    }
+}
+
+fn parse_stage_order(order: &str) -> Option<StageOrder> {
+    match order {
+        "default" => Some(StageOrder::DEFAULT),
+        "target-first" => Some(StageOrder::TARGET_FIRST),
+        _ => None,
+    }
+}
+
+fn parse_format(format: &str) -> Option<Format> {
+    match format {
+        "ascii" => Some(Format::Ascii),
+        "unicode" => Some(Format::Unicode),
+        "latex" => Some(Format::Latex),
+        "json" => Some(Format::Json),
+        "csv" => Some(Format::Csv),
+        _ => None,
+    }
+}
+
+/// The file given by `--config`, or else the default configuration file if there is one.
+fn config_path() -> Option<PathBuf> {
+    let mut args = env::args().skip_while(|arg| arg != "--config").skip(1);
+    args.next().map(PathBuf::from).or_else(Config::default_path)
+}
+
+fn parse_color(color: &str) -> Option<ColorMode> {
+    match color {
+        "auto" => Some(ColorMode::Auto),
+        "always" => Some(ColorMode::Always),
+        "never" => Some(ColorMode::Never),
+        _ => None,
+    }
+}
+
+/// The section and field of `--[<section>-]<field>` for a field of [`Limits`], the section
+/// being `None` for flags that apply to every section.
+fn parse_limit_flag(flag: &str) -> Option<(Option<&'static str>, &'static str)> {
+    let name = flag.strip_prefix("--")?;
+    let field = |name: &str| LIMIT_KEYS.iter().copied().find(|&field| field == name);
+    if let Some(field) = field(name) {
+        return Some((None, field));
     }
-    if let Some(index) = args[1].find('#') {
-        let target = args[1][..index].parse();
-        let n = args[1][(index + 1)..].parse();
-        if let (Ok(n), Ok(target)) = (n, target) {
-            let verbose = args.len() > 2 && args[2] == "--verbose";
-            Some((n, target, verbose))
+    let (section, name) = name.split_once('-')?;
+    let section = LIMIT_SECTIONS.iter().copied().find(|&s| s == section)?;
+    Some((Some(section), field(name)?))
+}
+
+/// Options start from the configuration file, and flags override it.
+fn parse_options(config: Config) -> Option<Options> {
+    let mut args = env::args().skip(1).peekable();
+    let problem = match args.peek() {
+        Some(arg) if !arg.starts_with("--") => Some(parse_problem(&args.next()?)?),
+        _ => None,
+    };
+    // Batches replace the problem of every line, so theirs is only a placeholder.
+    let (digit_set, target) = problem
+        .clone()
+        .unwrap_or_else(|| (vec![1], RationalQuadratic::from(1)));
+    let flag = |key: &str| config.get_bool(key).unwrap_or(false);
+    let deadline = match config.get_int("deadline") {
+        Some(seconds) => Some(Duration::from_secs(u64::try_from(seconds).ok()?)),
+        None => None,
+    };
+    let memory_budget = match config.get_int("memory-budget") {
+        Some(mib) => Some(usize::try_from(mib).ok()?.checked_mul(1 << 20)?),
+        None => None,
+    };
+    let mut options = Options {
+        n: digit_set[0],
+        digit_set,
+        target,
+        backend: parse_backend(config.get_str("backend").unwrap_or("progressive"))?,
+        format: parse_format(config.get_str("format").unwrap_or("ascii"))?,
+        latex: flag("latex"),
+        verbose: flag("verbose"),
+        approximate: flag("approximate"),
+        modular_pruning: flag("modular-pruning"),
+        goal_lookup: flag("goal-lookup"),
+        dead_state_pruning: flag("dead-state-pruning"),
+        bloom_filter: flag("bloom-filter"),
+        subfactorial: flag("subfactorial"),
+        modulo: flag("modulo"),
+        gcd_lcm: flag("gcd-lcm"),
+        negation: flag("negation"),
+        stage_order: parse_stage_order(config.get_str("stage-order").unwrap_or("default"))?,
+        optimize: flag("optimize"),
+        log: config.get_str("log").map(str::to_string),
+        output: config.get_str("output").map(str::to_string),
+        trace: config.get_str("trace").map(str::to_string),
+        replay: config.get_str("replay").map(str::to_string),
+        quiet: flag("quiet"),
+        tui: flag("tui"),
+        color: parse_color(config.get_str("color").unwrap_or("auto"))?,
+        dry_run: false,
+        histogram: flag("histogram"),
+        deadline,
+        memory_budget,
+        decompose: flag("decompose"),
+        split: flag("split"),
+        cache_dir: config.get_str("cache-dir").map(str::to_string),
+        config: config.clone(),
+        limit_flags: vec![],
+        batch: None,
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--backend" => options.backend = parse_backend(&args.next()?)?,
+            "--format" => options.format = parse_format(&args.next()?)?,
+            "--latex" => options.latex = true,
+            "--verbose" => options.verbose = true,
+            "--approximate" => options.approximate = true,
+            "--modular-pruning" => options.modular_pruning = true,
+            "--goal-lookup" => options.goal_lookup = true,
+            "--dead-state-pruning" => options.dead_state_pruning = true,
+            "--bloom-filter" => options.bloom_filter = true,
+            "--subfactorial" => options.subfactorial = true,
+            "--modulo" => options.modulo = true,
+            "--gcd-lcm" => options.gcd_lcm = true,
+            "--negation" => options.negation = true,
+            "--stage-order" => options.stage_order = parse_stage_order(&args.next()?)?,
+            "--optimize" => options.optimize = true,
+            "--log" => options.log = Some(args.next()?),
+            "--output" => options.output = Some(args.next()?),
+            "--trace" => options.trace = Some(args.next()?),
+            "--replay" => options.replay = Some(args.next()?),
+            "--quiet" => options.quiet = true,
+            #[cfg(feature = "tui")]
+            "--tui" => options.tui = true,
+            "--color" => options.color = parse_color(&args.next()?)?,
+            "--dry-run" => options.dry_run = true,
+            "--histogram" => options.histogram = true,
+            "--decompose" => options.decompose = true,
+            "--split" => options.split = true,
+            "--batch" => options.batch = Some(args.next()?),
+            "--deadline" => {
+                let seconds = args.next()?.parse().ok()?;
+                options.deadline = Some(Duration::try_from_secs_f64(seconds).ok()?);
+            }
+            "--memory-budget" => {
+                let mib: usize = args.next()?.parse().ok()?;
+                options.memory_budget = Some(mib.checked_mul(1 << 20)?);
+            }
+            // Already read by `config_path`.
+            "--config" => {
+                args.next()?;
+            }
+            flag => {
+                let (section, field) = parse_limit_flag(flag)?;
+                let value = args.next()?.parse().ok()?;
+                // Flags without a section apply to every one.
+                let sections = section.map_or(LIMIT_SECTIONS.to_vec(), |section| vec![section]);
+                for section in sections {
+                    if let Err(expected) = check_limit(section, field, value) {
+                        eprintln!("`{flag}` must be {expected}");
+                        return None;
+                    }
+                }
+                options.limit_flags.push((section, field, value));
+            }
+        }
+    }
+    // Exactly one of a problem and a batch.
+    (problem.is_some() != options.batch.is_some() && is_supported(&options)).then_some(options)
+}
+
+/// Whether the backend of `options` supports its problem and flags.
+fn is_supported(options: &Options) -> bool {
+    let target_in_domain = match options.backend {
+        Backend::Integral => options.target.is_int(),
+        Backend::Rational => options.target.is_rational(),
+        Backend::Quadratic | Backend::Progressive => true,
+    };
+    // Only the progressive backend reports progress for the dashboard and replays solutions,
+    // only the others take a deadline, and only the integral one decomposes or splits targets.
+    let tui_supported =
+        !options.tui || cfg!(feature = "tui") && options.backend == Backend::Progressive;
+    let replay_supported = options.replay.is_none() || options.backend == Backend::Progressive;
+    let deadline_supported = options.deadline.is_none() || options.backend != Backend::Progressive;
+    let decompose_supported =
+        !(options.decompose || options.split) || options.backend == Backend::Integral;
+    // Cost estimates and the progressive backend assume a single `n`.
+    let digit_set_supported =
+        options.digit_set.len() == 1 || !options.dry_run && options.backend != Backend::Progressive;
+    // A batch shares its output between problems, and solves each for real.
+    let batch_supported =
+        options.batch.is_none() || !(options.tui || options.dry_run || options.trace.is_some());
+    target_in_domain
+        && tui_supported
+        && replay_supported
+        && deadline_supported
+        && decompose_supported
+        && digit_set_supported
+        && batch_supported
+}
+
+/// The recommended limits of `domain` with the overrides of the configuration file and then of
+/// the flags, and subfactorials enabled on the same range as factorials when `--subfactorial`
+/// is given.
+fn limits(options: &Options, domain: Domain) -> Limits {
+    let section = match domain {
+        Domain::Integral | Domain::IntegralQuadratic => "integral",
+        Domain::Rational | Domain::Custom => "rational",
+        Domain::RationalQuadratic => "quadratic",
+        Domain::Radical => "radical",
+    };
+    let mut limits =
+        options
+            .config
+            .limits(options.n, section, Limits::recommended(options.n, domain));
+    // Flags of every section first, so that those of this section take precedence.
+    let flags = &options.limit_flags;
+    let every_section = flags
+        .iter()
+        .filter(|(flag_section, ..)| flag_section.is_none());
+    let this_section = flags
+        .iter()
+        .filter(|(flag_section, ..)| *flag_section == Some(section));
+    for &(_, field, value) in every_section.chain(this_section) {
+        set_limit(&mut limits, field, value);
+    }
+    if options.subfactorial {
+        limits.max_subfactorial = limits.max_factorial;
+    }
+    limits
+}
+
+fn format_approximation(x: f64) -> String {
+    if x != 0.0 && !(1e-6..1e15).contains(&x.abs()) {
+        format!("{x:.6e}")
+    } else {
+        format!("{x:.6}")
+    }
+}
+
+/// `n`, or the digits of a digit set separated by commas.
+fn format_digit_set(options: &Options) -> String {
+    let digits: Vec<_> = options.digit_set.iter().map(i64::to_string).collect();
+    digits.join(",")
+}
+
+fn is_text_format(format: Format) -> bool {
+    matches!(format, Format::Ascii | Format::Unicode | Format::Latex)
+}
+
+/// Whether results printed to stdout are colored.
+fn use_color(options: &Options) -> bool {
+    match options.color {
+        ColorMode::Always => true,
+        ColorMode::Never => false,
+        ColorMode::Auto => {
+            options.output.is_none()
+                && io::stdout().is_terminal()
+                && env::var_os("NO_COLOR").is_none()
+        }
+    }
+}
+
+const HIGHLIGHT: &str = "\x1b[1;33m";
+const DIM: &str = "\x1b[2m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+/// Highlights every run of ASCII digits, which in the plain-text formats are the literals one
+/// counts digits in, and dims the operators.
+fn colorize_expression(text: &str) -> String {
+    let mut output = String::new();
+    let mut style = "";
+    for c in text.chars() {
+        let next = if c.is_ascii_digit() {
+            HIGHLIGHT
+        } else if c.is_whitespace() {
+            style
         } else {
-            None
+            DIM
+        };
+        if next != style {
+            if !style.is_empty() {
+                output.push_str(RESET);
+            }
+            output.push_str(next);
+            style = next;
         }
+        output.push(c);
+    }
+    if !style.is_empty() {
+        output.push_str(RESET);
+    }
+    output
+}
+
+/// The expression of `solution` in `format`, colored if `color` and the format is plain text.
+fn format_expression(solution: &Solution, format: Format, color: bool) -> String {
+    let expression = solution.format(format);
+    if color && matches!(format, Format::Ascii | Format::Unicode) {
+        colorize_expression(&expression)
     } else {
-        None
+        expression
     }
 }
 
-fn main() {
-    if let Some((n, target, verbose)) = parse_problem() {
-        println!("{} # {}", target, n);
-        let mut solver = ProgressiveSolver::new(
-            n,
+fn format_progress(solution: &Solution) -> String {
+    match solution.elapsed() {
+        Some(elapsed) => format!(
+            "[{:>10.3}s depth {:>2}] ",
+            elapsed.as_secs_f64(),
+            solution.depth()
+        ),
+        None => format!("[depth {:>2}] ", solution.depth()),
+    }
+}
+
+fn print_solution(
+    out: &mut dyn Write,
+    options: &Options,
+    solution: &Solution,
+    color: bool,
+) -> io::Result<()> {
+    if options.format == Format::Json {
+        // The problem too, as batches interleave the solutions of many. Targets are strings
+        // since they need not be integers.
+        let mut json = solution.to_json();
+        json["n"] = options.n.into();
+        json["target"] = options.target.to_string().into();
+        return writeln!(out, "{json}");
+    } else if options.format == Format::Csv {
+        let row = solution.to_csv_row(options.n, &options.target.to_string());
+        return writeln!(out, "{row}");
+    }
+    let progress = if options.backend == Backend::Progressive {
+        format_progress(solution)
+    } else {
+        String::new()
+    };
+    let digits = if color {
+        format!("{BOLD}{}{RESET}", solution.digits())
+    } else {
+        solution.digits().to_string()
+    };
+    let mut expression = format_expression(solution, options.format, color);
+    if options.latex && options.format != Format::Latex {
+        expression = format!("{expression}\t{}", solution.to_latex_string());
+    }
+    if !is_text_format(options.format) {
+        writeln!(out, "{expression}")
+    } else if options.approximate {
+        writeln!(
+            out,
+            "{progress}{digits}: {expression}\t\u{2248} {}",
+            format_approximation(solution.expression().approximate())
+        )?;
+        write_steps(out, solution, options.format)
+    } else {
+        writeln!(out, "{progress}{digits}: {expression}")
+    }
+}
+
+/// Writes every step of `solution` below its line, each with its approximate value, operands
+/// before the operations on them. Steps shared between operands are written once.
+fn write_steps(out: &mut dyn Write, solution: &Solution, format: Format) -> io::Result<()> {
+    let operations: Vec<_> = solution
+        .expression()
+        .as_expression()
+        .iter_operations()
+        .skip(1)
+        .collect();
+    let mut written = HashSet::new();
+    for step in operations.into_iter().rev() {
+        if !written.insert(step) {
+            continue;
+        }
+        let formatted = match format {
+            Format::Unicode => step.to_unicode_string(),
+            Format::Latex => step.to_latex_string(),
+            _ => step.to_string(),
+        };
+        writeln!(
+            out,
+            "\t{formatted}\t\u{2248} {}",
+            format_approximation(step.approximate())
+        )?;
+    }
+    Ok(())
+}
+
+fn log_solution(log: &mut File, options: &Options, solution: &Solution) -> io::Result<()> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    writeln!(
+        log,
+        "{timestamp}\t{}#{}\t{:.3}\t{}\t{}\t{}",
+        options.target,
+        format_digit_set(options),
+        solution
+            .elapsed()
+            .map_or(0.0, |elapsed| elapsed.as_secs_f64()),
+        solution.depth(),
+        solution.digits(),
+        solution,
+    )
+}
+
+/// Prints `solution` and appends it to the log, reporting failures on stderr.
+fn record_solution(
+    out: &mut dyn Write,
+    log: Option<&mut File>,
+    options: &Options,
+    solution: &Solution,
+    color: bool,
+) {
+    if let Err(error) = print_solution(out, options, solution, color) {
+        eprintln!("cannot write output: {error}");
+    }
+    if let Some(log) = log {
+        if let Err(error) = log_solution(log, options, solution) {
+            eprintln!("cannot write log file: {error}");
+        }
+    }
+}
+
+enum Outcome {
+    Solved,
+    Unsolved,
+    /// The search stopped because no deeper depth can produce new states under the limits.
+    Exhausted,
+    /// The search stopped at its `--deadline`.
+    TimedOut,
+    /// The search stopped at its `--memory-budget`.
+    MemoryExceeded,
+}
+
+/// Writes why no solution was found, to the output alongside the solutions of the text formats,
+/// and to stderr otherwise.
+fn write_outcome(
+    out: &mut dyn Write,
+    options: &Options,
+    quiet: bool,
+    outcome: Outcome,
+) -> io::Result<()> {
+    let message = match outcome {
+        Outcome::Solved => return Ok(()),
+        Outcome::Unsolved => "No solution!",
+        Outcome::Exhausted => "Search space exhausted under current limits",
+        Outcome::TimedOut => "No solution before the deadline",
+        Outcome::MemoryExceeded => "No solution within the memory budget",
+    };
+    if is_text_format(options.format) && (options.output.is_some() || !quiet) {
+        writeln!(out, "{message}")
+    } else {
+        eprintln!("{message}");
+        Ok(())
+    }
+}
+
+/// Prints how many states of each depth fall in each power of ten.
+fn print_histograms(histograms: &[MagnitudeHistogram]) {
+    eprintln!("states by magnitude");
+    for histogram in histograms {
+        let buckets: Vec<_> = histogram
+            .buckets
+            .iter()
+            .map(|(exponent, count)| format!("10^{exponent} {count}"))
+            .collect();
+        eprintln!("  depth {:>2}: {}", histogram.depth, buckets.join(", "));
+    }
+}
+
+fn report_trace_error(result: io::Result<()>) {
+    if let Err(error) = result {
+        eprintln!("cannot write trace file: {error}");
+    }
+}
+
+/// The expressions of a solution file, one per line, optionally after the `<digits>: ` prefix of
+/// the text formats. Lines that do not parse are reported and skipped.
+fn read_solutions(path: &str) -> io::Result<Vec<Expression>> {
+    let mut solutions = vec![];
+    for line in fs::read_to_string(path)?.lines() {
+        let line = line.trim();
+        let line = line
+            .split_once(": ")
+            .filter(|(digits, _)| digits.parse::<usize>().is_ok())
+            .map_or(line, |(_, expression)| expression);
+        if line.is_empty() {
+            continue;
+        }
+        match Expression::parse(line) {
+            Ok(expression) => solutions.push(Rc::unwrap_or_clone(expression)),
+            Err(error) => eprintln!("cannot parse replayed solution {line:?}: {error}"),
+        }
+    }
+    Ok(solutions)
+}
+
+/// Called with the progress events of progressive searches.
+type ProgressCallback = Box<dyn FnMut(&Progress)>;
+
+fn solve(
+    options: &Options,
+    mut report: impl FnMut(&Solution),
+    on_progress: Option<ProgressCallback>,
+    trace: Option<io::BufWriter<File>>,
+    replay: &[Expression],
+) -> Outcome {
+    let n = options.n;
+    let (solution_found, exhausted, timed_out, memory_exceeded) = match options.backend {
+        Backend::Integral => {
+            let mut solver = Solver::<i64>::with_digit_set(
+                &options.digit_set,
+                limits(options, Domain::Integral),
+            );
+            solver.set_modular_pruning(options.modular_pruning);
+            solver.set_goal_lookup(options.goal_lookup);
+            solver.set_dead_state_pruning(options.dead_state_pruning);
+            solver.set_bloom_filter(options.bloom_filter);
+            solver.set_modulo(options.modulo);
+            solver.set_gcd_lcm(options.gcd_lcm);
+            solver.set_negation(options.negation);
+            solver.set_stage_order(options.stage_order);
+            solver.set_soft_deadline(options.deadline);
+            solver.set_memory_budget(options.memory_budget);
+            if let Some(trace) = trace {
+                solver.set_search_trace(trace);
+            }
+            let target = options.target.to_int().unwrap();
+            let solution = if options.decompose {
+                solver.solve_decomposed(target, None)
+            } else if options.split {
+                solver.solve_split(target, None)
+            } else {
+                solver.solve(target, None)
+            };
+            report_trace_error(solver.finish_search_trace());
+            if options.histogram {
+                print_histograms(&solver.magnitude_histograms());
+            }
+            (
+                solution.as_ref().map(&mut report).is_some(),
+                solver.is_exhausted(),
+                solver.deadline_reached(),
+                solver.memory_exceeded(),
+            )
+        }
+        Backend::Rational => {
+            let mut solver = Solver::<Rational>::with_digit_set(
+                &options.digit_set,
+                limits(options, Domain::Rational),
+            );
+            solver.set_bloom_filter(options.bloom_filter);
+            solver.set_modulo(options.modulo);
+            solver.set_gcd_lcm(options.gcd_lcm);
+            solver.set_negation(options.negation);
+            solver.set_stage_order(options.stage_order);
+            solver.set_soft_deadline(options.deadline);
+            solver.set_memory_budget(options.memory_budget);
+            if let Some(trace) = trace {
+                solver.set_search_trace(trace);
+            }
+            let solution = solver.solve(options.target.rational_part(), None);
+            report_trace_error(solver.finish_search_trace());
+            if options.histogram {
+                print_histograms(&solver.magnitude_histograms());
+            }
+            (
+                solution.as_ref().map(&mut report).is_some(),
+                solver.is_exhausted(),
+                solver.deadline_reached(),
+                solver.memory_exceeded(),
+            )
+        }
+        Backend::Quadratic => {
+            let mut solver = Solver::<RationalQuadratic>::with_digit_set(
+                &options.digit_set,
+                limits(options, Domain::RationalQuadratic),
+            );
+            solver.set_bloom_filter(options.bloom_filter);
+            solver.set_modulo(options.modulo);
+            solver.set_gcd_lcm(options.gcd_lcm);
+            solver.set_negation(options.negation);
+            solver.set_stage_order(options.stage_order);
+            solver.set_soft_deadline(options.deadline);
+            solver.set_memory_budget(options.memory_budget);
+            if let Some(trace) = trace {
+                solver.set_search_trace(trace);
+            }
+            let solution = solver.solve(options.target, None);
+            report_trace_error(solver.finish_search_trace());
+            if options.histogram {
+                print_histograms(&solver.magnitude_histograms());
+            }
+            (
+                solution.as_ref().map(&mut report).is_some(),
+                solver.is_exhausted(),
+                solver.deadline_reached(),
+                solver.memory_exceeded(),
+            )
+        }
+        Backend::Progressive => {
+            let mut solver = ProgressiveSolver::new_quadratic(
+                n,
+                options.target,
+                None,
+                limits(options, Domain::Integral),
+                limits(options, Domain::Rational),
+                limits(options, Domain::RationalQuadratic),
+            );
+            solver.set_verbose(options.verbose);
+            solver.set_optimize(options.optimize);
+            solver.set_modular_pruning(options.modular_pruning);
+            solver.set_goal_lookup(options.goal_lookup);
+            solver.set_dead_state_pruning(options.dead_state_pruning);
+            solver.set_bloom_filter(options.bloom_filter);
+            solver.set_modulo(options.modulo);
+            solver.set_gcd_lcm(options.gcd_lcm);
+            solver.set_negation(options.negation);
+            solver.set_stage_order(options.stage_order);
+            solver.set_memory_budget(options.memory_budget);
+            if let Some(trace) = trace {
+                solver.set_search_trace(trace);
+            }
+            if let Some(on_progress) = on_progress {
+                solver.on_progress(on_progress);
+            }
+            let cache = options
+                .cache_dir
+                .as_ref()
+                .map(|dir| Path::new(dir).join(format!("{n}.states")));
+            // A cache built with other settings fails to import, and is replaced.
+            let cached = cache
+                .as_ref()
+                .and_then(|path| fs::read(path).ok())
+                .filter(|bytes| solver.import_states(bytes).is_ok());
+            // Importing states drops queued ones, so solutions are replayed after.
+            solver.replay(replay);
+            let mut solution_found = false;
+            for solution in solver.solve() {
+                solution_found = true;
+                report(&solution);
+            }
+            report_trace_error(solver.finish_search_trace());
+            if options.histogram {
+                print_histograms(&solver.magnitude_histograms());
+            }
+            if let Some(path) = &cache {
+                let bytes = solver.export_states();
+                // Keep the imported tables if they are deeper than those this search built.
+                if cached.is_none_or(|cached| cached.len() < bytes.len()) {
+                    let result = fs::create_dir_all(path.parent().unwrap())
+                        .and_then(|()| fs::write(path, bytes));
+                    if let Err(error) = result {
+                        eprintln!("cannot write cache: {error}");
+                    }
+                }
+            }
+            (
+                solution_found,
+                solver.is_exhausted(),
+                false,
+                solver.memory_exceeded(),
+            )
+        }
+    };
+    if solution_found {
+        Outcome::Solved
+    } else if exhausted {
+        Outcome::Exhausted
+    } else if timed_out {
+        Outcome::TimedOut
+    } else if memory_exceeded {
+        Outcome::MemoryExceeded
+    } else {
+        Outcome::Unsolved
+    }
+}
+
+/// A solver for the integral targets of `options` that keeps its tables for later targets.
+fn reusable_solver(options: &Options) -> ReusableSolver {
+    let mut solver = ReusableSolver::new(
+        options.n,
+        limits(options, Domain::Integral),
+        limits(options, Domain::Rational),
+        limits(options, Domain::RationalQuadratic),
+    );
+    solver.set_modulo(options.modulo);
+    solver.set_gcd_lcm(options.gcd_lcm);
+    solver.set_negation(options.negation);
+    solver.set_memory_budget(options.memory_budget);
+    solver
+}
+
+/// Solves the problems of `path`, or of stdin for `-`, one `<target>#<n>` per line, writing the
+/// results of each problem as soon as they are found. Lines that do not parse or that the
+/// backend does not support are reported and skipped.
+///
+/// The progressive backend shares one [`ReusableSolver`] per `n` between integral targets, so
+/// later problems are answered from the tables earlier ones built, unless solutions are replayed
+/// or tables cached.
+fn run_batch(
+    options: &Options,
+    path: &str,
+    out: &mut dyn Write,
+    mut log: Option<&mut File>,
+    replay: &[Expression],
+    quiet: bool,
+) -> io::Result<()> {
+    let input: Box<dyn BufRead> = if path == "-" {
+        Box::new(io::stdin().lock())
+    } else {
+        Box::new(io::BufReader::new(File::open(path)?))
+    };
+    let reuse =
+        options.backend == Backend::Progressive && replay.is_empty() && options.cache_dir.is_none();
+    let color = use_color(options);
+    let mut solvers = HashMap::new();
+    for line in input.lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let Some((digit_set, target)) = parse_problem(line) else {
+            eprintln!("cannot parse problem {line:?}");
+            continue;
+        };
+        let problem = Options {
+            n: digit_set[0],
+            digit_set,
             target,
-            None,
-            Limits {
-                max_digits: 48,
-                max_factorial: 20,
-                max_quadratic_power: 0,
-            },
-            Limits {
-                max_digits: 30,
-                max_factorial: 12,
-                max_quadratic_power: 0,
-            },
-            Limits {
-                max_digits: 20,
-                max_factorial: 9,
-                max_quadratic_power: if n == 7 { 3 } else { 2 },
+            ..options.clone()
+        };
+        if !is_supported(&problem) {
+            eprintln!("unsupported problem {line:?}");
+            continue;
+        }
+        if is_text_format(problem.format) {
+            writeln!(out, "{} # {}", problem.target, format_digit_set(&problem))?;
+        }
+        let mut best = None;
+        let mut report = |solution: &Solution| {
+            record_solution(out, log.as_deref_mut(), &problem, solution, color);
+            best = Some(format_expression(solution, problem.format, color));
+        };
+        let outcome = match problem.target.to_int().filter(|_| reuse) {
+            Some(target) => {
+                let solver = solvers
+                    .entry(problem.n)
+                    .or_insert_with(|| reusable_solver(&problem));
+                match solver.solve(target, None) {
+                    Some(solution) => {
+                        report(&solution);
+                        Outcome::Solved
+                    }
+                    None if solver.is_exhausted() => Outcome::Exhausted,
+                    None if solver.memory_exceeded() => Outcome::MemoryExceeded,
+                    None => Outcome::Unsolved,
+                }
+            }
+            None => solve(&problem, &mut report, None, None, replay),
+        };
+        write_outcome(out, &problem, quiet, outcome)?;
+        out.flush()?;
+        if let Some(best) = best.filter(|_| quiet) {
+            println!("{best}");
+        }
+    }
+    Ok(())
+}
+
+/// The depth [`dry_run`] estimates for targets whose digits cannot be predicted.
+const DRY_RUN_DEPTH: usize = 8;
+
+fn print_cost(name: &str, estimate: &CostEstimate) {
+    const MIB: f64 = (1 << 20) as f64;
+    println!("{name}");
+    println!("  depth        states         pairs    memory");
+    for depth in &estimate.depths {
+        println!(
+            "  {:>5}{} {:>13} {:>13} {:>7.1} MiB",
+            depth.depth,
+            if depth.measured { ' ' } else { '~' },
+            depth.states,
+            depth.pairs,
+            depth.memory as f64 / MIB
+        );
+    }
+    println!(
+        "  total  {:>13} {:>13} {:>7.1} MiB",
+        estimate.total_states(),
+        estimate.total_pairs(),
+        estimate.total_memory() as f64 / MIB
+    );
+}
+
+/// Prints the estimated cost of the search `options` describe, to the predicted digits of an
+/// integral target. Depths marked `~` are extrapolated.
+fn dry_run(options: &Options) {
+    let n = options.n;
+    let depth = match options.target.to_int() {
+        Some(target) => {
+            let digits = estimate_difficulty(n, target).digits;
+            println!("{} # {n}: about {digits} digits", options.target);
+            digits
+        }
+        None => DRY_RUN_DEPTH,
+    };
+    if matches!(options.backend, Backend::Integral | Backend::Progressive) {
+        let estimate = estimate_cost::<i64>(n, limits(options, Domain::Integral), depth);
+        print_cost("integral", &estimate);
+    }
+    if matches!(options.backend, Backend::Rational | Backend::Progressive) {
+        let estimate = estimate_cost::<Rational>(n, limits(options, Domain::Rational), depth);
+        print_cost("rational", &estimate);
+    }
+    if matches!(options.backend, Backend::Quadratic | Backend::Progressive) {
+        let limits = limits(options, Domain::RationalQuadratic);
+        print_cost(
+            "quadratic",
+            &estimate_cost::<RationalQuadratic>(n, limits, depth),
+        );
+    }
+}
+
+fn explain(mut args: impl Iterator<Item = String>) -> Option<()> {
+    let input = args.next()?;
+    let n = args.next()?.parse().ok()?;
+    if args.next().is_some() {
+        return None;
+    }
+    match Expression::parse(&input) {
+        // Every subexpression with its exact value, digit usage and approximate magnitude.
+        Ok(expression) => print!(
+            "{}",
+            expression.to_tree_string_with(|node| {
+                let value = match node.try_evaluate_rational() {
+                    Ok(x) => x.to_string(),
+                    // Irrational operands may still give a rational or quadratic value.
+                    Err(EvaluationError::Irrational) => node
+                        .evaluate_quadratic()
+                        .map_or_else(|| "irrational".to_string(), |x| x.to_string()),
+                    Err(error) => error.to_string(),
+                };
+                format!(
+                    "{node}\t= {value}\t{} digits\t\u{2248} {}",
+                    node.digit_count(n),
+                    format_approximation(node.approximate())
+                )
+            })
+        ),
+        Err(error) => eprintln!("cannot parse expression: {error}"),
+    }
+    Some(())
+}
+
+/// Values of the distribution [`sample`] prints.
+const SAMPLE_VALUES: usize = 20;
+
+/// Prints the most frequent values among random expressions with `copies` copies of `n`, and
+/// every sample the evaluator disagrees with.
+fn sample(mut args: impl Iterator<Item = String>) -> Option<()> {
+    let n = args.next()?.parse().ok()?;
+    let copies = args.next()?.parse().ok()?;
+    let count = args
+        .next()
+        .map_or(Some(10000), |count| count.parse().ok())?;
+    let seed = args.next().map_or(Some(1), |seed| seed.parse().ok())?;
+    if args.next().is_some() || copies == 0 {
+        return None;
+    }
+    let mut sampler = Sampler::new(n, Limits::recommended(n, Domain::Rational), seed);
+    let report = sampler.sample_many(copies, count);
+    println!(
+        "{} of {} samples valid, {} distinct values",
+        report.samples(),
+        report.attempts,
+        report.values.len()
+    );
+    for (value, frequency) in report.values.iter().take(SAMPLE_VALUES) {
+        println!("{value}\t{frequency}");
+    }
+    for (expression, value) in &report.mismatches {
+        println!("mismatch: {expression} computed as {value}");
+    }
+    Some(())
+}
+
+/// Approximations [`approximate`] prints.
+const APPROXIMATIONS: usize = 10;
+
+/// Parses a real target: a decimal number, `pi` or `e`.
+fn parse_real(x: &str) -> Option<f64> {
+    match x {
+        "pi" => Some(std::f64::consts::PI),
+        "e" => Some(std::f64::consts::E),
+        _ => x.parse().ok().filter(|x: &f64| x.is_finite()),
+    }
+}
+
+/// Prints the best approximations of a real target among quadratic states, with their digits
+/// and errors.
+fn approximate(mut args: impl Iterator<Item = String>) -> Option<()> {
+    let target = parse_real(&args.next()?)?;
+    let n = args.next()?.parse().ok()?;
+    let tolerance = args
+        .next()
+        .map_or(Some(1e-6), |tolerance| tolerance.parse().ok())?;
+    let max_depth = match args.next() {
+        Some(depth) => Some(depth.parse().ok()?),
+        None => None,
+    };
+    if args.next().is_some() || tolerance < 0.0 {
+        return None;
+    }
+    let mut solver =
+        Solver::<RationalQuadratic>::new(n, Limits::recommended(n, Domain::RationalQuadratic));
+    let approximations = solver.solve_approximate(target, tolerance, max_depth);
+    if approximations.is_empty() {
+        println!("no approximation within {tolerance:e}");
+    }
+    for solution in approximations.iter().take(APPROXIMATIONS) {
+        let value = solution.expression().approximate();
+        println!(
+            "{}\t{solution}\t\u{2248} {}\terror {:.3e}",
+            solution.digits(),
+            format_approximation(value),
+            (value - target).abs()
+        );
+    }
+    Some(())
+}
+
+/// Prints the board scores of a record table exported as JSON: the targets solved and digits
+/// used for every `n`, then the totals.
+fn score(mut args: impl Iterator<Item = String>) -> Option<()> {
+    let path = args.next()?;
+    if args.next().is_some() {
+        return None;
+    }
+    let table = match fs::read_to_string(&path).map(|json| RecordTable::from_json(&json)) {
+        Ok(Ok(table)) => table,
+        Ok(Err(error)) => {
+            eprintln!("cannot parse record table: {error}");
+            return Some(());
+        }
+        Err(error) => {
+            eprintln!("cannot read record table: {error}");
+            return Some(());
+        }
+    };
+    let score = table.score();
+    println!("n\tsolved\tdigits");
+    for board in &score.boards {
+        println!("{}\t{}\t{}", board.n, board.solved(), board.digits());
+    }
+    println!("total\t{}\t{}", score.solved(), score.digits());
+    Some(())
+}
+
+/// Merges the records published at a URL into a local record table, creating it if needed, and
+/// lists the problems where each side has the better solution.
+fn sync(mut args: impl Iterator<Item = String>) -> Option<()> {
+    let path = args.next()?;
+    let url = args.next()?;
+    if args.next().is_some() {
+        return None;
+    }
+    #[cfg(feature = "sync")]
+    {
+        let mut table = match fs::read_to_string(&path) {
+            Ok(json) => match RecordTable::from_json(&json) {
+                Ok(table) => table,
+                Err(error) => {
+                    eprintln!("cannot parse record table: {error}");
+                    return Some(());
+                }
             },
+            Err(error) if error.kind() == io::ErrorKind::NotFound => RecordTable::new(),
+            Err(error) => {
+                eprintln!("cannot read record table: {error}");
+                return Some(());
+            }
+        };
+        let diff = match table.sync(&url) {
+            Ok(diff) => diff,
+            Err(error) => {
+                eprintln!("cannot fetch records: {error}");
+                return Some(());
+            }
+        };
+        let list = |label: &str, problems: &[Problem]| {
+            let problems: Vec<_> = problems.iter().map(Problem::to_string).collect();
+            println!("{label} ({}): {}", problems.len(), problems.join(" "));
+        };
+        list("better than published", &diff.better);
+        list("only local", &diff.added);
+        list("worse than published", &diff.worse);
+        list("only published", &diff.missing);
+        if let Err(error) = fs::write(&path, table.to_json()) {
+            eprintln!("cannot write record table: {error}");
+        }
+    }
+    #[cfg(not(feature = "sync"))]
+    eprintln!("cannot sync {path} with {url}: built without the sync feature");
+    Some(())
+}
+
+/// How the value after a flag completes.
+enum FlagValue {
+    None,
+    /// A free-form value, such as a number, with nothing to complete.
+    Value,
+    File,
+    Choices(&'static [&'static str]),
+}
+
+/// A command-line flag, as offered by shell completions.
+struct Flag {
+    name: &'static str,
+    description: &'static str,
+    value: FlagValue,
+}
+
+const FLAGS: &[Flag] = &[
+    Flag {
+        name: "config",
+        description: "read settings from a TOML or JSON file",
+        value: FlagValue::File,
+    },
+    Flag {
+        name: "backend",
+        description: "solver to use",
+        value: FlagValue::Choices(&["integral", "rational", "quadratic", "progressive"]),
+    },
+    Flag {
+        name: "format",
+        description: "output format",
+        value: FlagValue::Choices(&["ascii", "unicode", "latex", "json", "csv"]),
+    },
+    Flag {
+        name: "latex",
+        description: "print the LaTeX of each solution beside its expression",
+        value: FlagValue::None,
+    },
+    Flag {
+        name: "verbose",
+        description: "report each completed depth",
+        value: FlagValue::None,
+    },
+    Flag {
+        name: "approximate",
+        description: "print approximate values of the solution and its steps",
+        value: FlagValue::None,
+    },
+    Flag {
+        name: "modular-pruning",
+        description: "prune the final depth by residues",
+        value: FlagValue::None,
+    },
+    Flag {
+        name: "goal-lookup",
+        description: "look up partners of the target at the final depth",
+        value: FlagValue::None,
+    },
+    Flag {
+        name: "dead-state-pruning",
+        description: "skip states too large to reach the target at the final depth",
+        value: FlagValue::None,
+    },
+    Flag {
+        name: "bloom-filter",
+        description: "filter candidates before the state map",
+        value: FlagValue::None,
+    },
+    Flag {
+        name: "subfactorial",
+        description: "allow subfactorials",
+        value: FlagValue::None,
+    },
+    Flag {
+        name: "modulo",
+        description: "allow the modulo operation",
+        value: FlagValue::None,
+    },
+    Flag {
+        name: "gcd-lcm",
+        description: "allow gcd and lcm",
+        value: FlagValue::None,
+    },
+    Flag {
+        name: "negation",
+        description: "allow negation and negative targets",
+        value: FlagValue::None,
+    },
+    Flag {
+        name: "stage-order",
+        description: "the order of the stages of each depth",
+        value: FlagValue::Choices(&["default", "target-first"]),
+    },
+    Flag {
+        name: "optimize",
+        description: "shorten solutions after they are found",
+        value: FlagValue::None,
+    },
+    Flag {
+        name: "log",
+        description: "append solutions to a log file",
+        value: FlagValue::File,
+    },
+    Flag {
+        name: "output",
+        description: "write results to a file",
+        value: FlagValue::File,
+    },
+    Flag {
+        name: "trace",
+        description: "stream every state the search inserts to a file",
+        value: FlagValue::File,
+    },
+    Flag {
+        name: "replay",
+        description: "warm-start progressive searches from a file of known solutions",
+        value: FlagValue::File,
+    },
+    Flag {
+        name: "quiet",
+        description: "print only the final expression",
+        value: FlagValue::None,
+    },
+    Flag {
+        name: "tui",
+        description: "show a live dashboard, with the tui feature",
+        value: FlagValue::None,
+    },
+    Flag {
+        name: "dry-run",
+        description: "estimate the cost of the search without running it",
+        value: FlagValue::None,
+    },
+    Flag {
+        name: "histogram",
+        description: "print how state magnitudes spread over each depth",
+        value: FlagValue::None,
+    },
+    Flag {
+        name: "deadline",
+        description: "stop non-progressive searches after this many seconds",
+        value: FlagValue::Value,
+    },
+    Flag {
+        name: "memory-budget",
+        description: "stop the search once the states of a domain take this many MiB",
+        value: FlagValue::Value,
+    },
+    Flag {
+        name: "decompose",
+        description: "bound integral searches by decomposing the target",
+        value: FlagValue::None,
+    },
+    Flag {
+        name: "split",
+        description: "alternate integral searches with a top-down split of the target",
+        value: FlagValue::None,
+    },
+    Flag {
+        name: "batch",
+        description: "solve the problems of a file, one per line, or of stdin for -",
+        value: FlagValue::File,
+    },
+    Flag {
+        name: "max-digits",
+        description: "limit the binary digits of numbers in every phase",
+        value: FlagValue::Value,
+    },
+    Flag {
+        name: "max-factorial",
+        description: "limit the largest factorial in every phase",
+        value: FlagValue::Value,
+    },
+    Flag {
+        name: "max-quadratic-power",
+        description: "limit the nesting of square roots in every phase",
+        value: FlagValue::Value,
+    },
+    Flag {
+        name: "max-radical-power",
+        description: "limit the degree of roots in every phase",
+        value: FlagValue::Value,
+    },
+    Flag {
+        name: "max-subfactorial",
+        description: "limit the largest subfactorial in every phase",
+        value: FlagValue::Value,
+    },
+    Flag {
+        name: "color",
+        description: "color the output",
+        value: FlagValue::Choices(&["auto", "always", "never"]),
+    },
+];
+
+const SUBCOMMANDS: &[&str] = &[
+    "explain",
+    "sample",
+    "approximate",
+    "score",
+    "sync",
+    "completions",
+];
+
+const SHELLS: &[&str] = &["bash", "zsh", "fish"];
+
+fn bash_completions() -> String {
+    let mut values = String::new();
+    for flag in FLAGS {
+        let reply = match flag.value {
+            FlagValue::None => continue,
+            FlagValue::Value => "COMPREPLY=()".to_string(),
+            FlagValue::File => "COMPREPLY=($(compgen -f -- \"$cur\"))".to_string(),
+            FlagValue::Choices(choices) => format!(
+                "COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))",
+                choices.join(" ")
+            ),
+        };
+        values += &format!(
+            "        --{})\n            {reply}\n            return\n            ;;\n",
+            flag.name
         );
-        solver.set_verbose(verbose);
-        let mut solution_found = false;
-        for (expression, digits) in solver.solve() {
-            solution_found = true;
-            println!("{}: {}", digits, expression);
+    }
+    let flags: Vec<_> = FLAGS
+        .iter()
+        .map(|flag| format!("--{}", flag.name))
+        .collect();
+    format!(
+        "_tchisla_solver() {{
+    local cur=\"${{COMP_WORDS[COMP_CWORD]}}\"
+    local prev=\"${{COMP_WORDS[COMP_CWORD-1]}}\"
+    case \"$prev\" in
+{values}    esac
+    if [[ $COMP_CWORD -eq 1 ]]; then
+        COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))
+    elif [[ ${{COMP_WORDS[1]}} == completions ]]; then
+        COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))
+    elif [[ ${{COMP_WORDS[1]}} == score || ${{COMP_WORDS[1]}} == sync ]]; then
+        COMPREPLY=($(compgen -f -- \"$cur\"))
+    elif [[ ${{COMP_WORDS[1]}} != explain && ${{COMP_WORDS[1]}} != sample ]]; then
+        COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))
+    fi
+}}
+complete -F _tchisla_solver tchisla_solver
+",
+        SUBCOMMANDS.join(" "),
+        SHELLS.join(" "),
+        flags.join(" ")
+    )
+}
+
+fn zsh_completions() -> String {
+    let mut arguments = String::new();
+    for flag in FLAGS {
+        let value = match flag.value {
+            FlagValue::None => String::new(),
+            FlagValue::Value => format!(":{}:", flag.name),
+            FlagValue::File => ":file:_files".to_string(),
+            FlagValue::Choices(choices) => format!(":{}:({})", flag.name, choices.join(" ")),
+        };
+        arguments += &format!(
+            "        '--{}[{}]{value}' \\\n",
+            flag.name, flag.description
+        );
+    }
+    format!(
+        "#compdef tchisla_solver
+
+if (( CURRENT == 2 )); then
+    _alternative 'subcommands:subcommand:({})' 'problems:target#n:'
+elif [[ $words[2] == completions ]]; then
+    _values shell {}
+elif [[ $words[2] == score || $words[2] == sync ]]; then
+    _files
+elif [[ $words[2] != explain && $words[2] != sample ]]; then
+    _arguments \\
+{arguments}        '*: :'
+fi
+",
+        SUBCOMMANDS.join(" "),
+        SHELLS.join(" ")
+    )
+}
+
+fn fish_completions() -> String {
+    let mut lines = vec![
+        "complete -c tchisla_solver -f".to_string(),
+        format!(
+            "complete -c tchisla_solver -n __fish_use_subcommand -a \"{}\"",
+            SUBCOMMANDS.join(" ")
+        ),
+        format!(
+            "complete -c tchisla_solver -n \"__fish_seen_subcommand_from completions\" -a \"{}\"",
+            SHELLS.join(" ")
+        ),
+        "complete -c tchisla_solver -n \"__fish_seen_subcommand_from score sync\" -F".to_string(),
+    ];
+    for flag in FLAGS {
+        let value = match flag.value {
+            FlagValue::None => String::new(),
+            FlagValue::Value => " -x".to_string(),
+            FlagValue::File => " -r -F".to_string(),
+            FlagValue::Choices(choices) => format!(" -x -a \"{}\"", choices.join(" ")),
+        };
+        lines.push(format!(
+            "complete -c tchisla_solver -n \"not __fish_seen_subcommand_from {}\" -l {} -d \"{}\"{value}",
+            SUBCOMMANDS.join(" "),
+            flag.name,
+            flag.description
+        ));
+    }
+    lines.join("\n") + "\n"
+}
+
+/// Prints a completion script for the given shell.
+fn completions(mut args: impl Iterator<Item = String>) -> Option<()> {
+    let script = match args.next()?.as_str() {
+        "bash" => bash_completions(),
+        "zsh" => zsh_completions(),
+        "fish" => fish_completions(),
+        _ => return None,
+    };
+    if args.next().is_some() {
+        return None;
+    }
+    print!("{script}");
+    Some(())
+}
+
+fn main() {
+    let subcommand = match env::args().nth(1).as_deref() {
+        Some("explain") => Some(explain(env::args().skip(2))),
+        Some("sample") => Some(sample(env::args().skip(2))),
+        Some("approximate") => Some(approximate(env::args().skip(2))),
+        Some("score") => Some(score(env::args().skip(2))),
+        Some("sync") => Some(sync(env::args().skip(2))),
+        Some("completions") => Some(completions(env::args().skip(2))),
+        _ => None,
+    };
+    if let Some(result) = subcommand {
+        if result.is_none() {
+            eprintln!("{USAGE}");
+        }
+        return;
+    }
+    let config = match config_path().map(|path| Config::load(&path)) {
+        Some(Ok(config)) => config,
+        Some(Err(error)) => {
+            eprintln!("cannot load config: {error}");
+            return;
+        }
+        None => Config::default(),
+    };
+    let Some(options) = parse_options(config) else {
+        eprintln!("{USAGE}");
+        return;
+    };
+    if options.dry_run {
+        dry_run(&options);
+        return;
+    }
+    // Results go to the output file if one is given, and otherwise to stdout unless `--quiet`
+    // reserves stdout for the final expression alone. The dashboard implies `--quiet`, since
+    // interleaved output would break its redrawing.
+    let quiet = options.quiet || options.tui;
+    let mut out: Box<dyn Write> = match &options.output {
+        Some(path) => match File::create(path) {
+            Ok(file) => Box::new(io::BufWriter::new(file)),
+            Err(error) => {
+                eprintln!("cannot open output file: {error}");
+                return;
+            }
+        },
+        None if quiet => Box::new(io::sink()),
+        None => Box::new(io::stdout()),
+    };
+    // Batches write a header of their own for each problem.
+    let header = if is_text_format(options.format) && options.batch.is_none() {
+        writeln!(out, "{} # {}", options.target, format_digit_set(&options))
+    } else {
+        Ok(())
+    };
+    let header = header.and_then(|()| match options.format.header() {
+        Some(header) => writeln!(out, "{header}"),
+        None => Ok(()),
+    });
+    if let Err(error) = header {
+        eprintln!("cannot write output: {error}");
+        return;
+    }
+    let mut log = match options
+        .log
+        .as_deref()
+        .map(|path| OpenOptions::new().create(true).append(true).open(path))
+    {
+        Some(Ok(log)) => Some(log),
+        Some(Err(error)) => {
+            eprintln!("cannot open log file: {error}");
+            return;
+        }
+        None => None,
+    };
+    let trace = match options.trace.as_deref().map(File::create) {
+        Some(Ok(file)) => Some(io::BufWriter::new(file)),
+        Some(Err(error)) => {
+            eprintln!("cannot open trace file: {error}");
+            return;
+        }
+        None => None,
+    };
+    let replay = match options.replay.as_deref().map(read_solutions) {
+        Some(Ok(replay)) => replay,
+        Some(Err(error)) => {
+            eprintln!("cannot read replay file: {error}");
+            return;
         }
-        if !solution_found {
-            println!("No solution!");
+        None => vec![],
+    };
+    if let Some(path) = &options.batch {
+        if let Err(error) = run_batch(&options, path, &mut out, log.as_mut(), &replay, quiet) {
+            eprintln!("cannot run batch: {error}");
         }
+        return;
+    }
+    #[cfg(feature = "tui")]
+    let dashboard = options
+        .tui
+        .then(|| Rc::new(RefCell::new(Dashboard::new(&options))));
+    #[cfg(feature = "tui")]
+    let on_progress = dashboard.clone().map(|dashboard| {
+        Box::new(move |progress: &Progress| dashboard.borrow_mut().update_progress(progress))
+            as ProgressCallback
+    });
+    #[cfg(not(feature = "tui"))]
+    let on_progress = None;
+    let color = use_color(&options);
+    let mut best = None;
+    let report = |solution: &Solution| {
+        #[cfg(feature = "tui")]
+        if let Some(dashboard) = &dashboard {
+            dashboard.borrow_mut().update_solution(solution);
+        }
+        record_solution(&mut out, log.as_mut(), &options, solution, color);
+        best = Some(format_expression(solution, options.format, color));
+    };
+    let outcome = solve(&options, report, on_progress, trace, &replay);
+    let result = write_outcome(&mut out, &options, quiet, outcome);
+    if let Err(error) = result.and_then(|()| out.flush()) {
+        eprintln!("cannot write output: {error}");
+    }
+    if let Some(best) = best.filter(|_| quiet) {
+        println!("{best}");
     }
 }
@@ -1,16 +1,68 @@
-use crate::Rational;
-use num::{Num, Signed};
+#[cfg(feature = "bigint")]
+use crate::big_evaluation::try_sqrt;
+#[cfg(feature = "bigint")]
+use crate::big_number::bits;
+use crate::number_theory::power_within_u128;
+#[cfg(feature = "bigint")]
+use crate::BigNumber;
+use crate::{Domain, Limits, Rational, Rational128};
+#[cfg(feature = "bigint")]
+use num::traits::Pow;
+#[cfg(feature = "bigint")]
+use num::{BigRational, ToPrimitive};
+use num::{Num, Signed, Zero};
 use std::fmt::Display;
 use std::hash::Hash;
 use std::ops::Neg;
 
-pub trait Number: Copy + Display + Eq + Hash + Num + Signed + Neg + From<i64> {
+/// A domain of numbers the [`Solver`](crate::Solver) searches.
+///
+/// States are deduplicated by value, so equal numbers must compare and hash equal whatever
+/// expression produced them. Concatenations of `n` and the results of integral operations are
+/// converted with `From<i64>`; factorials, subfactorials, `x! / y!`, modulo, gcd and lcm apply to
+/// the values whose [`to_int`](Self::to_int) is `Some`.
+///
+/// The domains of this crate have specialized search code. Any other implementation is searched
+/// with the addition, subtraction and multiplication of [`Num`], the integral operations, and the
+/// optional operations below, which are all left out by default. Such domains should use
+/// [`Domain::Custom`].
+pub trait Number: Copy + Display + Eq + Hash + Num + Signed + Neg + From<i64> + 'static {
+    const DOMAIN: Domain;
+
     fn to_int(self) -> Option<i64>;
     fn is_int(self) -> bool;
     fn is_rational(self) -> bool;
+    fn to_f64(self) -> f64;
+
+    /// `self / rhs`, if the quotient lies in the domain. `rhs` is never zero.
+    #[inline]
+    fn checked_divide(self, _rhs: Self) -> Option<Self> {
+        None
+    }
+
+    /// `self` raised to `exponent`, if the power lies in the domain and its magnitude has at
+    /// most `max_digits` binary digits.
+    #[inline]
+    fn checked_power(self, _exponent: Self, _max_digits: usize) -> Option<Self> {
+        None
+    }
+
+    /// The square root of `self`, if it lies in the domain.
+    #[inline]
+    fn checked_sqrt(self) -> Option<Self> {
+        None
+    }
+
+    /// Whether `self` is small enough to be kept as a state under `limits`.
+    #[inline]
+    fn in_range(&self, _limits: &Limits) -> bool {
+        true
+    }
 }
 
 impl Number for i64 {
+    const DOMAIN: Domain = Domain::Integral;
+
     #[inline]
     fn to_int(self) -> Option<i64> {
         Some(self)
@@ -25,9 +77,16 @@ impl Number for i64 {
     fn is_rational(self) -> bool {
         true
     }
+
+    #[inline]
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
 }
 
 impl Number for Rational {
+    const DOMAIN: Domain = Domain::Rational;
+
     #[inline]
     fn to_int(self) -> Option<i64> {
         if self.is_integer() {
@@ -46,4 +105,198 @@ impl Number for Rational {
     fn is_rational(self) -> bool {
         true
     }
+
+    #[inline]
+    fn to_f64(self) -> f64 {
+        Rational::to_f64(&self)
+    }
+}
+
+/// The most binary digits of an `i128` state whatever the limits, so that the sum of two
+/// states still fits.
+pub const MAX_I128_DIGITS: usize = 125;
+
+/// The most binary digits of the numerator and denominator of a [`Rational128`] state whatever
+/// the limits, so that the cross products of a sum of two states still fit.
+pub const MAX_RATIONAL128_DIGITS: usize = 62;
+
+/// Wide integers, for integral searches with `max_digits` beyond what `i64` holds. Only values
+/// that fit in `i64` take factorials, modulo, gcd and lcm, and literals stay within `i64`.
+impl Number for i128 {
+    const DOMAIN: Domain = Domain::Integral;
+
+    #[inline]
+    fn to_int(self) -> Option<i64> {
+        i64::try_from(self).ok()
+    }
+
+    #[inline]
+    fn is_int(self) -> bool {
+        true
+    }
+
+    #[inline]
+    fn is_rational(self) -> bool {
+        true
+    }
+
+    #[inline]
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+
+    #[inline]
+    fn checked_divide(self, rhs: Self) -> Option<Self> {
+        (self % rhs == 0).then(|| self / rhs)
+    }
+
+    #[inline]
+    fn checked_power(self, exponent: Self, max_digits: usize) -> Option<Self> {
+        let exponent = u32::try_from(exponent).ok()?;
+        power_within_u128(
+            self.unsigned_abs(),
+            exponent,
+            max_digits.min(MAX_I128_DIGITS),
+        )
+        .then(|| self.pow(exponent))
+    }
+
+    #[inline]
+    fn checked_sqrt(self) -> Option<Self> {
+        self.checked_isqrt().filter(|root| root * root == self)
+    }
+
+    #[inline]
+    fn in_range(&self, limits: &Limits) -> bool {
+        self.unsigned_abs() <= 1 << limits.max_digits.min(MAX_I128_DIGITS)
+    }
+}
+
+/// Searched with the operations of [`Number`], rationals with 128-bit parts.
+impl Number for Rational128 {
+    const DOMAIN: Domain = Domain::Rational;
+
+    #[inline]
+    fn to_int(self) -> Option<i64> {
+        if self.is_integer() {
+            i64::try_from(self.numerator()).ok()
+        } else {
+            None
+        }
+    }
+
+    /// Integers beyond `i64` count as not integral, as they take no factorials.
+    #[inline]
+    fn is_int(self) -> bool {
+        self.to_int().is_some()
+    }
+
+    #[inline]
+    fn is_rational(self) -> bool {
+        true
+    }
+
+    #[inline]
+    fn to_f64(self) -> f64 {
+        Rational128::to_f64(&self)
+    }
+
+    #[inline]
+    fn checked_divide(self, rhs: Self) -> Option<Self> {
+        Some(self / rhs)
+    }
+
+    /// Integral exponents only, negative ones taking the reciprocal.
+    fn checked_power(self, exponent: Self, max_digits: usize) -> Option<Self> {
+        let exponent = exponent.to_int()?;
+        if exponent < 0 && self.is_zero() {
+            return None;
+        }
+        let magnitude = self
+            .numerator()
+            .unsigned_abs()
+            .max(self.denominator().unsigned_abs());
+        let power = u32::try_from(exponent.unsigned_abs()).ok()?;
+        if !power_within_u128(magnitude, power, max_digits.min(MAX_RATIONAL128_DIGITS)) {
+            return None;
+        }
+        let result =
+            Rational128::new_raw(self.numerator().pow(power), self.denominator().pow(power));
+        Some(if exponent < 0 { result.recip() } else { result })
+    }
+
+    fn checked_sqrt(self) -> Option<Self> {
+        let numerator = self.numerator().checked_isqrt()?;
+        let denominator = self.denominator().isqrt();
+        (numerator * numerator == self.numerator()
+            && denominator * denominator == self.denominator())
+        .then(|| Rational128::new_raw(numerator, denominator))
+    }
+
+    #[inline]
+    fn in_range(&self, limits: &Limits) -> bool {
+        let bound = 1 << limits.max_digits.min(MAX_RATIONAL128_DIGITS);
+        self.numerator().unsigned_abs() <= bound && self.denominator().unsigned_abs() <= bound
+    }
+}
+
+/// Arbitrary-precision rationals, whose range is `max_digits` alone.
+#[cfg(feature = "bigint")]
+impl Number for BigNumber {
+    const DOMAIN: Domain = Domain::Rational;
+
+    #[inline]
+    fn to_int(self) -> Option<i64> {
+        if self.is_integer() {
+            self.get().numer().to_i64()
+        } else {
+            None
+        }
+    }
+
+    /// Integers beyond `i64` count as not integral, as they take no factorials.
+    #[inline]
+    fn is_int(self) -> bool {
+        self.to_int().is_some()
+    }
+
+    #[inline]
+    fn is_rational(self) -> bool {
+        true
+    }
+
+    #[inline]
+    fn to_f64(self) -> f64 {
+        self.get().to_f64().unwrap_or(f64::NAN)
+    }
+
+    #[inline]
+    fn checked_divide(self, rhs: Self) -> Option<Self> {
+        Some(self / rhs)
+    }
+
+    /// Integral exponents only, negative ones taking the reciprocal. Powers whose bit length
+    /// surely exceeds `max_digits` are never computed.
+    fn checked_power(self, exponent: Self, max_digits: usize) -> Option<Self> {
+        let exponent = i32::try_from(exponent.to_int()?).ok()?;
+        if exponent < 0 && self.is_zero() {
+            return None;
+        }
+        if self.bits().saturating_sub(1) * u64::from(exponent.unsigned_abs()) > max_digits as u64 {
+            return None;
+        }
+        let result = Pow::pow(BigRational::clone(&self.get()), exponent);
+        (bits(&result) <= max_digits as u64).then(|| BigNumber::new(result))
+    }
+
+    fn checked_sqrt(self) -> Option<Self> {
+        let numerator = try_sqrt(self.get().numer())?;
+        let denominator = try_sqrt(self.get().denom())?;
+        Some(BigNumber::new(BigRational::new_raw(numerator, denominator)))
+    }
+
+    #[inline]
+    fn in_range(&self, limits: &Limits) -> bool {
+        self.bits() <= limits.max_digits as u64
+    }
 }
@@ -10,6 +10,18 @@ pub fn try_sqrt(n: i64) -> Option<i64> {
     }
 }
 
+/// The integer `y >= 2` with `y^exponent == x`, if there is one, or `x` itself for exponent 1.
+pub fn integer_root(x: i64, exponent: i64) -> Option<i64> {
+    if exponent == 1 {
+        return Some(x);
+    }
+    if exponent >= 63 {
+        return None;
+    }
+    let root = (x as f64).powf(1.0 / exponent as f64).round() as i64;
+    (root - 1..=root + 1).find(|&y| y >= 2 && y.checked_pow(exponent as u32) == Some(x))
+}
+
 pub fn factorial(n: i64) -> i64 {
     (2..=n).product::<i64>()
 }
@@ -17,3 +29,58 @@ pub fn factorial(n: i64) -> i64 {
 pub fn factorial_divide(m: i64, n: i64) -> i64 {
     ((n + 1)..=m).product::<i64>()
 }
+
+/// Whether `x^exponent <= 2^max_digits`, decided exactly from the bit length of `x` and, when
+/// that leaves a doubt, the power itself.
+pub fn power_within(x: u64, exponent: u32, max_digits: usize) -> bool {
+    if x <= 1 {
+        return true;
+    }
+    let (max_digits, exponent) = (max_digits as u64, exponent as u64);
+    // 2^ilog2(x) <= x < 2^(ilog2(x) + 1)
+    let log = x.ilog2() as u64;
+    if log * exponent > max_digits {
+        false
+    } else if (log + 1) * exponent <= max_digits {
+        true
+    } else {
+        (x as u128)
+            .checked_pow(exponent as u32)
+            .is_some_and(|power| max_digits >= 127 || power <= 1 << max_digits)
+    }
+}
+
+/// `m! / n!`, if it is at most `2^max_digits` and fits in `i64`.
+pub fn factorial_divide_within(m: i64, n: i64, max_digits: usize) -> Option<i64> {
+    let limit = 1i128 << max_digits.min(126);
+    let mut result = 1i128;
+    for k in (n + 1)..=m {
+        result *= k as i128;
+        if result > limit {
+            return None;
+        }
+    }
+    i64::try_from(result).ok()
+}
+
+/// The number of derangements of `n` elements, `!n = n * !(n - 1) + (-1)^n`.
+pub fn subfactorial(n: i64) -> i64 {
+    (1..=n).fold(1, |d, k| if k % 2 == 0 { d * k + 1 } else { d * k - 1 })
+}
+
+/// Like [`power_within`], for the 128-bit domains.
+pub fn power_within_u128(x: u128, exponent: u32, max_digits: usize) -> bool {
+    if x <= 1 {
+        return true;
+    }
+    let (max_digits, exponent) = (max_digits as u64, exponent as u64);
+    let log = x.ilog2() as u64;
+    if log * exponent > max_digits {
+        false
+    } else if (log + 1) * exponent <= max_digits {
+        true
+    } else {
+        x.checked_pow(exponent as u32)
+            .is_some_and(|power| max_digits >= 128 || power <= 1 << max_digits)
+    }
+}
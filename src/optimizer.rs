@@ -0,0 +1,203 @@
+use crate::{Expression, Number};
+use std::rc::Rc;
+
+/// Passes over the whole expression before the optimizer gives up on further improvements.
+const MAX_PASSES: usize = 8;
+
+impl Expression {
+    /// The number of digits `n` the expression is written with.
+    pub fn digit_count(&self, n: i64) -> usize {
+        let width = n.to_string().len();
+        self.iter_subexpressions()
+            .filter_map(Expression::to_number)
+            .map(|x| x.abs().to_string().len() / width)
+            .sum()
+    }
+
+    /// Whether every number in the expression is a concatenation of copies of `n`.
+    pub fn is_written_with(&self, n: i64) -> bool {
+        let digit = n.to_string();
+        self.iter_subexpressions()
+            .filter_map(Expression::to_number)
+            .all(|x| {
+                let x = x.to_string();
+                x.len().is_multiple_of(digit.len()) && x == digit.repeat(x.len() / digit.len())
+            })
+    }
+
+    /// The number of operations in the expression, counting nested square roots separately.
+    pub fn operator_count(&self) -> usize {
+        self.iter_operations()
+            .map(|node| match node {
+                Expression::Sqrt(_, order) => *order,
+                _ => 1,
+            })
+            .sum()
+    }
+}
+
+/// Rewrites `expression` into an equal one with fewer digits or, failing that, fewer operations.
+///
+/// Tries local algebraic rewrites (cancelling `x*y/y` and `x+y-y` detours, factoring out common
+/// factors) and replaces integer subexpressions with cheaper ones from `lookup`, which returns
+/// the expression and digit count of a known state.
+pub(crate) fn optimize(
+    expression: Rc<Expression>,
+    n: i64,
+    lookup: impl Fn(i64) -> Option<(Rc<Expression>, usize)>,
+) -> Rc<Expression> {
+    let optimizer = Optimizer { n, lookup };
+    let mut best = expression;
+    for _ in 0..MAX_PASSES {
+        let next = optimizer.optimize(&best);
+        if optimizer.cost(&next) >= optimizer.cost(&best) {
+            break;
+        }
+        best = next;
+    }
+    best
+}
+
+struct Optimizer<F> {
+    n: i64,
+    lookup: F,
+}
+
+impl<F: Fn(i64) -> Option<(Rc<Expression>, usize)>> Optimizer<F> {
+    #[inline]
+    fn cost(&self, expression: &Expression) -> (usize, usize) {
+        (expression.digit_count(self.n), expression.operator_count())
+    }
+
+    /// Optimizes the children first, then picks the cheapest rewrite of the node itself.
+    fn optimize(&self, expression: &Rc<Expression>) -> Rc<Expression> {
+        let node = match expression.as_ref() {
+            Expression::Number(_) => return expression.clone(),
+            Expression::Negate(x) => Rc::new(Expression::Negate(self.optimize(x))),
+            Expression::Sqrt(x, order) => Rc::new(Expression::Sqrt(self.optimize(x), *order)),
+            Expression::Factorial(x) => Rc::new(Expression::Factorial(self.optimize(x))),
+            Expression::Subfactorial(x) => Rc::new(Expression::Subfactorial(self.optimize(x))),
+            Expression::Add(x, y) => Rc::new(Expression::Add(self.optimize(x), self.optimize(y))),
+            Expression::Subtract(x, y) => {
+                Rc::new(Expression::Subtract(self.optimize(x), self.optimize(y)))
+            }
+            Expression::Multiply(x, y) => {
+                Rc::new(Expression::Multiply(self.optimize(x), self.optimize(y)))
+            }
+            Expression::Divide(x, y) => {
+                Rc::new(Expression::Divide(self.optimize(x), self.optimize(y)))
+            }
+            Expression::Power(x, y) => {
+                Rc::new(Expression::Power(self.optimize(x), self.optimize(y)))
+            }
+            Expression::Modulo(x, y) => {
+                Rc::new(Expression::Modulo(self.optimize(x), self.optimize(y)))
+            }
+            Expression::Gcd(x, y) => Rc::new(Expression::Gcd(self.optimize(x), self.optimize(y))),
+            Expression::Lcm(x, y) => Rc::new(Expression::Lcm(self.optimize(x), self.optimize(y))),
+        };
+        let mut best = node.clone();
+        let mut best_cost = self.cost(&best);
+        for candidate in self.rewrites(&node) {
+            let cost = self.cost(&candidate);
+            if cost < best_cost {
+                best = candidate;
+                best_cost = cost;
+            }
+        }
+        best
+    }
+
+    fn rewrites(&self, expression: &Rc<Expression>) -> Vec<Rc<Expression>> {
+        let mut candidates = vec![];
+        if let Some(x) = expression.evaluate_rational().and_then(|x| x.to_int()) {
+            if let Some((replacement, _)) = (self.lookup)(x) {
+                candidates.push(replacement);
+            }
+        }
+        match expression.as_ref() {
+            // x*y/y, y*x/y and x/y*y detours.
+            Expression::Divide(p, q) => {
+                if let Some((x, y)) = p.to_multiply() {
+                    if y == q {
+                        candidates.push(x.clone());
+                    }
+                    if x == q {
+                        candidates.push(y.clone());
+                    }
+                }
+                if let (Some((a, b)), Some((c, d))) = (p.to_multiply(), q.to_multiply()) {
+                    for (_, r, s) in common_factor(a, b, c, d) {
+                        candidates.push(Expression::from_divide(r, s));
+                    }
+                }
+            }
+            Expression::Multiply(p, q) => {
+                if let Some((x, y)) = q.to_divide() {
+                    if y == p {
+                        candidates.push(x.clone());
+                    }
+                }
+                if let Some((x, y)) = p.to_divide() {
+                    if y == q {
+                        candidates.push(x.clone());
+                    }
+                }
+            }
+            // x+y-y and y+x-y detours, and factoring out of differences.
+            Expression::Subtract(p, q) => {
+                if let Some((x, y)) = p.to_add() {
+                    if y == q {
+                        candidates.push(x.clone());
+                    }
+                    if x == q {
+                        candidates.push(y.clone());
+                    }
+                }
+                if let (Some((a, b)), Some((c, d))) = (p.to_multiply(), q.to_multiply()) {
+                    for (common, r, s) in common_factor(a, b, c, d) {
+                        candidates.push(Expression::from_multiply(
+                            common,
+                            Rc::new(Expression::Subtract(r, s)),
+                        ));
+                    }
+                }
+            }
+            Expression::Add(p, q) => {
+                if let Some((x, y)) = q.to_subtract() {
+                    if y == p {
+                        candidates.push(x.clone());
+                    }
+                }
+                if let (Some((a, b)), Some((c, d))) = (p.to_multiply(), q.to_multiply()) {
+                    for (common, r, s) in common_factor(a, b, c, d) {
+                        candidates.push(Expression::from_multiply(
+                            common,
+                            Rc::new(Expression::Add(r, s)),
+                        ));
+                    }
+                }
+            }
+            _ => {}
+        }
+        candidates
+    }
+}
+
+/// For products `a*b` and `c*d` sharing a factor, that factor with the two remaining ones.
+fn common_factor(
+    a: &Rc<Expression>,
+    b: &Rc<Expression>,
+    c: &Rc<Expression>,
+    d: &Rc<Expression>,
+) -> Vec<(Rc<Expression>, Rc<Expression>, Rc<Expression>)> {
+    let mut result = vec![];
+    for (x, r) in [(a, b), (b, a)] {
+        for (y, s) in [(c, d), (d, c)] {
+            if x == y {
+                result.push((x.clone(), r.clone(), s.clone()));
+            }
+        }
+    }
+    result
+}
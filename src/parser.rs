@@ -0,0 +1,341 @@
+use crate::{ExprHandle, Expression};
+use std::fmt;
+use std::rc::Rc;
+
+/// How deeply parentheses, operands and chains of operators may nest. Expressions are walked
+/// recursively, so deeper input could overflow the stack, when parsed or later.
+const MAX_NESTING: usize = 256;
+
+#[derive(Debug)]
+pub struct ParseExpressionError {
+    /// Byte offset of the first character that could not be parsed.
+    pub position: usize,
+    /// Whether the input nests deeper than the parser allows there, rather than being malformed.
+    pub too_deep: bool,
+}
+
+impl fmt::Display for ParseExpressionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.too_deep {
+            write!(
+                f,
+                "expression nests too deeply at position {}",
+                self.position
+            )
+        } else {
+            write!(f, "unexpected input at position {}", self.position)
+        }
+    }
+}
+
+type ParseResult = Result<Rc<Expression>, ParseExpressionError>;
+
+struct Parser<'a> {
+    input: &'a str,
+    position: usize,
+    /// The height of the tree being built above the current node.
+    nesting: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self) -> ParseExpressionError {
+        ParseExpressionError {
+            position: self.position,
+            too_deep: false,
+        }
+    }
+
+    /// Goes one level deeper into the tree, failing past [`MAX_NESTING`].
+    fn deeper(&mut self) -> Result<(), ParseExpressionError> {
+        if self.nesting == MAX_NESTING {
+            return Err(ParseExpressionError {
+                position: self.position,
+                too_deep: true,
+            });
+        }
+        self.nesting += 1;
+        Ok(())
+    }
+
+    /// Runs `parse` one level deeper.
+    fn nested(&mut self, parse: impl FnOnce(&mut Self) -> ParseResult) -> ParseResult {
+        self.deeper()?;
+        let x = parse(self)?;
+        self.nesting -= 1;
+        Ok(x)
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.position..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    /// Consumes `token` (after any whitespace) if the input continues with it.
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_whitespace();
+        if self.rest().starts_with(token) {
+            self.position += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_any(&mut self, tokens: &[&str]) -> bool {
+        tokens.iter().any(|token| self.eat(token))
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), ParseExpressionError> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.error())
+        }
+    }
+
+    /// expression = signed_term (('+' | '-') term)*
+    fn expression(&mut self) -> ParseResult {
+        let nesting = self.nesting;
+        let mut x = self.signed_term()?;
+        loop {
+            if self.eat("+") {
+                self.deeper()?;
+                x = Rc::new(Expression::Add(x, self.term()?));
+            } else if self.eat_any(&["-", "\u{2212}"]) {
+                self.deeper()?;
+                x = Rc::new(Expression::Subtract(x, self.term()?));
+            } else {
+                self.nesting = nesting;
+                return Ok(x);
+            }
+        }
+    }
+
+    /// signed_term = '-' signed_term | term
+    ///
+    /// A leading minus negates the whole first term, matching how `-x*y` is displayed.
+    fn signed_term(&mut self) -> ParseResult {
+        if self.eat_any(&["-", "\u{2212}"]) {
+            Ok(Rc::new(Expression::Negate(self.nested(Self::signed_term)?)))
+        } else {
+            self.term()
+        }
+    }
+
+    /// term = factor (('*' | '/' | 'mod') factor)*
+    fn term(&mut self) -> ParseResult {
+        let nesting = self.nesting;
+        let mut x = self.factor()?;
+        loop {
+            if self.eat_any(&["*", "\u{d7}"]) {
+                self.deeper()?;
+                x = Rc::new(Expression::Multiply(x, self.factor()?));
+            } else if self.eat_any(&["/", "\u{f7}"]) {
+                self.deeper()?;
+                x = Rc::new(Expression::Divide(x, self.factor()?));
+            } else if self.eat("mod") {
+                self.deeper()?;
+                x = Rc::new(Expression::Modulo(x, self.factor()?));
+            } else {
+                self.nesting = nesting;
+                return Ok(x);
+            }
+        }
+    }
+
+    /// factor = '-' factor | prefix ('^' factor)?
+    fn factor(&mut self) -> ParseResult {
+        if self.eat_any(&["-", "\u{2212}"]) {
+            return Ok(Rc::new(Expression::Negate(self.nested(Self::factor)?)));
+        }
+        let x = self.prefix()?;
+        if self.eat("^") {
+            Ok(Rc::new(Expression::Power(x, self.nested(Self::factor)?)))
+        } else {
+            Ok(x)
+        }
+    }
+
+    /// prefix = '!' prefix | '√' prefix | primary '!'*
+    ///
+    /// A prefix square root takes the factorials after its operand, as `√3!` is displayed for
+    /// the square root of `3!`.
+    fn prefix(&mut self) -> ParseResult {
+        if self.eat("!") {
+            return Ok(Rc::new(Expression::Subfactorial(
+                self.nested(Self::prefix)?,
+            )));
+        }
+        if self.eat("\u{221a}") {
+            return Ok(sqrt(self.nested(Self::prefix)?));
+        }
+        let nesting = self.nesting;
+        let mut x = self.primary()?;
+        while self.eat("!") {
+            self.deeper()?;
+            x = Rc::new(Expression::Factorial(x));
+        }
+        self.nesting = nesting;
+        Ok(x)
+    }
+
+    /// primary = number | '(' expression ')' | 'sqrt(' expression ')'
+    ///         | 'gcd(' expression ',' expression ')' | 'lcm(' expression ',' expression ')'
+    fn primary(&mut self) -> ParseResult {
+        if self.eat("(") {
+            let x = self.nested(Self::expression)?;
+            self.expect(")")?;
+            Ok(x)
+        } else if self.eat("sqrt") {
+            self.expect("(")?;
+            let x = self.nested(Self::expression)?;
+            self.expect(")")?;
+            Ok(sqrt(x))
+        } else if self.eat("gcd") {
+            let (x, y) = self.arguments()?;
+            Ok(Rc::new(Expression::Gcd(x, y)))
+        } else if self.eat("lcm") {
+            let (x, y) = self.arguments()?;
+            Ok(Rc::new(Expression::Lcm(x, y)))
+        } else {
+            self.number()
+        }
+    }
+
+    fn arguments(&mut self) -> Result<(Rc<Expression>, Rc<Expression>), ParseExpressionError> {
+        self.expect("(")?;
+        let x = self.nested(Self::expression)?;
+        self.expect(",")?;
+        let y = self.nested(Self::expression)?;
+        self.expect(")")?;
+        Ok((x, y))
+    }
+
+    fn number(&mut self) -> ParseResult {
+        self.skip_whitespace();
+        let rest = self.rest();
+        let length = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let x = rest[..length].parse().map_err(|_| self.error())?;
+        self.position += length;
+        Ok(Expression::from_number(x))
+    }
+}
+
+/// The square root of `x`, merged into `x` if it is a square root itself.
+fn sqrt(x: Rc<Expression>) -> Rc<Expression> {
+    Rc::new(match x.as_ref() {
+        Expression::Sqrt(y, order) => Expression::Sqrt(y.clone(), order + 1),
+        _ => Expression::Sqrt(x, 1),
+    })
+}
+
+impl Expression {
+    /// Parses an expression in the ASCII format [`Expression`] is displayed in, such as
+    /// `sqrt(4!)+4/4` or `gcd(12,18) mod 4`. The Unicode operators `×`, `÷`, `−` and `√` are
+    /// also accepted, so that the Unicode format parses back as well.
+    ///
+    /// Nested square roots are merged, so `sqrt(sqrt(x))` parses into a single node of order 2.
+    /// Input nesting deeper than 256 levels, counting parentheses, operands and each operator
+    /// of a chain such as `3+3+3`, is rejected, so that it cannot overflow the stack.
+    pub fn parse(input: &str) -> Result<Rc<Expression>, ParseExpressionError> {
+        let mut parser = Parser {
+            input,
+            position: 0,
+            nesting: 0,
+        };
+        let expression = parser.expression()?;
+        parser.skip_whitespace();
+        if parser.position < input.len() {
+            return Err(parser.error());
+        }
+        Ok(expression)
+    }
+}
+
+impl ExprHandle {
+    #[inline]
+    pub fn parse(input: &str) -> Result<ExprHandle, ParseExpressionError> {
+        Expression::parse(input).map(ExprHandle::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Expression;
+
+    #[test]
+    fn round_trips_displayed_expressions() {
+        let inputs = [
+            "-3+3-3*3/3",
+            "-(3+3)*3",
+            "3^3^3",
+            "(3^3)^3",
+            "sqrt(sqrt(3!))!+!4",
+            "gcd(12,18) mod lcm(4,6)",
+            "3-(3-3)",
+            "3/(3*3)",
+        ];
+        for input in inputs {
+            let expression = Expression::parse(input).unwrap();
+            assert_eq!(
+                Expression::parse(&expression.to_string()).unwrap(),
+                expression
+            );
+            let unicode = expression.to_unicode_string();
+            assert_eq!(
+                Expression::parse(&unicode).unwrap(),
+                expression,
+                "{unicode}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        let inputs = [
+            ("", 0),
+            ("3+", 2),
+            ("(3", 2),
+            ("3)", 1),
+            ("3 3", 2),
+            ("gcd(3)", 5),
+            ("sqrt 3", 5),
+            ("99999999999999999999", 0),
+        ];
+        for (input, position) in inputs {
+            let error = Expression::parse(input).unwrap_err();
+            assert_eq!(
+                (error.position, error.too_deep),
+                (position, false),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_deep_nesting() {
+        let nested = |depth: usize, open: &str, close: &str| {
+            format!("{}3{}", open.repeat(depth), close.repeat(depth))
+        };
+        assert!(Expression::parse(&nested(200, "(", ")")).is_ok());
+        let inputs = [
+            nested(100_000, "(", ")"),
+            nested(100_000, "sqrt(", ")"),
+            nested(100_000, "-", ""),
+            nested(100_000, "!", ""),
+            nested(100_000, "", "!"),
+            nested(100_000, "3^", ""),
+            nested(100_000, "", "+3"),
+            nested(100_000, "", "*3"),
+        ];
+        for input in inputs {
+            assert!(Expression::parse(&input).unwrap_err().too_deep);
+        }
+    }
+}
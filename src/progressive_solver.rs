@@ -1,7 +1,28 @@
 use super::{Limits, Solver};
-use crate::{Expression, Number, Rational, RationalQuadratic};
+use crate::optimizer::optimize;
+use crate::solution;
+use crate::solver::{outcome, Interruption, TraceSink};
+use crate::{
+    Bitmap, DecodeStateError, Domain, Expression, Number, Progress, ProgressEvent, Rational,
+    RationalQuadratic, SearchPhase, Solution, SolveOutcome, StageOrder,
+};
+use num::Signed;
+use rustc_hash::FxHashSet;
+#[cfg(feature = "checkpoint")]
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+#[cfg(feature = "checkpoint")]
+use std::io::Read;
+use std::io::{self, Write};
 use std::rc::Rc;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+type SolutionCallback = Box<dyn FnMut(&Solution)>;
+type ProgressCallback = Box<dyn FnMut(&Progress)>;
+
+#[cfg_attr(feature = "checkpoint", derive(Deserialize, Serialize))]
 enum ProgressiveSearchState {
     None,
     Integral,
@@ -11,8 +32,10 @@ enum ProgressiveSearchState {
     Finished,
 }
 
+#[cfg_attr(feature = "checkpoint", derive(Deserialize, Serialize))]
 pub struct ProgressiveSolver {
-    target: i64,
+    n: i64,
+    target: RationalQuadratic,
     max_depth: usize,
     integral_solver: Solver<i64>,
     full_integral_solver: Solver<i64>,
@@ -21,6 +44,20 @@ pub struct ProgressiveSolver {
     depth_searched: usize,
     search_state: ProgressiveSearchState,
     verbose: bool,
+    modular_pruning: bool,
+    goal_lookup: bool,
+    dead_state_pruning: bool,
+    optimize: bool,
+    negation: bool,
+    #[cfg_attr(feature = "checkpoint", serde(skip))]
+    start: Option<Instant>,
+    best: Option<Solution>,
+    #[cfg_attr(feature = "checkpoint", serde(skip))]
+    interruption: Option<Interruption>,
+    #[cfg_attr(feature = "checkpoint", serde(skip))]
+    solution_callbacks: Vec<SolutionCallback>,
+    #[cfg_attr(feature = "checkpoint", serde(skip))]
+    progress_callbacks: Vec<ProgressCallback>,
 }
 
 impl ProgressiveSolver {
@@ -31,8 +68,50 @@ impl ProgressiveSolver {
         integral_limits: Limits,
         rational_limits: Limits,
         quadratic_limits: Limits,
+    ) -> Self {
+        Self::new_rational(
+            n,
+            target.into(),
+            max_depth,
+            integral_limits,
+            rational_limits,
+            quadratic_limits,
+        )
+    }
+
+    /// Like [`new`](Self::new), for a rational target such as `22/7`. The integral solvers
+    /// still search to feed the others, but only the rational and quadratic solvers look for
+    /// a target that is not an integer.
+    pub fn new_rational(
+        n: i64,
+        target: Rational,
+        max_depth: Option<usize>,
+        integral_limits: Limits,
+        rational_limits: Limits,
+        quadratic_limits: Limits,
+    ) -> Self {
+        Self::new_quadratic(
+            n,
+            target.into(),
+            max_depth,
+            integral_limits,
+            rational_limits,
+            quadratic_limits,
+        )
+    }
+
+    /// Like [`new`](Self::new), for a target such as `5*sqrt(2)` that only the quadratic solver
+    /// looks for when it is irrational.
+    pub fn new_quadratic(
+        n: i64,
+        target: RationalQuadratic,
+        max_depth: Option<usize>,
+        integral_limits: Limits,
+        rational_limits: Limits,
+        quadratic_limits: Limits,
     ) -> Self {
         Self {
+            n,
             target,
             max_depth: max_depth.unwrap_or(usize::MAX),
             integral_solver: Solver::<i64>::new_progressive(n, integral_limits),
@@ -42,6 +121,16 @@ impl ProgressiveSolver {
             depth_searched: 0,
             search_state: ProgressiveSearchState::None,
             verbose: false,
+            modular_pruning: false,
+            goal_lookup: false,
+            dead_state_pruning: false,
+            optimize: false,
+            negation: false,
+            start: None,
+            best: None,
+            interruption: None,
+            solution_callbacks: vec![],
+            progress_callbacks: vec![],
         }
     }
 
@@ -49,23 +138,451 @@ impl ProgressiveSolver {
         self.verbose = verbose;
     }
 
-    pub fn solve(&mut self) -> SolverIterator {
+    /// Enables residue-based pruning in the bounded full-integral pass.
+    pub fn set_modular_pruning(&mut self, modular_pruning: bool) {
+        self.modular_pruning = modular_pruning;
+    }
+
+    /// Enables partner lookup at the final depth of the bounded full-integral pass.
+    pub fn set_goal_lookup(&mut self, goal_lookup: bool) {
+        self.goal_lookup = goal_lookup;
+    }
+
+    /// Enables dead-state pruning at the final depth of the bounded full-integral pass.
+    pub fn set_dead_state_pruning(&mut self, dead_state_pruning: bool) {
+        self.dead_state_pruning = dead_state_pruning;
+    }
+
+    /// Enables the Bloom filter pre-check on state insertion in every domain.
+    pub fn set_bloom_filter(&mut self, bloom_filter: bool) {
+        self.integral_solver.set_bloom_filter(bloom_filter);
+        self.rational_solver.set_bloom_filter(bloom_filter);
+        self.quadratic_solver.set_bloom_filter(bloom_filter);
+    }
+
+    /// Enables `x mod y` on positive integers in every domain.
+    pub fn set_modulo(&mut self, modulo: bool) {
+        self.integral_solver.set_modulo(modulo);
+        self.rational_solver.set_modulo(modulo);
+        self.quadratic_solver.set_modulo(modulo);
+    }
+
+    /// Enables `gcd(x, y)` and `lcm(x, y)` on positive integers in every domain.
+    pub fn set_gcd_lcm(&mut self, gcd_lcm: bool) {
+        self.integral_solver.set_gcd_lcm(gcd_lcm);
+        self.rational_solver.set_gcd_lcm(gcd_lcm);
+        self.quadratic_solver.set_gcd_lcm(gcd_lcm);
+    }
+
+    /// Sets the order of the stages of each depth in every domain, as described at
+    /// [`Solver::set_stage_order`].
+    pub fn set_stage_order(&mut self, order: StageOrder) {
+        self.integral_solver.set_stage_order(order);
+        self.rational_solver.set_stage_order(order);
+        self.quadratic_solver.set_stage_order(order);
+    }
+
+    /// Streams the states every domain inserts from now on to `writer`, as described at
+    /// [`Solver::set_search_trace`]. The full-integral pass starts from a copy of the integral
+    /// states, so its lines are in the integral domain too.
+    pub fn set_search_trace(&mut self, writer: impl Write + 'static) {
+        let sink = TraceSink::shared(writer);
+        self.integral_solver.set_search_trace_sink(sink.clone());
+        self.rational_solver.set_search_trace_sink(sink.clone());
+        self.quadratic_solver.set_search_trace_sink(sink);
+    }
+
+    /// Stops the search trace and flushes it, returning the first error of any write.
+    pub fn finish_search_trace(&mut self) -> io::Result<()> {
+        [
+            self.integral_solver.finish_search_trace(),
+            self.full_integral_solver.finish_search_trace(),
+            self.rational_solver.finish_search_trace(),
+            self.quadratic_solver.finish_search_trace(),
+        ]
+        .into_iter()
+        .collect()
+    }
+
+    /// Lets a host abort the search by setting `token`, in every domain, as described at
+    /// [`Solver::set_cancel_token`]. The iterator of solutions then ends, and resumes the depth
+    /// where it stopped when iterated again once `token` is cleared.
+    pub fn set_cancel_token(&mut self, token: Option<Arc<AtomicBool>>) {
+        self.integral_solver.set_cancel_token(token.clone());
+        self.full_integral_solver.set_cancel_token(token.clone());
+        self.rational_solver.set_cancel_token(token.clone());
+        self.quadratic_solver.set_cancel_token(token);
+    }
+
+    /// Stops the search once the states of a domain take about `budget` bytes, as described at
+    /// [`Solver::set_memory_budget`]. Each domain has a budget of its own, and the iterator of
+    /// solutions ends when one runs out.
+    pub fn set_memory_budget(&mut self, budget: Option<usize>) {
+        self.integral_solver.set_memory_budget(budget);
+        self.full_integral_solver.set_memory_budget(budget);
+        self.rational_solver.set_memory_budget(budget);
+        self.quadratic_solver.set_memory_budget(budget);
+    }
+
+    /// Whether the search last stopped at the memory budget.
+    #[inline]
+    pub fn memory_exceeded(&self) -> bool {
+        self.interruption == Some(Interruption::MemoryExceeded)
+    }
+
+    /// Rewrites every solution found into a cheaper equivalent where local rewrites or known
+    /// states allow, before reporting it.
+    pub fn set_optimize(&mut self, optimize: bool) {
+        self.optimize = optimize;
+    }
+
+    /// Accepts a negative target, solved by negating the solutions for its absolute value.
+    pub fn set_negation(&mut self, negation: bool) {
+        self.negation = negation;
+    }
+
+    /// The value the domain solvers search for.
+    #[inline]
+    fn goal(&self) -> RationalQuadratic {
+        if self.negation {
+            self.target.abs()
+        } else {
+            self.target
+        }
+    }
+
+    /// The goal of the integral solvers, if it is an integer.
+    #[inline]
+    fn integral_goal(&self) -> Option<i64> {
+        i64::try_from(self.goal()).ok()
+    }
+
+    /// The goal of the rational solver, if it is rational.
+    #[inline]
+    fn rational_goal(&self) -> Option<Rational> {
+        Rational::try_from(self.goal()).ok()
+    }
+
+    /// Registers a callback invoked with every new best solution, however the search is driven.
+    pub fn on_solution(&mut self, callback: impl FnMut(&Solution) + 'static) {
+        self.solution_callbacks.push(Box::new(callback));
+    }
+
+    /// Registers a callback invoked with a snapshot of the search as each phase of each depth
+    /// starts.
+    pub fn on_progress(&mut self, callback: impl FnMut(&Progress) + 'static) {
+        self.progress_callbacks.push(Box::new(callback));
+    }
+
+    /// Reports the events of the search in every domain to `callback` as they happen, as
+    /// described at [`Solver::set_progress_callback`]. Each domain reports its own closest
+    /// states, so a best candidate of one domain can be farther than that of another.
+    pub fn set_progress_callback(&mut self, callback: Box<dyn FnMut(ProgressEvent)>) {
+        let callback = Rc::new(RefCell::new(callback));
+        self.integral_solver
+            .set_shared_progress_callback(callback.clone());
+        self.full_integral_solver
+            .set_shared_progress_callback(callback.clone());
+        self.rational_solver
+            .set_shared_progress_callback(callback.clone());
+        self.quadratic_solver.set_shared_progress_callback(callback);
+    }
+
+    pub fn solve(&mut self) -> SolverIterator<'_> {
         SolverIterator { solver: self }
     }
 
-    pub fn get_solution(&self, x: &i64) -> Option<&(Rc<Expression>, usize)> {
+    /// Searches for `target` instead of the target the solver was built with, such as the
+    /// irrational `5*sqrt(2)` that only the quadratic solver looks for.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the solver has already searched, as the depths searched are not checked again.
+    pub fn solve_quadratic(&mut self, target: RationalQuadratic) -> SolverIterator<'_> {
+        assert!(
+            self.depth_searched == 0 && matches!(self.search_state, ProgressiveSearchState::None),
+            "solve_quadratic must come before any search"
+        );
+        self.target = target;
+        self.solve()
+    }
+
+    #[inline]
+    pub fn into_solutions(self) -> IntoSolutions {
+        IntoSolutions { solver: self }
+    }
+
+    /// Searches for the next better solution like the iterator of [`solve`](Self::solve), but
+    /// stops once `timeout` has passed, as described at [`Solver::solve_with_timeout`].
+    /// [`SolveOutcome::Unsolved`] means no better solution exists within the depth limit.
+    pub fn solve_with_timeout(&mut self, timeout: Duration) -> SolveOutcome {
+        self.set_timeout(solution::now().map(|now| now + timeout));
+        let solution = self.solve_next();
+        self.set_timeout(None);
+        outcome(solution, self.interruption)
+    }
+
+    fn set_timeout(&mut self, timeout: Option<Instant>) {
+        self.integral_solver.set_timeout(timeout);
+        self.full_integral_solver.set_timeout(timeout);
+        self.rational_solver.set_timeout(timeout);
+        self.quadratic_solver.set_timeout(timeout);
+    }
+
+    /// Searches until no better solution exists within the depth limit and returns the best one.
+    pub fn run(&mut self) -> Option<Solution> {
+        self.solve().last()
+    }
+
+    /// The values first reached with exactly `digits` digits in any domain, each once, widened
+    /// to [`RationalQuadratic`]. A value reached with fewer digits in another domain is left
+    /// out. The bounded full-integral pass is not included.
+    pub fn states_at_depth(
+        &self,
+        digits: usize,
+    ) -> impl Iterator<Item = (RationalQuadratic, &Rc<Expression>)> {
+        let integral = self
+            .integral_solver
+            .states_at_depth(digits)
+            .map(|(x, expression)| (RationalQuadratic::from(*x), expression));
+        let rational = self
+            .rational_solver
+            .states_at_depth(digits)
+            .map(|(x, expression)| (RationalQuadratic::from(*x), expression));
+        let quadratic = self
+            .quadratic_solver
+            .states_at_depth(digits)
+            .map(|(x, expression)| (*x, expression));
+        let mut seen = FxHashSet::default();
+        integral
+            .chain(rational)
+            .chain(quadratic)
+            .filter(move |(x, _)| self.min_digits(x) == Some(digits) && seen.insert(*x))
+    }
+
+    /// The fewest digits `x` is known to take in any domain.
+    fn min_digits(&self, x: &RationalQuadratic) -> Option<usize> {
+        let integral = i64::try_from(*x)
+            .ok()
+            .and_then(|x| self.integral_solver.get_state(&x));
+        let rational = Rational::try_from(*x)
+            .ok()
+            .and_then(|x| self.rational_solver.get_state(&x));
+        let quadratic = self.quadratic_solver.get_state(x);
+        [integral, rational, quadratic]
+            .into_iter()
+            .flatten()
+            .map(|(_, digits)| digits)
+            .min()
+    }
+
+    /// For each digit count `d`, the integers in `[1, limit]` reachable with at most `d` digits
+    /// in any domain.
+    pub fn reachability(&self, limit: u64) -> Vec<Bitmap> {
+        let mut bitmaps = vec![];
+        self.integral_solver.mark_reachable(limit, &mut bitmaps);
+        self.rational_solver.mark_reachable(limit, &mut bitmaps);
+        self.quadratic_solver.mark_reachable(limit, &mut bitmaps);
+        self.full_integral_solver
+            .mark_reachable(limit, &mut bitmaps);
+        bitmaps
+    }
+
+    /// Serializes the states of every depth searched to completion, for a later solver with the
+    /// same `n`, limits and operations to resume from with [`import_states`](Self::import_states).
+    pub fn export_states(&self) -> Vec<u8> {
+        let mut bytes = vec![];
         self.integral_solver
-            .get_solution(x)
-            .or_else(|| self.rational_solver.get_solution(&(*x).into()))
-            .or_else(|| self.quadratic_solver.get_solution(&(*x).into()))
-            .or_else(|| self.full_integral_solver.get_solution(x))
+            .export_states(self.depth_searched, &mut bytes);
+        self.rational_solver
+            .export_states(self.depth_searched, &mut bytes);
+        self.quadratic_solver
+            .export_states(self.depth_searched, &mut bytes);
+        bytes
+    }
+
+    /// Writes the whole search as JSON, including the depth being searched, so that a search of
+    /// days survives a restart of the process. Callbacks, the search trace, the cancel token and
+    /// the time spent are not saved. Wrap files in a `BufWriter`.
+    #[cfg(feature = "checkpoint")]
+    pub fn save(&self, writer: impl Write) -> serde_json::Result<()> {
+        serde_json::to_writer(writer, self)
+    }
+
+    /// Reads a search written by [`save`](Self::save), which resumes where it stopped. Wrap
+    /// files in a `BufReader`.
+    #[cfg(feature = "checkpoint")]
+    pub fn load(reader: impl Read) -> serde_json::Result<Self> {
+        serde_json::from_reader(reader)
+    }
+
+    /// Replaces the states of every domain with exported ones and restarts the search, which
+    /// then passes through the imported depths without recomputing them. On error, the solver
+    /// is left unchanged.
+    pub fn import_states(&mut self, bytes: &[u8]) -> Result<(), DecodeStateError> {
+        let mut bytes = bytes.iter().copied();
+        let integral = self.integral_solver.decode_states(&mut bytes)?;
+        let rational = self.rational_solver.decode_states(&mut bytes)?;
+        let quadratic = self.quadratic_solver.decode_states(&mut bytes)?;
+        if bytes.next().is_some() {
+            return Err(DecodeStateError {});
+        }
+        self.integral_solver.restore_states(integral);
+        self.rational_solver.restore_states(rational);
+        self.quadratic_solver.restore_states(quadratic);
+        self.depth_searched = 0;
+        self.search_state = ProgressiveSearchState::None;
+        Ok(())
+    }
+
+    /// Warm-starts the search from known solutions, such as those of nearby targets. Every
+    /// subexpression with a positive rational value is queued in the integral or rational tables
+    /// at its digit count, and enters the search when that depth is reached, unless it was
+    /// already found with fewer digits.
+    ///
+    /// A solution is skipped unless it is written with copies of `n` alone and evaluates exactly;
+    /// irrational subexpressions are skipped, as are those of depths already searched and those
+    /// the search could not build itself: out of range, or using an operation not enabled on
+    /// the solvers. Returns the number of solutions replayed.
+    pub fn replay(&mut self, solutions: &[Expression]) -> usize {
+        let mut replayed = 0;
+        for solution in solutions {
+            if !solution.is_written_with(self.n) || solution.evaluate_rational().is_none() {
+                continue;
+            }
+            replayed += 1;
+            for node in solution.iter_subexpressions() {
+                let digits = node.digit_count(self.n);
+                let Some(x) = node.evaluate_rational().filter(|x| x.is_positive()) else {
+                    continue;
+                };
+                if digits <= self.depth_searched {
+                    continue;
+                }
+                match x.to_int() {
+                    Some(x) if self.integral_solver.builds(&x, node) => {
+                        let expression = Rc::new(node.clone());
+                        self.integral_solver.insert_extra(x, digits, expression);
+                    }
+                    None if self.rational_solver.builds(&x, node) => {
+                        let expression = Rc::new(node.clone());
+                        self.rational_solver.insert_extra(x, digits, expression);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        replayed
+    }
+
+    #[inline]
+    pub fn depth_searched(&self) -> usize {
+        self.depth_searched
+    }
+
+    /// Estimates the fraction of the depth being searched that is done, from 0 before it starts
+    /// to 1 once every domain has searched it. Each phase of the depth counts as an equal share,
+    /// within which the progress of its solver is reported as by [`Solver::depth_progress`].
+    pub fn depth_progress(&self) -> f32 {
+        let (phase, progress) = match self.search_state {
+            ProgressiveSearchState::None => return 0.0,
+            ProgressiveSearchState::Finished => return 1.0,
+            ProgressiveSearchState::Integral => (0, self.integral_solver.depth_progress()),
+            ProgressiveSearchState::FullIntegral => (1, self.full_integral_solver.depth_progress()),
+            ProgressiveSearchState::Rational => (2, self.rational_solver.depth_progress()),
+            ProgressiveSearchState::RationalQuadratic => {
+                (3, self.quadratic_solver.depth_progress())
+            }
+        };
+        (phase as f32 + progress) / 4.0
     }
 
-    pub(crate) fn solve_next(&mut self) -> Option<(Rc<Expression>, usize)> {
-        for digits in self.depth_searched + 1..=self.max_depth {
-            if self.search(digits) {
-                let solution = self.get_solution(&self.target)?.clone();
-                self.max_depth = solution.1 - 1;
+    /// The best solution found so far, without searching any further.
+    #[inline]
+    pub fn best_solution(&self) -> Option<&Solution> {
+        self.best.as_ref()
+    }
+
+    /// The fewest digits a solution can take under the operations and limits searched, as
+    /// proven by the depths searched to completion. Once it reaches the digits of the
+    /// [`best_solution`](Self::best_solution), that solution is optimal.
+    pub fn bound(&self) -> usize {
+        let bound = self.depth_searched + 1;
+        self.best
+            .as_ref()
+            .map_or(bound, |best| bound.min(best.digits()))
+    }
+
+    /// Whether no deeper search can find a new state in any domain under the current limits.
+    pub fn is_exhausted(&self) -> bool {
+        self.integral_solver.is_exhausted()
+            && self.rational_solver.is_exhausted()
+            && self.quadratic_solver.is_exhausted()
+    }
+
+    pub fn get_solution(&self, x: &i64) -> Option<Solution> {
+        let ((expression, digits), domain) = self.find_solution((*x).into())?;
+        Some(Solution::new(expression, digits, domain, None))
+    }
+
+    fn find_solution(&self, x: RationalQuadratic) -> Option<((Rc<Expression>, usize), Domain)> {
+        let x_int = i64::try_from(x).ok();
+        x_int
+            .and_then(|x| self.integral_solver.get_state(&x))
+            .map(|solution| (solution, Domain::Integral))
+            .or_else(|| {
+                Rational::try_from(x)
+                    .ok()
+                    .and_then(|x| self.rational_solver.get_state(&x))
+                    .map(|solution| (solution, Domain::Rational))
+            })
+            .or_else(|| {
+                self.quadratic_solver
+                    .get_state(&x)
+                    .map(|solution| (solution, Domain::RationalQuadratic))
+            })
+            .or_else(|| {
+                x_int
+                    .and_then(|x| self.full_integral_solver.get_state(&x))
+                    .map(|solution| (solution, Domain::Integral))
+            })
+    }
+
+    pub(crate) fn solve_next(&mut self) -> Option<Solution> {
+        if self.start.is_none() {
+            self.start = solution::now();
+        }
+        self.interruption = None;
+        for depth in self.depth_searched + 1..=self.max_depth {
+            if self.is_exhausted() || self.interruption.is_some() {
+                break;
+            }
+            if self.search(depth) {
+                let ((expression, digits), domain) = self.find_solution(self.goal())?;
+                let (expression, digits) = if self.optimize {
+                    let expression = optimize(expression, self.n, |x| {
+                        self.find_solution(x.into()).map(|(state, _)| state)
+                    });
+                    let digits = expression.digit_count(self.n);
+                    (expression, digits)
+                } else {
+                    (expression, digits)
+                };
+                let mut solution = Solution::new(
+                    expression,
+                    digits,
+                    domain,
+                    self.start.map(|start| start.elapsed()),
+                )
+                .with_depth(depth);
+                if self.goal() != self.target {
+                    solution = solution.negate();
+                }
+                self.max_depth = solution.digits() - 1;
+                self.best = Some(solution.clone());
+                for callback in &mut self.solution_callbacks {
+                    callback(&solution);
+                }
                 return Some(solution);
             }
         }
@@ -77,12 +594,18 @@ impl ProgressiveSolver {
             self.search_state = ProgressiveSearchState::Integral;
         }
         if let ProgressiveSearchState::Integral = self.search_state {
-            if self
-                .integral_solver
-                .solve(self.target, Some(digits))
-                .is_some()
-            {
-                return true;
+            self.report_progress(digits, SearchPhase::Integral);
+            match self.integral_goal() {
+                Some(goal) => {
+                    if self.integral_solver.solve(goal, Some(digits)).is_some() {
+                        return true;
+                    }
+                }
+                None => self.integral_solver.exhaust_to_depth(digits),
+            }
+            if let Some(interruption) = self.integral_solver.interruption() {
+                self.interruption = Some(interruption);
+                return false;
             }
             for (x, expression, _) in self.integral_solver.new_numbers() {
                 self.rational_solver
@@ -91,17 +614,32 @@ impl ProgressiveSolver {
                     .try_insert((*x).into(), digits, || expression.clone());
             }
             self.clear_new_numbers();
+            // Cloned on entering the pass, so that a pass cut short, or saved and loaded,
+            // resumes where it stopped.
+            if self.integral_goal().is_some() && digits >= 3 && digits < self.max_depth {
+                self.full_integral_solver
+                    .clone_non_progressive_from(&self.integral_solver);
+            }
             self.search_state = ProgressiveSearchState::FullIntegral;
         }
         if let ProgressiveSearchState::FullIntegral = self.search_state {
             let mut found = false;
-            if digits >= 3 && digits < self.max_depth {
+            let goal = self.integral_goal();
+            if let Some(goal) = goal.filter(|_| digits >= 3 && digits < self.max_depth) {
+                self.report_progress(digits, SearchPhase::FullIntegral);
                 self.full_integral_solver
-                    .clone_non_progressive_from(&self.integral_solver);
+                    .set_modular_pruning(self.modular_pruning);
+                self.full_integral_solver.set_goal_lookup(self.goal_lookup);
+                self.full_integral_solver
+                    .set_dead_state_pruning(self.dead_state_pruning);
                 found = self
                     .full_integral_solver
-                    .solve(self.target, Some(self.max_depth))
+                    .solve(goal, Some(self.max_depth))
                     .is_some();
+                if let Some(interruption) = self.full_integral_solver.interruption() {
+                    self.interruption = Some(interruption);
+                    return false;
+                }
             }
             self.search_state = ProgressiveSearchState::Rational;
             if found {
@@ -109,15 +647,21 @@ impl ProgressiveSolver {
             }
         }
         if let ProgressiveSearchState::Rational = self.search_state {
-            if self
-                .rational_solver
-                .solve(self.target.into(), Some(digits))
-                .is_some()
-            {
-                return true;
+            self.report_progress(digits, SearchPhase::Rational);
+            match self.rational_goal() {
+                Some(goal) => {
+                    if self.rational_solver.solve(goal, Some(digits)).is_some() {
+                        return true;
+                    }
+                }
+                None => self.rational_solver.exhaust_to_depth(digits),
+            }
+            if let Some(interruption) = self.rational_solver.interruption() {
+                self.interruption = Some(interruption);
+                return false;
             }
             for (x, expression, _) in self.rational_solver.new_numbers() {
-                if let Some(x_int) = x.to_int() {
+                if let Ok(x_int) = i64::try_from(*x) {
                     self.integral_solver
                         .try_insert(x_int, digits, || expression.clone());
                 }
@@ -128,21 +672,26 @@ impl ProgressiveSolver {
             self.search_state = ProgressiveSearchState::RationalQuadratic;
         }
         if let ProgressiveSearchState::RationalQuadratic = self.search_state {
+            self.report_progress(digits, SearchPhase::RationalQuadratic);
             if self
                 .quadratic_solver
-                .solve(self.target.into(), Some(digits))
+                .solve(self.goal(), Some(digits))
                 .is_some()
             {
                 return true;
             }
+            if let Some(interruption) = self.quadratic_solver.interruption() {
+                self.interruption = Some(interruption);
+                return false;
+            }
             for (x, expression, _) in self.quadratic_solver.new_numbers() {
-                if let Some(x_int) = x.to_int() {
+                if let Ok(x_int) = i64::try_from(*x) {
                     self.integral_solver
                         .try_insert(x_int, digits, || expression.clone());
                 }
-                if x.is_rational() {
+                if let Ok(x_rational) = Rational::try_from(*x) {
                     self.rational_solver
-                        .try_insert(x.rational_part(), digits, || expression.clone());
+                        .try_insert(x_rational, digits, || expression.clone());
                 }
             }
             self.clear_new_numbers();
@@ -156,6 +705,47 @@ impl ProgressiveSolver {
         false
     }
 
+    /// Known states and candidates tried across every domain. The bounded full-integral pass
+    /// starts from a copy of the integral states, so only the states it adds are counted.
+    pub(crate) fn counters(&self) -> (usize, u64) {
+        let (integral_states, integral_candidates) = self.integral_solver.counters();
+        let (full_integral_states, full_integral_candidates) = self.full_integral_solver.counters();
+        let (rational_states, rational_candidates) = self.rational_solver.counters();
+        let (quadratic_states, quadratic_candidates) = self.quadratic_solver.counters();
+        (
+            integral_states
+                + full_integral_states.saturating_sub(integral_states)
+                + rational_states
+                + quadratic_states,
+            integral_candidates
+                + full_integral_candidates
+                + rational_candidates
+                + quadratic_candidates,
+        )
+    }
+
+    fn report_progress(&mut self, depth: usize, phase: SearchPhase) {
+        if self.progress_callbacks.is_empty() {
+            return;
+        }
+        let progress = Progress {
+            depth,
+            phase,
+            integral_states: self.integral_solver.counters().0,
+            full_integral_states: self.full_integral_solver.counters().0,
+            rational_states: self.rational_solver.counters().0,
+            quadratic_states: self.quadratic_solver.counters().0,
+            memory: self.integral_solver.memory_estimate()
+                + self.full_integral_solver.memory_estimate()
+                + self.rational_solver.memory_estimate()
+                + self.quadratic_solver.memory_estimate(),
+            elapsed: self.start.map(|start| start.elapsed()),
+        };
+        for callback in &mut self.progress_callbacks {
+            callback(&progress);
+        }
+    }
+
     fn clear_new_numbers(&mut self) {
         self.integral_solver.clear_new_numbers();
         self.rational_solver.clear_new_numbers();
@@ -168,9 +758,53 @@ pub struct SolverIterator<'a> {
 }
 
 impl<'a> Iterator for SolverIterator<'a> {
-    type Item = (Rc<Expression>, usize);
+    type Item = Solution;
 
     fn next(&mut self) -> Option<Self::Item> {
         self.solver.solve_next()
     }
 }
+
+impl<'a> IntoIterator for &'a mut ProgressiveSolver {
+    type Item = Solution;
+    type IntoIter = SolverIterator<'a>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.solve()
+    }
+}
+
+pub struct IntoSolutions {
+    solver: ProgressiveSolver,
+}
+
+impl IntoSolutions {
+    #[inline]
+    pub fn solver(&self) -> &ProgressiveSolver {
+        &self.solver
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> ProgressiveSolver {
+        self.solver
+    }
+}
+
+impl Iterator for IntoSolutions {
+    type Item = Solution;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.solver.solve_next()
+    }
+}
+
+impl IntoIterator for ProgressiveSolver {
+    type Item = Solution;
+    type IntoIter = IntoSolutions;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.into_solutions()
+    }
+}
@@ -1,19 +1,22 @@
-use super::{IntegralQuadratic, ParseQuadraticError, PRIMES};
+use super::{
+    multiply_radicals, parse, radicand, GenericIntegralQuadratic, ParseQuadraticError, PrimeBasis,
+};
 use crate::number_theory::try_sqrt;
-use crate::Number;
+use crate::{Domain, Number};
 use num::traits::Pow;
 use num::{Integer, Num, One, Signed, Zero};
 use std::fmt;
 use std::ops::{Add, Div, Mul, Neg, Rem, Sub};
+use std::str::FromStr;
 
-impl IntegralQuadratic {
+impl<B: PrimeBasis> GenericIntegralQuadratic<B> {
     #[inline]
     pub fn integral_part(&self) -> i64 {
         self.integral_part
     }
 
     #[inline]
-    pub fn quadratic_part(&self) -> &[u8; PRIMES.len()] {
+    pub fn quadratic_part(&self) -> &B::Powers {
         &self.quadratic_part
     }
 
@@ -21,22 +24,48 @@ impl IntegralQuadratic {
     pub fn quadratic_power(&self) -> u8 {
         self.quadratic_power
     }
+
+    pub fn to_f64(&self) -> f64 {
+        let exponent = 0.5f64.powi(self.quadratic_power as i32);
+        B::PRIMES
+            .iter()
+            .zip(self.quadratic_part.as_ref())
+            .map(|(&base, &power)| (base as f64).powf(power as f64 * exponent))
+            .product::<f64>()
+            * self.integral_part as f64
+    }
+
+    /// The product, or `None` if its integral part overflows.
+    pub fn checked_mul(self, rhs: Self) -> Option<Self> {
+        let (factor, quadratic_part, quadratic_power) = multiply_radicals::<B>(
+            (&self.quadratic_part, self.quadratic_power),
+            (&rhs.quadratic_part, rhs.quadratic_power),
+        );
+        let integral_part = self
+            .integral_part
+            .checked_mul(rhs.integral_part)?
+            .checked_mul(factor)?;
+        Some(if integral_part.is_zero() {
+            Self::zero()
+        } else {
+            Self {
+                integral_part,
+                quadratic_part,
+                quadratic_power,
+            }
+        })
+    }
 }
 
-impl fmt::Display for IntegralQuadratic {
+impl<B: PrimeBasis> fmt::Display for GenericIntegralQuadratic<B> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         if self.quadratic_power == 0 {
             write!(f, "{}", self.integral_part)
         } else {
-            let number_under_sqrt = PRIMES
-                .iter()
-                .zip(&self.quadratic_part)
-                .map(|(&base, &power)| base.pow(power as u32))
-                .product::<i64>();
             let quadratic_string = format!(
                 "{}{}{}",
                 "sqrt(".repeat(self.quadratic_power as usize),
-                number_under_sqrt,
+                radicand::<B>(&self.quadratic_part),
                 ")".repeat(self.quadratic_power as usize)
             );
             if self.integral_part == 1 {
@@ -50,18 +79,9 @@ impl fmt::Display for IntegralQuadratic {
     }
 }
 
-impl From<i64> for IntegralQuadratic {
-    #[inline]
-    fn from(x: i64) -> Self {
-        Self {
-            integral_part: x,
-            quadratic_part: [0; PRIMES.len()],
-            quadratic_power: 0,
-        }
-    }
-}
+impl<B: PrimeBasis> Number for GenericIntegralQuadratic<B> {
+    const DOMAIN: Domain = Domain::IntegralQuadratic;
 
-impl Number for IntegralQuadratic {
     #[inline]
     fn to_int(self) -> Option<i64> {
         if self.quadratic_power == 0 {
@@ -80,22 +100,38 @@ impl Number for IntegralQuadratic {
     fn is_rational(self) -> bool {
         self.quadratic_power == 0
     }
+
+    #[inline]
+    fn to_f64(self) -> f64 {
+        GenericIntegralQuadratic::to_f64(&self)
+    }
 }
 
-impl Num for IntegralQuadratic {
+impl<B: PrimeBasis> Num for GenericIntegralQuadratic<B> {
     type FromStrRadixErr = ParseQuadraticError;
 
-    fn from_str_radix(_str: &str, _radix: u32) -> Result<Self, Self::FromStrRadixErr> {
-        Err(ParseQuadraticError {})
+    /// Parses the format values are displayed in, such as `3*sqrt(2)` or `sqrt(sqrt(2^3*3))`.
+    #[inline]
+    fn from_str_radix(str: &str, radix: u32) -> Result<Self, Self::FromStrRadixErr> {
+        parse::parse(str, radix)
+    }
+}
+
+impl<B: PrimeBasis> FromStr for GenericIntegralQuadratic<B> {
+    type Err = ParseQuadraticError;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_str_radix(s, 10)
     }
 }
 
-impl Zero for IntegralQuadratic {
+impl<B: PrimeBasis> Zero for GenericIntegralQuadratic<B> {
     #[inline]
     fn zero() -> Self {
         Self {
             integral_part: 0,
-            quadratic_part: [0; PRIMES.len()],
+            quadratic_part: B::Powers::default(),
             quadratic_power: 0,
         }
     }
@@ -106,12 +142,12 @@ impl Zero for IntegralQuadratic {
     }
 }
 
-impl One for IntegralQuadratic {
+impl<B: PrimeBasis> One for GenericIntegralQuadratic<B> {
     #[inline]
     fn one() -> Self {
         Self {
             integral_part: 1,
-            quadratic_part: [0; PRIMES.len()],
+            quadratic_part: B::Powers::default(),
             quadratic_power: 0,
         }
     }
@@ -124,17 +160,17 @@ impl One for IntegralQuadratic {
 
 #[opimps::impl_uni_ops(Neg)]
 #[inline]
-fn neg(self: IntegralQuadratic) -> IntegralQuadratic {
-    IntegralQuadratic {
+fn neg<B: PrimeBasis>(self: GenericIntegralQuadratic<B>) -> GenericIntegralQuadratic<B> {
+    GenericIntegralQuadratic {
         integral_part: -self.integral_part,
         quadratic_part: self.quadratic_part,
         quadratic_power: self.quadratic_power,
     }
 }
 
-impl Signed for IntegralQuadratic {
+impl<B: PrimeBasis> Signed for GenericIntegralQuadratic<B> {
     fn abs(&self) -> Self {
-        IntegralQuadratic {
+        GenericIntegralQuadratic {
             integral_part: self.integral_part.abs(),
             quadratic_part: self.quadratic_part,
             quadratic_power: self.quadratic_power,
@@ -148,7 +184,7 @@ impl Signed for IntegralQuadratic {
     fn signum(&self) -> Self {
         Self {
             integral_part: self.integral_part.signum(),
-            quadratic_part: [0; PRIMES.len()],
+            quadratic_part: B::Powers::default(),
             quadratic_power: 0,
         }
     }
@@ -163,7 +199,11 @@ impl Signed for IntegralQuadratic {
 }
 
 #[opimps::impl_ops(Add)]
-fn add(self: IntegralQuadratic, rhs: IntegralQuadratic) -> IntegralQuadratic {
+#[allow(clippy::clone_on_copy)]
+fn add<B: PrimeBasis>(
+    self: GenericIntegralQuadratic<B>,
+    rhs: GenericIntegralQuadratic<B>,
+) -> GenericIntegralQuadratic<B> {
     if self.is_zero() {
         rhs.clone()
     } else if rhs.is_zero() {
@@ -171,9 +211,9 @@ fn add(self: IntegralQuadratic, rhs: IntegralQuadratic) -> IntegralQuadratic {
     } else {
         let integral_part = self.integral_part + rhs.integral_part;
         if integral_part == 0 {
-            IntegralQuadratic::zero()
+            GenericIntegralQuadratic::zero()
         } else {
-            IntegralQuadratic {
+            GenericIntegralQuadratic {
                 integral_part,
                 quadratic_part: self.quadratic_part,
                 quadratic_power: self.quadratic_power,
@@ -184,8 +224,8 @@ fn add(self: IntegralQuadratic, rhs: IntegralQuadratic) -> IntegralQuadratic {
 
 #[opimps::impl_ops(Add)]
 #[inline]
-fn add(self: IntegralQuadratic, rhs: i64) -> IntegralQuadratic {
-    IntegralQuadratic {
+fn add<B: PrimeBasis>(self: GenericIntegralQuadratic<B>, rhs: i64) -> GenericIntegralQuadratic<B> {
+    GenericIntegralQuadratic {
         integral_part: self.integral_part + rhs,
         quadratic_part: self.quadratic_part,
         quadratic_power: self.quadratic_power,
@@ -193,15 +233,19 @@ fn add(self: IntegralQuadratic, rhs: i64) -> IntegralQuadratic {
 }
 
 #[opimps::impl_ops(Sub)]
-fn sub(self: IntegralQuadratic, rhs: IntegralQuadratic) -> IntegralQuadratic {
+#[allow(clippy::clone_on_copy)]
+fn sub<B: PrimeBasis>(
+    self: GenericIntegralQuadratic<B>,
+    rhs: GenericIntegralQuadratic<B>,
+) -> GenericIntegralQuadratic<B> {
     if self.is_zero() {
         -rhs
     } else if rhs.is_zero() {
         self.clone()
     } else if self.integral_part == rhs.integral_part {
-        IntegralQuadratic::zero()
+        GenericIntegralQuadratic::zero()
     } else {
-        IntegralQuadratic {
+        GenericIntegralQuadratic {
             integral_part: self.integral_part - rhs.integral_part,
             quadratic_part: self.quadratic_part,
             quadratic_power: self.quadratic_power,
@@ -211,8 +255,8 @@ fn sub(self: IntegralQuadratic, rhs: IntegralQuadratic) -> IntegralQuadratic {
 
 #[opimps::impl_ops(Sub)]
 #[inline]
-fn sub(self: IntegralQuadratic, rhs: i64) -> IntegralQuadratic {
-    IntegralQuadratic {
+fn sub<B: PrimeBasis>(self: GenericIntegralQuadratic<B>, rhs: i64) -> GenericIntegralQuadratic<B> {
+    GenericIntegralQuadratic {
         integral_part: self.integral_part - rhs,
         quadratic_part: self.quadratic_part,
         quadratic_power: self.quadratic_power,
@@ -220,34 +264,20 @@ fn sub(self: IntegralQuadratic, rhs: i64) -> IntegralQuadratic {
 }
 
 #[opimps::impl_ops(Mul)]
-fn mul(self: IntegralQuadratic, rhs: IntegralQuadratic) -> IntegralQuadratic {
-    let mut integral_part = self.integral_part * rhs.integral_part;
+fn mul<B: PrimeBasis>(
+    self: GenericIntegralQuadratic<B>,
+    rhs: GenericIntegralQuadratic<B>,
+) -> GenericIntegralQuadratic<B> {
+    let integral_part = self.integral_part * rhs.integral_part;
     if integral_part == 0 {
-        return IntegralQuadratic::zero();
-    }
-    let mut quadratic_part = [0u8; PRIMES.len()];
-    let mut quadratic_power = u8::max(self.quadratic_power, rhs.quadratic_power);
-    if quadratic_power > 0 {
-        for i in 0..PRIMES.len() {
-            quadratic_part[i] = (self.quadratic_part[i]
-                << (quadratic_power - self.quadratic_power))
-                + (rhs.quadratic_part[i] << (quadratic_power - rhs.quadratic_power));
-        }
-        for (&prime, power) in PRIMES.iter().zip(&mut quadratic_part) {
-            if *power >= 1 << quadratic_power {
-                *power &= (1 << quadratic_power) - 1;
-                integral_part *= prime;
-            }
-        }
-        while quadratic_power > 0 && quadratic_part.iter().all(|&x| x % 2 == 0) {
-            quadratic_power -= 1;
-            for x in &mut quadratic_part {
-                *x >>= 1;
-            }
-        }
-    }
-    IntegralQuadratic {
-        integral_part,
+        return GenericIntegralQuadratic::zero();
+    }
+    let (factor, quadratic_part, quadratic_power) = multiply_radicals::<B>(
+        (&self.quadratic_part, self.quadratic_power),
+        (&rhs.quadratic_part, rhs.quadratic_power),
+    );
+    GenericIntegralQuadratic {
+        integral_part: integral_part * factor,
         quadratic_part,
         quadratic_power,
     }
@@ -255,11 +285,11 @@ fn mul(self: IntegralQuadratic, rhs: IntegralQuadratic) -> IntegralQuadratic {
 
 #[opimps::impl_ops(Mul)]
 #[inline]
-fn mul(self: IntegralQuadratic, rhs: i64) -> IntegralQuadratic {
+fn mul<B: PrimeBasis>(self: GenericIntegralQuadratic<B>, rhs: i64) -> GenericIntegralQuadratic<B> {
     if rhs.is_zero() {
-        IntegralQuadratic::zero()
+        GenericIntegralQuadratic::zero()
     } else {
-        IntegralQuadratic {
+        GenericIntegralQuadratic {
             integral_part: self.integral_part * rhs,
             quadratic_part: self.quadratic_part,
             quadratic_power: self.quadratic_power,
@@ -268,32 +298,35 @@ fn mul(self: IntegralQuadratic, rhs: i64) -> IntegralQuadratic {
 }
 
 #[opimps::impl_ops(Div)]
-fn div(self: IntegralQuadratic, rhs: IntegralQuadratic) -> IntegralQuadratic {
+fn div<B: PrimeBasis>(
+    self: GenericIntegralQuadratic<B>,
+    rhs: GenericIntegralQuadratic<B>,
+) -> GenericIntegralQuadratic<B> {
     let mut integral_part = self.integral_part / rhs.integral_part;
     if integral_part.is_zero() {
-        return IntegralQuadratic::zero();
+        return GenericIntegralQuadratic::zero();
     }
-    let mut quadratic_part = [0u8; PRIMES.len()];
+    let mut quadratic_part = B::Powers::default();
     let mut quadratic_power = u8::max(self.quadratic_power, rhs.quadratic_power);
     if quadratic_power > 0 {
-        for i in 0..PRIMES.len() {
+        for i in 0..B::PRIMES.len() {
             let x = self.quadratic_part[i] << (quadratic_power - self.quadratic_power);
             let y = rhs.quadratic_part[i] << (quadratic_power - rhs.quadratic_power);
             if x < y {
-                integral_part /= PRIMES[i];
+                integral_part /= B::PRIMES[i];
                 quadratic_part[i] = (1 << quadratic_power) + x - y;
             } else {
                 quadratic_part[i] = x - y;
             }
         }
-        while quadratic_power > 0 && quadratic_part.iter().all(|&x| x % 2 == 0) {
+        while quadratic_power > 0 && quadratic_part.as_ref().iter().all(|&x| x % 2 == 0) {
             quadratic_power -= 1;
-            for x in &mut quadratic_part {
+            for x in quadratic_part.as_mut() {
                 *x >>= 1;
             }
         }
     }
-    IntegralQuadratic {
+    GenericIntegralQuadratic {
         integral_part,
         quadratic_part,
         quadratic_power,
@@ -302,8 +335,8 @@ fn div(self: IntegralQuadratic, rhs: IntegralQuadratic) -> IntegralQuadratic {
 
 #[opimps::impl_ops(Div)]
 #[inline]
-fn div(self: IntegralQuadratic, rhs: i64) -> IntegralQuadratic {
-    IntegralQuadratic {
+fn div<B: PrimeBasis>(self: GenericIntegralQuadratic<B>, rhs: i64) -> GenericIntegralQuadratic<B> {
+    GenericIntegralQuadratic {
         integral_part: self.integral_part / rhs,
         quadratic_part: self.quadratic_part,
         quadratic_power: self.quadratic_power,
@@ -312,30 +345,33 @@ fn div(self: IntegralQuadratic, rhs: i64) -> IntegralQuadratic {
 
 #[opimps::impl_ops(Rem)]
 #[inline]
-fn rem(self: IntegralQuadratic, _rhs: IntegralQuadratic) -> IntegralQuadratic {
-    IntegralQuadratic::zero()
+fn rem<B: PrimeBasis>(
+    self: GenericIntegralQuadratic<B>,
+    _rhs: GenericIntegralQuadratic<B>,
+) -> GenericIntegralQuadratic<B> {
+    GenericIntegralQuadratic::zero()
 }
 
-impl Pow<u32> for IntegralQuadratic {
-    type Output = IntegralQuadratic;
+impl<B: PrimeBasis> Pow<u32> for GenericIntegralQuadratic<B> {
+    type Output = GenericIntegralQuadratic<B>;
 
-    fn pow(self, power: u32) -> IntegralQuadratic {
+    fn pow(self, power: u32) -> GenericIntegralQuadratic<B> {
         if power == 0 {
             return Self::one();
         }
         let mut integral_part = self.integral_part.pow(power);
-        let mut quadratic_part = [0u8; PRIMES.len()];
+        let mut quadratic_part = B::Powers::default();
         let mut quadratic_power = self.quadratic_power;
         let mut power = power;
-        while quadratic_power > 0 && power % 2 == 0 {
+        while quadratic_power > 0 && power.is_multiple_of(2) {
             quadratic_power -= 1;
             power >>= 1;
         }
-        for i in 0..PRIMES.len() {
+        for i in 0..B::PRIMES.len() {
             let (q, r) =
                 ((self.quadratic_part[i] as u32) * power).div_mod_floor(&(1 << quadratic_power));
-            integral_part *= PRIMES[i].pow(q);
-            quadratic_part[i] = r as u8;
+            integral_part *= B::PRIMES[i].pow(q);
+            quadratic_part[i] = r as u16;
         }
         Self {
             integral_part,
@@ -345,16 +381,19 @@ impl Pow<u32> for IntegralQuadratic {
     }
 }
 
-impl IntegralQuadratic {
+impl<B: PrimeBasis> GenericIntegralQuadratic<B> {
+    /// Whether `self / rhs` is again integral quadratic. Both are single terms `a * radical`, so
+    /// the quotient is exact when the integral parts divide and every prime whose power under the
+    /// root drops is borrowed from the integral quotient.
     pub fn is_divisible_by(&self, rhs: &Self) -> bool {
         if self.integral_part % rhs.integral_part != 0 {
             return false;
         }
         let x = self.integral_part / rhs.integral_part;
-        (0..PRIMES.len()).all(|i| {
-            self.quadratic_part[i] << rhs.quadratic_power
-                >= rhs.quadratic_part[i] << self.quadratic_power
-                || x % PRIMES[i] == 0
+        (0..B::PRIMES.len()).all(|i| {
+            (self.quadratic_part[i] as u32) << rhs.quadratic_power
+                >= (rhs.quadratic_part[i] as u32) << self.quadratic_power
+                || x % B::PRIMES[i] == 0
         })
     }
 
@@ -365,11 +404,11 @@ impl IntegralQuadratic {
             return None;
         }
         let mut p = self.integral_part;
-        let mut quadratic_part: [u8; PRIMES.len()] = self.quadratic_part;
+        let mut quadratic_part: B::Powers = self.quadratic_part;
         let mut quadratic_power = self.quadratic_power + 1;
         let mut integral_part = 1i64;
-        for i in 0..PRIMES.len() {
-            let prime = PRIMES[i];
+        for i in 0..B::PRIMES.len() {
+            let prime = B::PRIMES[i];
             while p % (prime * prime) == 0 {
                 integral_part *= prime;
                 p /= prime * prime;
@@ -380,7 +419,7 @@ impl IntegralQuadratic {
             }
         }
         integral_part *= try_sqrt(p)?;
-        if quadratic_part.iter().all(|&x| x == 0) {
+        if quadratic_part.as_ref().iter().all(|&x| x == 0) {
             quadratic_power = 0;
         }
         Some(Self {
@@ -1,22 +1,107 @@
 use crate::Rational;
+#[cfg(feature = "checkpoint")]
+use serde::{Deserialize, Serialize};
+use std::hash::Hash;
+use std::ops::{Index, IndexMut};
 
 mod integral;
+mod parse;
 mod rational;
 
+pub use parse::ParseQuadraticError;
+
 pub const PRIMES: [i64; 4] = [2, 3, 5, 7];
 
+/// The deepest square root nesting the quadratic types represent. Each prime's power under the
+/// root is stored in a `u16` and below `2^quadratic_power`, so products must fit in 16 bits.
+pub const MAX_QUADRATIC_POWER: u8 = 15;
+
+/// The primes whose nested square roots a quadratic type represents.
+pub trait PrimeBasis: Copy + Eq + Hash + 'static {
+    const PRIMES: &'static [i64];
+    /// The power under the root of each prime, `[u16; PRIMES.len()]`.
+    type Powers: Copy
+        + Default
+        + Eq
+        + Hash
+        + AsRef<[u16]>
+        + AsMut<[u16]>
+        + Index<usize, Output = u16>
+        + IndexMut<usize>;
+}
+
+/// The default basis of [`PRIMES`], which covers every root the solver takes for `n` below 10.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct SmallPrimes;
+
+impl PrimeBasis for SmallPrimes {
+    const PRIMES: &'static [i64] = &PRIMES;
+    type Powers = [u16; PRIMES.len()];
+}
+
 #[derive(Clone, Copy, Eq, Hash, PartialEq)]
-pub struct IntegralQuadratic {
-    integral_part: i64,
-    quadratic_part: [u8; PRIMES.len()],
-    quadratic_power: u8,
+pub struct GenericIntegralQuadratic<B: PrimeBasis> {
+    pub(crate) integral_part: i64,
+    pub(crate) quadratic_part: B::Powers,
+    pub(crate) quadratic_power: u8,
 }
 
 #[derive(Clone, Copy, Eq, Hash, PartialEq)]
-pub struct RationalQuadratic {
-    rational_part: Rational,
-    quadratic_part: [u8; PRIMES.len()],
-    quadratic_power: u8,
+#[cfg_attr(feature = "checkpoint", derive(Deserialize, Serialize))]
+pub struct GenericRationalQuadratic<B: PrimeBasis> {
+    pub(crate) rational_part: Rational,
+    pub(crate) quadratic_part: B::Powers,
+    pub(crate) quadratic_power: u8,
 }
 
-pub struct ParseQuadraticError {}
+pub type IntegralQuadratic = GenericIntegralQuadratic<SmallPrimes>;
+pub type RationalQuadratic = GenericRationalQuadratic<SmallPrimes>;
+
+/// The product of the nested square roots of `x` and `y`, as the primes it takes out from under
+/// the roots, the powers left under them, and their nesting.
+fn multiply_radicals<B: PrimeBasis>(
+    (x, x_power): (&B::Powers, u8),
+    (y, y_power): (&B::Powers, u8),
+) -> (i64, B::Powers, u8) {
+    let mut factor = 1;
+    let mut quadratic_part = B::Powers::default();
+    let mut quadratic_power = u8::max(x_power, y_power);
+    if quadratic_power > 0 {
+        for (i, power) in quadratic_part.as_mut().iter_mut().enumerate() {
+            *power = (x[i] << (quadratic_power - x_power)) + (y[i] << (quadratic_power - y_power));
+        }
+        for (&prime, power) in B::PRIMES.iter().zip(quadratic_part.as_mut()) {
+            if *power >= 1 << quadratic_power {
+                *power &= (1 << quadratic_power) - 1;
+                factor *= prime;
+            }
+        }
+        while quadratic_power > 0 && quadratic_part.as_ref().iter().all(|&x| x % 2 == 0) {
+            quadratic_power -= 1;
+            for x in quadratic_part.as_mut() {
+                *x >>= 1;
+            }
+        }
+    }
+    (factor, quadratic_part, quadratic_power)
+}
+
+/// The number under the nested square roots, factored once it no longer fits in an `i64`.
+fn radicand<B: PrimeBasis>(quadratic_part: &B::Powers) -> String {
+    let product = B::PRIMES
+        .iter()
+        .zip(quadratic_part.as_ref())
+        .try_fold(1i64, |product, (&base, &power)| {
+            product.checked_mul(base.checked_pow(power as u32)?)
+        });
+    match product {
+        Some(product) => product.to_string(),
+        None => B::PRIMES
+            .iter()
+            .zip(quadratic_part.as_ref())
+            .filter(|(_, &power)| power > 0)
+            .map(|(base, power)| format!("{base}^{power}"))
+            .collect::<Vec<_>>()
+            .join("*"),
+    }
+}
@@ -0,0 +1,264 @@
+use super::{GenericIntegralQuadratic, GenericRationalQuadratic, PrimeBasis, MAX_QUADRATIC_POWER};
+use crate::Number;
+use std::fmt;
+use std::num::ParseIntError;
+
+#[derive(Debug)]
+pub enum ParseQuadraticError {
+    /// A coefficient, radicand or exponent is not an integer.
+    Integer(ParseIntError),
+    /// A factor is neither an integer nor a square root, or its parentheses do not match.
+    Syntax(String),
+    DivisionByZero,
+    /// The value is not in the domain: a root of a negative number or of a prime outside the
+    /// basis, a non-integral quotient in the integral type, or nesting deeper than supported.
+    OutOfDomain(String),
+    /// A product, quotient or power overflows the integers of the type, or an exponent is
+    /// larger than any displayed value carries.
+    Overflow(String),
+}
+
+impl fmt::Display for ParseQuadraticError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseQuadraticError::Integer(error) => write!(f, "invalid integer: {error}"),
+            ParseQuadraticError::Syntax(factor) => write!(f, "cannot parse `{factor}`"),
+            ParseQuadraticError::DivisionByZero => write!(f, "division by zero"),
+            ParseQuadraticError::OutOfDomain(factor) => {
+                write!(f, "`{factor}` is not representable")
+            }
+            ParseQuadraticError::Overflow(factor) => write!(f, "`{factor}` overflows"),
+        }
+    }
+}
+
+impl From<ParseIntError> for ParseQuadraticError {
+    #[inline]
+    fn from(error: ParseIntError) -> Self {
+        ParseQuadraticError::Integer(error)
+    }
+}
+
+/// The exact operations parsing needs beyond [`Number`].
+pub(super) trait Radical: Number {
+    fn try_sqrt(&self) -> Option<Self>;
+    fn checked_mul(self, rhs: Self) -> Option<Self>;
+    /// Whether the quotient by `rhs` is in the domain.
+    fn is_divisible_by(&self, rhs: &Self) -> bool;
+    fn checked_div(self, rhs: Self) -> Option<Self>;
+}
+
+impl<B: PrimeBasis> Radical for GenericIntegralQuadratic<B> {
+    #[inline]
+    fn try_sqrt(&self) -> Option<Self> {
+        GenericIntegralQuadratic::try_sqrt(self)
+    }
+
+    #[inline]
+    fn checked_mul(self, rhs: Self) -> Option<Self> {
+        GenericIntegralQuadratic::checked_mul(self, rhs)
+    }
+
+    #[inline]
+    fn is_divisible_by(&self, rhs: &Self) -> bool {
+        GenericIntegralQuadratic::is_divisible_by(self, rhs)
+    }
+
+    #[inline]
+    fn checked_div(self, rhs: Self) -> Option<Self> {
+        Some(self / rhs)
+    }
+}
+
+impl<B: PrimeBasis> Radical for GenericRationalQuadratic<B> {
+    #[inline]
+    fn try_sqrt(&self) -> Option<Self> {
+        GenericRationalQuadratic::try_sqrt(self)
+    }
+
+    #[inline]
+    fn checked_mul(self, rhs: Self) -> Option<Self> {
+        GenericRationalQuadratic::checked_mul(self, rhs)
+    }
+
+    #[inline]
+    fn is_divisible_by(&self, _rhs: &Self) -> bool {
+        true
+    }
+
+    #[inline]
+    fn checked_div(self, rhs: Self) -> Option<Self> {
+        GenericRationalQuadratic::checked_div(self, rhs)
+    }
+}
+
+/// Parses the forms quadratic numbers are displayed in, such as `-2`, `3*sqrt(2)/5` or
+/// `sqrt(sqrt(2^3*3))`: an optionally negated product and quotient of integers and nested
+/// square roots of such products, where every factor may carry an integer exponent.
+pub(super) fn parse<T: Radical>(input: &str, radix: u32) -> Result<T, ParseQuadraticError> {
+    let input = input.trim();
+    match input.strip_prefix('-') {
+        Some(rest) => Ok(T::zero() - product(rest, radix, 0)?),
+        None => product(input, radix, 0),
+    }
+}
+
+/// The `depth`-th nested square root of the product `input`. Roots are taken factor by factor,
+/// so radicands too large for an `i64` parse as long as their prime powers are written out.
+fn product<T: Radical>(input: &str, radix: u32, depth: u8) -> Result<T, ParseQuadraticError> {
+    let mut result = T::one();
+    let mut divide = false;
+    let mut start = 0;
+    let mut parentheses = 0usize;
+    for (position, c) in input.char_indices().chain([(input.len(), '*')]) {
+        match c {
+            '(' => parentheses += 1,
+            ')' => {
+                parentheses = parentheses
+                    .checked_sub(1)
+                    .ok_or_else(|| ParseQuadraticError::Syntax(input.to_string()))?;
+            }
+            '*' | '/' if parentheses == 0 => {
+                let x: T = power(input[start..position].trim(), radix, depth)?;
+                result = if divide {
+                    if x.is_zero() {
+                        return Err(ParseQuadraticError::DivisionByZero);
+                    }
+                    if !result.is_divisible_by(&x) {
+                        return Err(ParseQuadraticError::OutOfDomain(input.to_string()));
+                    }
+                    result.checked_div(x)
+                } else {
+                    result.checked_mul(x)
+                }
+                .ok_or_else(|| ParseQuadraticError::Overflow(input.to_string()))?;
+                divide = c == '/';
+                start = position + 1;
+            }
+            _ => {}
+        }
+    }
+    if parentheses > 0 {
+        return Err(ParseQuadraticError::Syntax(input.to_string()));
+    }
+    Ok(result)
+}
+
+/// A factor `x` or `x^e` under `depth` nested square roots. Displayed radicands carry prime
+/// powers below `2^MAX_QUADRATIC_POWER`, so larger exponents are rejected as overflowing.
+fn power<T: Radical>(input: &str, radix: u32, depth: u8) -> Result<T, ParseQuadraticError> {
+    let (base, mut exponent) = match input.rsplit_once('^') {
+        Some((base, exponent)) if !exponent.contains(')') => {
+            (base.trim(), u32::from_str_radix(exponent.trim(), radix)?)
+        }
+        _ => (input, 1),
+    };
+    let overflow = || ParseQuadraticError::Overflow(input.to_string());
+    if exponent >= 1 << MAX_QUADRATIC_POWER {
+        return Err(overflow());
+    }
+    let mut x: T = factor(base, radix, depth)?;
+    let mut result = T::one();
+    loop {
+        if exponent % 2 == 1 {
+            result = result.checked_mul(x).ok_or_else(overflow)?;
+        }
+        exponent /= 2;
+        if exponent == 0 {
+            return Ok(result);
+        }
+        x = x.checked_mul(x).ok_or_else(overflow)?;
+    }
+}
+
+fn factor<T: Radical>(input: &str, radix: u32, depth: u8) -> Result<T, ParseQuadraticError> {
+    if let Some(inner) = input
+        .strip_prefix("sqrt(")
+        .and_then(|rest| rest.strip_suffix(')'))
+    {
+        if depth >= MAX_QUADRATIC_POWER {
+            return Err(ParseQuadraticError::OutOfDomain(input.to_string()));
+        }
+        return product(inner, radix, depth + 1);
+    }
+    if input.starts_with('(') || input.ends_with(')') || input.is_empty() {
+        return Err(ParseQuadraticError::Syntax(input.to_string()));
+    }
+    let mut x = T::from(i64::from_str_radix(input, radix)?);
+    for _ in 0..depth {
+        x = x
+            .try_sqrt()
+            .ok_or_else(|| ParseQuadraticError::OutOfDomain(format!("sqrt({input})")))?;
+    }
+    Ok(x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ParseQuadraticError;
+    use crate::quadratic::{IntegralQuadratic, RationalQuadratic};
+
+    #[test]
+    fn round_trips_displayed_values() {
+        for input in [
+            "-2",
+            "3*sqrt(2)",
+            "sqrt(sqrt(24))",
+            "-5/7*sqrt(6)",
+            "sqrt(sqrt(2^3*3))",
+        ] {
+            let x: RationalQuadratic = input.parse().unwrap();
+            assert!(x.to_string().parse::<RationalQuadratic>().unwrap() == x);
+        }
+        let x: IntegralQuadratic = "2^3*sqrt(3)/2".parse().unwrap();
+        assert_eq!(x.to_string(), "4*sqrt(3)");
+    }
+
+    #[test]
+    fn rejects_overflow() {
+        for input in [
+            "2^64",
+            "99999999999*99999999999",
+            "1/99999999999/99999999999",
+            "2^4294967295",
+            "1^99999",
+        ] {
+            assert!(matches!(
+                input.parse::<RationalQuadratic>(),
+                Err(ParseQuadraticError::Overflow(_))
+            ));
+        }
+        assert!(matches!(
+            "2^64".parse::<IntegralQuadratic>(),
+            Err(ParseQuadraticError::Overflow(_))
+        ));
+        assert_eq!(
+            "2^62".parse::<IntegralQuadratic>().unwrap().to_string(),
+            (1i64 << 62).to_string()
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        assert!(matches!(
+            "1/0".parse::<RationalQuadratic>(),
+            Err(ParseQuadraticError::DivisionByZero)
+        ));
+        assert!(matches!(
+            "sqrt(2".parse::<RationalQuadratic>(),
+            Err(ParseQuadraticError::Syntax(_))
+        ));
+        assert!(matches!(
+            "sqrt(11)".parse::<RationalQuadratic>(),
+            Err(ParseQuadraticError::OutOfDomain(_))
+        ));
+        assert!(matches!(
+            "1/2".parse::<IntegralQuadratic>(),
+            Err(ParseQuadraticError::OutOfDomain(_))
+        ));
+        assert!(matches!(
+            "x".parse::<RationalQuadratic>(),
+            Err(ParseQuadraticError::Integer(_))
+        ));
+    }
+}
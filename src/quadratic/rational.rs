@@ -1,19 +1,22 @@
-use super::{ParseQuadraticError, RationalQuadratic, PRIMES};
+use super::{
+    multiply_radicals, parse, radicand, GenericRationalQuadratic, ParseQuadraticError, PrimeBasis,
+};
 use crate::number_theory::try_sqrt;
-use crate::{Number, Rational};
+use crate::{Domain, Number, Rational};
 use num::traits::{Inv, Pow};
 use num::{Integer, Num, One, Signed, Zero};
 use std::fmt;
 use std::ops::{Add, Div, Mul, Neg, Rem, Sub};
+use std::str::FromStr;
 
-impl RationalQuadratic {
+impl<B: PrimeBasis> GenericRationalQuadratic<B> {
     #[inline]
     pub fn rational_part(&self) -> Rational {
         self.rational_part
     }
 
     #[inline]
-    pub fn quadratic_part(&self) -> &[u8; PRIMES.len()] {
+    pub fn quadratic_part(&self) -> &B::Powers {
         &self.quadratic_part
     }
 
@@ -21,20 +24,65 @@ impl RationalQuadratic {
     pub fn quadratic_power(&self) -> u8 {
         self.quadratic_power
     }
+
+    pub fn to_f64(&self) -> f64 {
+        let exponent = 0.5f64.powi(self.quadratic_power as i32);
+        B::PRIMES
+            .iter()
+            .zip(self.quadratic_part.as_ref())
+            .map(|(&base, &power)| (base as f64).powf(power as f64 * exponent))
+            .product::<f64>()
+            * self.rational_part.to_f64()
+    }
+
+    /// The product, or `None` if its rational part overflows.
+    pub fn checked_mul(self, rhs: Self) -> Option<Self> {
+        let (factor, quadratic_part, quadratic_power) = multiply_radicals::<B>(
+            (&self.quadratic_part, self.quadratic_power),
+            (&rhs.quadratic_part, rhs.quadratic_power),
+        );
+        let rational_part = self
+            .rational_part
+            .checked_mul(rhs.rational_part)?
+            .checked_mul(Rational::from(factor))?;
+        Some(if rational_part.is_zero() {
+            Self::zero()
+        } else {
+            Self {
+                rational_part,
+                quadratic_part,
+                quadratic_power,
+            }
+        })
+    }
+
+    /// The quotient, or `None` if its rational part overflows.
+    pub fn checked_div(self, rhs: Self) -> Option<Self> {
+        let mut rational_part = rhs.rational_part.inv();
+        let mut quadratic_part = B::Powers::default();
+        for i in 0..B::PRIMES.len() {
+            if rhs.quadratic_part[i] > 0 {
+                rational_part = rational_part.checked_mul(Rational::new_raw(1, B::PRIMES[i]))?;
+                quadratic_part[i] = (1 << rhs.quadratic_power) - rhs.quadratic_part[i];
+            }
+        }
+        self.checked_mul(Self {
+            rational_part,
+            quadratic_part,
+            quadratic_power: rhs.quadratic_power,
+        })
+    }
 }
 
-impl fmt::Display for RationalQuadratic {
+impl<B: PrimeBasis> fmt::Display for GenericRationalQuadratic<B> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         if self.is_rational() {
             write!(f, "{}", self.rational_part)
         } else {
-            let mut number_under_sqrt = 1;
-            for i in 0..PRIMES.len() {
-                number_under_sqrt *= PRIMES[i].pow(self.quadratic_part[i] as u32);
-            }
             let quadratic_string = format!(
-                "{}{number_under_sqrt}{}",
+                "{}{}{}",
                 "sqrt(".repeat(self.quadratic_power as usize),
+                radicand::<B>(&self.quadratic_part),
                 ")".repeat(self.quadratic_power as usize)
             );
             if self.rational_part.denominator() == 1 {
@@ -49,29 +97,9 @@ impl fmt::Display for RationalQuadratic {
     }
 }
 
-impl From<i64> for RationalQuadratic {
-    #[inline]
-    fn from(x: i64) -> Self {
-        Self {
-            rational_part: x.into(),
-            quadratic_part: [0; PRIMES.len()],
-            quadratic_power: 0,
-        }
-    }
-}
-
-impl From<Rational> for RationalQuadratic {
-    #[inline]
-    fn from(x: Rational) -> Self {
-        Self {
-            rational_part: x,
-            quadratic_part: [0; PRIMES.len()],
-            quadratic_power: 0,
-        }
-    }
-}
+impl<B: PrimeBasis> Number for GenericRationalQuadratic<B> {
+    const DOMAIN: Domain = Domain::RationalQuadratic;
 
-impl Number for RationalQuadratic {
     #[inline]
     fn to_int(self) -> Option<i64> {
         if self.quadratic_power == 0 && self.rational_part.is_integer() {
@@ -90,22 +118,38 @@ impl Number for RationalQuadratic {
     fn is_rational(self) -> bool {
         self.quadratic_power == 0
     }
+
+    #[inline]
+    fn to_f64(self) -> f64 {
+        GenericRationalQuadratic::to_f64(&self)
+    }
 }
 
-impl Num for RationalQuadratic {
+impl<B: PrimeBasis> Num for GenericRationalQuadratic<B> {
     type FromStrRadixErr = ParseQuadraticError;
 
-    fn from_str_radix(_str: &str, _radix: u32) -> Result<Self, Self::FromStrRadixErr> {
-        Err(ParseQuadraticError {})
+    /// Parses the format values are displayed in, such as `3*sqrt(2)` or `sqrt(sqrt(2^3*3))`.
+    #[inline]
+    fn from_str_radix(str: &str, radix: u32) -> Result<Self, Self::FromStrRadixErr> {
+        parse::parse(str, radix)
+    }
+}
+
+impl<B: PrimeBasis> FromStr for GenericRationalQuadratic<B> {
+    type Err = ParseQuadraticError;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_str_radix(s, 10)
     }
 }
 
-impl Zero for RationalQuadratic {
+impl<B: PrimeBasis> Zero for GenericRationalQuadratic<B> {
     #[inline]
     fn zero() -> Self {
         Self {
             rational_part: Rational::zero(),
-            quadratic_part: [0; PRIMES.len()],
+            quadratic_part: B::Powers::default(),
             quadratic_power: 0,
         }
     }
@@ -116,12 +160,12 @@ impl Zero for RationalQuadratic {
     }
 }
 
-impl One for RationalQuadratic {
+impl<B: PrimeBasis> One for GenericRationalQuadratic<B> {
     #[inline]
     fn one() -> Self {
         Self {
             rational_part: Rational::one(),
-            quadratic_part: [0; PRIMES.len()],
+            quadratic_part: B::Powers::default(),
             quadratic_power: 0,
         }
     }
@@ -134,17 +178,17 @@ impl One for RationalQuadratic {
 
 #[opimps::impl_uni_ops(Neg)]
 #[inline]
-fn neg(self: RationalQuadratic) -> RationalQuadratic {
-    RationalQuadratic {
+fn neg<B: PrimeBasis>(self: GenericRationalQuadratic<B>) -> GenericRationalQuadratic<B> {
+    GenericRationalQuadratic {
         rational_part: -self.rational_part,
         quadratic_part: self.quadratic_part,
         quadratic_power: self.quadratic_power,
     }
 }
 
-impl Signed for RationalQuadratic {
+impl<B: PrimeBasis> Signed for GenericRationalQuadratic<B> {
     fn abs(&self) -> Self {
-        RationalQuadratic {
+        GenericRationalQuadratic {
             rational_part: self.rational_part.abs(),
             quadratic_part: self.quadratic_part,
             quadratic_power: self.quadratic_power,
@@ -158,7 +202,7 @@ impl Signed for RationalQuadratic {
     fn signum(&self) -> Self {
         Self {
             rational_part: self.rational_part.signum(),
-            quadratic_part: [0; PRIMES.len()],
+            quadratic_part: B::Powers::default(),
             quadratic_power: 0,
         }
     }
@@ -173,7 +217,11 @@ impl Signed for RationalQuadratic {
 }
 
 #[opimps::impl_ops(Add)]
-fn add(self: RationalQuadratic, rhs: RationalQuadratic) -> RationalQuadratic {
+#[allow(clippy::clone_on_copy)]
+fn add<B: PrimeBasis>(
+    self: GenericRationalQuadratic<B>,
+    rhs: GenericRationalQuadratic<B>,
+) -> GenericRationalQuadratic<B> {
     if self.is_zero() {
         rhs.clone()
     } else if rhs.is_zero() {
@@ -181,9 +229,9 @@ fn add(self: RationalQuadratic, rhs: RationalQuadratic) -> RationalQuadratic {
     } else {
         let rational_part = self.rational_part + rhs.rational_part;
         if rational_part.is_zero() {
-            RationalQuadratic::zero()
+            GenericRationalQuadratic::zero()
         } else {
-            RationalQuadratic {
+            GenericRationalQuadratic {
                 rational_part,
                 quadratic_part: self.quadratic_part,
                 quadratic_power: self.quadratic_power,
@@ -194,8 +242,8 @@ fn add(self: RationalQuadratic, rhs: RationalQuadratic) -> RationalQuadratic {
 
 #[opimps::impl_ops(Add)]
 #[inline]
-fn add(self: RationalQuadratic, rhs: i64) -> RationalQuadratic {
-    RationalQuadratic {
+fn add<B: PrimeBasis>(self: GenericRationalQuadratic<B>, rhs: i64) -> GenericRationalQuadratic<B> {
+    GenericRationalQuadratic {
         rational_part: self.rational_part + rhs,
         quadratic_part: self.quadratic_part,
         quadratic_power: self.quadratic_power,
@@ -204,8 +252,11 @@ fn add(self: RationalQuadratic, rhs: i64) -> RationalQuadratic {
 
 #[opimps::impl_ops(Add)]
 #[inline]
-fn add(self: RationalQuadratic, rhs: Rational) -> RationalQuadratic {
-    RationalQuadratic {
+fn add<B: PrimeBasis>(
+    self: GenericRationalQuadratic<B>,
+    rhs: Rational,
+) -> GenericRationalQuadratic<B> {
+    GenericRationalQuadratic {
         rational_part: self.rational_part + rhs,
         quadratic_part: self.quadratic_part,
         quadratic_power: self.quadratic_power,
@@ -213,15 +264,19 @@ fn add(self: RationalQuadratic, rhs: Rational) -> RationalQuadratic {
 }
 
 #[opimps::impl_ops(Sub)]
-fn sub(self: RationalQuadratic, rhs: RationalQuadratic) -> RationalQuadratic {
+#[allow(clippy::clone_on_copy)]
+fn sub<B: PrimeBasis>(
+    self: GenericRationalQuadratic<B>,
+    rhs: GenericRationalQuadratic<B>,
+) -> GenericRationalQuadratic<B> {
     if self.is_zero() {
         -rhs
     } else if rhs.is_zero() {
         self.clone()
     } else if self.rational_part == rhs.rational_part {
-        RationalQuadratic::zero()
+        GenericRationalQuadratic::zero()
     } else {
-        RationalQuadratic {
+        GenericRationalQuadratic {
             rational_part: self.rational_part - rhs.rational_part,
             quadratic_part: self.quadratic_part,
             quadratic_power: self.quadratic_power,
@@ -231,8 +286,8 @@ fn sub(self: RationalQuadratic, rhs: RationalQuadratic) -> RationalQuadratic {
 
 #[opimps::impl_ops(Sub)]
 #[inline]
-fn sub(self: RationalQuadratic, rhs: i64) -> RationalQuadratic {
-    RationalQuadratic {
+fn sub<B: PrimeBasis>(self: GenericRationalQuadratic<B>, rhs: i64) -> GenericRationalQuadratic<B> {
+    GenericRationalQuadratic {
         rational_part: self.rational_part - rhs,
         quadratic_part: self.quadratic_part,
         quadratic_power: self.quadratic_power,
@@ -241,8 +296,11 @@ fn sub(self: RationalQuadratic, rhs: i64) -> RationalQuadratic {
 
 #[opimps::impl_ops(Sub)]
 #[inline]
-fn sub(self: RationalQuadratic, rhs: Rational) -> RationalQuadratic {
-    RationalQuadratic {
+fn sub<B: PrimeBasis>(
+    self: GenericRationalQuadratic<B>,
+    rhs: Rational,
+) -> GenericRationalQuadratic<B> {
+    GenericRationalQuadratic {
         rational_part: self.rational_part - rhs,
         quadratic_part: self.quadratic_part,
         quadratic_power: self.quadratic_power,
@@ -250,34 +308,20 @@ fn sub(self: RationalQuadratic, rhs: Rational) -> RationalQuadratic {
 }
 
 #[opimps::impl_ops(Mul)]
-fn mul(self: RationalQuadratic, rhs: RationalQuadratic) -> RationalQuadratic {
-    let mut rational_part = self.rational_part * rhs.rational_part;
+fn mul<B: PrimeBasis>(
+    self: GenericRationalQuadratic<B>,
+    rhs: GenericRationalQuadratic<B>,
+) -> GenericRationalQuadratic<B> {
+    let rational_part = self.rational_part * rhs.rational_part;
     if rational_part.is_zero() {
-        return RationalQuadratic::zero();
-    }
-    let mut quadratic_part = [0; PRIMES.len()];
-    let mut quadratic_power = u8::max(self.quadratic_power, rhs.quadratic_power);
-    if quadratic_power > 0 {
-        for i in 0..PRIMES.len() {
-            quadratic_part[i] = (self.quadratic_part[i]
-                << (quadratic_power - self.quadratic_power))
-                + (rhs.quadratic_part[i] << (quadratic_power - rhs.quadratic_power));
-        }
-        for (&prime, power) in PRIMES.iter().zip(&mut quadratic_part) {
-            if *power >= 1 << quadratic_power {
-                *power &= (1 << quadratic_power) - 1;
-                rational_part *= prime;
-            }
-        }
-        while quadratic_power > 0 && quadratic_part.iter().all(|&x| x % 2 == 0) {
-            quadratic_power -= 1;
-            for x in &mut quadratic_part {
-                *x >>= 1;
-            }
-        }
-    }
-    RationalQuadratic {
-        rational_part,
+        return GenericRationalQuadratic::zero();
+    }
+    let (factor, quadratic_part, quadratic_power) = multiply_radicals::<B>(
+        (&self.quadratic_part, self.quadratic_power),
+        (&rhs.quadratic_part, rhs.quadratic_power),
+    );
+    GenericRationalQuadratic {
+        rational_part: rational_part * factor,
         quadratic_part,
         quadratic_power,
     }
@@ -285,11 +329,11 @@ fn mul(self: RationalQuadratic, rhs: RationalQuadratic) -> RationalQuadratic {
 
 #[opimps::impl_ops(Mul)]
 #[inline]
-fn mul(self: RationalQuadratic, rhs: i64) -> RationalQuadratic {
+fn mul<B: PrimeBasis>(self: GenericRationalQuadratic<B>, rhs: i64) -> GenericRationalQuadratic<B> {
     if rhs.is_zero() {
-        RationalQuadratic::zero()
+        GenericRationalQuadratic::zero()
     } else {
-        RationalQuadratic {
+        GenericRationalQuadratic {
             rational_part: self.rational_part * rhs,
             quadratic_part: self.quadratic_part,
             quadratic_power: self.quadratic_power,
@@ -299,11 +343,14 @@ fn mul(self: RationalQuadratic, rhs: i64) -> RationalQuadratic {
 
 #[opimps::impl_ops(Mul)]
 #[inline]
-fn mul(self: RationalQuadratic, rhs: Rational) -> RationalQuadratic {
+fn mul<B: PrimeBasis>(
+    self: GenericRationalQuadratic<B>,
+    rhs: Rational,
+) -> GenericRationalQuadratic<B> {
     if rhs.is_zero() {
-        RationalQuadratic::zero()
+        GenericRationalQuadratic::zero()
     } else {
-        RationalQuadratic {
+        GenericRationalQuadratic {
             rational_part: self.rational_part * rhs,
             quadratic_part: self.quadratic_part,
             quadratic_power: self.quadratic_power,
@@ -311,15 +358,15 @@ fn mul(self: RationalQuadratic, rhs: Rational) -> RationalQuadratic {
     }
 }
 
-impl Inv for RationalQuadratic {
-    type Output = RationalQuadratic;
+impl<B: PrimeBasis> Inv for GenericRationalQuadratic<B> {
+    type Output = GenericRationalQuadratic<B>;
 
     fn inv(self) -> Self {
         let mut rational_part = self.rational_part.inv();
-        let mut quadratic_part = [0u8; PRIMES.len()];
-        for i in 0..PRIMES.len() {
+        let mut quadratic_part = B::Powers::default();
+        for i in 0..B::PRIMES.len() {
             if self.quadratic_part[i] > 0 {
-                rational_part /= PRIMES[i];
+                rational_part /= B::PRIMES[i];
                 quadratic_part[i] = (1 << self.quadratic_power) - self.quadratic_part[i];
             }
         }
@@ -331,19 +378,19 @@ impl Inv for RationalQuadratic {
     }
 }
 
-impl Inv for &RationalQuadratic {
-    type Output = RationalQuadratic;
+impl<B: PrimeBasis> Inv for &GenericRationalQuadratic<B> {
+    type Output = GenericRationalQuadratic<B>;
 
-    fn inv(self) -> RationalQuadratic {
+    fn inv(self) -> GenericRationalQuadratic<B> {
         let mut rational_part = self.rational_part.inv();
-        let mut quadratic_part = [0u8; PRIMES.len()];
-        for i in 0..PRIMES.len() {
+        let mut quadratic_part = B::Powers::default();
+        for i in 0..B::PRIMES.len() {
             if self.quadratic_part[i] > 0 {
-                rational_part /= PRIMES[i];
+                rational_part /= B::PRIMES[i];
                 quadratic_part[i] = (1 << self.quadratic_power) - self.quadratic_part[i];
             }
         }
-        RationalQuadratic {
+        GenericRationalQuadratic {
             rational_part,
             quadratic_part,
             quadratic_power: self.quadratic_power,
@@ -352,32 +399,35 @@ impl Inv for &RationalQuadratic {
 }
 
 #[opimps::impl_ops(Div)]
-fn div(self: RationalQuadratic, rhs: RationalQuadratic) -> RationalQuadratic {
+fn div<B: PrimeBasis>(
+    self: GenericRationalQuadratic<B>,
+    rhs: GenericRationalQuadratic<B>,
+) -> GenericRationalQuadratic<B> {
     let mut rational_part = self.rational_part / rhs.rational_part;
     if rational_part.is_zero() {
-        return RationalQuadratic::zero();
+        return GenericRationalQuadratic::zero();
     }
-    let mut quadratic_part = [0; PRIMES.len()];
+    let mut quadratic_part = B::Powers::default();
     let mut quadratic_power = u8::max(self.quadratic_power, rhs.quadratic_power);
     if quadratic_power > 0 {
-        for i in 0..PRIMES.len() {
+        for i in 0..B::PRIMES.len() {
             let x = self.quadratic_part[i] << (quadratic_power - self.quadratic_power);
             let y = rhs.quadratic_part[i] << (quadratic_power - rhs.quadratic_power);
             if x < y {
-                rational_part /= PRIMES[i];
+                rational_part /= B::PRIMES[i];
                 quadratic_part[i] = (1 << quadratic_power) + x - y;
             } else {
                 quadratic_part[i] = x - y;
             }
         }
-        while quadratic_power > 0 && quadratic_part.iter().all(|&x| x % 2 == 0) {
+        while quadratic_power > 0 && quadratic_part.as_ref().iter().all(|&x| x % 2 == 0) {
             quadratic_power -= 1;
-            for x in &mut quadratic_part {
+            for x in quadratic_part.as_mut() {
                 *x >>= 1;
             }
         }
     }
-    RationalQuadratic {
+    GenericRationalQuadratic {
         rational_part,
         quadratic_part,
         quadratic_power,
@@ -386,8 +436,8 @@ fn div(self: RationalQuadratic, rhs: RationalQuadratic) -> RationalQuadratic {
 
 #[opimps::impl_ops(Div)]
 #[inline]
-fn div(self: RationalQuadratic, rhs: i64) -> RationalQuadratic {
-    RationalQuadratic {
+fn div<B: PrimeBasis>(self: GenericRationalQuadratic<B>, rhs: i64) -> GenericRationalQuadratic<B> {
+    GenericRationalQuadratic {
         rational_part: self.rational_part / rhs,
         quadratic_part: self.quadratic_part,
         quadratic_power: self.quadratic_power,
@@ -396,8 +446,11 @@ fn div(self: RationalQuadratic, rhs: i64) -> RationalQuadratic {
 
 #[opimps::impl_ops(Div)]
 #[inline]
-fn div(self: RationalQuadratic, rhs: Rational) -> RationalQuadratic {
-    RationalQuadratic {
+fn div<B: PrimeBasis>(
+    self: GenericRationalQuadratic<B>,
+    rhs: Rational,
+) -> GenericRationalQuadratic<B> {
+    GenericRationalQuadratic {
         rational_part: self.rational_part / rhs,
         quadratic_part: self.quadratic_part,
         quadratic_power: self.quadratic_power,
@@ -406,30 +459,33 @@ fn div(self: RationalQuadratic, rhs: Rational) -> RationalQuadratic {
 
 #[opimps::impl_ops(Rem)]
 #[inline]
-fn rem(self: RationalQuadratic, _rhs: RationalQuadratic) -> RationalQuadratic {
-    RationalQuadratic::zero()
+fn rem<B: PrimeBasis>(
+    self: GenericRationalQuadratic<B>,
+    _rhs: GenericRationalQuadratic<B>,
+) -> GenericRationalQuadratic<B> {
+    GenericRationalQuadratic::zero()
 }
 
-impl Pow<i32> for RationalQuadratic {
-    type Output = RationalQuadratic;
+impl<B: PrimeBasis> Pow<i32> for GenericRationalQuadratic<B> {
+    type Output = GenericRationalQuadratic<B>;
 
-    fn pow(self, power: i32) -> RationalQuadratic {
+    fn pow(self, power: i32) -> GenericRationalQuadratic<B> {
         if power == 0 {
             return Self::one();
         }
         let mut rational_part = self.rational_part.pow(power);
-        let mut quadratic_part = [0u8; PRIMES.len()];
+        let mut quadratic_part = B::Powers::default();
         let mut quadratic_power = self.quadratic_power;
         let mut power = power;
         while quadratic_power > 0 && power % 2 == 0 {
             quadratic_power -= 1;
             power >>= 1;
         }
-        for i in 0..PRIMES.len() {
+        for i in 0..B::PRIMES.len() {
             let (q, r) =
                 ((self.quadratic_part[i] as i32) * power).div_mod_floor(&(1 << quadratic_power));
-            rational_part *= Rational::from(PRIMES[i]).pow(q);
-            quadratic_part[i] = r as u8;
+            rational_part *= Rational::from(B::PRIMES[i]).pow(q);
+            quadratic_part[i] = r as u16;
         }
         Self {
             rational_part,
@@ -439,16 +495,16 @@ impl Pow<i32> for RationalQuadratic {
     }
 }
 
-impl Pow<&i32> for RationalQuadratic {
-    type Output = RationalQuadratic;
+impl<B: PrimeBasis> Pow<&i32> for GenericRationalQuadratic<B> {
+    type Output = GenericRationalQuadratic<B>;
 
     #[inline]
-    fn pow(self, power: &i32) -> RationalQuadratic {
+    fn pow(self, power: &i32) -> GenericRationalQuadratic<B> {
         self.pow(*power)
     }
 }
 
-impl RationalQuadratic {
+impl<B: PrimeBasis> GenericRationalQuadratic<B> {
     pub fn try_sqrt(&self) -> Option<Self> {
         if self.rational_part.is_zero() {
             return Some(*self);
@@ -457,12 +513,12 @@ impl RationalQuadratic {
         }
         let mut p = self.rational_part.numerator();
         let mut q = self.rational_part.denominator();
-        let mut quadratic_part: [u8; PRIMES.len()] = self.quadratic_part;
+        let mut quadratic_part: B::Powers = self.quadratic_part;
         let mut quadratic_power = self.quadratic_power + 1;
         let mut numerator = 1;
         let mut denominator = 1;
-        for i in 0..PRIMES.len() {
-            let prime = PRIMES[i];
+        for i in 0..B::PRIMES.len() {
+            let prime = B::PRIMES[i];
             while p % (prime * prime) == 0 {
                 numerator *= prime;
                 p /= prime * prime;
@@ -483,7 +539,7 @@ impl RationalQuadratic {
         }
         numerator *= try_sqrt(p)?;
         denominator *= try_sqrt(q)?;
-        if quadratic_part.iter().all(|&x| x == 0) {
+        if quadratic_part.as_ref().iter().all(|&x| x == 0) {
             quadratic_power = 0;
         }
         Some(Self {
@@ -0,0 +1,385 @@
+use crate::number_theory::integer_root;
+use crate::{Domain, Limits, Number, ParseRationalError, Rational, PRIMES};
+use num::traits::{Inv, Pow};
+use num::{Integer, Num, One, Signed, Zero};
+use std::fmt;
+use std::ops::{Add, Div, Mul, Neg, Rem, Sub};
+
+/// The common denominator of the exponents of the primes under a radical, divisible by every
+/// root degree up to 10 and by the 16 of square roots nested four deep.
+const DENOMINATOR: i64 = 5040;
+
+/// A number `q * 2^(a/D) * 3^(b/D) * 5^(c/D) * 7^(d/D)` for a rational `q`, `D = 5040` and
+/// `0 <= a, b, c, d < D`, which holds exact cube roots and other radicals beside the square
+/// roots of the quadratic types.
+///
+/// Roots other than square roots come from powers with fractional exponents, such as
+/// `(3*3*3)^(3/(3*3))`. Products, quotients and powers are exact; sums are only exact for terms
+/// with the same [`radical_part`](Self::radical_part), the only ones the solver adds.
+#[derive(Clone, Copy, Eq, Hash, PartialEq)]
+pub struct Radical {
+    rational_part: Rational,
+    radical_part: [u16; PRIMES.len()],
+}
+
+impl Radical {
+    /// `rational_part` times each prime to its exponent over `D`, with whole powers moved into
+    /// the rational part.
+    pub(crate) fn from_exponents(
+        mut rational_part: Rational,
+        exponents: [i64; PRIMES.len()],
+    ) -> Self {
+        if rational_part.is_zero() {
+            return Self::zero();
+        }
+        let mut radical_part = [0; PRIMES.len()];
+        for (i, exponent) in exponents.iter().enumerate() {
+            let (q, r) = exponent.div_mod_floor(&DENOMINATOR);
+            if q != 0 {
+                rational_part *= Rational::from(PRIMES[i]).pow(q as i32);
+            }
+            radical_part[i] = r as u16;
+        }
+        Self {
+            rational_part,
+            radical_part,
+        }
+    }
+
+    #[inline]
+    pub fn rational_part(&self) -> Rational {
+        self.rational_part
+    }
+
+    /// The exponent of each prime of [`PRIMES`], over `D = 5040`.
+    #[inline]
+    pub fn radical_part(&self) -> &[u16; PRIMES.len()] {
+        &self.radical_part
+    }
+
+    /// The degree of the root, 1 for rationals, 2 for square roots and 3 for cube roots.
+    pub fn radical_power(&self) -> u16 {
+        let gcd = self
+            .radical_part
+            .iter()
+            .fold(DENOMINATOR, |gcd, &x| gcd.gcd(&(x as i64)));
+        (DENOMINATOR / gcd) as u16
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        PRIMES
+            .iter()
+            .zip(self.radical_part)
+            .map(|(&base, power)| (base as f64).powf(power as f64 / DENOMINATOR as f64))
+            .product::<f64>()
+            * self.rational_part.to_f64()
+    }
+
+    /// The binary digits of the magnitude, an estimate as radicals are irrational.
+    fn digits(&self) -> f64 {
+        let rational_part = self.rational_part;
+        let mut result = f64::max(
+            (rational_part.numerator().unsigned_abs() as f64).log2(),
+            (rational_part.denominator() as f64).log2(),
+        );
+        for (&prime, &power) in PRIMES.iter().zip(&self.radical_part) {
+            result += (prime as f64).log2() * power as f64 / DENOMINATOR as f64;
+        }
+        result
+    }
+
+    /// The `degree`-th root, if it is a radical.
+    pub fn checked_root(&self, degree: u32) -> Option<Self> {
+        if self.rational_part.is_zero() || degree == 1 {
+            return Some(*self);
+        } else if self.rational_part.is_negative() {
+            return None;
+        }
+        let mut p = self.rational_part.numerator();
+        let mut q = self.rational_part.denominator();
+        let mut exponents = self.radical_part.map(i64::from);
+        for (i, &prime) in PRIMES.iter().enumerate() {
+            while p % prime == 0 {
+                exponents[i] += DENOMINATOR;
+                p /= prime;
+            }
+            while q % prime == 0 {
+                exponents[i] -= DENOMINATOR;
+                q /= prime;
+            }
+        }
+        let degree = degree as i64;
+        if exponents.iter().any(|x| x % degree != 0) {
+            return None;
+        }
+        let root = |x: i64| {
+            if x == 1 {
+                Some(1)
+            } else {
+                integer_root(x, degree)
+            }
+        };
+        Some(Self::from_exponents(
+            Rational::new_raw(root(p)?, root(q)?),
+            exponents.map(|x| x / degree),
+        ))
+    }
+}
+
+/// The number under the root, factored once it no longer fits in an `i64`.
+fn radicand(radical_part: &[u16; PRIMES.len()], radical_power: u16) -> String {
+    let scale = DENOMINATOR / radical_power as i64;
+    let powers = radical_part.map(|x| (x as i64 / scale) as u32);
+    let product = PRIMES
+        .iter()
+        .zip(powers)
+        .try_fold(1i64, |product, (&base, power)| {
+            product.checked_mul(base.checked_pow(power)?)
+        });
+    match product {
+        Some(product) => product.to_string(),
+        None => PRIMES
+            .iter()
+            .zip(powers)
+            .filter(|(_, power)| *power > 0)
+            .map(|(base, power)| format!("{base}^{power}"))
+            .collect::<Vec<_>>()
+            .join("*"),
+    }
+}
+
+impl fmt::Display for Radical {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_rational() {
+            return write!(f, "{}", self.rational_part);
+        }
+        let radical_power = self.radical_power();
+        let radicand = radicand(&self.radical_part, radical_power);
+        let radical_string = if radical_power == 2 {
+            format!("sqrt({radicand})")
+        } else {
+            format!("({radicand})^(1/{radical_power})")
+        };
+        if self.rational_part.is_one() {
+            write!(f, "{radical_string}")
+        } else if (-self.rational_part).is_one() {
+            write!(f, "-{radical_string}")
+        } else {
+            write!(f, "{}*{radical_string}", self.rational_part)
+        }
+    }
+}
+
+impl Number for Radical {
+    const DOMAIN: Domain = Domain::Radical;
+
+    #[inline]
+    fn to_int(self) -> Option<i64> {
+        if self.is_int() {
+            Some(self.rational_part.numerator())
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn is_int(self) -> bool {
+        self.is_rational() && self.rational_part.is_integer()
+    }
+
+    #[inline]
+    fn is_rational(self) -> bool {
+        self.radical_part.iter().all(|&x| x == 0)
+    }
+
+    #[inline]
+    fn to_f64(self) -> f64 {
+        Radical::to_f64(&self)
+    }
+
+    #[inline]
+    fn checked_divide(self, rhs: Self) -> Option<Self> {
+        Some(self / rhs)
+    }
+
+    /// Rational exponents only, `x^(p/q)` being the `q`-th root of `x` to the `p`.
+    fn checked_power(self, exponent: Self, max_digits: usize) -> Option<Self> {
+        if !exponent.is_rational() {
+            return None;
+        }
+        let power = i32::try_from(exponent.rational_part.numerator()).ok()?;
+        let degree = u32::try_from(exponent.rational_part.denominator()).ok()?;
+        if self.is_one() {
+            return Some(self);
+        } else if self.is_zero() {
+            return (power > 0).then_some(self);
+        }
+        if self.digits() * power.unsigned_abs() as f64 / degree as f64 > max_digits as f64 {
+            return None;
+        }
+        Some(self.checked_root(degree)?.pow(power))
+    }
+
+    #[inline]
+    fn checked_sqrt(self) -> Option<Self> {
+        self.checked_root(2)
+    }
+
+    #[inline]
+    fn in_range(&self, limits: &Limits) -> bool {
+        self.rational_part.numerator() <= 1 << limits.max_digits
+            && self.rational_part.denominator() <= 1 << limits.max_digits
+            && self.radical_power() <= limits.max_radical_power
+    }
+}
+
+impl Num for Radical {
+    type FromStrRadixErr = ParseRationalError;
+
+    /// Parses rationals only.
+    #[inline]
+    fn from_str_radix(str: &str, radix: u32) -> Result<Self, Self::FromStrRadixErr> {
+        Rational::from_str_radix(str, radix).map(Self::from)
+    }
+}
+
+impl Zero for Radical {
+    #[inline]
+    fn zero() -> Self {
+        Self {
+            rational_part: Rational::zero(),
+            radical_part: [0; PRIMES.len()],
+        }
+    }
+
+    #[inline]
+    fn is_zero(&self) -> bool {
+        self.rational_part.is_zero()
+    }
+}
+
+impl One for Radical {
+    #[inline]
+    fn one() -> Self {
+        Self {
+            rational_part: Rational::one(),
+            radical_part: [0; PRIMES.len()],
+        }
+    }
+
+    #[inline]
+    fn is_one(&self) -> bool {
+        self.rational_part.is_one() && self.is_rational()
+    }
+}
+
+#[opimps::impl_uni_ops(Neg)]
+#[inline]
+fn neg(self: Radical) -> Radical {
+    Radical {
+        rational_part: -self.rational_part,
+        radical_part: self.radical_part,
+    }
+}
+
+impl Signed for Radical {
+    fn abs(&self) -> Self {
+        Self {
+            rational_part: self.rational_part.abs(),
+            radical_part: self.radical_part,
+        }
+    }
+
+    fn abs_sub(&self, other: &Self) -> Self {
+        (self - other).abs()
+    }
+
+    fn signum(&self) -> Self {
+        Self::from(self.rational_part.signum())
+    }
+
+    fn is_positive(&self) -> bool {
+        self.rational_part.is_positive()
+    }
+
+    fn is_negative(&self) -> bool {
+        self.rational_part.is_negative()
+    }
+}
+
+#[opimps::impl_ops(Add)]
+#[allow(clippy::clone_on_copy)]
+fn add(self: Radical, rhs: Radical) -> Radical {
+    if self.is_zero() {
+        rhs.clone()
+    } else if rhs.is_zero() {
+        self.clone()
+    } else {
+        Radical::from_exponents(
+            self.rational_part + rhs.rational_part,
+            self.radical_part.map(i64::from),
+        )
+    }
+}
+
+#[opimps::impl_ops(Sub)]
+#[allow(clippy::clone_on_copy)]
+fn sub(self: Radical, rhs: Radical) -> Radical {
+    if self.is_zero() {
+        -rhs
+    } else if rhs.is_zero() {
+        self.clone()
+    } else {
+        Radical::from_exponents(
+            self.rational_part - rhs.rational_part,
+            self.radical_part.map(i64::from),
+        )
+    }
+}
+
+#[opimps::impl_ops(Mul)]
+fn mul(self: Radical, rhs: Radical) -> Radical {
+    let mut exponents = [0; PRIMES.len()];
+    for (i, exponent) in exponents.iter_mut().enumerate() {
+        *exponent = self.radical_part[i] as i64 + rhs.radical_part[i] as i64;
+    }
+    Radical::from_exponents(self.rational_part * rhs.rational_part, exponents)
+}
+
+impl Inv for Radical {
+    type Output = Radical;
+
+    #[inline]
+    fn inv(self) -> Self {
+        Self::from_exponents(
+            self.rational_part.inv(),
+            self.radical_part.map(|x| -(x as i64)),
+        )
+    }
+}
+
+#[opimps::impl_ops(Div)]
+fn div(self: Radical, rhs: Radical) -> Radical {
+    let mut exponents = [0; PRIMES.len()];
+    for (i, exponent) in exponents.iter_mut().enumerate() {
+        *exponent = self.radical_part[i] as i64 - rhs.radical_part[i] as i64;
+    }
+    Radical::from_exponents(self.rational_part / rhs.rational_part, exponents)
+}
+
+#[opimps::impl_ops(Rem)]
+#[inline]
+fn rem(self: Radical, _rhs: Radical) -> Radical {
+    Radical::zero()
+}
+
+impl Pow<i32> for Radical {
+    type Output = Radical;
+
+    fn pow(self, power: i32) -> Radical {
+        Self::from_exponents(
+            self.rational_part.pow(power),
+            self.radical_part.map(|x| x as i64 * power as i64),
+        )
+    }
+}
@@ -1,11 +1,16 @@
 use num::traits::{Inv, Pow};
 use num::{Num, One, Signed, Zero};
+#[cfg(feature = "checkpoint")]
+use serde::{Deserialize, Serialize};
 use std::fmt;
+use std::num::ParseIntError;
 use std::ops::{
     Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Rem, RemAssign, Sub, SubAssign,
 };
+use std::str::FromStr;
 
 #[derive(Clone, Copy, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "checkpoint", derive(Deserialize, Serialize))]
 pub struct Rational {
     numerator: i64,
     denominator: i64,
@@ -43,6 +48,24 @@ impl Rational {
     pub fn is_integer(&self) -> bool {
         self.denominator == 1
     }
+
+    #[inline]
+    pub fn to_f64(&self) -> f64 {
+        self.numerator as f64 / self.denominator as f64
+    }
+
+    /// The product, or `None` if its reduced numerator or denominator overflows.
+    pub fn checked_mul(self, rhs: Self) -> Option<Self> {
+        if self.is_zero() || rhs.is_zero() {
+            return Some(Self::zero());
+        }
+        let g = gcd(self.numerator, rhs.denominator);
+        let h = gcd(rhs.numerator, self.denominator);
+        Some(Self::new_raw(
+            (self.numerator / g).checked_mul(rhs.numerator / h)?,
+            (self.denominator / h).checked_mul(rhs.denominator / g)?,
+        ))
+    }
 }
 
 impl Signed for Rational {
@@ -67,12 +90,6 @@ impl Signed for Rational {
     }
 }
 
-impl From<i64> for Rational {
-    fn from(value: i64) -> Self {
-        Self::new_raw(value, 1)
-    }
-}
-
 impl fmt::Display for Rational {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         if self.denominator == 1 {
@@ -83,13 +100,50 @@ impl fmt::Display for Rational {
     }
 }
 
-pub struct ParseRationalError {}
+#[derive(Debug)]
+pub enum ParseRationalError {
+    /// The numerator or the denominator is not an integer.
+    Integer(ParseIntError),
+    ZeroDenominator,
+}
+
+impl fmt::Display for ParseRationalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseRationalError::Integer(error) => write!(f, "invalid integer: {error}"),
+            ParseRationalError::ZeroDenominator => write!(f, "zero denominator"),
+        }
+    }
+}
+
+impl From<ParseIntError> for ParseRationalError {
+    #[inline]
+    fn from(error: ParseIntError) -> Self {
+        ParseRationalError::Integer(error)
+    }
+}
 
 impl Num for Rational {
     type FromStrRadixErr = ParseRationalError;
 
-    fn from_str_radix(_str: &str, _radix: u32) -> Result<Self, Self::FromStrRadixErr> {
-        todo!()
+    /// Parses an integer `a` or a fraction `a/b`, which need not be in lowest terms.
+    fn from_str_radix(str: &str, radix: u32) -> Result<Self, Self::FromStrRadixErr> {
+        let (numerator, denominator) = str.split_once('/').unwrap_or((str, "1"));
+        let numerator = i64::from_str_radix(numerator.trim(), radix)?;
+        let denominator = i64::from_str_radix(denominator.trim(), radix)?;
+        if denominator == 0 {
+            return Err(ParseRationalError::ZeroDenominator);
+        }
+        Ok(Rational::new(numerator, denominator))
+    }
+}
+
+impl FromStr for Rational {
+    type Err = ParseRationalError;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Rational::from_str_radix(s, 10)
     }
 }
 
@@ -197,7 +251,7 @@ impl AddAssign<&i64> for Rational {
 #[inline]
 fn sub(self: Rational, rhs: Rational) -> Rational {
     Rational::new(
-        self.numerator * rhs.denominator + self.denominator * rhs.numerator,
+        self.numerator * rhs.denominator - self.denominator * rhs.numerator,
         self.denominator * rhs.denominator,
     )
 }
@@ -290,6 +344,7 @@ fn div(self: Rational, rhs: Rational) -> Rational {
 
 #[opimps::impl_ops(Div)]
 #[inline]
+#[allow(clippy::suspicious_arithmetic_impl)]
 fn div(self: Rational, rhs: i64) -> Rational {
     Rational::new(self.numerator, self.denominator * rhs)
 }
@@ -362,10 +417,7 @@ impl Pow<u32> for Rational {
         if rhs == 0 {
             Rational::one()
         } else {
-            Rational::new_raw(
-                self.numerator.pow(rhs as u32),
-                self.denominator.pow(rhs as u32),
-            )
+            Rational::new_raw(self.numerator.pow(rhs), self.denominator.pow(rhs))
         }
     }
 }
@@ -0,0 +1,159 @@
+use num::rational::{ParseRatioError, Ratio};
+use num::{Num, One, Signed, Zero};
+use std::fmt;
+use std::ops::{Add, Div, Mul, Neg, Rem, Sub};
+
+/// A rational number with 128-bit numerator and denominator, for rational searches whose
+/// `max_digits` would overflow [`Rational`](crate::Rational). Sums and products of two states
+/// must still fit, so states are kept to numerators and denominators of at most
+/// [`MAX_RATIONAL128_DIGITS`](crate::MAX_RATIONAL128_DIGITS) binary digits whatever the limits.
+#[derive(Clone, Copy, Eq, PartialEq, Hash)]
+pub struct Rational128(Ratio<i128>);
+
+impl Rational128 {
+    /// # Panics
+    ///
+    /// Panics if `denominator` is zero.
+    #[inline]
+    pub fn new(numerator: i128, denominator: i128) -> Self {
+        Self(Ratio::new(numerator, denominator))
+    }
+
+    /// A fraction already in lowest terms with a positive denominator.
+    #[inline]
+    pub(crate) fn new_raw(numerator: i128, denominator: i128) -> Self {
+        Self(Ratio::new_raw(numerator, denominator))
+    }
+
+    #[inline]
+    pub fn numerator(&self) -> i128 {
+        *self.0.numer()
+    }
+
+    #[inline]
+    pub fn denominator(&self) -> i128 {
+        *self.0.denom()
+    }
+
+    #[inline]
+    pub fn is_integer(&self) -> bool {
+        self.0.is_integer()
+    }
+
+    #[inline]
+    pub fn to_f64(&self) -> f64 {
+        self.numerator() as f64 / self.denominator() as f64
+    }
+
+    #[inline]
+    pub fn recip(&self) -> Self {
+        Self(self.0.recip())
+    }
+}
+
+impl fmt::Display for Rational128 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_integer() {
+            write!(f, "{}", self.numerator())
+        } else {
+            write!(f, "{}/{}", self.numerator(), self.denominator())
+        }
+    }
+}
+
+impl Signed for Rational128 {
+    #[inline]
+    fn abs(&self) -> Self {
+        Self(self.0.abs())
+    }
+
+    #[inline]
+    fn abs_sub(&self, other: &Self) -> Self {
+        Self(self.0.abs_sub(&other.0))
+    }
+
+    #[inline]
+    fn signum(&self) -> Self {
+        Self(self.0.signum())
+    }
+
+    #[inline]
+    fn is_positive(&self) -> bool {
+        self.0.is_positive()
+    }
+
+    #[inline]
+    fn is_negative(&self) -> bool {
+        self.0.is_negative()
+    }
+}
+
+impl Num for Rational128 {
+    type FromStrRadixErr = ParseRatioError;
+
+    /// Parses an integer `a` or a fraction `a/b`, which need not be in lowest terms.
+    #[inline]
+    fn from_str_radix(str: &str, radix: u32) -> Result<Self, Self::FromStrRadixErr> {
+        Ratio::from_str_radix(str, radix).map(Self)
+    }
+}
+
+impl Zero for Rational128 {
+    #[inline]
+    fn zero() -> Self {
+        Self(Ratio::zero())
+    }
+
+    #[inline]
+    fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+}
+
+impl One for Rational128 {
+    #[inline]
+    fn one() -> Self {
+        Self(Ratio::one())
+    }
+
+    #[inline]
+    fn is_one(&self) -> bool {
+        self.0.is_one()
+    }
+}
+
+#[opimps::impl_uni_ops(Neg)]
+#[inline]
+fn neg(self: Rational128) -> Rational128 {
+    Rational128(-self.0)
+}
+
+#[opimps::impl_ops(Add)]
+#[inline]
+fn add(self: Rational128, rhs: Rational128) -> Rational128 {
+    Rational128(self.0 + rhs.0)
+}
+
+#[opimps::impl_ops(Sub)]
+#[inline]
+fn sub(self: Rational128, rhs: Rational128) -> Rational128 {
+    Rational128(self.0 - rhs.0)
+}
+
+#[opimps::impl_ops(Mul)]
+#[inline]
+fn mul(self: Rational128, rhs: Rational128) -> Rational128 {
+    Rational128(self.0 * rhs.0)
+}
+
+#[opimps::impl_ops(Div)]
+#[inline]
+fn div(self: Rational128, rhs: Rational128) -> Rational128 {
+    Rational128(self.0 / rhs.0)
+}
+
+#[opimps::impl_ops(Rem)]
+#[inline]
+fn rem(self: Rational128, rhs: Rational128) -> Rational128 {
+    Rational128(self.0 % rhs.0)
+}
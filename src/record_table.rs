@@ -0,0 +1,190 @@
+use crate::{RationalQuadratic, Solution};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// A puzzle: reach `target` using only copies of the digit `n`.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct Problem {
+    pub target: i64,
+    pub n: i64,
+}
+
+impl Problem {
+    /// Whether `solution` solves the problem: written with copies of `n` alone, with as many
+    /// digits as it claims, and evaluating exactly to the target.
+    pub fn is_solved_by(&self, solution: &Solution) -> bool {
+        let expression = solution.expression().as_expression();
+        expression.is_written_with(self.n)
+            && expression.digit_count(self.n) == solution.digits()
+            && expression.evaluate_quadratic() == Some(RationalQuadratic::from(self.target))
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}#{}", self.target, self.n)
+    }
+}
+
+/// The best known solution of every problem in a collection, ordered by target and then `n`.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(from = "Vec<Record>", into = "Vec<Record>")]
+pub struct RecordTable {
+    records: BTreeMap<Problem, Solution>,
+}
+
+/// How two tables compare, problem by problem. Problems solved with the same digit count in
+/// both are not listed.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RecordDiff {
+    /// Solved with fewer digits in the first table.
+    pub better: Vec<Problem>,
+    /// Solved with more digits in the first table.
+    pub worse: Vec<Problem>,
+    /// Solved in the first table only.
+    pub added: Vec<Problem>,
+    /// Solved in the second table only.
+    pub missing: Vec<Problem>,
+}
+
+/// A table entry as stored: JSON object keys cannot be problems, so tables serialize as lists.
+#[derive(Deserialize, Serialize)]
+struct Record {
+    target: i64,
+    n: i64,
+    solution: Solution,
+}
+
+impl RecordTable {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    #[inline]
+    pub fn get(&self, problem: Problem) -> Option<&Solution> {
+        self.records.get(&problem)
+    }
+
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = (Problem, &Solution)> {
+        self.records
+            .iter()
+            .map(|(problem, solution)| (*problem, solution))
+    }
+
+    /// Records `solution` unless the table already solves `problem` with at most as many
+    /// digits. Returns whether the table changed.
+    pub fn insert(&mut self, problem: Problem, solution: Solution) -> bool {
+        match self.records.get(&problem) {
+            Some(record) if record.digits() <= solution.digits() => false,
+            _ => {
+                self.records.insert(problem, solution);
+                true
+            }
+        }
+    }
+
+    /// Inserts every record of `other` that [solves](Problem::is_solved_by) its problem, keeping
+    /// the solution with fewer digits where both tables solve a problem, so that tables from
+    /// elsewhere cannot bring in wrong records. Returns the number of problems that changed.
+    pub fn merge(&mut self, mut other: RecordTable) -> usize {
+        other.retain_valid();
+        other
+            .records
+            .into_iter()
+            .filter(|(problem, solution)| self.insert(*problem, solution.clone()))
+            .count()
+    }
+
+    /// Drops every record that does not [solve](Problem::is_solved_by) its problem.
+    pub(crate) fn retain_valid(&mut self) {
+        self.records
+            .retain(|problem, solution| problem.is_solved_by(solution));
+    }
+
+    pub fn diff(&self, other: &RecordTable) -> RecordDiff {
+        let mut diff = RecordDiff::default();
+        for (problem, solution) in &self.records {
+            match other.records.get(problem) {
+                None => diff.added.push(*problem),
+                Some(record) if solution.digits() < record.digits() => diff.better.push(*problem),
+                Some(record) if solution.digits() > record.digits() => diff.worse.push(*problem),
+                Some(_) => {}
+            }
+        }
+        diff.missing = other
+            .records
+            .keys()
+            .filter(|problem| !self.records.contains_key(problem))
+            .copied()
+            .collect();
+        diff
+    }
+
+    #[inline]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    #[inline]
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+}
+
+impl From<Vec<Record>> for RecordTable {
+    fn from(records: Vec<Record>) -> Self {
+        let mut table = RecordTable::new();
+        for Record {
+            target,
+            n,
+            solution,
+        } in records
+        {
+            table.insert(Problem { target, n }, solution);
+        }
+        table
+    }
+}
+
+impl From<RecordTable> for Vec<Record> {
+    fn from(table: RecordTable) -> Self {
+        table
+            .records
+            .into_iter()
+            .map(|(Problem { target, n }, solution)| Record {
+                target,
+                n,
+                solution,
+            })
+            .collect()
+    }
+}
+
+impl Extend<(Problem, Solution)> for RecordTable {
+    fn extend<I: IntoIterator<Item = (Problem, Solution)>>(&mut self, iter: I) {
+        for (problem, solution) in iter {
+            self.insert(problem, solution);
+        }
+    }
+}
+
+impl FromIterator<(Problem, Solution)> for RecordTable {
+    fn from_iter<I: IntoIterator<Item = (Problem, Solution)>>(iter: I) -> Self {
+        let mut table = RecordTable::new();
+        table.extend(iter);
+        table
+    }
+}
@@ -1,6 +1,13 @@
 use super::{Limits, Solver};
-use crate::{Expression, Number, Rational, RationalQuadratic};
+use crate::solution;
+use crate::solver::{outcome, Interruption};
+use crate::{Bitmap, Domain, Expression, Rational, RationalQuadratic, Solution, SolveOutcome};
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
 use std::rc::Rc;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 enum ReusableSearchState {
     None,
@@ -17,6 +24,8 @@ pub struct ReusableSolver {
     rational_quadratic_solver: Solver<RationalQuadratic>,
     depth_searched: usize,
     search_state: ReusableSearchState,
+    interruption: Option<Interruption>,
+    negation: bool,
 }
 
 impl ReusableSolver {
@@ -36,36 +45,256 @@ impl ReusableSolver {
             ),
             depth_searched: 0,
             search_state: ReusableSearchState::None,
+            interruption: None,
+            negation: false,
         }
     }
 
-    pub fn solve(
-        &mut self,
-        target: i64,
-        max_depth: Option<usize>,
-    ) -> Option<(Rc<Expression>, usize)> {
+    /// Enables `x mod y` on positive integers in every domain.
+    pub fn set_modulo(&mut self, modulo: bool) {
+        self.integral_solver.set_modulo(modulo);
+        self.rational_solver.set_modulo(modulo);
+        self.rational_quadratic_solver.set_modulo(modulo);
+    }
+
+    /// Enables `gcd(x, y)` and `lcm(x, y)` on positive integers in every domain.
+    pub fn set_gcd_lcm(&mut self, gcd_lcm: bool) {
+        self.integral_solver.set_gcd_lcm(gcd_lcm);
+        self.rational_solver.set_gcd_lcm(gcd_lcm);
+        self.rational_quadratic_solver.set_gcd_lcm(gcd_lcm);
+    }
+
+    /// Accepts negative targets, solved by negating the solutions for their absolute values.
+    pub fn set_negation(&mut self, negation: bool) {
+        self.negation = negation;
+    }
+
+    /// Lets a host abort searches by setting `token`, in every domain, as described at
+    /// [`Solver::set_cancel_token`]. The call running then returns as if nothing was found, and
+    /// a later call resumes the depth where it stopped once `token` is cleared.
+    pub fn set_cancel_token(&mut self, token: Option<Arc<AtomicBool>>) {
+        self.integral_solver.set_cancel_token(token.clone());
+        self.rational_solver.set_cancel_token(token.clone());
+        self.rational_quadratic_solver.set_cancel_token(token);
+    }
+
+    /// Stops searches once the states of a domain take about `budget` bytes, as described at
+    /// [`Solver::set_memory_budget`]. Each domain has a budget of its own.
+    pub fn set_memory_budget(&mut self, budget: Option<usize>) {
+        self.integral_solver.set_memory_budget(budget);
+        self.rational_solver.set_memory_budget(budget);
+        self.rational_quadratic_solver.set_memory_budget(budget);
+    }
+
+    /// Whether the last call stopped at the memory budget.
+    #[inline]
+    pub fn memory_exceeded(&self) -> bool {
+        self.interruption == Some(Interruption::MemoryExceeded)
+    }
+
+    pub fn solve(&mut self, target: i64, max_depth: Option<usize>) -> Option<Solution> {
+        if self.negation && target < 0 {
+            return self.solve(-target, max_depth).map(Solution::negate);
+        }
+        let start = solution::now();
         let max_depth = max_depth.unwrap_or(usize::MAX);
         self.target = target;
-        if let Some((expression, digits)) = self.get_solution(&self.target) {
-            return if max_depth >= *digits {
-                Some((expression.clone(), *digits))
+        self.interruption = None;
+        if let Some(((expression, digits), domain)) = self.find_solution(&self.target) {
+            return if max_depth >= digits {
+                Some(Solution::new(
+                    expression,
+                    digits,
+                    domain,
+                    start.map(|start| start.elapsed()),
+                ))
             } else {
                 None
             };
         }
         for digits in self.depth_searched + 1..=max_depth {
+            if self.is_exhausted() || self.interruption.is_some() {
+                break;
+            }
             if self.search(digits) {
-                return Some(self.get_solution(&self.target)?.clone());
+                let ((expression, digits), domain) = self.find_solution(&self.target)?;
+                return Some(Solution::new(
+                    expression,
+                    digits,
+                    domain,
+                    start.map(|start| start.elapsed()),
+                ));
             }
         }
         None
     }
 
-    pub fn get_solution(&self, x: &i64) -> Option<&(Rc<Expression>, usize)> {
+    /// Like [`solve`](Self::solve), but stops once `timeout` has passed, as described at
+    /// [`Solver::solve_with_timeout`].
+    pub fn solve_with_timeout(
+        &mut self,
+        target: i64,
+        max_depth: Option<usize>,
+        timeout: Duration,
+    ) -> SolveOutcome {
+        self.set_timeout(solution::now().map(|now| now + timeout));
+        let solution = self.solve(target, max_depth);
+        self.set_timeout(None);
+        outcome(solution, self.interruption)
+    }
+
+    fn set_timeout(&mut self, timeout: Option<Instant>) {
+        self.integral_solver.set_timeout(timeout);
+        self.rational_solver.set_timeout(timeout);
+        self.rational_quadratic_solver.set_timeout(timeout);
+    }
+
+    /// Searches every depth up to `max_depth` to completion, regardless of the target. A depth
+    /// left half-searched by a solved target is finished first, since each depth goes through
+    /// [`deepen`](Self::deepen). Returns early once no deeper search can find a new state, or
+    /// when the search is interrupted.
+    pub fn exhaust_to_depth(&mut self, max_depth: usize) {
+        self.interruption = None;
+        while self.depth_searched < max_depth && !self.is_exhausted() && self.interruption.is_none()
+        {
+            self.deepen();
+        }
+    }
+
+    /// Searches the next depth to completion, regardless of the target, and returns the number
+    /// of depths now fully searched.
+    ///
+    /// Meant to be called repeatedly while no query is running, so that later targets are
+    /// answered from already-built states.
+    pub fn deepen(&mut self) -> usize {
+        // No state is ever 0, so a depth left unfinished by a solved target runs to completion.
+        self.target = 0;
+        self.search(self.depth_searched + 1);
+        self.depth_searched
+    }
+
+    #[inline]
+    pub fn depth_searched(&self) -> usize {
+        self.depth_searched
+    }
+
+    /// Whether no deeper search can find a new state in any domain under the current limits.
+    pub fn is_exhausted(&self) -> bool {
+        self.integral_solver.is_exhausted()
+            && self.rational_solver.is_exhausted()
+            && self.rational_quadratic_solver.is_exhausted()
+    }
+
+    /// Runs one shared search until every target is solved or `max_depth` is exhausted. Targets
+    /// without a solution within `max_depth` are absent from the result.
+    pub fn solve_many(
+        &mut self,
+        targets: &[i64],
+        max_depth: Option<usize>,
+    ) -> BTreeMap<i64, Solution> {
+        let start = solution::now();
+        let max_depth = max_depth.unwrap_or(usize::MAX);
+        let mut solutions = BTreeMap::new();
+        let mut remaining = targets.to_vec();
+        self.interruption = None;
+        loop {
+            remaining.retain(|target| {
+                if let Some(((expression, digits), domain)) = self.find_solution(target) {
+                    if digits <= max_depth {
+                        solutions.insert(
+                            *target,
+                            Solution::new(
+                                expression,
+                                digits,
+                                domain,
+                                start.map(|start| start.elapsed()),
+                            ),
+                        );
+                    }
+                    false
+                } else {
+                    true
+                }
+            });
+            let Some(&target) = remaining.first() else {
+                break;
+            };
+            self.target = target;
+            let mut found = false;
+            for digits in self.depth_searched + 1..=max_depth {
+                if self.is_exhausted() || self.interruption.is_some() {
+                    break;
+                }
+                if self.search(digits) {
+                    found = true;
+                    break;
+                }
+            }
+            if !found {
+                break;
+            }
+        }
+        solutions
+    }
+
+    /// Solves every target of `targets` from one shared table, deepening whole depths until each
+    /// has a solution or the search is exhausted or interrupted, which is far cheaper than a
+    /// search per target. Each entry is the expression and digit count of the target at the
+    /// same position, or `None` if it was left unsolved.
+    pub fn solve_range(
+        &mut self,
+        targets: RangeInclusive<i64>,
+    ) -> Vec<Option<(Rc<Expression>, usize)>> {
+        self.interruption = None;
+        while !self.is_exhausted()
+            && self.interruption.is_none()
+            && targets
+                .clone()
+                .any(|target| self.find_solution(&target).is_none())
+        {
+            self.deepen();
+        }
+        targets
+            .map(|target| self.find_solution(&target).map(|(state, _)| state))
+            .collect()
+    }
+
+    /// For each digit count `d`, the integers in `[1, limit]` reachable with at most `d` digits
+    /// in any domain.
+    pub fn reachability(&self, limit: u64) -> Vec<Bitmap> {
+        let mut bitmaps = vec![];
+        self.integral_solver.mark_reachable(limit, &mut bitmaps);
+        self.rational_solver.mark_reachable(limit, &mut bitmaps);
+        self.rational_quadratic_solver
+            .mark_reachable(limit, &mut bitmaps);
+        bitmaps
+    }
+
+    pub fn get_solution(&self, x: &i64) -> Option<Solution> {
+        let ((expression, digits), domain) = self.find_solution(x)?;
+        Some(Solution::new(expression, digits, domain, None))
+    }
+
+    /// Every integer reached in any domain, with its digit count. Integers found in the other
+    /// domains are always passed on to the integral one.
+    pub(crate) fn integer_digit_counts(&self) -> impl Iterator<Item = (i64, usize)> + '_ {
+        self.integral_solver.digit_counts()
+    }
+
+    fn find_solution(&self, x: &i64) -> Option<((Rc<Expression>, usize), Domain)> {
         self.integral_solver
-            .get_solution(x)
-            .or_else(|| self.rational_solver.get_solution(&(*x).into()))
-            .or_else(|| self.rational_quadratic_solver.get_solution(&(*x).into()))
+            .get_state(x)
+            .map(|solution| (solution, Domain::Integral))
+            .or_else(|| {
+                self.rational_solver
+                    .get_state(&(*x).into())
+                    .map(|solution| (solution, Domain::Rational))
+            })
+            .or_else(|| {
+                self.rational_quadratic_solver
+                    .get_state(&(*x).into())
+                    .map(|solution| (solution, Domain::RationalQuadratic))
+            })
     }
 
     fn search(&mut self, digits: usize) -> bool {
@@ -80,6 +309,10 @@ impl ReusableSolver {
             {
                 return true;
             }
+            if let Some(interruption) = self.integral_solver.interruption() {
+                self.interruption = Some(interruption);
+                return false;
+            }
             for (&x, expression, _) in self.integral_solver.new_numbers() {
                 self.rational_solver
                     .try_insert(x.into(), digits, || expression.clone());
@@ -97,8 +330,12 @@ impl ReusableSolver {
             {
                 return true;
             }
+            if let Some(interruption) = self.rational_solver.interruption() {
+                self.interruption = Some(interruption);
+                return false;
+            }
             for (x, expression, _) in self.rational_solver.new_numbers() {
-                if let Some(x_int) = x.to_int() {
+                if let Ok(x_int) = i64::try_from(*x) {
                     self.integral_solver
                         .try_insert(x_int, digits, || expression.clone());
                 }
@@ -116,14 +353,18 @@ impl ReusableSolver {
             {
                 return true;
             }
+            if let Some(interruption) = self.rational_quadratic_solver.interruption() {
+                self.interruption = Some(interruption);
+                return false;
+            }
             for (x, expression, _) in self.rational_quadratic_solver.new_numbers() {
-                if let Some(x_int) = x.to_int() {
+                if let Ok(x_int) = i64::try_from(*x) {
                     self.integral_solver
                         .try_insert(x_int, digits, || expression.clone());
                 }
-                if x.is_rational() {
+                if let Ok(x_rational) = Rational::try_from(*x) {
                     self.rational_solver
-                        .try_insert(x.rational_part(), digits, || expression.clone());
+                        .try_insert(x_rational, digits, || expression.clone());
                 }
             }
             self.clear_new_numbers();
@@ -140,3 +381,28 @@ impl ReusableSolver {
         self.rational_quadratic_solver.clear_new_numbers();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::ReusableSolver;
+    use crate::Limits;
+
+    fn solver() -> ReusableSolver {
+        ReusableSolver::new(
+            3,
+            Limits::recommended_integral(),
+            Limits::recommended_rational(),
+            Limits::recommended_quadratic(3),
+        )
+    }
+
+    #[test]
+    fn exhausts_depths_left_unfinished_by_a_solved_target() {
+        let mut solver = solver();
+        // 9 = 3 * 3 is found halfway through depth 2, which the solve leaves unfinished.
+        assert_eq!(solver.solve(9, None).unwrap().digits(), 2);
+        solver.exhaust_to_depth(3);
+        assert_eq!(solver.depth_searched(), 3);
+        assert_eq!(solver.solve(2023, Some(3)), None);
+    }
+}
@@ -0,0 +1,251 @@
+use crate::number_theory::{factorial, subfactorial, try_sqrt};
+use crate::{Expression, Limits, Number, Rational};
+use num::traits::Pow;
+use num::{Integer, Signed};
+use rustc_hash::FxHashMap;
+use std::rc::Rc;
+
+/// Attempts at one random tree before [`Sampler::sample`] gives up.
+const MAX_ATTEMPTS: usize = 64;
+
+/// Values stay below `2^MAX_BITS` whatever the limits, so that the products of numerators and
+/// denominators inside rational operations cannot overflow.
+const MAX_BITS: usize = 31;
+
+/// Factorials and subfactorials above this overflow 64 bits.
+const MAX_FACTORIAL: i64 = 20;
+
+/// Relative error tolerated between [`Expression::approximate`] and an exact value.
+const TOLERANCE: f64 = 1e-9;
+
+/// Generates random expressions with a given number of copies of `n`, computing their values
+/// with the rational operations the solver uses.
+///
+/// Values are kept within the limits, as in [`Solver<Rational>`](crate::Solver), so the
+/// distribution of sampled values hints at how densely the reachable values cover each digit
+/// count.
+pub struct Sampler {
+    n: i64,
+    limits: Limits,
+    modulo: bool,
+    gcd_lcm: bool,
+    seed: u64,
+}
+
+/// The values of a batch of samples, and the samples whose values disagree with the evaluator.
+#[derive(Clone, Default)]
+pub struct SampleReport {
+    /// Random trees generated, valid or not.
+    pub attempts: usize,
+    /// Values produced and how often, most frequent first.
+    pub values: Vec<(Rational, usize)>,
+    /// Samples whose computed value differs from [`Expression::evaluate_rational`], or from
+    /// [`Expression::approximate`] where the exact evaluator gives up.
+    pub mismatches: Vec<(Rc<Expression>, Rational)>,
+}
+
+impl SampleReport {
+    /// The number of valid samples.
+    pub fn samples(&self) -> usize {
+        self.values.iter().map(|(_, count)| count).sum()
+    }
+}
+
+impl Sampler {
+    pub fn new(n: i64, limits: Limits, seed: u64) -> Self {
+        Self {
+            n,
+            limits,
+            modulo: false,
+            gcd_lcm: false,
+            // xorshift never leaves the all-zero state.
+            seed: seed.max(1),
+        }
+    }
+
+    /// Enables the modulo operation on integers.
+    pub fn set_modulo(&mut self, modulo: bool) {
+        self.modulo = modulo;
+    }
+
+    /// Enables gcd and lcm of positive integers.
+    pub fn set_gcd_lcm(&mut self, gcd_lcm: bool) {
+        self.gcd_lcm = gcd_lcm;
+    }
+
+    /// A random valid expression with exactly `copies` copies of `n`, and its value. Returns
+    /// `None` if no valid expression turned up within a fixed number of attempts, as always
+    /// for no copies.
+    pub fn sample(&mut self, copies: usize) -> Option<(Rc<Expression>, Rational)> {
+        (0..MAX_ATTEMPTS).find_map(|_| self.tree(copies))
+    }
+
+    /// Draws `count` random trees with `copies` copies of `n` and checks every valid one
+    /// against the evaluator. No tree has no copies, so the report of that has no samples.
+    pub fn sample_many(&mut self, copies: usize, count: usize) -> SampleReport {
+        let mut values: FxHashMap<Rational, usize> = FxHashMap::default();
+        let mut mismatches = vec![];
+        for _ in 0..count {
+            let Some((expression, value)) = self.tree(copies) else {
+                continue;
+            };
+            *values.entry(value).or_default() += 1;
+            if !agrees(&expression, value) || expression.digit_count(self.n) != copies {
+                mismatches.push((expression, value));
+            }
+        }
+        let mut values: Vec<_> = values.into_iter().collect();
+        values.sort_by(|(x, p), (y, q)| q.cmp(p).then(x.to_f64().total_cmp(&y.to_f64())));
+        SampleReport {
+            attempts: count,
+            values,
+            mismatches,
+        }
+    }
+
+    /// xorshift64*.
+    fn next(&mut self) -> u64 {
+        self.seed ^= self.seed >> 12;
+        self.seed ^= self.seed << 25;
+        self.seed ^= self.seed >> 27;
+        self.seed.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    #[inline]
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+
+    #[inline]
+    fn max_bits(&self) -> usize {
+        self.limits.max_digits.min(MAX_BITS)
+    }
+
+    /// Like the solver's states, values are positive.
+    #[inline]
+    fn in_range(&self, x: Rational) -> bool {
+        x.numerator() > 0
+            && x.numerator() <= 1 << self.max_bits()
+            && x.denominator() <= 1 << self.max_bits()
+    }
+
+    /// One random tree: either `n` concatenated `copies` times or an operation on smaller
+    /// trees, optionally followed by a unary operation. `None` if any step leaves the limits.
+    fn tree(&mut self, copies: usize) -> Option<(Rc<Expression>, Rational)> {
+        if copies == 0 {
+            return None;
+        }
+        let concat_in_range = copies as f64 * 10f64.log2() - 9f64.log2() <= self.max_bits() as f64;
+        let (expression, value) = if copies == 1 || (concat_in_range && self.below(copies) == 0) {
+            let x = (10i64.pow(copies as u32) - 1) / 9 * self.n;
+            (Expression::from_number(x), x.into())
+        } else {
+            let left = 1 + self.below(copies - 1);
+            let x = self.tree(left)?;
+            let y = self.tree(copies - left)?;
+            self.binary_operation(x, y)?
+        };
+        let (expression, value) = if self.in_range(value) && self.below(4) == 0 {
+            self.unary_operation(expression, value)?
+        } else {
+            (expression, value)
+        };
+        self.in_range(value).then_some((expression, value))
+    }
+
+    fn binary_operation(
+        &mut self,
+        (x, p): (Rc<Expression>, Rational),
+        (y, q): (Rc<Expression>, Rational),
+    ) -> Option<(Rc<Expression>, Rational)> {
+        match self.below(8) {
+            0 => Some((Expression::from_add(x, y), p + q)),
+            1 if (p - q).is_negative() => Some((Expression::from_subtract(y, x), q - p)),
+            1 => Some((Expression::from_subtract(x, y), p - q)),
+            2 => Some((Expression::from_multiply(x, y), p * q)),
+            3 => Some((Expression::from_divide(x, y), p / q)),
+            4 => {
+                let exponent = q.to_int()?;
+                let bits = f64::max(
+                    (p.numerator() as f64).log2(),
+                    (p.denominator() as f64).log2(),
+                );
+                if bits * exponent.abs() as f64 > self.max_bits() as f64 {
+                    return None;
+                }
+                Some((Expression::from_power(x, y), p.pow(exponent as i32)))
+            }
+            5 if self.modulo => {
+                let (a, b) = (p.to_int()?, q.to_int()?);
+                Some((Expression::from_modulo(x, y), a.mod_floor(&b).into()))
+            }
+            6 if self.gcd_lcm => {
+                let (a, b) = (p.to_int()?, q.to_int()?);
+                Some((Expression::from_gcd(x, y), a.gcd(&b).into()))
+            }
+            7 if self.gcd_lcm => {
+                let (a, b) = (p.to_int()?, q.to_int()?);
+                Some((Expression::from_lcm(x, y), (a / a.gcd(&b) * b).into()))
+            }
+            _ => None,
+        }
+    }
+
+    fn unary_operation(
+        &mut self,
+        x: Rc<Expression>,
+        p: Rational,
+    ) -> Option<(Rc<Expression>, Rational)> {
+        match self.below(3) {
+            0 => {
+                let value = Rational::new(try_sqrt(p.numerator())?, try_sqrt(p.denominator())?);
+                Some((Expression::from_sqrt(x, 1), value))
+            }
+            1 => {
+                let a = p.to_int()?;
+                (a < self.limits.max_factorial && a <= MAX_FACTORIAL)
+                    .then(|| (Expression::from_factorial(x), factorial(a).into()))
+            }
+            _ => {
+                let a = p.to_int()?;
+                (a >= 3 && a < self.limits.max_subfactorial && a <= MAX_FACTORIAL)
+                    .then(|| (Expression::from_subfactorial(x), subfactorial(a).into()))
+            }
+        }
+    }
+}
+
+/// Whether the evaluator reproduces `value` for `expression`.
+fn agrees(expression: &Expression, value: Rational) -> bool {
+    match expression.evaluate_rational() {
+        Some(exact) => exact == value,
+        None => {
+            let approximation = expression.approximate();
+            let value = value.to_f64();
+            (approximation - value).abs() <= TOLERANCE * value.abs().max(1.0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Sampler;
+    use crate::Limits;
+
+    #[test]
+    fn samples_nothing_without_copies() {
+        let mut sampler = Sampler::new(3, Limits::recommended_rational(), 1);
+        assert!(sampler.sample(0).is_none());
+        let report = sampler.sample_many(0, 16);
+        assert_eq!((report.attempts, report.samples()), (16, 0));
+        assert!(report.mismatches.is_empty());
+    }
+
+    #[test]
+    fn samples_agree_with_the_evaluator() {
+        let mut sampler = Sampler::new(3, Limits::recommended_rational(), 1);
+        let report = sampler.sample_many(4, 256);
+        assert!(report.samples() > 0);
+        assert!(report.mismatches.is_empty());
+    }
+}
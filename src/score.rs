@@ -0,0 +1,68 @@
+use crate::RecordTable;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The digit counts of the solved targets of one `n`, the board of that digit in the game.
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct Board {
+    pub n: i64,
+    /// The digits of the solution of every solved target.
+    pub targets: BTreeMap<i64, usize>,
+}
+
+impl Board {
+    #[inline]
+    pub fn solved(&self) -> usize {
+        self.targets.len()
+    }
+
+    /// The board total the leaderboards rank, lower being better among boards solving the same
+    /// targets.
+    pub fn digits(&self) -> usize {
+        self.targets.values().sum()
+    }
+}
+
+/// A score by the rules of the Tchisla leaderboards: every solved target scores the digits of
+/// its solution, each board totals its targets, and the overall score totals every board.
+/// Unsolved targets do not count.
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct Score {
+    /// One board per `n` with a solved target, in order of `n`.
+    pub boards: Vec<Board>,
+}
+
+impl Score {
+    pub fn solved(&self) -> usize {
+        self.boards.iter().map(Board::solved).sum()
+    }
+
+    pub fn digits(&self) -> usize {
+        self.boards.iter().map(Board::digits).sum()
+    }
+
+    #[inline]
+    pub fn board(&self, n: i64) -> Option<&Board> {
+        self.boards.iter().find(|board| board.n == n)
+    }
+}
+
+impl RecordTable {
+    /// Scores the best solution of every problem in the table.
+    pub fn score(&self) -> Score {
+        let mut boards = BTreeMap::<i64, Board>::new();
+        for (problem, solution) in self.iter() {
+            boards
+                .entry(problem.n)
+                .or_insert_with(|| Board {
+                    n: problem.n,
+                    targets: BTreeMap::new(),
+                })
+                .targets
+                .insert(problem.target, solution.digits());
+        }
+        Score {
+            boards: boards.into_values().collect(),
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use crate::{ExprHandle, Expression};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Domain {
+    Integral,
+    Rational,
+    IntegralQuadratic,
+    RationalQuadratic,
+    Radical,
+    /// A [`Number`](crate::Number) implemented outside this crate.
+    Custom,
+}
+
+impl Domain {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Domain::Integral => "integral",
+            Domain::Rational => "rational",
+            Domain::IntegralQuadratic => "integral_quadratic",
+            Domain::RationalQuadratic => "rational_quadratic",
+            Domain::Radical => "radical",
+            Domain::Custom => "custom",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Format {
+    #[default]
+    Ascii,
+    Unicode,
+    Latex,
+    Json,
+    Csv,
+}
+
+impl Format {
+    /// Header line to print before a sequence of solutions in this format, if any.
+    pub fn header(self) -> Option<&'static str> {
+        match self {
+            Format::Csv => Some("n,target,digits,expression,seconds"),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Solution {
+    expression: ExprHandle,
+    digits: usize,
+    domain: Domain,
+    depth: usize,
+    elapsed: Option<Duration>,
+}
+
+impl Solution {
+    #[inline]
+    pub(crate) fn new(
+        expression: Rc<Expression>,
+        digits: usize,
+        domain: Domain,
+        elapsed: Option<Duration>,
+    ) -> Self {
+        Self {
+            expression: ExprHandle::new(expression),
+            digits,
+            domain,
+            depth: digits,
+            elapsed,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn with_depth(mut self, depth: usize) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    /// The same solution for the opposite target. Negation uses no digits.
+    #[inline]
+    pub(crate) fn negate(mut self) -> Self {
+        self.expression = ExprHandle::new(Expression::from_negate(self.expression.into_inner()));
+        self
+    }
+
+    #[inline]
+    pub fn expression(&self) -> &ExprHandle {
+        &self.expression
+    }
+
+    #[inline]
+    pub fn digits(&self) -> usize {
+        self.digits
+    }
+
+    #[inline]
+    pub fn domain(&self) -> Domain {
+        self.domain
+    }
+
+    /// Search depth at which this solution was found. Usually equal to `digits()`, but a
+    /// progressive search can find a longer solution early through its full-integral pass.
+    #[inline]
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Time from the start of the search to this solution. Always `None` on wasm, where there is
+    /// no monotonic clock.
+    #[inline]
+    pub fn elapsed(&self) -> Option<Duration> {
+        self.elapsed
+    }
+
+    #[inline]
+    pub fn to_latex_string(&self) -> String {
+        self.expression.to_latex_string()
+    }
+
+    /// The object [`Format::Json`] prints, to which callers can add the fields of the problem.
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "digits": self.digits,
+            "expression": self.expression.to_string(),
+            "latex": self.expression.to_latex_string(),
+            "domain": self.domain,
+            "elapsed": self.elapsed.map(|elapsed| elapsed.as_secs_f64()),
+            "depth": self.depth,
+        })
+    }
+
+    /// The row [`Format::Csv`] prints for the solution of `target # n`, under the columns of
+    /// [`Format::header`].
+    pub fn to_csv_row(&self, n: i64, target: &str) -> String {
+        self.csv_row(Some((n, target)))
+    }
+
+    /// The row of the solution, with the columns of the problem left empty without one.
+    fn csv_row(&self, problem: Option<(i64, &str)>) -> String {
+        let (n, target) = problem.map_or((String::new(), String::new()), |(n, target)| {
+            (n.to_string(), csv_quote(target))
+        });
+        format!(
+            "{n},{target},{},{},{}",
+            self.digits,
+            csv_quote(&self.expression.to_string()),
+            self.elapsed
+                .map(|elapsed| elapsed.as_secs_f64().to_string())
+                .unwrap_or_default(),
+        )
+    }
+
+    pub fn format(&self, format: Format) -> String {
+        match format {
+            Format::Ascii => self.expression.to_string(),
+            Format::Unicode => self.expression.as_expression().to_unicode_string(),
+            Format::Latex => self.expression.to_latex_string(),
+            Format::Json => self.to_json().to_string(),
+            // A solution does not know its problem.
+            Format::Csv => self.csv_row(None),
+        }
+    }
+}
+
+/// `field` in quotes, with quotes inside doubled.
+fn csv_quote(field: &str) -> String {
+    format!("\"{}\"", field.replace('"', "\"\""))
+}
+
+impl fmt::Display for Solution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.expression)
+    }
+}
+
+impl From<Solution> for (ExprHandle, usize) {
+    #[inline]
+    fn from(solution: Solution) -> Self {
+        (solution.expression, solution.digits)
+    }
+}
+
+/// How a [`solve_with_timeout`](crate::Solver::solve_with_timeout) call ended.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SolveOutcome {
+    Solved(Solution),
+    /// The search ended without a solution within the depth limit.
+    Unsolved,
+    /// The time budget ran out first. The search keeps its place, so a later call resumes it.
+    Timeout,
+    /// The states outgrew the memory budget first. The search keeps its place, so a later call
+    /// with a larger budget resumes it.
+    MemoryExceeded,
+    /// The state store failed, as reported by
+    /// [`Solver::storage_error`](crate::Solver::storage_error).
+    StorageFailed,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[inline]
+pub(crate) fn now() -> Option<Instant> {
+    Some(Instant::now())
+}
+
+#[cfg(target_arch = "wasm32")]
+#[inline]
+pub(crate) fn now() -> Option<Instant> {
+    None
+}
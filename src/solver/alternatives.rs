@@ -0,0 +1,71 @@
+use super::Solver;
+use crate::{Expression, Number, Solution};
+use std::rc::Rc;
+
+/// The alternative expressions kept per value, beyond the first one found.
+const MAX_ALTERNATIVES: usize = 16;
+
+impl<T: Number> Solver<T> {
+    /// Keeps, for each state, up to a few more expressions reaching it with the same number of
+    /// digits as the first one found, for [`all_solutions`](Self::all_solutions). Expressions
+    /// with the same [`canonical`](Expression::canonical) form count once.
+    ///
+    /// The alternatives are only found as candidates built from the first expression of each
+    /// operand, so those of the operands are not combined, and the pairs modular pruning, goal
+    /// lookup or dead-state pruning skip are not tried at all.
+    #[inline]
+    pub fn set_keep_all(&mut self, keep_all: bool) {
+        self.keep_all = keep_all;
+        if !keep_all {
+            self.alternatives.clear();
+        }
+    }
+
+    /// Every expression found for `target` with the fewest digits, the one
+    /// [`solve`](Self::solve) returns first, or none if `target` is not reached yet.
+    ///
+    /// The search stops at the first solution, so the rest of its depth is searched first to
+    /// find the others, which needs [`set_keep_all`](Self::set_keep_all) to be on.
+    pub fn all_solutions(&mut self, target: &T) -> Vec<Solution> {
+        if self.negation && target.is_negative() {
+            return self
+                .all_solutions(&-*target)
+                .into_iter()
+                .map(Solution::negate)
+                .collect();
+        }
+        let Some(digits) = self.states.digits(target) else {
+            return vec![];
+        };
+        if self.keep_all && (self.depth_searched < digits || self.pruned_depth.is_some()) {
+            self.exhaust_to_depth(digits);
+        }
+        let Some((expression, digits)) = self.states.get(target) else {
+            return vec![];
+        };
+        std::iter::once(expression)
+            .chain(self.alternatives.get(target).into_iter().flatten().cloned())
+            .map(|expression| Solution::new(expression, digits, T::DOMAIN, None))
+            .collect()
+    }
+
+    /// Keeps `expression` as an alternative for the state `x` it reaches with as many digits as
+    /// the first one, unless it is canonically the same as one already kept.
+    pub(super) fn keep_alternative(&mut self, x: T, expression: Rc<Expression>) {
+        let Some((first, _)) = self.states.get(&x) else {
+            return;
+        };
+        let alternatives = self.alternatives.entry(x).or_default();
+        if alternatives.len() >= MAX_ALTERNATIVES {
+            return;
+        }
+        let canonical = expression.canonical();
+        if first.canonical() != canonical
+            && alternatives
+                .iter()
+                .all(|alternative| alternative.canonical() != canonical)
+        {
+            alternatives.push(expression);
+        }
+    }
+}
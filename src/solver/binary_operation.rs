@@ -1,35 +1,44 @@
-use super::{Solver, State};
-use crate::number_theory::factorial_divide;
+use super::modular_pruning::Residues;
+use super::{Operation, Solver, State};
+use crate::number_theory::{factorial_divide_within, power_within, power_within_u128};
 use crate::quadratic::PRIMES;
-use crate::{Expression, IntegralQuadratic, Number, Rational, RationalQuadratic};
+#[cfg(feature = "bigint")]
+use crate::BigNumber;
+use crate::{
+    Expression, IntegralQuadratic, Number, Radical, Rational, RationalQuadratic, MAX_I128_DIGITS,
+};
 use num::traits::{Inv, Pow};
-use num::One;
+#[cfg(feature = "bigint")]
+use num::{BigInt, BigRational, Signed, Zero};
+use num::{Integer, One};
 
+/// The binary digits of the magnitude of a quadratic number, an estimate as its square roots
+/// are irrational. Integers and rationals are measured exactly with [`power_within`].
 trait Digits {
     fn digits(&self) -> f64;
 }
 
-impl Digits for i64 {
-    #[inline]
-    fn digits(&self) -> f64 {
-        (*self as f64).log2()
-    }
+#[inline]
+fn log2(x: i64) -> f64 {
+    (x as f64).log2()
 }
 
-impl Digits for Rational {
-    #[inline]
-    fn digits(&self) -> f64 {
-        f64::max(self.numerator().digits(), self.denominator().digits())
-    }
+/// The larger of the numerator and denominator of `x`, whose bit length bounds its powers.
+#[inline]
+fn magnitude(x: Rational) -> u64 {
+    x.numerator()
+        .unsigned_abs()
+        .max(x.denominator().unsigned_abs())
 }
 
 impl Digits for IntegralQuadratic {
     #[inline]
     fn digits(&self) -> f64 {
-        let mut result = self.integral_part().digits();
+        let mut result = log2(self.integral_part());
         for (prime, power) in PRIMES.iter().zip(self.quadratic_part().iter()) {
             if *power > 0 {
-                result += (*prime as f64).log2() * *power as f64 / 2f64.pow(self.quadratic_power());
+                result += (*prime as f64).log2() * *power as f64
+                    / 2f64.powi(self.quadratic_power().into());
             }
         }
         result
@@ -39,10 +48,15 @@ impl Digits for IntegralQuadratic {
 impl Digits for RationalQuadratic {
     #[inline]
     fn digits(&self) -> f64 {
-        let mut result = self.rational_part().digits();
+        let rational_part = self.rational_part();
+        let mut result = f64::max(
+            log2(rational_part.numerator()),
+            log2(rational_part.denominator()),
+        );
         for (prime, power) in PRIMES.iter().zip(self.quadratic_part().iter()) {
             if *power > 0 {
-                result += (*prime as f64).log2() * *power as f64 / 2f64.pow(self.quadratic_power());
+                result += (*prime as f64).log2() * *power as f64
+                    / 2f64.powi(self.quadratic_power().into());
             }
         }
         result
@@ -57,49 +71,119 @@ pub(super) trait BinaryOperation<T: Number> {
     fn divide(&mut self, x: &State<T>, y: &State<T>) -> bool;
     fn power(&mut self, x: &State<T>, y: &State<T>) -> bool;
     fn factorial_divide(&mut self, x: &State<T>, y: &State<T>) -> bool;
+    fn modulo(&mut self, x: &State<T>, y: &State<T>) -> bool;
+    fn gcd_lcm(&mut self, x: &State<T>, y: &State<T>) -> bool;
 }
 
 impl<T: Number> BinaryOperation<T> for Solver<T> {
-    default fn binary_operation(&mut self, _x: State<T>, _y: State<T>) -> bool {
-        false
+    /// Every operation on a pair of states, for domains without specialized search code.
+    default fn binary_operation(&mut self, x: State<T>, y: State<T>) -> bool {
+        let mut found = false;
+        if self.add(&x, &y) {
+            found = true;
+        }
+        if self.subtract(&x, &y) {
+            found = true;
+        }
+        if self.multiply(&x, &y) {
+            found = true;
+        }
+        if self.divide(&x, &y) {
+            found = true;
+        }
+        if self.divide(&y, &x) {
+            found = true;
+        }
+        if self.power(&x, &y) {
+            found = true;
+        }
+        if self.power(&y, &x) {
+            found = true;
+        }
+        if x.number.is_int() && y.number.is_int() && self.factorial_divide(&x, &y) {
+            found = true;
+        }
+        if self.modulo(&x, &y) {
+            found = true;
+        }
+        if self.gcd_lcm(&x, &y) {
+            found = true;
+        }
+        found
     }
 
     default fn add(&mut self, x: &State<T>, y: &State<T>) -> bool {
-        self.try_insert(x.number + y.number, x.digits + y.digits, || {
-            Expression::from_add(x.expression.clone(), y.expression.clone())
+        self.set_producer(Operation::Add, [Some(x.number), Some(y.number)]);
+        self.try_insert_with(x.number + y.number, x.digits + y.digits, |solver| {
+            Expression::from_add(
+                solver.expression(x.index).clone(),
+                solver.expression(y.index).clone(),
+            )
         })
     }
 
     default fn subtract(&mut self, x: &State<T>, y: &State<T>) -> bool {
+        self.set_producer(Operation::Subtract, [Some(x.number), Some(y.number)]);
         let result = x.number - y.number;
         if result.is_zero() {
             false
         } else if result.is_negative() {
-            self.try_insert(-result, x.digits + y.digits, || {
-                Expression::from_subtract(y.expression.clone(), x.expression.clone())
+            self.try_insert_with(-result, x.digits + y.digits, |solver| {
+                Expression::from_subtract(
+                    solver.expression(y.index).clone(),
+                    solver.expression(x.index).clone(),
+                )
             })
         } else {
-            self.try_insert(result, x.digits + y.digits, || {
-                Expression::from_subtract(x.expression.clone(), y.expression.clone())
+            self.try_insert_with(result, x.digits + y.digits, |solver| {
+                Expression::from_subtract(
+                    solver.expression(x.index).clone(),
+                    solver.expression(y.index).clone(),
+                )
             })
         }
     }
 
     default fn multiply(&mut self, x: &State<T>, y: &State<T>) -> bool {
-        self.try_insert(x.number * y.number, x.digits + y.digits, || {
-            Expression::from_multiply(x.expression.clone(), y.expression.clone())
+        self.set_producer(Operation::Multiply, [Some(x.number), Some(y.number)]);
+        self.try_insert_with(x.number * y.number, x.digits + y.digits, |solver| {
+            Expression::from_multiply(
+                solver.expression(x.index).clone(),
+                solver.expression(y.index).clone(),
+            )
         })
     }
 
-    default fn divide(&mut self, _x: &State<T>, _y: &State<T>) -> bool {
-        false
+    default fn divide(&mut self, x: &State<T>, y: &State<T>) -> bool {
+        self.set_producer(Operation::Divide, [Some(x.number), Some(y.number)]);
+        if let Some(z) = x.number.checked_divide(y.number) {
+            self.try_insert_with(z, x.digits + y.digits, |solver| {
+                Expression::from_divide(
+                    solver.expression(x.index).clone(),
+                    solver.expression(y.index).clone(),
+                )
+            })
+        } else {
+            false
+        }
     }
 
-    default fn power(&mut self, _x: &State<T>, _y: &State<T>) -> bool {
-        false
+    default fn power(&mut self, x: &State<T>, y: &State<T>) -> bool {
+        self.set_producer(Operation::Power, [Some(x.number), Some(y.number)]);
+        if let Some(z) = x.number.checked_power(y.number, self.limits.max_digits) {
+            self.try_insert_with(z, x.digits + y.digits, |solver| {
+                Expression::from_power(
+                    solver.expression(x.index).clone(),
+                    solver.expression(y.index).clone(),
+                )
+            })
+        } else {
+            false
+        }
     }
 
     default fn factorial_divide(&mut self, x: &State<T>, y: &State<T>) -> bool {
+        self.set_producer(Operation::FactorialDivide, [Some(x.number), Some(y.number)]);
         if x.number == y.number {
             return false;
         }
@@ -111,44 +195,122 @@ impl<T: Number> BinaryOperation<T> for Solver<T> {
             (x, y) = (y, x);
             (x_int, y_int) = (y_int, x_int);
         }
-        if x_int <= self.limits.max_factorial
-            || y_int <= 2
-            || x_int - y_int == 1
-            || (x_int - y_int) as f64 * (x_int.digits() + y_int.digits())
-                > self.limits.max_digits as f64 * 2.0
-        {
+        if x_int <= self.limits.max_factorial || y_int <= 2 || x_int - y_int == 1 {
             return false;
         }
-        self.try_insert(
-            factorial_divide(x_int, y_int).into(),
-            x.digits + y.digits,
-            || {
-                Expression::from_divide(
-                    Expression::from_factorial(x.expression.clone()),
-                    Expression::from_factorial(y.expression.clone()),
+        let Some(result) = factorial_divide_within(x_int, y_int, self.limits.max_digits) else {
+            return false;
+        };
+        self.try_insert_with(result.into(), x.digits + y.digits, |solver| {
+            Expression::from_divide(
+                Expression::from_factorial(solver.expression(x.index).clone()),
+                Expression::from_factorial(solver.expression(y.index).clone()),
+            )
+        })
+    }
+
+    /// The larger of two integers modulo the smaller, when modulo is enabled.
+    fn modulo(&mut self, x: &State<T>, y: &State<T>) -> bool {
+        self.set_producer(Operation::Modulo, [Some(x.number), Some(y.number)]);
+        if !self.modulo {
+            return false;
+        }
+        let (Some(mut x_int), Some(mut y_int)) = (x.number.to_int(), y.number.to_int()) else {
+            return false;
+        };
+        let mut x = x;
+        let mut y = y;
+        if x_int < y_int {
+            (x, y) = (y, x);
+            (x_int, y_int) = (y_int, x_int);
+        }
+        if y_int <= 1 || x_int % y_int == 0 {
+            return false;
+        }
+        self.try_insert_with((x_int % y_int).into(), x.digits + y.digits, |solver| {
+            Expression::from_modulo(
+                solver.expression(x.index).clone(),
+                solver.expression(y.index).clone(),
+            )
+        })
+    }
+
+    /// The gcd and lcm of two positive integers, when gcd/lcm are enabled. Results equal to
+    /// either operand or to their product are left to the cheaper operations.
+    fn gcd_lcm(&mut self, x: &State<T>, y: &State<T>) -> bool {
+        self.set_producer(Operation::Gcd, [Some(x.number), Some(y.number)]);
+        if !self.gcd_lcm {
+            return false;
+        }
+        let (Some(x_int), Some(y_int)) = (x.number.to_int(), y.number.to_int()) else {
+            return false;
+        };
+        if x_int <= 1 || y_int <= 1 {
+            return false;
+        }
+        let gcd = x_int.gcd(&y_int);
+        if gcd == 1 || gcd == x_int || gcd == y_int {
+            return false;
+        }
+        let mut found = false;
+        if self.try_insert_with(gcd.into(), x.digits + y.digits, |solver| {
+            Expression::from_gcd(
+                solver.expression(x.index).clone(),
+                solver.expression(y.index).clone(),
+            )
+        }) {
+            found = true;
+        }
+        if let Some(lcm) = (x_int / gcd).checked_mul(y_int) {
+            self.set_producer(Operation::Lcm, [Some(x.number), Some(y.number)]);
+            if self.try_insert_with(lcm.into(), x.digits + y.digits, |solver| {
+                Expression::from_lcm(
+                    solver.expression(x.index).clone(),
+                    solver.expression(y.index).clone(),
                 )
-            },
-        )
+            }) {
+                found = true;
+            }
+        }
+        found
     }
 }
 
 impl BinaryOperation<i64> for Solver<i64> {
     fn binary_operation(&mut self, x: State<i64>, y: State<i64>) -> bool {
+        let (may_divide, may_multiply, may_add, may_subtract) = match self.goal_residues {
+            Some(goal_residues) if x.digits + y.digits == self.max_depth => {
+                let (larger, smaller) = if x.number < y.number {
+                    (Residues::of(y.number), Residues::of(x.number))
+                } else {
+                    (Residues::of(x.number), Residues::of(y.number))
+                };
+                (
+                    goal_residues.accepts_quotient(&larger, &smaller),
+                    goal_residues.accepts(&larger.multiply(&smaller)),
+                    goal_residues.accepts(&larger.add(&smaller)),
+                    goal_residues.accepts(&larger.subtract(&smaller)),
+                )
+            }
+            _ => (true, true, true, true),
+        };
         let mut found = false;
-        if x.number < y.number {
-            if self.divide(&y, &x) {
+        if may_divide {
+            if x.number < y.number {
+                if self.divide(&y, &x) {
+                    found = true;
+                }
+            } else if self.divide(&x, &y) {
                 found = true;
             }
-        } else if self.divide(&x, &y) {
-            found = true;
         }
-        if self.multiply(&x, &y) {
+        if may_multiply && self.multiply(&x, &y) {
             found = true;
         }
-        if self.add(&x, &y) {
+        if may_add && self.add(&x, &y) {
             found = true;
         }
-        if self.subtract(&x, &y) {
+        if may_subtract && self.subtract(&x, &y) {
             found = true;
         }
         if self.power(&x, &y) {
@@ -160,13 +322,23 @@ impl BinaryOperation<i64> for Solver<i64> {
         if self.factorial_divide(&x, &y) {
             found = true;
         }
+        if self.modulo(&x, &y) {
+            found = true;
+        }
+        if self.gcd_lcm(&x, &y) {
+            found = true;
+        }
         found
     }
 
     fn multiply(&mut self, x: &State<i64>, y: &State<i64>) -> bool {
+        self.set_producer(Operation::Multiply, [Some(x.number), Some(y.number)]);
         if let Some(z) = x.number.checked_mul(y.number) {
-            self.try_insert(z, x.digits + y.digits, || {
-                Expression::from_multiply(x.expression.clone(), y.expression.clone())
+            self.try_insert_with(z, x.digits + y.digits, |solver| {
+                Expression::from_multiply(
+                    solver.expression(x.index).clone(),
+                    solver.expression(y.index).clone(),
+                )
             })
         } else {
             false
@@ -174,18 +346,25 @@ impl BinaryOperation<i64> for Solver<i64> {
     }
 
     fn divide(&mut self, x: &State<i64>, y: &State<i64>) -> bool {
+        self.set_producer(Operation::Divide, [Some(x.number), Some(y.number)]);
         if x.number == y.number {
-            return if x.number == self.n {
-                self.try_insert(1, 2, || {
-                    Expression::from_divide(x.expression.clone(), x.expression.clone())
+            return if self.is_digit(x.number) {
+                self.try_insert_with(1, 2, |solver| {
+                    Expression::from_divide(
+                        solver.expression(x.index).clone(),
+                        solver.expression(x.index).clone(),
+                    )
                 })
             } else {
                 false
             };
         }
         if x.number % y.number == 0 {
-            self.try_insert(x.number / y.number, x.digits + y.digits, || {
-                Expression::from_divide(x.expression.clone(), y.expression.clone())
+            self.try_insert_with(x.number / y.number, x.digits + y.digits, |solver| {
+                Expression::from_divide(
+                    solver.expression(x.index).clone(),
+                    solver.expression(y.index).clone(),
+                )
             })
         } else {
             false
@@ -193,26 +372,29 @@ impl BinaryOperation<i64> for Solver<i64> {
     }
 
     fn power(&mut self, x: &State<i64>, y: &State<i64>) -> bool {
+        self.set_producer(Operation::Power, [Some(x.number), Some(y.number)]);
         if x.number == 1 || y.number == 1 {
             return false;
         }
-        let x_digits = x.number.digits();
         if y.number > 0x80000000 {
             return false;
         }
         let mut exponent = y.number as u32;
         let mut sqrt_order = 0usize;
-        while x_digits * exponent as f64 > self.limits.max_digits as f64 {
-            if exponent % 2 == 0 {
+        while !power_within(x.number.unsigned_abs(), exponent, self.limits.max_digits) {
+            if exponent.is_multiple_of(2) {
                 exponent >>= 1;
                 sqrt_order += 1;
             } else {
                 return false;
             }
         }
-        self.try_insert(x.number.pow(exponent), x.digits + y.digits, || {
+        self.try_insert_with(x.number.pow(exponent), x.digits + y.digits, |solver| {
             Expression::from_sqrt(
-                Expression::from_power(x.expression.clone(), y.expression.clone()),
+                Expression::from_power(
+                    solver.expression(x.index).clone(),
+                    solver.expression(y.index).clone(),
+                ),
                 sqrt_order,
             )
         })
@@ -245,14 +427,24 @@ impl BinaryOperation<Rational> for Solver<Rational> {
         if x.number.is_integer() && y.number.is_integer() && self.factorial_divide(&x, &y) {
             found = true;
         }
+        if self.modulo(&x, &y) {
+            found = true;
+        }
+        if self.gcd_lcm(&x, &y) {
+            found = true;
+        }
         found
     }
 
     fn divide(&mut self, x: &State<Rational>, y: &State<Rational>) -> bool {
+        self.set_producer(Operation::Divide, [Some(x.number), Some(y.number)]);
         if x.number == y.number {
-            return if x.number.to_int() == Some(self.n) {
-                self.try_insert(Rational::one(), 2, || {
-                    Expression::from_divide(x.expression.clone(), x.expression.clone())
+            return if x.number.to_int().is_some_and(|x| self.is_digit(x)) {
+                self.try_insert_with(Rational::one(), 2, |solver| {
+                    Expression::from_divide(
+                        solver.expression(x.index).clone(),
+                        solver.expression(x.index).clone(),
+                    )
                 })
             } else {
                 false
@@ -260,16 +452,22 @@ impl BinaryOperation<Rational> for Solver<Rational> {
         }
         let mut found = false;
         let result = x.number / y.number;
-        if !y.expression.is_divide()
-            && self.try_insert(result, x.digits + y.digits, || {
-                Expression::from_divide(x.expression.clone(), y.expression.clone())
+        if !self.expression(y.index).is_divide()
+            && self.try_insert_with(result, x.digits + y.digits, |solver| {
+                Expression::from_divide(
+                    solver.expression(x.index).clone(),
+                    solver.expression(y.index).clone(),
+                )
             })
         {
             found = true;
         }
-        if !x.expression.is_divide()
-            && self.try_insert(result.inv(), x.digits + y.digits, || {
-                Expression::from_divide(y.expression.clone(), x.expression.clone())
+        if !self.expression(x.index).is_divide()
+            && self.try_insert_with(result.inv(), x.digits + y.digits, |solver| {
+                Expression::from_divide(
+                    solver.expression(y.index).clone(),
+                    solver.expression(x.index).clone(),
+                )
             })
         {
             found = true;
@@ -278,13 +476,13 @@ impl BinaryOperation<Rational> for Solver<Rational> {
     }
 
     fn power(&mut self, x: &State<Rational>, y: &State<Rational>) -> bool {
+        self.set_producer(Operation::Power, [Some(x.number), Some(y.number)]);
         if x.number.is_one() || y.number.is_one() || y.number.numerator() > 0x40000000 {
             return false;
         }
-        let x_digits = x.number.digits();
         let mut exponent = y.number.numerator() as i32;
         let mut sqrt_order = 0usize;
-        while x_digits * exponent as f64 > self.limits.max_digits as f64 {
+        while !power_within(magnitude(x.number), exponent as u32, self.limits.max_digits) {
             if exponent % 2 == 0 {
                 exponent >>= 1;
                 sqrt_order += 1;
@@ -294,20 +492,23 @@ impl BinaryOperation<Rational> for Solver<Rational> {
         }
         let mut found = false;
         let z = x.number.pow(exponent);
-        if self.try_insert(z, x.digits + y.digits, || {
+        if self.try_insert_with(z, x.digits + y.digits, |solver| {
             Expression::from_sqrt(
-                Expression::from_power(x.expression.clone(), y.expression.clone()),
+                Expression::from_power(
+                    solver.expression(x.index).clone(),
+                    solver.expression(y.index).clone(),
+                ),
                 sqrt_order,
             )
         }) {
             found = true;
         }
-        if !x.expression.is_divide()
-            && self.try_insert(z.inv(), x.digits + y.digits, || {
+        if !self.expression(x.index).is_divide()
+            && self.try_insert_with(z.inv(), x.digits + y.digits, |solver| {
                 Expression::from_sqrt(
                     Expression::from_power(
-                        x.expression.clone(),
-                        Expression::from_negate(y.expression.clone()),
+                        solver.expression(x.index).clone(),
+                        Expression::from_negate(solver.expression(y.index).clone()),
                     ),
                     sqrt_order,
                 )
@@ -319,6 +520,7 @@ impl BinaryOperation<Rational> for Solver<Rational> {
     }
 
     fn factorial_divide(&mut self, x: &State<Rational>, y: &State<Rational>) -> bool {
+        self.set_producer(Operation::FactorialDivide, [Some(x.number), Some(y.number)]);
         if x.number == y.number {
             return false;
         }
@@ -330,24 +532,22 @@ impl BinaryOperation<Rational> for Solver<Rational> {
             (x, y) = (y, x);
             (x_int, y_int) = (y_int, x_int);
         }
-        if x_int <= self.limits.max_factorial
-            || y_int <= 2
-            || x_int - y_int == 1
-            || (x_int - y_int) as f64 * (x_int.digits() + y_int.digits())
-                > self.limits.max_digits as f64 * 2.0
-        {
+        if x_int <= self.limits.max_factorial || y_int <= 2 || x_int - y_int == 1 {
             return false;
         }
+        let Some(result) = factorial_divide_within(x_int, y_int, self.limits.max_digits) else {
+            return false;
+        };
         let mut found = false;
-        let x_expression = Expression::from_factorial(x.expression.clone());
-        let y_expression = Expression::from_factorial(y.expression.clone());
-        let result = factorial_divide(x_int, y_int).into();
-        if self.try_insert(result, x.digits + y.digits, || {
+        let x_expression = Expression::from_factorial(self.expression(x.index).clone());
+        let y_expression = Expression::from_factorial(self.expression(y.index).clone());
+        let result = result.into();
+        if self.try_insert_with(result, x.digits + y.digits, |_| {
             Expression::from_divide(x_expression.clone(), y_expression.clone())
         }) {
             found = true;
         }
-        if self.try_insert(result.inv(), x.digits + y.digits, || {
+        if self.try_insert_with(result.inv(), x.digits + y.digits, |_| {
             Expression::from_divide(y_expression, x_expression)
         }) {
             found = true;
@@ -363,11 +563,12 @@ impl BinaryOperation<IntegralQuadratic> for Solver<IntegralQuadratic> {
         y: State<IntegralQuadratic>,
     ) -> bool {
         let mut found = false;
-        if x.number.integral_part() < y.number.integral_part() {
-            if self.divide(&y, &x) {
-                found = true;
-            }
-        } else if self.divide(&x, &y) {
+        let (a, b) = (x.number.integral_part(), y.number.integral_part());
+        if a >= b && self.divide(&x, &y) {
+            found = true;
+        }
+        // With equal integral parts either quotient may be exact, as in 2*sqrt(2)/2.
+        if a <= b && x.number != y.number && self.divide(&y, &x) {
             found = true;
         }
         if !self.progressive || !x.number.is_int() || !y.number.is_int() {
@@ -398,22 +599,35 @@ impl BinaryOperation<IntegralQuadratic> for Solver<IntegralQuadratic> {
         {
             found = true;
         }
+        if self.modulo(&x, &y) {
+            found = true;
+        }
+        if self.gcd_lcm(&x, &y) {
+            found = true;
+        }
         found
     }
 
     fn divide(&mut self, x: &State<IntegralQuadratic>, y: &State<IntegralQuadratic>) -> bool {
+        self.set_producer(Operation::Divide, [Some(x.number), Some(y.number)]);
         if x.number == y.number {
-            return if x.number.to_int() == Some(self.n) {
-                self.try_insert(IntegralQuadratic::one(), 2, || {
-                    Expression::from_divide(x.expression.clone(), x.expression.clone())
+            return if x.number.to_int().is_some_and(|x| self.is_digit(x)) {
+                self.try_insert_with(IntegralQuadratic::one(), 2, |solver| {
+                    Expression::from_divide(
+                        solver.expression(x.index).clone(),
+                        solver.expression(x.index).clone(),
+                    )
                 })
             } else {
                 false
             };
         }
         if x.number.is_divisible_by(&y.number) {
-            self.try_insert(x.number / y.number, x.digits + y.digits, || {
-                Expression::from_divide(x.expression.clone(), y.expression.clone())
+            self.try_insert_with(x.number / y.number, x.digits + y.digits, |solver| {
+                Expression::from_divide(
+                    solver.expression(x.index).clone(),
+                    solver.expression(y.index).clone(),
+                )
             })
         } else {
             false
@@ -421,6 +635,7 @@ impl BinaryOperation<IntegralQuadratic> for Solver<IntegralQuadratic> {
     }
 
     fn power(&mut self, x: &State<IntegralQuadratic>, y: &State<IntegralQuadratic>) -> bool {
+        self.set_producer(Operation::Power, [Some(x.number), Some(y.number)]);
         if x.number.is_one() || y.number.is_one() {
             return false;
         }
@@ -432,16 +647,19 @@ impl BinaryOperation<IntegralQuadratic> for Solver<IntegralQuadratic> {
         let x_digits = x.number.digits();
         let mut sqrt_order = 0usize;
         while x_digits * exponent as f64 > self.limits.max_digits as f64 {
-            if exponent % 2 == 0 {
+            if exponent.is_multiple_of(2) {
                 exponent >>= 1;
                 sqrt_order += 1;
             } else {
                 return false;
             }
         }
-        self.try_insert(x.number.pow(exponent), x.digits + y.digits, || {
+        self.try_insert_with(x.number.pow(exponent), x.digits + y.digits, |solver| {
             Expression::from_sqrt(
-                Expression::from_power(x.expression.clone(), y.expression.clone()),
+                Expression::from_power(
+                    solver.expression(x.index).clone(),
+                    solver.expression(y.index).clone(),
+                ),
                 sqrt_order,
             )
         })
@@ -488,14 +706,24 @@ impl BinaryOperation<RationalQuadratic> for Solver<RationalQuadratic> {
         {
             found = true;
         }
+        if self.modulo(&x, &y) {
+            found = true;
+        }
+        if self.gcd_lcm(&x, &y) {
+            found = true;
+        }
         found
     }
 
     fn divide(&mut self, x: &State<RationalQuadratic>, y: &State<RationalQuadratic>) -> bool {
+        self.set_producer(Operation::Divide, [Some(x.number), Some(y.number)]);
         if x.number == y.number {
-            return if x.number.to_int() == Some(self.n) {
-                self.try_insert(RationalQuadratic::one(), 2, || {
-                    Expression::from_divide(x.expression.clone(), x.expression.clone())
+            return if x.number.to_int().is_some_and(|x| self.is_digit(x)) {
+                self.try_insert_with(RationalQuadratic::one(), 2, |solver| {
+                    Expression::from_divide(
+                        solver.expression(x.index).clone(),
+                        solver.expression(x.index).clone(),
+                    )
                 })
             } else {
                 false
@@ -503,16 +731,22 @@ impl BinaryOperation<RationalQuadratic> for Solver<RationalQuadratic> {
         }
         let mut found = false;
         let result = x.number / y.number;
-        if !y.expression.is_divide()
-            && self.try_insert(result, x.digits + y.digits, || {
-                Expression::from_divide(x.expression.clone(), y.expression.clone())
+        if !self.expression(y.index).is_divide()
+            && self.try_insert_with(result, x.digits + y.digits, |solver| {
+                Expression::from_divide(
+                    solver.expression(x.index).clone(),
+                    solver.expression(y.index).clone(),
+                )
             })
         {
             found = true;
         }
-        if !x.expression.is_divide()
-            && self.try_insert(result.inv(), x.digits + y.digits, || {
-                Expression::from_divide(y.expression.clone(), x.expression.clone())
+        if !self.expression(x.index).is_divide()
+            && self.try_insert_with(result.inv(), x.digits + y.digits, |solver| {
+                Expression::from_divide(
+                    solver.expression(y.index).clone(),
+                    solver.expression(x.index).clone(),
+                )
             })
         {
             found = true;
@@ -521,6 +755,7 @@ impl BinaryOperation<RationalQuadratic> for Solver<RationalQuadratic> {
     }
 
     fn power(&mut self, x: &State<RationalQuadratic>, y: &State<RationalQuadratic>) -> bool {
+        self.set_producer(Operation::Power, [Some(x.number), Some(y.number)]);
         if x.number.is_one() || y.number.is_one() {
             return false;
         }
@@ -540,19 +775,22 @@ impl BinaryOperation<RationalQuadratic> for Solver<RationalQuadratic> {
             }
         }
         let result = x.number.pow(exponent);
-        if self.try_insert(result, x.digits + y.digits, || {
+        if self.try_insert_with(result, x.digits + y.digits, |solver| {
             Expression::from_sqrt(
-                Expression::from_power(x.expression.clone(), y.expression.clone()),
+                Expression::from_power(
+                    solver.expression(x.index).clone(),
+                    solver.expression(y.index).clone(),
+                ),
                 sqrt_order,
             )
         }) {
             true
-        } else if !x.expression.is_divide() {
-            self.try_insert(result.inv(), x.digits + y.digits, || {
+        } else if !self.expression(x.index).is_divide() {
+            self.try_insert_with(result.inv(), x.digits + y.digits, |solver| {
                 Expression::from_sqrt(
                     Expression::from_power(
-                        x.expression.clone(),
-                        Expression::from_negate(y.expression.clone()),
+                        solver.expression(x.index).clone(),
+                        Expression::from_negate(solver.expression(y.index).clone()),
                     ),
                     sqrt_order,
                 )
@@ -567,6 +805,7 @@ impl BinaryOperation<RationalQuadratic> for Solver<RationalQuadratic> {
         x: &State<RationalQuadratic>,
         y: &State<RationalQuadratic>,
     ) -> bool {
+        self.set_producer(Operation::FactorialDivide, [Some(x.number), Some(y.number)]);
         if x.number == y.number {
             return false;
         }
@@ -578,24 +817,22 @@ impl BinaryOperation<RationalQuadratic> for Solver<RationalQuadratic> {
             (x, y) = (y, x);
             (x_int, y_int) = (y_int, x_int);
         }
-        if x_int <= self.limits.max_factorial
-            || y_int <= 2
-            || x_int - y_int == 1
-            || (x_int - y_int) as f64 * (x_int.digits() + y_int.digits())
-                > self.limits.max_digits as f64 * 2.0
-        {
+        if x_int <= self.limits.max_factorial || y_int <= 2 || x_int - y_int == 1 {
             return false;
         }
+        let Some(result) = factorial_divide_within(x_int, y_int, self.limits.max_digits) else {
+            return false;
+        };
         let mut found = false;
-        let x_expression = Expression::from_factorial(x.expression.clone());
-        let y_expression = Expression::from_factorial(y.expression.clone());
-        let result = factorial_divide(x_int, y_int).into();
-        if self.try_insert(result, x.digits + y.digits, || {
+        let x_expression = Expression::from_factorial(self.expression(x.index).clone());
+        let y_expression = Expression::from_factorial(self.expression(y.index).clone());
+        let result = result.into();
+        if self.try_insert_with(result, x.digits + y.digits, |_| {
             Expression::from_divide(x_expression.clone(), y_expression.clone())
         }) {
             found = true;
         }
-        if self.try_insert(result.inv(), x.digits + y.digits, || {
+        if self.try_insert_with(result.inv(), x.digits + y.digits, |_| {
             Expression::from_divide(y_expression, x_expression)
         }) {
             found = true;
@@ -603,3 +840,297 @@ impl BinaryOperation<RationalQuadratic> for Solver<RationalQuadratic> {
         found
     }
 }
+
+/// Sums only of terms with the same radical part, and powers with rational exponents, which
+/// take the roots other than square roots.
+impl BinaryOperation<Radical> for Solver<Radical> {
+    fn binary_operation(&mut self, x: State<Radical>, y: State<Radical>) -> bool {
+        let mut found = false;
+        if self.divide(&x, &y) {
+            found = true;
+        }
+        if self.divide(&y, &x) {
+            found = true;
+        }
+        if self.multiply(&x, &y) {
+            found = true;
+        }
+        if x.number.radical_part() == y.number.radical_part() {
+            if self.add(&x, &y) {
+                found = true;
+            }
+            if self.subtract(&x, &y) {
+                found = true;
+            }
+        }
+        if y.number.is_rational() && !y.number.is_one() && self.power(&x, &y) {
+            found = true;
+        }
+        if x.number.is_rational() && !x.number.is_one() && self.power(&y, &x) {
+            found = true;
+        }
+        if x.number.is_int() && y.number.is_int() && self.factorial_divide(&x, &y) {
+            found = true;
+        }
+        if self.modulo(&x, &y) {
+            found = true;
+        }
+        if self.gcd_lcm(&x, &y) {
+            found = true;
+        }
+        found
+    }
+}
+
+impl BinaryOperation<i128> for Solver<i128> {
+    fn binary_operation(&mut self, x: State<i128>, y: State<i128>) -> bool {
+        let mut found = false;
+        if x.number < y.number {
+            if self.divide(&y, &x) {
+                found = true;
+            }
+        } else if self.divide(&x, &y) {
+            found = true;
+        }
+        if self.multiply(&x, &y) {
+            found = true;
+        }
+        if self.add(&x, &y) {
+            found = true;
+        }
+        if self.subtract(&x, &y) {
+            found = true;
+        }
+        if self.power(&x, &y) {
+            found = true;
+        }
+        if self.power(&y, &x) {
+            found = true;
+        }
+        if self.factorial_divide(&x, &y) {
+            found = true;
+        }
+        if self.modulo(&x, &y) {
+            found = true;
+        }
+        if self.gcd_lcm(&x, &y) {
+            found = true;
+        }
+        found
+    }
+
+    fn multiply(&mut self, x: &State<i128>, y: &State<i128>) -> bool {
+        self.set_producer(Operation::Multiply, [Some(x.number), Some(y.number)]);
+        if let Some(z) = x.number.checked_mul(y.number) {
+            self.try_insert_with(z, x.digits + y.digits, |solver| {
+                Expression::from_multiply(
+                    solver.expression(x.index).clone(),
+                    solver.expression(y.index).clone(),
+                )
+            })
+        } else {
+            false
+        }
+    }
+
+    fn power(&mut self, x: &State<i128>, y: &State<i128>) -> bool {
+        self.set_producer(Operation::Power, [Some(x.number), Some(y.number)]);
+        if x.number == 1 || y.number == 1 || y.number > 0x80000000 {
+            return false;
+        }
+        let max_digits = self.limits.max_digits.min(MAX_I128_DIGITS);
+        let mut exponent = y.number as u32;
+        let mut sqrt_order = 0usize;
+        while !power_within_u128(x.number.unsigned_abs(), exponent, max_digits) {
+            if exponent.is_multiple_of(2) {
+                exponent >>= 1;
+                sqrt_order += 1;
+            } else {
+                return false;
+            }
+        }
+        self.try_insert_with(x.number.pow(exponent), x.digits + y.digits, |solver| {
+            Expression::from_sqrt(
+                Expression::from_power(
+                    solver.expression(x.index).clone(),
+                    solver.expression(y.index).clone(),
+                ),
+                sqrt_order,
+            )
+        })
+    }
+
+    fn factorial_divide(&mut self, x: &State<i128>, y: &State<i128>) -> bool {
+        self.set_producer(Operation::FactorialDivide, [Some(x.number), Some(y.number)]);
+        let (x, y) = if x.number < y.number { (y, x) } else { (x, y) };
+        let (m, n) = (x.number, y.number);
+        if m <= self.limits.max_factorial as i128 || n <= 2 || m - n <= 1 {
+            return false;
+        }
+        let limit = 1i128 << self.limits.max_digits.min(MAX_I128_DIGITS);
+        let mut result = 1i128;
+        for k in (n + 1)..=m {
+            match result.checked_mul(k) {
+                Some(product) if product <= limit => result = product,
+                _ => return false,
+            }
+        }
+        self.try_insert_with(result, x.digits + y.digits, |solver| {
+            Expression::from_divide(
+                Expression::from_factorial(solver.expression(x.index).clone()),
+                Expression::from_factorial(solver.expression(y.index).clone()),
+            )
+        })
+    }
+}
+
+/// The bit length less one, a lower bound on the binary logarithm of the magnitude, so that
+/// powers it finds too long surely are.
+#[cfg(feature = "bigint")]
+impl Digits for BigNumber {
+    #[inline]
+    fn digits(&self) -> f64 {
+        self.bits().saturating_sub(1) as f64
+    }
+}
+
+/// Sums, differences, products and quotients are computed before they are stored, so that the
+/// candidates out of range never enter the store of the thread, which keeps every value made
+/// until the solver is dropped.
+#[cfg(feature = "bigint")]
+impl BinaryOperation<BigNumber> for Solver<BigNumber> {
+    fn add(&mut self, x: &State<BigNumber>, y: &State<BigNumber>) -> bool {
+        self.set_producer(Operation::Add, [Some(x.number), Some(y.number)]);
+        let Some(z) =
+            BigNumber::new_within(&*x.number.get() + &*y.number.get(), self.limits.max_digits)
+        else {
+            return false;
+        };
+        self.try_insert_with(z, x.digits + y.digits, |solver| {
+            Expression::from_add(
+                solver.expression(x.index).clone(),
+                solver.expression(y.index).clone(),
+            )
+        })
+    }
+
+    fn subtract(&mut self, x: &State<BigNumber>, y: &State<BigNumber>) -> bool {
+        self.set_producer(Operation::Subtract, [Some(x.number), Some(y.number)]);
+        let difference = &*x.number.get() - &*y.number.get();
+        if difference.is_zero() {
+            return false;
+        }
+        let (x, y) = if difference.is_negative() {
+            (y, x)
+        } else {
+            (x, y)
+        };
+        let Some(z) = BigNumber::new_within(difference.abs(), self.limits.max_digits) else {
+            return false;
+        };
+        self.try_insert_with(z, x.digits + y.digits, |solver| {
+            Expression::from_subtract(
+                solver.expression(x.index).clone(),
+                solver.expression(y.index).clone(),
+            )
+        })
+    }
+
+    fn multiply(&mut self, x: &State<BigNumber>, y: &State<BigNumber>) -> bool {
+        self.set_producer(Operation::Multiply, [Some(x.number), Some(y.number)]);
+        let product = &*x.number.get() * &*y.number.get();
+        let Some(z) = BigNumber::new_within(product, self.limits.max_digits) else {
+            return false;
+        };
+        self.try_insert_with(z, x.digits + y.digits, |solver| {
+            Expression::from_multiply(
+                solver.expression(x.index).clone(),
+                solver.expression(y.index).clone(),
+            )
+        })
+    }
+
+    fn divide(&mut self, x: &State<BigNumber>, y: &State<BigNumber>) -> bool {
+        self.set_producer(Operation::Divide, [Some(x.number), Some(y.number)]);
+        let quotient = &*x.number.get() / &*y.number.get();
+        let Some(z) = BigNumber::new_within(quotient, self.limits.max_digits) else {
+            return false;
+        };
+        self.try_insert_with(z, x.digits + y.digits, |solver| {
+            Expression::from_divide(
+                solver.expression(x.index).clone(),
+                solver.expression(y.index).clone(),
+            )
+        })
+    }
+
+    fn power(&mut self, x: &State<BigNumber>, y: &State<BigNumber>) -> bool {
+        self.set_producer(Operation::Power, [Some(x.number), Some(y.number)]);
+        if x.number.is_one() || y.number.is_one() {
+            return false;
+        }
+        let Some(y_int) = y.number.to_int() else {
+            return false;
+        };
+        if y_int <= 0 || y_int > 0x40000000 {
+            return false;
+        }
+        let mut exponent = y_int as u32;
+        let x_digits = x.number.digits();
+        let mut sqrt_order = 0usize;
+        while x_digits * exponent as f64 > self.limits.max_digits as f64 {
+            if exponent.is_multiple_of(2) {
+                exponent >>= 1;
+                sqrt_order += 1;
+            } else {
+                return false;
+            }
+        }
+        let power = BigNumber::from(i64::from(exponent));
+        let Some(z) = x.number.checked_power(power, self.limits.max_digits) else {
+            return false;
+        };
+        self.try_insert_with(z, x.digits + y.digits, |solver| {
+            Expression::from_sqrt(
+                Expression::from_power(
+                    solver.expression(x.index).clone(),
+                    solver.expression(y.index).clone(),
+                ),
+                sqrt_order,
+            )
+        })
+    }
+
+    /// `m! / n!` multiplied out in `BigInt` while its bit length stays within `max_digits`.
+    fn factorial_divide(&mut self, x: &State<BigNumber>, y: &State<BigNumber>) -> bool {
+        self.set_producer(Operation::FactorialDivide, [Some(x.number), Some(y.number)]);
+        let (Some(x_int), Some(y_int)) = (x.number.to_int(), y.number.to_int()) else {
+            return false;
+        };
+        let ((x, m), (y, n)) = if x_int < y_int {
+            ((y, y_int), (x, x_int))
+        } else {
+            ((x, x_int), (y, y_int))
+        };
+        if m <= self.limits.max_factorial || n <= 2 || m - n <= 1 {
+            return false;
+        }
+        let mut result = BigInt::one();
+        for k in (n + 1)..=m {
+            result *= k;
+            if result.bits() > self.limits.max_digits as u64 {
+                return false;
+            }
+        }
+        self.try_insert_with(
+            BigNumber::new(BigRational::from_integer(result)),
+            x.digits + y.digits,
+            |solver| {
+                Expression::from_divide(
+                    Expression::from_factorial(solver.expression(x.index).clone()),
+                    Expression::from_factorial(solver.expression(y.index).clone()),
+                )
+            },
+        )
+    }
+}
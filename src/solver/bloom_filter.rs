@@ -0,0 +1,71 @@
+use super::Solver;
+use crate::Number;
+use rustc_hash::FxHasher;
+use std::hash::{Hash, Hasher};
+
+const BITS_PER_STATE: usize = 16;
+const MIN_BITS: usize = 1 << 16;
+const PROBES: usize = 3;
+
+/// A set of hashed values that may report false positives but never false negatives.
+#[derive(Clone)]
+pub(super) struct BloomFilter {
+    bits: Vec<u64>,
+    mask: u64,
+}
+
+impl BloomFilter {
+    fn with_capacity(capacity: usize) -> Self {
+        let bits = (capacity * BITS_PER_STATE)
+            .max(MIN_BITS)
+            .next_power_of_two();
+        Self {
+            bits: vec![0; bits / 64],
+            mask: bits as u64 - 1,
+        }
+    }
+
+    #[inline]
+    fn hash<T: Hash>(x: &T) -> u64 {
+        let mut hasher = FxHasher::default();
+        x.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Double hashing: the `i`-th probe is `h1 + i * h2`, with `h2` odd so probes stay distinct.
+    #[inline]
+    fn probes<T: Hash>(&self, x: &T) -> [u64; PROBES] {
+        let h1 = Self::hash(x);
+        let h2 = h1.rotate_left(32) | 1;
+        std::array::from_fn(|i| h1.wrapping_add((i as u64).wrapping_mul(h2)) & self.mask)
+    }
+
+    #[inline]
+    pub(super) fn insert<T: Hash>(&mut self, x: &T) {
+        for bit in self.probes(x) {
+            self.bits[(bit >> 6) as usize] |= 1 << (bit & 63);
+        }
+    }
+
+    #[inline]
+    pub(super) fn may_contain<T: Hash>(&self, x: &T) -> bool {
+        self.probes(x)
+            .iter()
+            .all(|bit| self.bits[(bit >> 6) as usize] & (1 << (bit & 63)) != 0)
+    }
+}
+
+impl<T: Number> Solver<T> {
+    /// Rebuilds the filter from every known state, sized for the states seen so far.
+    pub(super) fn rebuild_bloom_filter(&mut self) {
+        if !self.bloom_filter {
+            self.seen_filter = None;
+            return;
+        }
+        let mut filter = BloomFilter::with_capacity(self.states.len());
+        for (x, _, _) in self.states.iter() {
+            filter.insert(&x);
+        }
+        self.seen_filter = Some(filter);
+    }
+}
@@ -0,0 +1,121 @@
+use super::Solver;
+use crate::solution;
+use crate::{Number, Solution, SolveOutcome};
+use std::io;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+impl<T: Number> Solver<T> {
+    /// Lets a host abort searches by setting `token`, which is checked before each state the
+    /// depth being searched expands. The search then returns as if nothing was found, and keeps
+    /// its position in the depth, so that a later call resumes it once `token` is cleared.
+    #[inline]
+    pub fn set_cancel_token(&mut self, token: Option<Arc<AtomicBool>>) {
+        self.cancel_token = token;
+    }
+
+    /// Whether the last [`solve`](Self::solve) or [`exhaust_to_depth`](Self::exhaust_to_depth)
+    /// call stopped at cancellation.
+    #[inline]
+    pub fn cancelled(&self) -> bool {
+        self.interruption == Some(Interruption::Cancelled)
+    }
+
+    /// The I/O error the [`StateStore`](super::StateStore) failed with, if it did. Searches stop
+    /// at the first state they expand once it fails, and the states it lost are not found again,
+    /// so the solver is of no further use.
+    #[inline]
+    pub fn storage_error(&self) -> Option<&io::Error> {
+        self.storage_error.as_deref()
+    }
+
+    /// Like [`solve`](Self::solve), but stops once `timeout` has passed, as at cancellation, so
+    /// that batch jobs can skip pathological targets and come back to them later. Unlike a soft
+    /// deadline, the depth being searched keeps its place instead of being searched again. No
+    /// clock is available on WebAssembly, where the timeout never passes.
+    pub fn solve_with_timeout(
+        &mut self,
+        target: T,
+        max_depth: Option<usize>,
+        timeout: Duration,
+    ) -> SolveOutcome {
+        self.timeout = solution::now().map(|now| now + timeout);
+        let solution = self.solve(target, max_depth);
+        self.timeout = None;
+        outcome(solution, self.interruption)
+    }
+
+    /// Whether the current call stopped at cancellation, at its timeout or at the memory budget.
+    #[inline]
+    pub(crate) fn interrupted(&self) -> bool {
+        self.interruption.is_some()
+    }
+
+    /// Why the current call stopped early, if it did.
+    #[inline]
+    pub(crate) fn interruption(&self) -> Option<Interruption> {
+        self.interruption
+    }
+
+    #[inline]
+    pub(crate) fn set_timeout(&mut self, timeout: Option<Instant>) {
+        self.timeout = timeout;
+    }
+
+    /// Whether the state store failed, the host asked to cancel, the timeout passed or the
+    /// states outgrew the memory budget, which stops the search for the rest of the call.
+    pub(super) fn cancel_requested(&mut self) -> bool {
+        if self.interruption.is_some() {
+            return true;
+        }
+        if let Some(error) = self.states.take_error() {
+            self.storage_error = Some(Rc::new(error));
+        }
+        if self.storage_error.is_some() {
+            self.interruption = Some(Interruption::StorageFailed);
+        } else if self
+            .cancel_token
+            .as_ref()
+            .is_some_and(|token| token.load(Ordering::Relaxed))
+        {
+            self.interruption = Some(Interruption::Cancelled);
+        } else if self
+            .timeout
+            .is_some_and(|timeout| solution::now().is_some_and(|now| now >= timeout))
+        {
+            self.interruption = Some(Interruption::Timeout);
+        } else if self
+            .memory_budget
+            .is_some_and(|budget| self.memory_estimate() > budget)
+        {
+            self.interruption = Some(Interruption::MemoryExceeded);
+        }
+        self.interruption.is_some()
+    }
+}
+
+/// Why a call stopped before finishing, keeping its place.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Interruption {
+    Cancelled,
+    Timeout,
+    MemoryExceeded,
+    StorageFailed,
+}
+
+/// The outcome of a call that found `solution`, or else stopped early for `interruption`.
+#[inline]
+pub(crate) fn outcome(
+    solution: Option<Solution>,
+    interruption: Option<Interruption>,
+) -> SolveOutcome {
+    match (solution, interruption) {
+        (Some(solution), _) => SolveOutcome::Solved(solution),
+        (None, Some(Interruption::Timeout)) => SolveOutcome::Timeout,
+        (None, Some(Interruption::MemoryExceeded)) => SolveOutcome::MemoryExceeded,
+        (None, Some(Interruption::StorageFailed)) => SolveOutcome::StorageFailed,
+        (None, _) => SolveOutcome::Unsolved,
+    }
+}
@@ -0,0 +1,280 @@
+use super::persistence::{read_state, read_usize, write_state, StateCodec};
+use super::{DeadStates, DecodeStateError, DepthStates, Limits, SearchState, Solver, StageOrder};
+use crate::encoding::write_varint;
+use crate::MAX_QUADRATIC_POWER;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::time::Duration;
+
+/// A solver as it is serialized: its settings, its position in the search, and its states in
+/// the byte encoding of exported tables, which keeps shared subexpressions small.
+#[derive(Deserialize, Serialize)]
+struct Checkpoint {
+    digit_set: Vec<i64>,
+    limits: Limits,
+    stage_order: StageOrder,
+    progressive: bool,
+    keep_all: bool,
+    modular_pruning: bool,
+    goal_lookup: bool,
+    dead_state_pruning: bool,
+    bloom_filter: bool,
+    modulo: bool,
+    gcd_lcm: bool,
+    negation: bool,
+    soft_deadline: Option<Duration>,
+    memory_budget: Option<usize>,
+    target: Vec<u8>,
+    max_depth: usize,
+    depth_searched: usize,
+    search_state: SearchState,
+    pruned_depth: Option<usize>,
+    /// The states of each depth in the order the search indexes them, the extra states queued
+    /// for each depth, and the new numbers not yet taken by the other domains.
+    states: Vec<u8>,
+}
+
+/// Checkpoints the solver, so that a search of days survives a restart of the process. The
+/// settings, the states and the position in the depth being searched are kept, and
+/// deserializing resumes the search where it stopped. Alternative expressions, the tracer, the
+/// search trace, the cancel token and the progress callback are not kept, and the states are
+/// restored into memory whatever [`StateStore`](super::StateStore) held them.
+impl<T: StateCodec> Serialize for Solver<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut target = vec![];
+        self.target.write(&mut target);
+        let mut states = vec![];
+        write_varint(&mut states, self.states_by_depth.len() as u64);
+        for depth_states in &self.states_by_depth {
+            write_varint(&mut states, depth_states.len() as u64);
+            for (x, expression) in depth_states.iter() {
+                write_state(&mut states, x, expression);
+            }
+        }
+        write_varint(&mut states, self.extra_states_by_depth.len() as u64);
+        for extra_states in &self.extra_states_by_depth {
+            write_varint(&mut states, extra_states.len() as u64);
+            for (x, expression) in extra_states {
+                write_state(&mut states, x, expression);
+            }
+        }
+        write_varint(&mut states, self.new_numbers.len() as u64);
+        for x in &self.new_numbers {
+            x.write(&mut states);
+        }
+        Checkpoint {
+            digit_set: self.digit_set.clone(),
+            limits: self.limits,
+            stage_order: self.stage_order,
+            progressive: self.progressive,
+            keep_all: self.keep_all,
+            modular_pruning: self.modular_pruning,
+            goal_lookup: self.goal_lookup,
+            dead_state_pruning: self.dead_state_pruning,
+            bloom_filter: self.bloom_filter,
+            modulo: self.modulo,
+            gcd_lcm: self.gcd_lcm,
+            negation: self.negation,
+            soft_deadline: self.soft_deadline,
+            memory_budget: self.memory_budget,
+            target,
+            max_depth: self.max_depth,
+            depth_searched: self.depth_searched,
+            search_state: self.search_state.clone(),
+            pruned_depth: self.pruned_depth,
+            states,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de, T: StateCodec> Deserialize<'de> for Solver<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let checkpoint = Checkpoint::deserialize(deserializer)?;
+        Solver::from_checkpoint(checkpoint).map_err(D::Error::custom)
+    }
+}
+
+impl<T: StateCodec> Solver<T> {
+    /// Rebuilds a solver from `checkpoint`, failing unless it is one [`serialize`] could have
+    /// written: every state must be what its expression writes with the digits of its depth,
+    /// and the position in the search must lie within the states. The depth limit of the last
+    /// call is only ever compared with depths, so any value of it is consistent.
+    ///
+    /// [`serialize`]: Serialize::serialize
+    fn from_checkpoint(checkpoint: Checkpoint) -> Result<Self, DecodeStateError> {
+        let &n = checkpoint.digit_set.first().ok_or(DecodeStateError {})?;
+        let digit_set_valid = match checkpoint.digit_set[..] {
+            [n] => n > 0,
+            ref digits => {
+                digits.is_sorted_by(|x, y| x < y) && digits.iter().all(|x| (1..=9).contains(x))
+            }
+        };
+        if !digit_set_valid || checkpoint.limits.max_quadratic_power > MAX_QUADRATIC_POWER {
+            return Err(DecodeStateError {});
+        }
+        let mut solver = if checkpoint.progressive {
+            Self::new_progressive(n, checkpoint.limits)
+        } else {
+            Self::new(n, checkpoint.limits)
+        };
+        solver.digit_set = checkpoint.digit_set;
+        solver.stage_order = checkpoint.stage_order;
+        solver.keep_all = checkpoint.keep_all;
+        solver.modular_pruning = checkpoint.modular_pruning;
+        solver.goal_lookup = checkpoint.goal_lookup;
+        solver.dead_state_pruning = checkpoint.dead_state_pruning;
+        solver.bloom_filter = checkpoint.bloom_filter;
+        solver.modulo = checkpoint.modulo;
+        solver.gcd_lcm = checkpoint.gcd_lcm;
+        solver.negation = checkpoint.negation;
+        solver.soft_deadline = checkpoint.soft_deadline;
+        solver.memory_budget = checkpoint.memory_budget;
+        solver.target = T::read(&mut checkpoint.target.iter().copied())?;
+        solver.max_depth = checkpoint.max_depth;
+        let mut bytes = checkpoint.states.iter().copied();
+        let depths = read_usize(&mut bytes)?;
+        solver
+            .states_by_depth
+            .resize_with(depths, DepthStates::default);
+        for digits in 0..depths {
+            for _ in 0..read_usize(&mut bytes)? {
+                let (x, expression) = read_state(&mut bytes)?;
+                solver.check_state(&x, digits, &expression)?;
+                if solver.states.contains(&x) {
+                    return Err(DecodeStateError {});
+                }
+                solver.insert(x, digits, expression);
+            }
+        }
+        for digits in 0..read_usize(&mut bytes)? {
+            let extra_states = (0..read_usize(&mut bytes)?)
+                .map(|_| {
+                    let (x, expression) = read_state(&mut bytes)?;
+                    solver.check_state(&x, digits, &expression)?;
+                    Ok((x, expression))
+                })
+                .collect::<Result<_, DecodeStateError>>()?;
+            solver.extra_states_by_depth.push(extra_states);
+        }
+        solver.new_numbers = (0..read_usize(&mut bytes)?)
+            .map(|_| {
+                let x = T::read(&mut bytes)?;
+                if solver.states.contains(&x) {
+                    Ok(x)
+                } else {
+                    Err(DecodeStateError {})
+                }
+            })
+            .collect::<Result<_, _>>()?;
+        if bytes.next().is_some() {
+            return Err(DecodeStateError {});
+        }
+        solver.depth_searched = checkpoint.depth_searched;
+        solver.search_state = checkpoint.search_state;
+        solver.pruned_depth = checkpoint.pruned_depth;
+        if !solver.is_position_valid() {
+            return Err(DecodeStateError {});
+        }
+        // The filter and the dead-state cuts are rebuilt when a depth starts, so a depth resumed
+        // halfway needs them rebuilt here.
+        if !matches!(solver.search_state, SearchState::None) {
+            solver.rebuild_bloom_filter();
+            solver.sweep_dead_states(solver.depth_searched + 1);
+        }
+        Ok(solver)
+    }
+
+    /// Whether the depths searched, the depth being searched and the position in it all lie
+    /// within the states, as they do in a solver that was checkpointed.
+    fn is_position_valid(&self) -> bool {
+        let depths = self.states_by_depth.len();
+        let digits = self.depth_searched + 1;
+        // A depth is pruned once searched, or when the deadline stops it.
+        if self
+            .pruned_depth
+            .is_some_and(|depth| depth == 0 || depth > digits || depth >= depths)
+        {
+            return false;
+        }
+        let len = |depth: usize| self.states_by_depth[depth].len();
+        match self.search_state {
+            SearchState::None => self.depth_searched < depths.max(1),
+            _ if depths <= digits => false,
+            SearchState::Concat | SearchState::Finish => true,
+            SearchState::ExtraState(start) => {
+                start <= self.extra_states_by_depth.get(digits).map_or(0, Vec::len)
+            }
+            SearchState::UnaryOperation(start) => start <= len(digits - 1),
+            SearchState::BinaryOperationOfDifferentDepth(index, (i, j)) => {
+                let pairs = ((digits + 1) >> 1) - 1;
+                index == pairs
+                    || index < pairs && {
+                        let d1 = self.pair_depth(digits, index);
+                        i <= len(d1) && j <= len(digits - d1)
+                    }
+            }
+            SearchState::BinaryOperationOfSameDepth((i, j)) => {
+                !digits.is_multiple_of(2) || i <= len(digits / 2) && j <= len(digits / 2)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Limits, Solver};
+    use serde_json::{json, Value};
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+
+    /// A checkpoint of a search for 2023 with 3s cancelled halfway through depth 4.
+    fn checkpoint() -> Value {
+        let mut solver = Solver::<i64>::new(3, Limits::recommended_integral());
+        solver.exhaust_to_depth(3);
+        let token = Arc::new(AtomicBool::new(true));
+        solver.set_cancel_token(Some(token));
+        solver.solve(2023, None);
+        serde_json::to_value(&solver).unwrap()
+    }
+
+    fn load(checkpoint: &Value) -> serde_json::Result<Solver<i64>> {
+        serde_json::from_value(checkpoint.clone())
+    }
+
+    #[test]
+    fn resumes_from_checkpoint() {
+        let mut solver = load(&checkpoint()).unwrap();
+        assert_eq!(solver.solve(2023, None).unwrap().digits(), 6);
+    }
+
+    #[test]
+    fn rejects_positions_outside_the_states() {
+        let checkpoint = checkpoint();
+        let edits: [(&str, Value); 5] = [
+            ("depth_searched", 9.into()),
+            ("pruned_depth", 0.into()),
+            ("pruned_depth", 9.into()),
+            ("search_state", json!({ "UnaryOperation": 1 << 20 })),
+            ("digit_set", json!([0])),
+        ];
+        for (field, value) in edits {
+            let mut edited = checkpoint.clone();
+            edited[field] = value;
+            assert!(load(&edited).is_err(), "{field}");
+        }
+    }
+
+    #[test]
+    fn rejects_states_their_expressions_do_not_write() {
+        let mut edited = checkpoint();
+        let Value::Array(states) = &mut edited["states"] else {
+            panic!("states are bytes");
+        };
+        // The depth count, the count of the first depth, which is empty, and that of the
+        // second, followed by its first state: the value 3, whose zigzag encoding is 6.
+        assert_eq!(states[3], 6);
+        states[3] = 8.into();
+        assert!(load(&edited).is_err());
+    }
+}
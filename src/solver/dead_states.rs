@@ -0,0 +1,92 @@
+use super::Solver;
+use crate::Number;
+
+/// Where the dead states of each depth start, for one final depth.
+#[derive(Clone)]
+pub(super) struct DeadStateCuts {
+    /// The largest value whose unary closure reaches the target.
+    goal_max: i64,
+    /// Indexed by depth, the position of the first state that may be dead.
+    cuts: Vec<usize>,
+}
+
+pub(super) trait DeadStates<T: Number> {
+    /// Whether the final depth `digits` skips dead states, so that it is left incomplete.
+    fn uses_dead_state_pruning(&self, digits: usize) -> bool;
+    /// Marks the states that no pair can turn into the target at the final depth `digits`.
+    fn sweep_dead_states(&mut self, digits: usize);
+    fn is_dead(&self, depth: usize, position: usize) -> bool;
+}
+
+impl<T: Number> DeadStates<T> for Solver<T> {
+    default fn uses_dead_state_pruning(&self, _digits: usize) -> bool {
+        false
+    }
+
+    default fn sweep_dead_states(&mut self, _digits: usize) {}
+
+    default fn is_dead(&self, _depth: usize, _position: usize) -> bool {
+        false
+    }
+}
+
+impl DeadStates<i64> for Solver<i64> {
+    #[inline]
+    fn uses_dead_state_pruning(&self, digits: usize) -> bool {
+        self.dead_state_pruning
+            && !self.modulo
+            && !self.gcd_lcm
+            && !self.progressive
+            && self.target > 0
+            && digits == self.max_depth
+    }
+
+    /// A state `x` with partners up to `y_max` is dead when even its smallest results exceed
+    /// the largest value `goal_max` whose unary closure reaches the target: `x - y` once
+    /// `x > goal_max + y_max`, `x / y` once `x > goal_max * y_max`, and sums, products, powers `x^y`
+    /// and quotients of factorials are at least `x`. A power `y^x` with its exponent halved by
+    /// square roots is at least `2^o` for the odd part `o` of `x`, which is checked per state.
+    /// States are sorted, so the dead ones of each depth start at a cut, past which only states
+    /// with small odd parts survive.
+    fn sweep_dead_states(&mut self, digits: usize) {
+        self.dead_states = None;
+        if !self.uses_dead_state_pruning(digits) {
+            return;
+        }
+        let goal_max = self.goal_closure().into_iter().max().unwrap_or(self.target);
+        for depth in 1..digits {
+            self.states_by_depth[depth].sort();
+        }
+        let mut cuts = vec![usize::MAX];
+        for depth in 1..digits {
+            let partners = &self.states_by_depth[digits - depth];
+            let y_max = partners.values().last().copied().unwrap_or(0);
+            let bound = goal_max
+                .saturating_mul(y_max)
+                .max(goal_max.saturating_add(y_max));
+            cuts.push(
+                self.states_by_depth[depth]
+                    .values()
+                    .partition_point(|x| *x <= bound),
+            );
+        }
+        self.dead_states = Some(DeadStateCuts { goal_max, cuts });
+    }
+
+    #[inline]
+    fn is_dead(&self, depth: usize, position: usize) -> bool {
+        let Some(dead_states) = &self.dead_states else {
+            return false;
+        };
+        if dead_states
+            .cuts
+            .get(depth)
+            .is_none_or(|cut| position < *cut)
+        {
+            return false;
+        }
+        let x = self.states_by_depth[depth].value(position);
+        let odd = x >> x.trailing_zeros();
+        odd >= 63 || 1 << odd > dead_states.goal_max
+    }
+}
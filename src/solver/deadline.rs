@@ -0,0 +1,48 @@
+use super::{GoalLookup, Solver};
+use crate::solution;
+use crate::Number;
+use std::time::{Duration, Instant};
+
+impl<T: Number> Solver<T> {
+    /// Gives every [`solve`](Self::solve) call a soft time limit. Once it passes, the depth
+    /// being searched is expanded no further. Integral searches still look the target up among
+    /// the pairs of known states that could produce it at that depth, so a solution within
+    /// reach is reported rather than discarded with the unfinished depth; as every smaller depth
+    /// is complete, such a solution is still optimal.
+    ///
+    /// The cut-short depth is searched again by later calls, as with modular pruning. No clock
+    /// is available on WebAssembly, where the deadline never passes.
+    #[inline]
+    pub fn set_soft_deadline(&mut self, deadline: Option<Duration>) {
+        self.soft_deadline = deadline;
+    }
+
+    /// Whether the last [`solve`](Self::solve) call stopped at its soft deadline.
+    #[inline]
+    pub fn deadline_reached(&self) -> bool {
+        self.deadline_reached
+    }
+
+    /// Starts the clock of a solve call, forgetting how the last one stopped.
+    pub(super) fn start_deadline(&mut self, start: Option<Instant>) {
+        self.deadline = start
+            .zip(self.soft_deadline)
+            .map(|(start, limit)| start + limit);
+        self.deadline_reached = false;
+        self.interruption = None;
+    }
+
+    #[inline]
+    pub(super) fn deadline_passed(&self) -> bool {
+        self.deadline
+            .is_some_and(|deadline| solution::now().is_some_and(|now| now >= deadline))
+    }
+
+    /// Abandons the expansion of depth `digits` for a last goal-directed check. Returns whether
+    /// it found the target.
+    pub(super) fn stop_at_deadline(&mut self, digits: usize) -> bool {
+        self.deadline_reached = true;
+        self.pruned_depth = Some(digits);
+        self.search_goal_pairs_at_deadline(digits)
+    }
+}
@@ -0,0 +1,118 @@
+use super::Solver;
+use crate::number_theory::{factorial, integer_root};
+use crate::solution;
+use crate::{Domain, Expression, Solution};
+use std::rc::Rc;
+
+/// Depths searched in full before the target is decomposed, cheap for every `n`.
+const SHALLOW_DEPTH: usize = 5;
+
+/// How many times a piece missing from the tables is decomposed in turn.
+const MAX_NESTING: usize = 2;
+
+/// The largest `k` with `k!` in range of `i64`.
+const MAX_FACTORIAL: i64 = 20;
+
+type Piece = (Rc<Expression>, usize);
+
+impl Solver<i64> {
+    /// Like [`solve`](Self::solve), but once the shallow depths are searched, first builds a
+    /// solution from pieces of the target: a perfect power from its root and exponent, or a
+    /// multiple of a factorial or of a known state. Pieces come from the tables, or are
+    /// decomposed in turn.
+    ///
+    /// The digits of such a solution bound the exhaustive search, which then stops at the depth
+    /// before; the decomposition is returned if that search finds nothing shorter.
+    pub fn solve_decomposed(&mut self, target: i64, max_depth: Option<usize>) -> Option<Solution> {
+        if self.negation && target < 0 {
+            return self
+                .solve_decomposed(-target, max_depth)
+                .map(Solution::negate);
+        }
+        let start = solution::now();
+        let max_depth = max_depth.unwrap_or(usize::MAX);
+        self.exhaust_to_depth(SHALLOW_DEPTH.min(max_depth));
+        let bound = if target > 1 && !self.states.contains(&target) {
+            self.decompose(target, MAX_NESTING)
+                .filter(|(_, digits)| *digits <= max_depth)
+        } else {
+            None
+        };
+        let Some((expression, digits)) = bound else {
+            return self.solve(target, Some(max_depth));
+        };
+        self.solve(target, Some(digits - 1)).or_else(|| {
+            Some(Solution::new(
+                expression,
+                digits,
+                Domain::Integral,
+                start.map(|start| start.elapsed()),
+            ))
+        })
+    }
+
+    /// The cheapest expression for `x` built from pieces, if any.
+    fn decompose(&self, x: i64, nesting: usize) -> Option<Piece> {
+        let mut best: Option<Piece> = None;
+        let mut consider = |candidate: Option<Piece>| {
+            if let Some(candidate) = candidate {
+                if best.as_ref().is_none_or(|best| candidate.1 < best.1) {
+                    best = Some(candidate);
+                }
+            }
+        };
+        let mut exponent = 2;
+        while exponent < 63 && 1i64 << exponent <= x {
+            if let Some(root) = integer_root(x, exponent) {
+                consider(self.pieces(root, exponent, nesting).map(
+                    |((root, d1), (exponent, d2))| {
+                        (Expression::from_power(root, exponent), d1 + d2)
+                    },
+                ));
+            }
+            exponent += 1;
+        }
+        let mut k = 3;
+        while k <= MAX_FACTORIAL && factorial(k) <= x {
+            let m = x / factorial(k);
+            if m * factorial(k) == x {
+                consider(if m == 1 {
+                    self.piece(k, nesting)
+                        .map(|(k, digits)| (Expression::from_factorial(k), digits))
+                } else {
+                    self.pieces(k, m, nesting).map(|((k, d1), (m, d2))| {
+                        (
+                            Expression::from_multiply(Expression::from_factorial(k), m),
+                            d1 + d2,
+                        )
+                    })
+                });
+            }
+            k += 1;
+        }
+        for (d, divisor, d1) in self.states.iter() {
+            if d > 1 && d < x && x % d == 0 {
+                consider(
+                    self.piece(x / d, nesting).map(|(quotient, d2)| {
+                        (Expression::from_multiply(divisor, quotient), d1 + d2)
+                    }),
+                );
+            }
+        }
+        best
+    }
+
+    /// `x` from the tables, or else decomposed while nesting allows.
+    fn piece(&self, x: i64, nesting: usize) -> Option<Piece> {
+        match self.states.get(&x) {
+            Some(piece) => Some(piece),
+            None if nesting > 0 && x > 1 => self.decompose(x, nesting - 1),
+            None => None,
+        }
+    }
+
+    #[inline]
+    fn pieces(&self, x: i64, y: i64, nesting: usize) -> Option<(Piece, Piece)> {
+        Some((self.piece(x, nesting)?, self.piece(y, nesting)?))
+    }
+}
@@ -0,0 +1,106 @@
+use super::{SearchState, Solver, Stage};
+use crate::Number;
+
+impl<T: Number> Solver<T> {
+    /// Estimates the fraction of the depth being searched that is done, from 0 before it starts
+    /// to 1 once every stage has run, counting one unit of work per extra state, per state of
+    /// the depth before for the unary stage, and per pair of states for the binary stage.
+    ///
+    /// Goal lookup replaces the pairs of the final depth, whose binary stage then counts as done
+    /// as soon as it starts.
+    pub fn depth_progress(&self) -> f32 {
+        let current = match self.search_state {
+            SearchState::None => return 0.0,
+            SearchState::Finish => return 1.0,
+            SearchState::Concat => Stage::Concat,
+            SearchState::ExtraState(_) => Stage::Extra,
+            SearchState::UnaryOperation(_) => Stage::Unary,
+            SearchState::BinaryOperationOfDifferentDepth(..)
+            | SearchState::BinaryOperationOfSameDepth(_) => Stage::Binary,
+        };
+        let digits = self.depth_searched + 1;
+        let mut total = 0;
+        let mut done = 0;
+        let mut before_current = true;
+        for &stage in &self.stage_order.stages {
+            let work = self.stage_work(stage, digits);
+            total += work;
+            if stage == current {
+                done += self.stage_work_done(digits);
+                before_current = false;
+            } else if before_current {
+                done += work;
+            }
+        }
+        if total == 0 {
+            0.0
+        } else {
+            (done as f64 / total as f64) as f32
+        }
+    }
+
+    /// The units of work of `stage` at depth `digits`.
+    fn stage_work(&self, stage: Stage, digits: usize) -> u64 {
+        match stage {
+            Stage::Concat => 1,
+            Stage::Extra => self.extra_states_by_depth.get(digits).map_or(0, Vec::len) as u64,
+            Stage::Unary => self.depth_len(digits - 1),
+            Stage::Binary => {
+                let different: u64 = (0..((digits + 1) >> 1) - 1)
+                    .map(|index| self.pairs_of_depths(digits, index))
+                    .sum();
+                different + self.pairs_of_same_depth(digits)
+            }
+        }
+    }
+
+    /// The units of work the cursor of the current stage has passed at depth `digits`.
+    fn stage_work_done(&self, digits: usize) -> u64 {
+        match self.search_state {
+            SearchState::ExtraState(i) | SearchState::UnaryOperation(i) => i as u64,
+            SearchState::BinaryOperationOfDifferentDepth(index, (i, j)) => {
+                let count = ((digits + 1) >> 1) - 1;
+                let pairs: u64 = (0..index.min(count))
+                    .map(|index| self.pairs_of_depths(digits, index))
+                    .sum();
+                if index >= count {
+                    return pairs;
+                }
+                let d2 = digits - self.pair_depth(digits, index);
+                pairs + i as u64 * self.depth_len(d2) + j as u64
+            }
+            SearchState::BinaryOperationOfSameDepth((i, j)) => {
+                let different =
+                    self.stage_work(Stage::Binary, digits) - self.pairs_of_same_depth(digits);
+                let l = self.depth_len(digits >> 1);
+                let (i, j) = (i as u64, j as u64);
+                // Row `k` holds the `l - k` pairs of its state with itself and the later ones.
+                different + i * l - i * i.saturating_sub(1) / 2 + j.saturating_sub(i)
+            }
+            _ => 0,
+        }
+    }
+
+    #[inline]
+    fn depth_len(&self, depth: usize) -> u64 {
+        self.states_by_depth
+            .get(depth)
+            .map_or(0, |states| states.len() as u64)
+    }
+
+    /// The pairs of the `index`-th pair of different depths adding up to `digits`.
+    fn pairs_of_depths(&self, digits: usize, index: usize) -> u64 {
+        let d1 = self.pair_depth(digits, index);
+        self.depth_len(d1) * self.depth_len(digits - d1)
+    }
+
+    /// The pairs of states of half of `digits`, if it is even.
+    fn pairs_of_same_depth(&self, digits: usize) -> u64 {
+        if digits.is_multiple_of(2) {
+            let l = self.depth_len(digits >> 1);
+            l * (l + 1) / 2
+        } else {
+            0
+        }
+    }
+}
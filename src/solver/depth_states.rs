@@ -0,0 +1,87 @@
+use crate::{Expression, Number};
+use std::rc::Rc;
+
+/// The states first reached at one depth, stored as parallel arrays. The pair loops scan the
+/// values alone, contiguously, and an expression is only fetched by position once a candidate
+/// built from it is accepted. Every state of a depth has that many digits, so the depth itself
+/// is the digit count of all of them.
+#[derive(Clone)]
+pub(super) struct DepthStates<T> {
+    values: Vec<T>,
+    expressions: Vec<Rc<Expression>>,
+}
+
+impl<T> Default for DepthStates<T> {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            values: vec![],
+            expressions: vec![],
+        }
+    }
+}
+
+impl<T: Number> DepthStates<T> {
+    #[inline]
+    pub(super) fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    #[inline]
+    pub(super) fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    #[inline]
+    pub(super) fn values(&self) -> &[T] {
+        &self.values
+    }
+
+    #[inline]
+    pub(super) fn value(&self, position: usize) -> T {
+        self.values[position]
+    }
+
+    #[inline]
+    pub(super) fn expression(&self, position: usize) -> &Rc<Expression> {
+        &self.expressions[position]
+    }
+
+    #[inline]
+    pub(super) fn iter(&self) -> impl Iterator<Item = (&T, &Rc<Expression>)> {
+        self.values.iter().zip(&self.expressions)
+    }
+
+    #[inline]
+    pub(super) fn push(&mut self, x: T, expression: Rc<Expression>) {
+        self.values.push(x);
+        self.expressions.push(expression);
+    }
+
+    /// Removes every state, returning the values.
+    pub(super) fn drain_values(&mut self) -> impl Iterator<Item = T> + '_ {
+        self.expressions.clear();
+        self.values.drain(..)
+    }
+
+    pub(super) fn shrink_to_fit(&mut self) {
+        self.values.shrink_to_fit();
+        self.expressions.shrink_to_fit();
+    }
+}
+
+impl DepthStates<i64> {
+    /// Sorts the states by value, keeping each expression with its value.
+    pub(super) fn sort(&mut self) {
+        if self.values.is_sorted() {
+            return;
+        }
+        let mut states: Vec<_> = self
+            .values
+            .drain(..)
+            .zip(self.expressions.drain(..))
+            .collect();
+        states.sort_unstable_by_key(|(x, _)| *x);
+        (self.values, self.expressions) = states.into_iter().unzip();
+    }
+}
@@ -0,0 +1,91 @@
+use super::{Limits, Solver};
+use crate::{Expression, Number};
+
+/// Depths whose concatenations of the digits outnumber this skip concatenation, as a handful
+/// of them would not be worth the thousands of states they add.
+const MAX_CONCATENATIONS: usize = 1 << 14;
+
+impl<T: Number> Solver<T> {
+    /// Like [`new`](Self::new), with the literals written with any of `digits`, each usable any
+    /// number of times, instead of a single `n`. A solution costs the total number of digits it
+    /// is written with, as with a single `n`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `digits` is empty or holds anything but digits from 1 to 9, or if
+    /// `limits.max_quadratic_power` exceeds [`MAX_QUADRATIC_POWER`](crate::MAX_QUADRATIC_POWER).
+    pub fn with_digit_set(digits: &[i64], limits: Limits) -> Self {
+        assert!(
+            !digits.is_empty() && digits.iter().all(|digit| (1..=9).contains(digit)),
+            "digit set {digits:?} must hold digits from 1 to 9"
+        );
+        let mut digit_set = digits.to_vec();
+        digit_set.sort_unstable();
+        digit_set.dedup();
+        let mut solver = Self::new(digit_set[0], limits);
+        solver.digit_set = digit_set;
+        solver
+    }
+
+    /// The digits the literals are written with, in increasing order.
+    #[inline]
+    pub fn digit_set(&self) -> &[i64] {
+        &self.digit_set
+    }
+
+    /// Whether `x` is one of the digits, so that `x / x` writes 1 with two digits.
+    #[inline]
+    pub(super) fn is_digit(&self, x: i64) -> bool {
+        self.digit_set.binary_search(&x).is_ok()
+    }
+
+    /// The number of digits of the set `expression` is written with, or `None` if one of its
+    /// literals is not a concatenation of them.
+    pub(super) fn digit_count(&self, expression: &Expression) -> Option<usize> {
+        if let [n] = self.digit_set[..] {
+            return expression
+                .is_written_with(n)
+                .then(|| expression.digit_count(n));
+        }
+        expression
+            .iter_subexpressions()
+            .filter_map(Expression::to_number)
+            .try_fold(0, |count, x| {
+                let x = x.to_string();
+                x.bytes()
+                    .all(|digit| self.is_digit((digit - b'0') as i64))
+                    .then(|| count + x.len())
+            })
+    }
+
+    /// The decimal width of the widest digit, 2 for a two-digit `n` such as 10.
+    #[inline]
+    pub(super) fn digit_width(&self) -> u32 {
+        self.digit_set.last().unwrap().ilog10() + 1
+    }
+
+    /// The numbers written by concatenating `digits` digits of the set, in increasing order. A
+    /// multi-digit `n` shifts by its whole width, so two copies of 10 write 1010.
+    pub(super) fn concatenations(&self, digits: usize) -> Vec<i64> {
+        if self
+            .digit_set
+            .len()
+            .checked_pow(digits as u32)
+            .is_none_or(|count| count > MAX_CONCATENATIONS)
+        {
+            return vec![];
+        }
+        let mut numbers = vec![0];
+        for _ in 0..digits {
+            numbers = numbers
+                .iter()
+                .flat_map(|x| {
+                    self.digit_set
+                        .iter()
+                        .map(move |digit| x * 10i64.pow(digit.ilog10() + 1) + digit)
+                })
+                .collect();
+        }
+        numbers
+    }
+}
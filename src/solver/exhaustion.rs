@@ -0,0 +1,42 @@
+use super::Solver;
+use crate::Number;
+
+impl<T: Number> Solver<T> {
+    /// Whether the number written with `digits` copies of `n` passes the range limit, measured
+    /// by the repunit `11...1` in base `10^w` for an `n` of `w` decimal digits. Literals are
+    /// `i64`, so longer concatenations are never made even in the 128-bit domains.
+    #[inline]
+    pub(super) fn concat_in_range(&self, digits: usize) -> bool {
+        let width = self.digit_width();
+        let base = 10f64.powi(width as i32);
+        width as usize * digits <= 18
+            && digits as f64 * base.log2() - (base - 1.0).log2() <= self.limits.max_digits as f64
+    }
+
+    /// The deepest depth that holds any state, or 0 before the first search.
+    fn last_nonempty_depth(&self) -> usize {
+        self.states_by_depth
+            .iter()
+            .rposition(|states| !states.is_empty())
+            .unwrap_or(0)
+    }
+
+    /// Whether no deeper search can find a new state under the current limits.
+    ///
+    /// Every state of depth `d` combines states of two smaller depths summing to `d`, or is the
+    /// number written with `d` copies of `n`. So once the depths after the last non-empty one `m`
+    /// have come up empty through depth `2m`, and the next written number is out of range, every
+    /// deeper depth stays empty too.
+    pub fn is_exhausted(&self) -> bool {
+        let depth = self.depth_searched;
+        depth > 0
+            && self.pruned_depth.is_none()
+            && self
+                .extra_states_by_depth
+                .iter()
+                .skip(depth + 1)
+                .all(Vec::is_empty)
+            && depth >= 2 * self.last_nonempty_depth()
+            && !self.concat_in_range(depth + 1)
+    }
+}
@@ -0,0 +1,222 @@
+use super::{BinaryOperation, RangeCheck, Solver, State, StateIndex};
+use crate::number_theory::{factorial, integer_root, subfactorial};
+use crate::Number;
+
+pub(super) trait GoalLookup<T: Number> {
+    fn update_goals(&mut self);
+    fn uses_goal_lookup(&self, digits: usize) -> bool;
+    fn search_goal_pairs(&mut self, digits: usize) -> bool;
+    fn search_goal_pairs_at_deadline(&mut self, digits: usize) -> bool;
+}
+
+impl<T: Number> GoalLookup<T> for Solver<T> {
+    default fn update_goals(&mut self) {}
+
+    default fn uses_goal_lookup(&self, _digits: usize) -> bool {
+        false
+    }
+
+    default fn search_goal_pairs(&mut self, _digits: usize) -> bool {
+        false
+    }
+
+    default fn search_goal_pairs_at_deadline(&mut self, _digits: usize) -> bool {
+        false
+    }
+}
+
+impl Solver<i64> {
+    /// Every value whose unary closure (repeated square roots, factorials and subfactorials, as
+    /// applied on insertion) can reach the target.
+    pub(super) fn goal_closure(&self) -> Vec<i64> {
+        let mut goals = vec![self.target];
+        let mut i = 0;
+        while i < goals.len() {
+            let goal = goals[i];
+            i += 1;
+            if let Some(square) = goal.checked_mul(goal) {
+                if self.range_check(&square) && !goals.contains(&square) {
+                    goals.push(square);
+                }
+            }
+            for k in 3..self.limits.max_factorial {
+                if factorial(k) == goal && !goals.contains(&k) {
+                    goals.push(k);
+                }
+            }
+            for k in 3..self.limits.max_subfactorial {
+                if subfactorial(k) == goal && !goals.contains(&k) {
+                    goals.push(k);
+                }
+            }
+        }
+        goals
+    }
+
+    /// Partners `y` for which some binary operation on `x` and `y`, in either order, can produce
+    /// `goal`. May contain values no operation actually accepts.
+    fn partners(x: i64, goal: i64, partners: &mut Vec<i64>) {
+        partners.push(goal - x);
+        partners.push(x - goal);
+        partners.push(x + goal);
+        if goal % x == 0 {
+            partners.push(goal / x);
+        }
+        if x % goal == 0 {
+            partners.push(x / goal);
+        }
+        if let Some(y) = goal.checked_mul(x) {
+            partners.push(y);
+        }
+        // x^y, possibly under square roots: goal = x^e with y = e * 2^k.
+        if x >= 2 {
+            let mut power = x;
+            let mut exponent = 1i64;
+            while power < goal {
+                match power.checked_mul(x) {
+                    Some(p) => power = p,
+                    None => break,
+                }
+                exponent += 1;
+            }
+            if power == goal {
+                let mut y = exponent;
+                while y <= 0x80000000 {
+                    partners.push(y);
+                    y <<= 1;
+                }
+            }
+        }
+        // y^x, possibly under square roots: goal = y^e with x = e * 2^k.
+        let mut exponent = x;
+        loop {
+            if let Some(y) = integer_root(goal, exponent) {
+                partners.push(y);
+            }
+            if exponent % 2 != 0 {
+                break;
+            }
+            exponent >>= 1;
+        }
+        // x! / y! and y! / x!.
+        let mut product = x;
+        let mut y = x - 1;
+        while y >= 1 && product <= goal {
+            if product == goal {
+                partners.push(y);
+            }
+            match product.checked_mul(y) {
+                Some(p) => product = p,
+                None => break,
+            }
+            y -= 1;
+        }
+        let mut product = x + 1;
+        let mut y = x + 1;
+        while product <= goal {
+            if product == goal {
+                partners.push(y);
+            }
+            y += 1;
+            match product.checked_mul(y) {
+                Some(p) => product = p,
+                None => break,
+            }
+        }
+    }
+}
+
+impl GoalLookup<i64> for Solver<i64> {
+    fn update_goals(&mut self) {
+        self.goals = if self.goal_lookup
+            && !self.modulo
+            && !self.gcd_lcm
+            && !self.progressive
+            && self.target > 0
+        {
+            self.goal_closure()
+        } else {
+            vec![]
+        };
+    }
+
+    #[inline]
+    fn uses_goal_lookup(&self, digits: usize) -> bool {
+        !self.goals.is_empty() && digits == self.max_depth
+    }
+
+    fn search_goal_pairs(&mut self, digits: usize) -> bool {
+        for depth in 1..digits {
+            self.states_by_depth[depth].sort();
+        }
+        let goals = self.goals.clone();
+        let mut partners = vec![];
+        for d1 in 1..=(digits >> 1) {
+            let d2 = digits - d1;
+            // Every operation is tried in both orders, so scanning the shorter side suffices.
+            let (d1, d2) = if self.states_by_depth[d1].len() <= self.states_by_depth[d2].len() {
+                (d1, d2)
+            } else {
+                (d2, d1)
+            };
+            for i in 0..self.states_by_depth[d1].len() {
+                let x = self.states_by_depth[d1].value(i);
+                if x <= 0 {
+                    continue;
+                }
+                partners.clear();
+                for &goal in &goals {
+                    Self::partners(x, goal, &mut partners);
+                }
+                partners.sort_unstable();
+                partners.dedup();
+                for &y in &partners {
+                    let Ok(j) = self.states_by_depth[d2].values().binary_search(&y) else {
+                        continue;
+                    };
+                    if d1 == d2 && j < i {
+                        continue;
+                    }
+                    if self.binary_operation(
+                        State {
+                            number: x,
+                            digits: d1,
+                            index: StateIndex {
+                                depth: d1,
+                                position: i,
+                            },
+                        },
+                        State {
+                            number: y,
+                            digits: d2,
+                            index: StateIndex {
+                                depth: d2,
+                                position: j,
+                            },
+                        },
+                    ) {
+                        return true;
+                    }
+                }
+            }
+        }
+        false
+    }
+    /// Like [`search_goal_pairs`](Self::search_goal_pairs), with the goals of the target even
+    /// when goal lookup is off. Partners miss the values only modulo, gcd or lcm produce, which
+    /// is acceptable for a last best-effort check.
+    fn search_goal_pairs_at_deadline(&mut self, digits: usize) -> bool {
+        if self.target <= 0 {
+            return false;
+        }
+        let lookup_off = self.goals.is_empty();
+        if lookup_off {
+            self.goals = self.goal_closure();
+        }
+        let found = self.search_goal_pairs(digits);
+        if lookup_off {
+            self.goals.clear();
+        }
+        found
+    }
+}
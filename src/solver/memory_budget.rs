@@ -0,0 +1,20 @@
+use super::{Interruption, Solver};
+use crate::Number;
+
+impl<T: Number> Solver<T> {
+    /// Stops searches once the states take about `budget` bytes, as estimated from their count,
+    /// instead of growing until the process runs out of memory. Searches then return as if
+    /// nothing was found, keep their position in the depth, and stay stopped until the budget
+    /// is raised.
+    #[inline]
+    pub fn set_memory_budget(&mut self, budget: Option<usize>) {
+        self.memory_budget = budget;
+    }
+
+    /// Whether the last [`solve`](Self::solve) or [`exhaust_to_depth`](Self::exhaust_to_depth)
+    /// call stopped at the memory budget.
+    #[inline]
+    pub fn memory_exceeded(&self) -> bool {
+        self.interruption == Some(Interruption::MemoryExceeded)
+    }
+}
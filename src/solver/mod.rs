@@ -1,36 +1,130 @@
-use crate::{Expression, Number};
+use crate::{Expression, Number, MAX_QUADRATIC_POWER};
 use binary_operation::BinaryOperation;
+use bloom_filter::BloomFilter;
+use dead_states::{DeadStateCuts, DeadStates};
+use depth_states::DepthStates;
+use goal_lookup::GoalLookup;
+use modular_pruning::{GoalResidues, ModularPruning};
 use range_check::RangeCheck;
 use rustc_hash::FxHashMap;
+use search_trace::SearchTrace;
 use searcher::Searcher;
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::io;
 use std::rc::Rc;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::Tracer;
 use unary_operation::UnaryOperation;
+use value_store::ValueStore;
 
+pub(crate) use cancellation::{outcome, Interruption};
+pub use persistence::{DecodeStateError, StateCodec};
+pub(crate) use progress_events::SharedProgressCallback;
+pub use search_trace::Operation;
+pub(crate) use search_trace::{SharedTraceSink, TraceSink};
+pub use solve_iter::SolveIter;
+pub use stage_order::{Stage, StageOrder};
+pub use state_store::{FileStateStore, MemoryStateStore, StateStore};
+pub use tracing::{Rejection, RejectionReason};
+
+mod alternatives;
 mod binary_operation;
+mod bloom_filter;
+mod cancellation;
+#[cfg(feature = "checkpoint")]
+mod checkpoint;
+mod dead_states;
+mod deadline;
+mod decomposition;
+mod depth_progress;
+mod depth_states;
+mod digit_set;
+mod exhaustion;
+mod goal_lookup;
+mod memory_budget;
+mod modular_pruning;
+mod persistence;
+mod progress_events;
 mod range_check;
+mod search_trace;
 mod searcher;
+mod solve_iter;
+#[allow(clippy::module_inception)]
 mod solver;
+mod splitting;
+mod stage_order;
+mod state_store;
+mod tracing;
+mod tuning;
 mod unary_operation;
+mod value_index;
+mod value_store;
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 pub struct Limits {
     pub max_digits: usize,
     pub max_factorial: i64,
+    /// Square roots of quadratic numbers nest at most this deep, which must not exceed
+    /// [`MAX_QUADRATIC_POWER`].
     pub max_quadratic_power: u8,
+    /// [`Radical`](crate::Radical) states have roots of degree at most this.
+    #[serde(default = "default_max_radical_power")]
+    pub max_radical_power: u16,
+    /// Subfactorials `!x` are taken for `x < max_subfactorial`; 0 leaves them out.
+    pub max_subfactorial: i64,
+}
+
+#[inline]
+fn default_max_radical_power() -> u16 {
+    1
+}
+
+impl Limits {
+    /// Checks that the limits can be searched with, describing the first that cannot: only
+    /// `max_quadratic_power` is bounded, by [`MAX_QUADRATIC_POWER`]. Solvers panic on limits
+    /// failing this, so hosts passing on limits from elsewhere should check them first.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_quadratic_power > MAX_QUADRATIC_POWER {
+            return Err(format!(
+                "max_quadratic_power {} exceeds {}",
+                self.max_quadratic_power, MAX_QUADRATIC_POWER
+            ));
+        }
+        Ok(())
+    }
+
+    fn assert_valid(&self) {
+        if let Err(error) = self.validate() {
+            panic!("{error}");
+        }
+    }
+}
+
+/// Position of a state in `states_by_depth`, so that its expression is only fetched when a
+/// candidate built from it is actually inserted.
+#[derive(Clone, Copy)]
+struct StateIndex {
+    depth: usize,
+    position: usize,
 }
 
 struct State<T: Number> {
     number: T,
     digits: usize,
-    expression: Rc<Expression>,
+    index: StateIndex,
 }
 
 #[derive(Clone)]
+#[cfg_attr(feature = "checkpoint", derive(Deserialize, Serialize))]
 enum SearchState {
     None,
     Concat,
     ExtraState(usize),
     UnaryOperation(usize),
+    /// The index of the pair of depths, as ordered by [`Solver::pair_depth`], and the positions.
     BinaryOperationOfDifferentDepth(usize, (usize, usize)),
     BinaryOperationOfSameDepth((usize, usize)),
     Finish,
@@ -39,13 +133,53 @@ enum SearchState {
 #[derive(Clone)]
 pub struct Solver<T: Number> {
     n: i64,
+    /// The digits literals are written with, just `n` unless given a digit set.
+    digit_set: Vec<i64>,
     target: T,
-    states: FxHashMap<T, (Rc<Expression>, usize)>,
-    states_by_depth: Vec<Vec<T>>,
+    states: Box<dyn StateStore<T>>,
+    keep_all: bool,
+    /// Further expressions of states with as many digits as the first, when keeping them all.
+    alternatives: FxHashMap<T, Vec<Rc<Expression>>>,
+    states_by_depth: Vec<DepthStates<T>>,
     extra_states_by_depth: Vec<Vec<(T, Rc<Expression>)>>,
     depth_searched: usize,
     search_state: SearchState,
+    stage_order: StageOrder,
     limits: Limits,
     progressive: bool,
     new_numbers: Vec<T>,
+    max_depth: usize,
+    modular_pruning: bool,
+    goal_residues: Option<GoalResidues>,
+    goal_lookup: bool,
+    goals: Vec<i64>,
+    dead_state_pruning: bool,
+    dead_states: Option<DeadStateCuts>,
+    bloom_filter: bool,
+    modulo: bool,
+    gcd_lcm: bool,
+    negation: bool,
+    seen_filter: Option<BloomFilter>,
+    pruned_depth: Option<usize>,
+    tracer: Option<Box<Tracer<T>>>,
+    search_trace: Option<Box<SearchTrace<T>>>,
+    value_index: Vec<(f64, T)>,
+    candidates: u64,
+    soft_deadline: Option<Duration>,
+    deadline: Option<Instant>,
+    deadline_reached: bool,
+    cancel_token: Option<Arc<AtomicBool>>,
+    /// Why the current call stopped early, keeping its place.
+    interruption: Option<Interruption>,
+    /// The error the state store failed with, which stops every later search.
+    storage_error: Option<Rc<io::Error>>,
+    timeout: Option<Instant>,
+    /// The bytes [`memory_estimate`](Solver::memory_estimate) may reach before searches stop.
+    memory_budget: Option<usize>,
+    progress_callback: Option<SharedProgressCallback>,
+    /// The distance to the target of the closest state inserted during the solve call.
+    closest: f64,
+    /// Keeps values of domains such as [`BigNumber`](crate::BigNumber) allocated while the
+    /// solver lives.
+    _value_store: Option<Rc<dyn Any>>,
 }
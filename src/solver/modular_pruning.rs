@@ -0,0 +1,103 @@
+use super::Solver;
+use crate::Number;
+
+const MODULI: [i64; 5] = [7, 8, 9, 11, 13];
+
+#[derive(Clone, Copy)]
+pub(super) struct Residues([i64; MODULI.len()]);
+
+impl Residues {
+    #[inline]
+    pub(super) fn of(x: i64) -> Self {
+        Self(MODULI.map(|m| x.rem_euclid(m)))
+    }
+
+    #[inline]
+    pub(super) fn add(&self, rhs: &Self) -> Self {
+        self.zip_with(rhs, |x, y, m| (x + y) % m)
+    }
+
+    #[inline]
+    pub(super) fn subtract(&self, rhs: &Self) -> Self {
+        self.zip_with(rhs, |x, y, m| (x - y).rem_euclid(m))
+    }
+
+    #[inline]
+    pub(super) fn multiply(&self, rhs: &Self) -> Self {
+        self.zip_with(rhs, |x, y, m| x * y % m)
+    }
+
+    fn zip_with(&self, rhs: &Self, f: impl Fn(i64, i64, i64) -> i64) -> Self {
+        let mut result = [0; MODULI.len()];
+        for (i, r) in result.iter_mut().enumerate() {
+            *r = f(self.0[i], rhs.0[i], MODULI[i]);
+        }
+        Self(result)
+    }
+}
+
+/// The residues of every value whose unary closure (repeated square roots, factorials and
+/// subfactorials, as applied on insertion) can reach the target.
+#[derive(Clone, Copy)]
+pub(super) struct GoalResidues([u16; MODULI.len()]);
+
+impl GoalResidues {
+    #[inline]
+    pub(super) fn accepts(&self, residues: &Residues) -> bool {
+        self.0
+            .iter()
+            .zip(&residues.0)
+            .all(|(&mask, &r)| mask & (1 << r) != 0)
+    }
+
+    /// Whether `x / y` can be a goal, when `y` is known to divide `x`. Moduli sharing a factor
+    /// with `y` cannot decide this and always pass.
+    pub(super) fn accepts_quotient(&self, x: &Residues, y: &Residues) -> bool {
+        (0..MODULI.len()).all(|i| {
+            let m = MODULI[i];
+            match (1..m).find(|&inverse| y.0[i] * inverse % m == 1) {
+                Some(inverse) => self.0[i] & (1 << (x.0[i] * inverse % m)) != 0,
+                None => true,
+            }
+        })
+    }
+}
+
+pub(super) trait ModularPruning<T: Number> {
+    fn update_goal_residues(&mut self);
+    fn residue_check(&self, x: &T, digits: usize) -> bool;
+}
+
+impl<T: Number> ModularPruning<T> for Solver<T> {
+    default fn update_goal_residues(&mut self) {}
+
+    default fn residue_check(&self, _x: &T, _digits: usize) -> bool {
+        true
+    }
+}
+
+impl ModularPruning<i64> for Solver<i64> {
+    fn update_goal_residues(&mut self) {
+        if !self.modular_pruning || self.progressive {
+            self.goal_residues = None;
+            return;
+        }
+        let mut masks = [0u16; MODULI.len()];
+        for goal in self.goal_closure() {
+            for (mask, &r) in masks.iter_mut().zip(&Residues::of(goal).0) {
+                *mask |= 1 << r;
+            }
+        }
+        self.goal_residues = Some(GoalResidues(masks));
+    }
+
+    #[inline]
+    fn residue_check(&self, x: &i64, digits: usize) -> bool {
+        match &self.goal_residues {
+            Some(goal_residues) if digits == self.max_depth => {
+                goal_residues.accepts(&Residues::of(*x))
+            }
+            _ => true,
+        }
+    }
+}
@@ -0,0 +1,376 @@
+use super::{DepthStates, SearchState, Solver};
+use crate::encoding::{read_varint, write_varint};
+use crate::{
+    DecodeExpressionError, Expression, GenericIntegralQuadratic, GenericRationalQuadratic, Number,
+    PrimeBasis, Rational, Rational128, MAX_QUADRATIC_POWER,
+};
+use std::fmt;
+use std::rc::Rc;
+
+/// Exported tables that cannot be imported: malformed, truncated, or built for another `n`,
+/// other limits or other operations.
+#[derive(Debug)]
+pub struct DecodeStateError {}
+
+impl fmt::Display for DecodeStateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "cannot import solver states")
+    }
+}
+
+impl From<DecodeExpressionError> for DecodeStateError {
+    #[inline]
+    fn from(_: DecodeExpressionError) -> Self {
+        DecodeStateError {}
+    }
+}
+
+pub(crate) type Bytes<'a> = std::iter::Copied<std::slice::Iter<'a, u8>>;
+
+#[inline]
+fn write_signed(bytes: &mut Vec<u8>, x: i64) {
+    write_varint(bytes, ((x << 1) ^ (x >> 63)) as u64);
+}
+
+#[inline]
+fn read_signed(bytes: &mut Bytes) -> Result<i64, DecodeStateError> {
+    let x = read_varint(bytes)?;
+    Ok(((x >> 1) as i64) ^ -((x & 1) as i64))
+}
+
+/// A zigzag-encoded `i128`, as its low and high 64-bit halves.
+#[inline]
+fn write_signed128(bytes: &mut Vec<u8>, x: i128) {
+    let x = ((x << 1) ^ (x >> 127)) as u128;
+    write_varint(bytes, x as u64);
+    write_varint(bytes, (x >> 64) as u64);
+}
+
+#[inline]
+fn read_signed128(bytes: &mut Bytes) -> Result<i128, DecodeStateError> {
+    let x = (read_varint(bytes)? as u128) | ((read_varint(bytes)? as u128) << 64);
+    Ok(((x >> 1) as i128) ^ -((x & 1) as i128))
+}
+
+#[inline]
+pub(super) fn read_usize(bytes: &mut Bytes) -> Result<usize, DecodeStateError> {
+    Ok(read_varint(bytes)? as usize)
+}
+
+/// The byte encoding of state values.
+pub trait StateCodec: Number {
+    fn write(&self, bytes: &mut Vec<u8>);
+    fn read(bytes: &mut Bytes) -> Result<Self, DecodeStateError>;
+}
+
+impl StateCodec for i64 {
+    #[inline]
+    fn write(&self, bytes: &mut Vec<u8>) {
+        write_signed(bytes, *self);
+    }
+
+    #[inline]
+    fn read(bytes: &mut Bytes) -> Result<Self, DecodeStateError> {
+        read_signed(bytes)
+    }
+}
+
+impl StateCodec for Rational {
+    #[inline]
+    fn write(&self, bytes: &mut Vec<u8>) {
+        write_signed(bytes, self.numerator());
+        write_signed(bytes, self.denominator());
+    }
+
+    #[inline]
+    fn read(bytes: &mut Bytes) -> Result<Self, DecodeStateError> {
+        let numerator = read_signed(bytes)?;
+        let denominator = read_signed(bytes)?;
+        if denominator <= 0 {
+            return Err(DecodeStateError {});
+        }
+        Ok(Rational::new(numerator, denominator))
+    }
+}
+
+impl StateCodec for i128 {
+    #[inline]
+    fn write(&self, bytes: &mut Vec<u8>) {
+        write_signed128(bytes, *self);
+    }
+
+    #[inline]
+    fn read(bytes: &mut Bytes) -> Result<Self, DecodeStateError> {
+        read_signed128(bytes)
+    }
+}
+
+impl StateCodec for Rational128 {
+    #[inline]
+    fn write(&self, bytes: &mut Vec<u8>) {
+        write_signed128(bytes, self.numerator());
+        write_signed128(bytes, self.denominator());
+    }
+
+    #[inline]
+    fn read(bytes: &mut Bytes) -> Result<Self, DecodeStateError> {
+        let numerator = read_signed128(bytes)?;
+        let denominator = read_signed128(bytes)?;
+        if denominator <= 0 {
+            return Err(DecodeStateError {});
+        }
+        Ok(Rational128::new(numerator, denominator))
+    }
+}
+
+fn write_quadratic_part<B: PrimeBasis>(bytes: &mut Vec<u8>, part: &B::Powers, power: u8) {
+    bytes.push(power);
+    for &x in part.as_ref() {
+        write_varint(bytes, x as u64);
+    }
+}
+
+/// Reads the nested square root of a quadratic value whose other part is `zero` or not. Only
+/// the normal form is accepted: no root of zero, nesting within [`MAX_QUADRATIC_POWER`], every
+/// power below `2^power`, and not all of them even, which would be one root fewer.
+fn read_quadratic_part<B: PrimeBasis>(
+    bytes: &mut Bytes,
+    zero: bool,
+) -> Result<(B::Powers, u8), DecodeStateError> {
+    let power = bytes.next().ok_or(DecodeStateError {})?;
+    if power > MAX_QUADRATIC_POWER || (zero && power > 0) {
+        return Err(DecodeStateError {});
+    }
+    let mut part = B::Powers::default();
+    for x in part.as_mut() {
+        *x = u16::try_from(read_varint(bytes)?)
+            .ok()
+            .filter(|&x| x >> power == 0)
+            .ok_or(DecodeStateError {})?;
+    }
+    if power > 0 && part.as_ref().iter().all(|x| x % 2 == 0) {
+        return Err(DecodeStateError {});
+    }
+    Ok((part, power))
+}
+
+impl<B: PrimeBasis> StateCodec for GenericIntegralQuadratic<B> {
+    fn write(&self, bytes: &mut Vec<u8>) {
+        write_signed(bytes, self.integral_part);
+        write_quadratic_part::<B>(bytes, &self.quadratic_part, self.quadratic_power);
+    }
+
+    fn read(bytes: &mut Bytes) -> Result<Self, DecodeStateError> {
+        let integral_part = read_signed(bytes)?;
+        let (quadratic_part, quadratic_power) =
+            read_quadratic_part::<B>(bytes, integral_part == 0)?;
+        Ok(Self {
+            integral_part,
+            quadratic_part,
+            quadratic_power,
+        })
+    }
+}
+
+impl<B: PrimeBasis> StateCodec for GenericRationalQuadratic<B> {
+    fn write(&self, bytes: &mut Vec<u8>) {
+        self.rational_part.write(bytes);
+        write_quadratic_part::<B>(bytes, &self.quadratic_part, self.quadratic_power);
+    }
+
+    fn read(bytes: &mut Bytes) -> Result<Self, DecodeStateError> {
+        let rational_part = Rational::read(bytes)?;
+        let (quadratic_part, quadratic_power) =
+            read_quadratic_part::<B>(bytes, rational_part.numerator() == 0)?;
+        Ok(Self {
+            rational_part,
+            quadratic_part,
+            quadratic_power,
+        })
+    }
+}
+
+impl<T: StateCodec> Solver<T> {
+    /// The settings that decide which states a search builds.
+    fn write_settings(&self, bytes: &mut Vec<u8>) {
+        // A digit set is written as its negated size and its digits, which no `n` collides with.
+        if let [n] = self.digit_set[..] {
+            write_signed(bytes, n);
+        } else {
+            write_signed(bytes, -(self.digit_set.len() as i64));
+            for &digit in &self.digit_set {
+                write_signed(bytes, digit);
+            }
+        }
+        write_varint(bytes, self.limits.max_digits as u64);
+        write_signed(bytes, self.limits.max_factorial);
+        bytes.push(self.limits.max_quadratic_power);
+        write_varint(bytes, self.limits.max_radical_power as u64);
+        write_signed(bytes, self.limits.max_subfactorial);
+        bytes.push(self.modulo as u8);
+        bytes.push(self.gcd_lcm as u8);
+    }
+
+    /// Appends the settings and the states of every depth up to `depth`, which must have been
+    /// searched to completion.
+    pub(crate) fn export_states(&self, depth: usize, bytes: &mut Vec<u8>) {
+        self.write_settings(bytes);
+        write_varint(bytes, depth as u64);
+        for digits in 1..=depth {
+            let states = self.states_by_depth.get(digits);
+            write_varint(bytes, states.map_or(0, DepthStates::len) as u64);
+            for (x, expression) in states.into_iter().flat_map(DepthStates::iter) {
+                write_state(bytes, x, expression);
+            }
+        }
+    }
+
+    /// Reads the states appended by [`export_states`](Self::export_states), and the depth they
+    /// are complete to. Fails if the export was made with other settings, or if a state is not
+    /// what its expression evaluates to with its digit count.
+    pub(crate) fn decode_states(
+        &self,
+        bytes: &mut Bytes,
+    ) -> Result<DecodedStates<T>, DecodeStateError> {
+        let mut settings = vec![];
+        self.write_settings(&mut settings);
+        for expected in settings {
+            if bytes.next() != Some(expected) {
+                return Err(DecodeStateError {});
+            }
+        }
+        let depth = read_usize(bytes)?;
+        let mut states = vec![];
+        for digits in 1..=depth {
+            for _ in 0..read_usize(bytes)? {
+                let (x, expression) = read_state(bytes)?;
+                self.check_state(&x, digits, &expression)?;
+                states.push((x, digits, expression));
+            }
+        }
+        Ok(DecodedStates { depth, states })
+    }
+
+    /// Fails unless `expression` is written with `digits` digits of the solver and evaluates to
+    /// `x`, so that imported states cannot bring in wrong solutions. The search reaches some
+    /// values through intermediates too large to evaluate exactly, such as `((3!)!)!/((3!)!-3)!`,
+    /// and those are only checked for their digits.
+    pub(super) fn check_state(
+        &self,
+        x: &T,
+        digits: usize,
+        expression: &Expression,
+    ) -> Result<(), DecodeStateError> {
+        let evaluates_to_x = expression.evaluate::<T>().is_none_or(|value| value == *x);
+        if self.digit_count(expression) == Some(digits) && evaluates_to_x {
+            Ok(())
+        } else {
+            Err(DecodeStateError {})
+        }
+    }
+
+    /// Replaces all states with decoded ones, as if the search had just completed their depth.
+    pub(crate) fn restore_states(&mut self, decoded: DecodedStates<T>) {
+        self.states.clear();
+        self.states_by_depth = vec![DepthStates::default(); decoded.depth + 1];
+        self.extra_states_by_depth.clear();
+        self.seen_filter = None;
+        self.pruned_depth = None;
+        for (x, digits, expression) in decoded.states {
+            self.insert(x, digits, expression);
+        }
+        self.new_numbers.clear();
+        self.depth_searched = decoded.depth;
+        self.search_state = SearchState::None;
+    }
+}
+
+/// Appends a state value and its encoded expression.
+pub(super) fn write_state<T: StateCodec>(bytes: &mut Vec<u8>, x: &T, expression: &Expression) {
+    x.write(bytes);
+    let expression = expression.encode();
+    write_varint(bytes, expression.len() as u64);
+    bytes.extend(expression);
+}
+
+/// Reads a state appended by [`write_state`].
+pub(super) fn read_state<T: StateCodec>(
+    bytes: &mut Bytes,
+) -> Result<(T, Rc<Expression>), DecodeStateError> {
+    let x = T::read(bytes)?;
+    let length = read_usize(bytes)?;
+    let expression: Vec<u8> = bytes.by_ref().take(length).collect();
+    if expression.len() != length {
+        return Err(DecodeStateError {});
+    }
+    Ok((x, Expression::decode(&expression)?))
+}
+
+pub(crate) struct DecodedStates<T> {
+    depth: usize,
+    states: Vec<(T, usize, Rc<Expression>)>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{write_state, StateCodec};
+    use crate::encoding::write_varint;
+    use crate::{Expression, Limits, RationalQuadratic, Solver};
+
+    fn solver() -> Solver<i64> {
+        Solver::new(3, Limits::recommended_integral())
+    }
+
+    /// An export of the settings of [`solver`] and a single state at depth 1.
+    fn export_one(x: i64, expression: &Expression) -> Vec<u8> {
+        let mut bytes = vec![];
+        solver().write_settings(&mut bytes);
+        write_varint(&mut bytes, 1);
+        write_varint(&mut bytes, 1);
+        write_state(&mut bytes, &x, expression);
+        bytes
+    }
+
+    #[test]
+    fn round_trips_searched_states() {
+        let mut searched = solver();
+        searched.exhaust_to_depth(3);
+        let mut bytes = vec![];
+        searched.export_states(3, &mut bytes);
+        let decoded = solver().decode_states(&mut bytes.iter().copied()).unwrap();
+        assert_eq!(decoded.depth, 3);
+        assert_eq!(decoded.states.len(), searched.states.len());
+    }
+
+    #[test]
+    fn rejects_wrong_states() {
+        let three = Expression::from_number(3);
+        let bytes = export_one(3, &three);
+        assert!(solver().decode_states(&mut bytes.iter().copied()).is_ok());
+        let bytes = export_one(4, &three);
+        assert!(solver().decode_states(&mut bytes.iter().copied()).is_err());
+        let bytes = export_one(33, &Expression::from_number(33));
+        assert!(solver().decode_states(&mut bytes.iter().copied()).is_err());
+        let bytes = export_one(4, &Expression::from_number(4));
+        assert!(solver().decode_states(&mut bytes.iter().copied()).is_err());
+        let mut bytes = vec![];
+        Solver::<i64>::new(4, Limits::recommended_integral()).write_settings(&mut bytes);
+        bytes.extend([1, 0]);
+        assert!(solver().decode_states(&mut bytes.iter().copied()).is_err());
+    }
+
+    #[test]
+    fn rejects_quadratic_values_out_of_normal_form() {
+        let read = |bytes: &[u8]| RationalQuadratic::read(&mut bytes.iter().copied());
+        let x: RationalQuadratic = "3*sqrt(sqrt(2))".parse().unwrap();
+        let mut bytes = vec![];
+        x.write(&mut bytes);
+        assert!(read(&bytes).unwrap() == x);
+        // 1, then the power and the powers of 2, 3, 5 and 7 under the root.
+        assert!(read(&[2, 2, 1, 1, 0, 0, 0]).is_ok());
+        assert!(read(&[2, 2, 16, 1, 0, 0, 0]).is_err());
+        assert!(read(&[2, 2, 1, 2, 0, 0, 0]).is_err());
+        assert!(read(&[2, 2, 1, 0, 0, 0, 0]).is_err());
+        assert!(read(&[0, 2, 1, 1, 0, 0, 0]).is_err());
+    }
+}
@@ -0,0 +1,64 @@
+use super::Solver;
+use crate::{Expression, Number, ProgressEvent, Solution};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// The states inserted between two [`ProgressEvent::StatesInserted`] events.
+const PROGRESS_INTERVAL: usize = 1 << 16;
+
+/// A progress callback, shared by the solvers of every domain of a search.
+pub(crate) type SharedProgressCallback = Rc<RefCell<Box<dyn FnMut(ProgressEvent)>>>;
+
+impl<T: Number> Solver<T> {
+    /// Reports the events of the search to `callback` as they happen, replacing any earlier
+    /// callback, for hosts such as a GUI that show the search live.
+    #[inline]
+    pub fn set_progress_callback(&mut self, callback: Box<dyn FnMut(ProgressEvent)>) {
+        self.progress_callback = Some(Rc::new(RefCell::new(callback)));
+    }
+
+    #[inline]
+    pub(crate) fn set_shared_progress_callback(&mut self, callback: SharedProgressCallback) {
+        self.progress_callback = Some(callback);
+    }
+
+    fn report(&self, event: ProgressEvent) {
+        if let Some(callback) = &self.progress_callback {
+            (callback.borrow_mut())(event);
+        }
+    }
+
+    pub(super) fn report_depth_started(&self, digits: usize) {
+        self.report(ProgressEvent::DepthStarted {
+            domain: T::DOMAIN,
+            depth: digits,
+        });
+    }
+
+    pub(super) fn report_depth_finished(&self, digits: usize) {
+        self.report(ProgressEvent::DepthFinished {
+            domain: T::DOMAIN,
+            depth: digits,
+            states: self.states_by_depth[digits].len(),
+        });
+    }
+
+    /// Reports a batch of insertions, and `x` if it is the closest state to the target yet.
+    pub(super) fn report_insertion(&mut self, x: T, digits: usize, expression: &Rc<Expression>) {
+        if self.states.len().is_multiple_of(PROGRESS_INTERVAL) {
+            self.report(ProgressEvent::StatesInserted {
+                domain: T::DOMAIN,
+                depth: self.depth_searched + 1,
+                states: self.states.len(),
+            });
+        }
+        let error = (x.to_f64() - self.target.to_f64()).abs();
+        if error < self.closest {
+            self.closest = error;
+            self.report(ProgressEvent::BestCandidate {
+                solution: Solution::new(expression.clone(), digits, T::DOMAIN, None),
+                error,
+            });
+        }
+    }
+}
@@ -6,8 +6,8 @@ pub(super) trait RangeCheck<T: Number> {
 }
 
 impl<T: Number> RangeCheck<T> for Solver<T> {
-    default fn range_check(&self, _x: &T) -> bool {
-        true
+    default fn range_check(&self, x: &T) -> bool {
+        x.in_range(&self.limits)
     }
 }
 
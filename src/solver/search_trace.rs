@@ -0,0 +1,142 @@
+use crate::Number;
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+/// The operation that produced an inserted state.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Operation {
+    /// A concatenation of copies of `n`, with no operands.
+    Concat,
+    /// A state queued with `insert_extra`, with no operands.
+    Extra,
+    /// A state handed to [`Solver::try_insert`](super::Solver::try_insert) from outside the
+    /// search, such as a value carried over from another domain.
+    Import,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Power,
+    /// `x! / y!`.
+    FactorialDivide,
+    Modulo,
+    Gcd,
+    Lcm,
+    Sqrt,
+    Factorial,
+    Subfactorial,
+    /// `(p ± q) / q` or its inverse, from a quotient `p / q` whose denominator is cheap.
+    DivisionDiffOne,
+}
+
+impl Operation {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Operation::Concat => "concat",
+            Operation::Extra => "extra",
+            Operation::Import => "import",
+            Operation::Add => "add",
+            Operation::Subtract => "subtract",
+            Operation::Multiply => "multiply",
+            Operation::Divide => "divide",
+            Operation::Power => "power",
+            Operation::FactorialDivide => "factorial_divide",
+            Operation::Modulo => "modulo",
+            Operation::Gcd => "gcd",
+            Operation::Lcm => "lcm",
+            Operation::Sqrt => "sqrt",
+            Operation::Factorial => "factorial",
+            Operation::Subfactorial => "subfactorial",
+            Operation::DivisionDiffOne => "division_diff_one",
+        }
+    }
+}
+
+/// Where trace lines go, shared by every solver tracing into the same file.
+pub(crate) struct TraceSink {
+    writer: Box<dyn Write>,
+    error: Option<io::Error>,
+}
+
+pub(crate) type SharedTraceSink = Rc<RefCell<TraceSink>>;
+
+impl TraceSink {
+    /// Wraps `writer` and writes the header line.
+    pub(crate) fn shared(writer: impl Write + 'static) -> SharedTraceSink {
+        let mut sink = TraceSink {
+            writer: Box::new(writer),
+            error: None,
+        };
+        sink.write_line(format_args!("domain\tdigits\tvalue\toperation\tx\ty"));
+        Rc::new(RefCell::new(sink))
+    }
+
+    /// Writes one line, unless an earlier write failed; only the first error is kept.
+    fn write_line(&mut self, line: std::fmt::Arguments) {
+        if self.error.is_none() {
+            if let Err(error) = writeln!(self.writer, "{line}") {
+                self.error = Some(error);
+            }
+        }
+    }
+
+    /// Flushes the writer, reporting the first error of any write so far.
+    pub(crate) fn finish(&mut self) -> io::Result<()> {
+        match self.error.take() {
+            Some(error) => Err(error),
+            None => self.writer.flush(),
+        }
+    }
+}
+
+/// Streams every accepted insertion as a tab-separated line: domain, digits, value, producing
+/// operation, and the values of its operands, which are states of the same solver. Missing
+/// operands are left empty.
+#[derive(Clone)]
+pub(super) struct SearchTrace<T: Number> {
+    sink: SharedTraceSink,
+    operation: Operation,
+    operands: [Option<T>; 2],
+}
+
+impl<T: Number> SearchTrace<T> {
+    pub(super) fn new(sink: SharedTraceSink) -> Self {
+        Self {
+            sink,
+            operation: Operation::Import,
+            operands: [None, None],
+        }
+    }
+
+    #[inline]
+    pub(super) fn sink(&self) -> &SharedTraceSink {
+        &self.sink
+    }
+
+    #[inline]
+    pub(super) fn set_producer(&mut self, operation: Operation, operands: [Option<T>; 2]) {
+        self.operation = operation;
+        self.operands = operands;
+    }
+
+    #[inline]
+    pub(super) fn producer(&self) -> (Operation, [Option<T>; 2]) {
+        (self.operation, self.operands)
+    }
+
+    pub(super) fn record(&self, x: &T, digits: usize) {
+        let operand = |operand: &Option<T>| {
+            operand
+                .as_ref()
+                .map_or_else(String::new, ToString::to_string)
+        };
+        self.sink.borrow_mut().write_line(format_args!(
+            "{}\t{digits}\t{x}\t{}\t{}\t{}",
+            T::DOMAIN.as_str(),
+            self.operation.as_str(),
+            operand(&self.operands[0]),
+            operand(&self.operands[1]),
+        ));
+    }
+}
@@ -1,5 +1,9 @@
-use super::{BinaryOperation, SearchState, Solver, State, UnaryOperation};
+use super::{
+    BinaryOperation, DeadStates, DepthStates, GoalLookup, Operation, SearchState, Solver, Stage,
+    State, StateIndex, UnaryOperation,
+};
 use crate::Number;
+use std::ops::ControlFlow;
 
 pub(super) trait Searcher<T: Number> {
     fn search(&mut self, digits: usize) -> bool;
@@ -9,119 +13,90 @@ pub(super) trait Searcher<T: Number> {
 impl<T: Number> Searcher<T> for Solver<T> {
     fn search(&mut self, digits: usize) -> bool {
         if let SearchState::None = self.search_state {
-            self.search_state = SearchState::Concat;
-            self.states_by_depth.resize(digits + 1, vec![]);
+            self.search_state = self.next_stage(None);
+            self.states_by_depth
+                .resize_with(digits + 1, DepthStates::default);
+            self.rebuild_bloom_filter();
+            self.sweep_dead_states(digits);
+            self.report_depth_started(digits);
         }
-        if let SearchState::Concat = self.search_state {
-            self.search_state = SearchState::ExtraState(0);
-            if self.concat(digits) {
-                return true;
-            }
-        }
-        if let SearchState::ExtraState(start) = self.search_state {
-            if self.extra_states_by_depth.len() > digits {
-                let l = self.extra_states_by_depth[digits].len();
-                for i in start..l {
-                    self.search_state = SearchState::ExtraState(i + 1);
-                    let (number, expression) = self.extra_states_by_depth[digits][i].clone();
-                    if self.try_insert(number, digits, || expression) {
+        loop {
+            match self.search_state {
+                SearchState::Concat => {
+                    self.search_state = self.next_stage(Some(Stage::Concat));
+                    if self.concat(digits) {
                         return true;
                     }
                 }
-            }
-            self.search_state = SearchState::UnaryOperation(0);
-        }
-        if let SearchState::UnaryOperation(start) = self.search_state {
-            let l = self.states_by_depth[digits - 1].len();
-            for i in start..l {
-                self.search_state = SearchState::UnaryOperation(i + 1);
-                let number = self.states_by_depth[digits - 1][i];
-                if self.unary_operation(State {
-                    number,
-                    digits,
-                    expression: self.states.get(&number).unwrap().0.clone(),
-                }) {
-                    return true;
-                }
-            }
-            self.search_state = SearchState::BinaryOperationOfDifferentDepth(1, (0, 0));
-        }
-        if let SearchState::BinaryOperationOfDifferentDepth(start_depth, start_position) =
-            self.search_state
-        {
-            for d1 in start_depth..((digits + 1) >> 1) {
-                let d2 = digits - d1;
-                let l1 = self.states_by_depth[d1].len();
-                let l2 = self.states_by_depth[d2].len();
-                for i in 0..l1 {
-                    if d1 == start_depth && i < start_position.0 {
-                        continue;
-                    }
-                    let n1 = self.states_by_depth[d1][i];
-                    let e1 = self.states.get(&n1).unwrap().0.clone();
-                    for j in 0..l2 {
-                        if d1 == start_depth && i == start_position.0 && j < start_position.1 {
-                            continue;
-                        }
-                        self.search_state =
-                            SearchState::BinaryOperationOfDifferentDepth(d1, (i, j + 1));
-                        let n2 = self.states_by_depth[d2][j];
-                        if self.binary_operation(
-                            State {
-                                number: n1,
-                                digits: d1,
-                                expression: e1.clone(),
-                            },
-                            State {
-                                number: n2,
-                                digits: d2,
-                                expression: self.states.get(&n2).unwrap().0.clone(),
-                            },
-                        ) {
-                            return true;
+                SearchState::ExtraState(start) => {
+                    if self.extra_states_by_depth.len() > digits {
+                        let l = self.extra_states_by_depth[digits].len();
+                        for i in start..l {
+                            if self.cancel_requested() {
+                                return false;
+                            }
+                            self.search_state = SearchState::ExtraState(i + 1);
+                            let (number, expression) =
+                                self.extra_states_by_depth[digits][i].clone();
+                            self.set_producer(Operation::Extra, [None, None]);
+                            if self.try_insert_with(number, digits, |_| expression) {
+                                return true;
+                            }
                         }
                     }
-                    self.search_state =
-                        SearchState::BinaryOperationOfDifferentDepth(d1, (i + 1, 0));
+                    self.search_state = self.next_stage(Some(Stage::Extra));
                 }
-                self.search_state = SearchState::BinaryOperationOfDifferentDepth(d1 + 1, (0, 0));
-            }
-            self.search_state = SearchState::BinaryOperationOfSameDepth((0, 0));
-        }
-        if let SearchState::BinaryOperationOfSameDepth(start_position) = self.search_state {
-            if digits % 2 == 0 {
-                let d = digits >> 1;
-                let l = self.states_by_depth[d].len();
-                for i in start_position.0..l {
-                    let n1 = self.states_by_depth[d][i];
-                    let e1 = self.states.get(&n1).unwrap().0.clone();
-                    for j in i..l {
-                        if i == start_position.0 && j < start_position.1 {
-                            continue;
+                SearchState::UnaryOperation(start) => {
+                    let l = self.states_by_depth[digits - 1].len();
+                    for i in start..l {
+                        if self.cancel_requested() {
+                            return false;
                         }
-                        self.search_state = SearchState::BinaryOperationOfSameDepth((i, j + 1));
-                        let n2 = self.states_by_depth[d][j];
-                        if self.binary_operation(
-                            State {
-                                number: n1,
-                                digits: d,
-                                expression: e1.clone(),
+                        self.search_state = SearchState::UnaryOperation(i + 1);
+                        let number = self.states_by_depth[digits - 1].value(i);
+                        if self.unary_operation(State {
+                            number,
+                            digits,
+                            index: StateIndex {
+                                depth: digits - 1,
+                                position: i,
                             },
-                            State {
-                                number: n2,
-                                digits: d,
-                                expression: self.states.get(&n2).unwrap().0.clone(),
-                            },
-                        ) {
+                        }) {
                             return true;
                         }
                     }
-                    self.search_state = SearchState::BinaryOperationOfSameDepth((i + 1, i + 1));
+                    self.search_state = self.next_stage(Some(Stage::Unary));
+                }
+                SearchState::BinaryOperationOfDifferentDepth(..)
+                    if self.uses_goal_lookup(digits) =>
+                {
+                    self.search_state = self.next_stage(Some(Stage::Binary));
+                    if self.search_goal_pairs(digits) {
+                        return true;
+                    }
+                }
+                SearchState::BinaryOperationOfDifferentDepth(start_index, start_position) => {
+                    if let ControlFlow::Break(found) =
+                        self.search_pairs_of_different_depth(digits, start_index, start_position)
+                    {
+                        return found;
+                    }
+                    self.search_state = SearchState::BinaryOperationOfSameDepth((0, 0));
                 }
+                SearchState::BinaryOperationOfSameDepth(start_position) => {
+                    if let ControlFlow::Break(found) =
+                        self.search_pairs_of_same_depth(digits, start_position)
+                    {
+                        return found;
+                    }
+                    self.search_state = self.next_stage(Some(Stage::Binary));
+                }
+                SearchState::None | SearchState::Finish => break,
             }
-            self.search_state = SearchState::Finish;
         }
         self.sort_states(digits);
+        self.compact(digits);
+        self.report_depth_finished(digits);
         self.depth_searched = digits;
         self.search_state = SearchState::None;
         false
@@ -130,6 +105,127 @@ impl<T: Number> Searcher<T> for Solver<T> {
     default fn sort_states(&mut self, _digits: usize) {}
 }
 
+impl<T: Number> Solver<T> {
+    /// Tries the pairs of states of different depths adding up to `digits`, from the
+    /// `start_index`-th pair of depths on. Breaks with whether the target was found, or with
+    /// `false` at the deadline or on cancellation.
+    fn search_pairs_of_different_depth(
+        &mut self,
+        digits: usize,
+        start_index: usize,
+        start_position: (usize, usize),
+    ) -> ControlFlow<bool> {
+        for index in start_index..(((digits + 1) >> 1) - 1) {
+            let d1 = self.pair_depth(digits, index);
+            let d2 = digits - d1;
+            let l1 = self.states_by_depth[d1].len();
+            let l2 = self.states_by_depth[d2].len();
+            for i in 0..l1 {
+                if index == start_index && i < start_position.0 {
+                    continue;
+                }
+                if self.cancel_requested() {
+                    return ControlFlow::Break(false);
+                }
+                if self.deadline_passed() {
+                    return ControlFlow::Break(self.stop_at_deadline(digits));
+                }
+                if self.is_dead(d1, i) {
+                    continue;
+                }
+                let n1 = self.states_by_depth[d1].value(i);
+                for j in 0..l2 {
+                    if index == start_index && i == start_position.0 && j < start_position.1
+                        || self.is_dead(d2, j)
+                    {
+                        continue;
+                    }
+                    self.search_state =
+                        SearchState::BinaryOperationOfDifferentDepth(index, (i, j + 1));
+                    let n2 = self.states_by_depth[d2].value(j);
+                    if self.binary_operation(
+                        State {
+                            number: n1,
+                            digits: d1,
+                            index: StateIndex {
+                                depth: d1,
+                                position: i,
+                            },
+                        },
+                        State {
+                            number: n2,
+                            digits: d2,
+                            index: StateIndex {
+                                depth: d2,
+                                position: j,
+                            },
+                        },
+                    ) {
+                        return ControlFlow::Break(true);
+                    }
+                }
+                self.search_state = SearchState::BinaryOperationOfDifferentDepth(index, (i + 1, 0));
+            }
+            self.search_state = SearchState::BinaryOperationOfDifferentDepth(index + 1, (0, 0));
+        }
+        ControlFlow::Continue(())
+    }
+
+    /// Tries the pairs of states of half of `digits`, if it is even.
+    fn search_pairs_of_same_depth(
+        &mut self,
+        digits: usize,
+        start_position: (usize, usize),
+    ) -> ControlFlow<bool> {
+        if !digits.is_multiple_of(2) {
+            return ControlFlow::Continue(());
+        }
+        let d = digits >> 1;
+        let l = self.states_by_depth[d].len();
+        for i in start_position.0..l {
+            if self.cancel_requested() {
+                return ControlFlow::Break(false);
+            }
+            if self.deadline_passed() {
+                return ControlFlow::Break(self.stop_at_deadline(digits));
+            }
+            if self.is_dead(d, i) {
+                continue;
+            }
+            let n1 = self.states_by_depth[d].value(i);
+            for j in i..l {
+                if i == start_position.0 && j < start_position.1 || self.is_dead(d, j) {
+                    continue;
+                }
+                self.search_state = SearchState::BinaryOperationOfSameDepth((i, j + 1));
+                let n2 = self.states_by_depth[d].value(j);
+                if self.binary_operation(
+                    State {
+                        number: n1,
+                        digits: d,
+                        index: StateIndex {
+                            depth: d,
+                            position: i,
+                        },
+                    },
+                    State {
+                        number: n2,
+                        digits: d,
+                        index: StateIndex {
+                            depth: d,
+                            position: j,
+                        },
+                    },
+                ) {
+                    return ControlFlow::Break(true);
+                }
+            }
+            self.search_state = SearchState::BinaryOperationOfSameDepth((i + 1, i + 1));
+        }
+        ControlFlow::Continue(())
+    }
+}
+
 impl Searcher<i64> for Solver<i64> {
     fn sort_states(&mut self, digits: usize) {
         self.states_by_depth[digits].sort();
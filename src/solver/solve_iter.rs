@@ -0,0 +1,107 @@
+use super::Solver;
+use crate::solution;
+use crate::{Expression, Number, Solution};
+use std::rc::Rc;
+use std::time::Instant;
+
+pub(super) trait UpperBound<T: Number> {
+    /// A solution for `target` built from the states known so far, not necessarily optimal.
+    fn upper_bound(&self, target: T) -> Option<(Rc<Expression>, usize)>;
+}
+
+impl<T: Number> UpperBound<T> for Solver<T> {
+    #[inline]
+    default fn upper_bound(&self, _target: T) -> Option<(Rc<Expression>, usize)> {
+        None
+    }
+}
+
+impl<T: Number> Solver<T> {
+    /// Searches for `target` depth by depth like [`solve`](Self::solve), yielding every
+    /// strictly better solution as it is found. The breadth-first search finds an optimal
+    /// solution first, so the improvements come from integral searches, where the target is also
+    /// split into pieces looked up in the tables built so far, as by
+    /// [`solve_split`](Solver::solve_split); each split shorter than the last solution is
+    /// yielded before the search goes a depth deeper.
+    ///
+    /// The iterator ends once the last solution is proven optimal, or when the search is
+    /// exhausted or reaches its deadline.
+    #[inline]
+    pub fn solve_iter(&mut self, target: T) -> SolveIter<'_, T> {
+        let (goal, negate) = if self.negation && target.is_negative() {
+            (-target, true)
+        } else {
+            (target, false)
+        };
+        SolveIter {
+            solver: self,
+            goal,
+            negate,
+            best: None,
+            finished: false,
+            start: solution::now(),
+        }
+    }
+}
+
+/// The improving solutions of [`Solver::solve_iter`].
+pub struct SolveIter<'a, T: Number> {
+    solver: &'a mut Solver<T>,
+    goal: T,
+    /// Whether the solutions found for `goal` are negated for a negative target.
+    negate: bool,
+    /// The digits of the last solution yielded.
+    best: Option<usize>,
+    finished: bool,
+    start: Option<Instant>,
+}
+
+impl<T: Number> SolveIter<'_, T> {
+    fn improves(&self, digits: usize) -> bool {
+        self.best.is_none_or(|best| digits < best)
+    }
+
+    fn report(&mut self, solution: Solution) -> Solution {
+        self.best = Some(solution.digits());
+        if self.negate {
+            solution.negate()
+        } else {
+            solution
+        }
+    }
+}
+
+impl<T: Number> Iterator for SolveIter<'_, T> {
+    type Item = Solution;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.finished {
+            let depth = self.solver.depth_searched;
+            if let Some((expression, digits)) = self.solver.upper_bound(self.goal) {
+                if self.improves(digits) {
+                    let solution = Solution::new(
+                        expression,
+                        digits,
+                        T::DOMAIN,
+                        self.start.map(|start| start.elapsed()),
+                    )
+                    .with_depth(depth);
+                    return Some(self.report(solution));
+                }
+            }
+            if self.best.is_some_and(|best| best <= depth + 1) {
+                break;
+            }
+            let solution = self.solver.solve(self.goal, Some(depth + 1));
+            self.finished = solution.is_some()
+                || self.solver.is_exhausted()
+                || self.solver.deadline_reached()
+                || self.solver.interrupted();
+            if let Some(solution) = solution.filter(|solution| self.improves(solution.digits())) {
+                return Some(self.report(solution));
+            }
+        }
+        self.finished = true;
+        None
+    }
+}
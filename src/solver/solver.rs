@@ -1,91 +1,522 @@
-use super::{Limits, RangeCheck, SearchState, Searcher, Solver, State, UnaryOperation};
-use crate::{Expression, Number};
+use super::{
+    DeadStates, DepthStates, GoalLookup, Limits, MemoryStateStore, ModularPruning, Operation,
+    RangeCheck, Rejection, RejectionReason, SearchState, SearchTrace, Searcher, SharedTraceSink,
+    Solver, StageOrder, State, StateIndex, StateStore, TraceSink, Tracer, UnaryOperation,
+    ValueStore,
+};
+use crate::solution;
+use crate::{Bitmap, Expression, Number, Solution};
 use rustc_hash::FxHashMap;
+use std::io::{self, Write};
 use std::rc::Rc;
 use std::slice::Iter;
 
 impl<T: Number> Solver<T> {
+    /// # Panics
+    ///
+    /// Panics if `limits.max_quadratic_power` exceeds
+    /// [`MAX_QUADRATIC_POWER`](crate::MAX_QUADRATIC_POWER).
     pub fn new(n: i64, limits: Limits) -> Self {
+        limits.assert_valid();
         Self {
             n,
+            digit_set: vec![n],
             target: T::zero(),
-            states: FxHashMap::default(),
+            states: Box::new(MemoryStateStore::default()),
+            keep_all: false,
+            alternatives: FxHashMap::default(),
             states_by_depth: vec![],
             extra_states_by_depth: vec![],
             depth_searched: 0,
             search_state: SearchState::None,
+            stage_order: StageOrder::DEFAULT,
             limits,
             progressive: false,
             new_numbers: vec![],
+            max_depth: usize::MAX,
+            modular_pruning: false,
+            goal_residues: None,
+            goal_lookup: false,
+            goals: vec![],
+            dead_state_pruning: false,
+            dead_states: None,
+            bloom_filter: false,
+            modulo: false,
+            gcd_lcm: false,
+            negation: false,
+            seen_filter: None,
+            pruned_depth: None,
+            tracer: None,
+            search_trace: None,
+            value_index: vec![],
+            candidates: 0,
+            soft_deadline: None,
+            deadline: None,
+            deadline_reached: false,
+            cancel_token: None,
+            interruption: None,
+            storage_error: None,
+            timeout: None,
+            memory_budget: None,
+            progress_callback: None,
+            closest: f64::INFINITY,
+            _value_store: T::hold_store(),
         }
     }
 
+    /// # Panics
+    ///
+    /// Panics if `limits.max_quadratic_power` exceeds
+    /// [`MAX_QUADRATIC_POWER`](crate::MAX_QUADRATIC_POWER).
     pub fn new_progressive(n: i64, limits: Limits) -> Self {
+        limits.assert_valid();
         Self {
             n,
+            digit_set: vec![n],
             target: T::zero(),
-            states: FxHashMap::default(),
+            states: Box::new(MemoryStateStore::default()),
+            keep_all: false,
+            alternatives: FxHashMap::default(),
             states_by_depth: vec![],
             extra_states_by_depth: vec![],
             depth_searched: 0,
             search_state: SearchState::None,
+            stage_order: StageOrder::DEFAULT,
             limits,
             progressive: true,
             new_numbers: vec![],
+            max_depth: usize::MAX,
+            modular_pruning: false,
+            goal_residues: None,
+            goal_lookup: false,
+            goals: vec![],
+            dead_state_pruning: false,
+            dead_states: None,
+            bloom_filter: false,
+            modulo: false,
+            gcd_lcm: false,
+            negation: false,
+            seen_filter: None,
+            pruned_depth: None,
+            tracer: None,
+            search_trace: None,
+            value_index: vec![],
+            candidates: 0,
+            soft_deadline: None,
+            deadline: None,
+            deadline_reached: false,
+            cancel_token: None,
+            interruption: None,
+            storage_error: None,
+            timeout: None,
+            memory_budget: None,
+            progress_callback: None,
+            closest: f64::INFINITY,
+            _value_store: T::hold_store(),
         }
     }
 
+    /// Like [`new`](Self::new), with room for `capacity` states before the state map grows.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `limits.max_quadratic_power` exceeds
+    /// [`MAX_QUADRATIC_POWER`](crate::MAX_QUADRATIC_POWER).
+    pub fn with_capacity(n: i64, limits: Limits, capacity: usize) -> Self {
+        let mut solver = Self::new(n, limits);
+        solver.states.reserve(capacity);
+        solver
+    }
+
+    /// Keeps the states in `store` instead of in memory, such as a [`FileStateStore`] for
+    /// depths whose states outgrow memory. The states already known are moved into it.
+    ///
+    /// [`FileStateStore`]: crate::FileStateStore
+    pub fn set_state_store(&mut self, mut store: impl StateStore<T> + 'static) {
+        store.clear();
+        for (x, expression, digits) in self.states.iter() {
+            store.insert(x, digits, expression);
+        }
+        self.states = Box::new(store);
+    }
+
+    /// Returns the solver to the state of a new one for `n` and `limits`, keeping its settings
+    /// and the capacity of its state map and buffers, so that reusing one solver across many
+    /// searches does not regrow them from nothing each time. Traced values and their
+    /// rejections are dropped; a search trace keeps streaming.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `limits.max_quadratic_power` exceeds
+    /// [`MAX_QUADRATIC_POWER`](crate::MAX_QUADRATIC_POWER).
+    pub fn reset(&mut self, n: i64, limits: Limits) {
+        limits.assert_valid();
+        self.n = n;
+        self.digit_set = vec![n];
+        self.limits = limits;
+        self.target = T::zero();
+        self.states.clear();
+        self.alternatives.clear();
+        self.states_by_depth.clear();
+        self.extra_states_by_depth.clear();
+        self.depth_searched = 0;
+        self.search_state = SearchState::None;
+        self.new_numbers.clear();
+        self.max_depth = usize::MAX;
+        self.goal_residues = None;
+        self.goals.clear();
+        self.dead_states = None;
+        self.seen_filter = None;
+        self.pruned_depth = None;
+        self.tracer = None;
+        self.value_index.clear();
+        self.candidates = 0;
+        self.deadline = None;
+        self.deadline_reached = false;
+        self.interruption = None;
+    }
+
     #[inline]
     pub(crate) fn clone_non_progressive_from(&mut self, source: &Self) {
+        let candidates = self.candidates;
         self.clone_from(source);
         self.progressive = false;
+        self.candidates = candidates;
     }
 
-    pub fn solve(
-        &mut self,
-        target: T,
-        max_depth: Option<usize>,
-    ) -> Option<(Rc<Expression>, usize)> {
+    /// Enables residue-based pruning at the final depth of non-progressive integral searches.
+    ///
+    /// States of the final depth are then incomplete, so that depth is searched again if a later
+    /// call changes the target or searches deeper.
+    #[inline]
+    pub fn set_modular_pruning(&mut self, modular_pruning: bool) {
+        self.modular_pruning = modular_pruning;
+    }
+
+    /// At the final depth of non-progressive integral searches, looks up the partners that can
+    /// combine with each state into the target instead of trying every pair.
+    ///
+    /// Like modular pruning, this leaves the final depth incomplete.
+    #[inline]
+    pub fn set_goal_lookup(&mut self, goal_lookup: bool) {
+        self.goal_lookup = goal_lookup;
+    }
+
+    /// At the final depth of non-progressive integral searches, skips the states too large for
+    /// any pair with them to produce the target. Modulo and gcd/lcm, whose results can be small
+    /// whatever their operands, turn this off.
+    ///
+    /// Like modular pruning, this leaves the final depth incomplete.
+    #[inline]
+    pub fn set_dead_state_pruning(&mut self, dead_state_pruning: bool) {
+        self.dead_state_pruning = dead_state_pruning;
+    }
+
+    /// Checks candidates against a Bloom filter of known states before probing the state map,
+    /// so that most new values skip the map lookup. The filter is rebuilt at each depth.
+    #[inline]
+    pub fn set_bloom_filter(&mut self, bloom_filter: bool) {
+        self.bloom_filter = bloom_filter;
+    }
+
+    /// Enables `x mod y` on positive integers, a non-standard operation. Goal lookup is skipped
+    /// while it is enabled, as modulo partners cannot be looked up.
+    #[inline]
+    pub fn set_modulo(&mut self, modulo: bool) {
+        self.modulo = modulo;
+    }
+
+    /// Enables `gcd(x, y)` and `lcm(x, y)` on positive integers, non-standard operations. Like
+    /// modulo, this skips goal lookup.
+    #[inline]
+    pub fn set_gcd_lcm(&mut self, gcd_lcm: bool) {
+        self.gcd_lcm = gcd_lcm;
+    }
+
+    /// Accepts negative targets, solved by negating an expression for their absolute value.
+    ///
+    /// States stay positive. Subtraction already yields whichever sign of a difference is
+    /// positive and powers already take negated exponents, while a negated operand only flips
+    /// the sign of a product, quotient or odd power, so negation is only needed at the top.
+    #[inline]
+    pub fn set_negation(&mut self, negation: bool) {
+        self.negation = negation;
+    }
+
+    /// Records why candidate expressions for `x` get rejected, queryable with
+    /// [`rejections`](Self::rejections). Only the most recent rejections are kept.
+    pub fn trace(&mut self, x: T) {
+        self.tracer
+            .get_or_insert_with(|| Box::new(Tracer::new()))
+            .watch(x);
+    }
+
+    /// Sets how many rejections are kept across all traced values. Defaults to 1024.
+    pub fn set_trace_capacity(&mut self, capacity: usize) {
+        self.tracer
+            .get_or_insert_with(|| Box::new(Tracer::new()))
+            .set_capacity(capacity);
+    }
+
+    pub fn rejections<'a>(&'a self, x: &'a T) -> impl Iterator<Item = &'a Rejection<T>> {
+        self.tracer
+            .iter()
+            .flat_map(move |tracer| tracer.rejections(x))
+    }
+
+    /// Streams every state the search inserts from now on to `writer`, one tab-separated line
+    /// each with the domain, digits, value, producing operation and operand values, after a
+    /// header line. Wrap files in a `BufWriter`, as lines are written one at a time.
+    ///
+    /// Write errors stop the trace and are reported by
+    /// [`finish_search_trace`](Self::finish_search_trace).
+    pub fn set_search_trace(&mut self, writer: impl Write + 'static) {
+        self.set_search_trace_sink(TraceSink::shared(writer));
+    }
+
+    #[inline]
+    pub(crate) fn set_search_trace_sink(&mut self, sink: SharedTraceSink) {
+        self.search_trace = Some(Box::new(SearchTrace::new(sink)));
+    }
+
+    /// Stops the search trace and flushes it, returning the first error of any write.
+    pub fn finish_search_trace(&mut self) -> io::Result<()> {
+        match self.search_trace.take() {
+            Some(trace) => trace.sink().borrow_mut().finish(),
+            None => Ok(()),
+        }
+    }
+
+    /// Attributes the next insertion to `operation` on `operands`, when a search trace is on.
+    #[inline]
+    pub(super) fn set_producer(&mut self, operation: Operation, operands: [Option<T>; 2]) {
+        if let Some(trace) = &mut self.search_trace {
+            trace.set_producer(operation, operands);
+        }
+    }
+
+    pub fn solve(&mut self, target: T, max_depth: Option<usize>) -> Option<Solution> {
+        if self.negation && target.is_negative() {
+            return self.solve(-target, max_depth).map(Solution::negate);
+        }
+        let start = solution::now();
+        self.start_deadline(start);
         let max_depth = max_depth.unwrap_or(usize::MAX);
+        if let Some(depth) = self.pruned_depth {
+            if target != self.target || max_depth > depth {
+                self.rollback_depth(depth);
+            }
+        }
         self.target = target;
+        self.max_depth = max_depth;
+        self.closest = f64::INFINITY;
+        self.update_goal_residues();
+        self.update_goals();
         if let Some((expression, digits)) = self.states.get(&self.target) {
-            return if max_depth >= *digits {
-                Some((expression.clone(), *digits))
+            return if max_depth >= digits {
+                Some(Solution::new(
+                    expression,
+                    digits,
+                    T::DOMAIN,
+                    start.map(|start| start.elapsed()),
+                ))
             } else {
                 None
             };
         }
-        for digits in (self.depth_searched + 1)..=max_depth {
-            if self.search(digits) {
-                return Some(self.states.get(&self.target)?.clone());
+        for depth in (self.depth_searched + 1)..=max_depth {
+            if self.is_exhausted() {
+                break;
+            }
+            if (self.goal_residues.is_some() || !self.goals.is_empty()) && depth == max_depth
+                || self.uses_dead_state_pruning(depth)
+            {
+                self.pruned_depth = Some(depth);
+            }
+            if self.search(depth) {
+                let (expression, digits) = self.states.get(&self.target)?;
+                return Some(
+                    Solution::new(
+                        expression,
+                        digits,
+                        T::DOMAIN,
+                        start.map(|start| start.elapsed()),
+                    )
+                    .with_depth(depth),
+                );
+            }
+            if self.deadline_reached || self.interrupted() {
+                break;
             }
         }
         None
     }
 
+    /// Searches every depth up to `max_depth` to completion, regardless of the target. Returns
+    /// early once no deeper search can find a new state, or when the search is interrupted; a
+    /// later call resumes where it stopped.
+    pub fn exhaust_to_depth(&mut self, max_depth: usize) {
+        if let Some(depth) = self.pruned_depth {
+            self.rollback_depth(depth);
+        }
+        self.goal_residues = None;
+        self.goals.clear();
+        self.start_deadline(None);
+        while self.depth_searched < max_depth && !self.is_exhausted() && !self.interrupted() {
+            self.search(self.depth_searched + 1);
+        }
+    }
+
+    #[inline]
+    pub fn depth_searched(&self) -> usize {
+        self.depth_searched
+    }
+
+    #[inline]
+    pub fn get_solution(&self, x: &T) -> Option<Solution> {
+        let (expression, digits) = self.states.get(x)?;
+        Some(Solution::new(expression, digits, T::DOMAIN, None))
+    }
+
+    /// The states first reached with exactly `digits` digits, in no particular order.
+    /// Depths not searched yet are empty, and the depth being searched yields the states found
+    /// so far.
+    pub fn states_at_depth(&self, digits: usize) -> impl Iterator<Item = (&T, &Rc<Expression>)> {
+        self.states_by_depth
+            .get(digits)
+            .into_iter()
+            .flat_map(DepthStates::iter)
+    }
+
+    /// For each digit count `d`, the integers in `[1, limit]` reachable with at most `d` digits.
+    pub fn reachability(&self, limit: u64) -> Vec<Bitmap> {
+        let mut bitmaps = vec![];
+        self.mark_reachable(limit, &mut bitmaps);
+        bitmaps
+    }
+
+    pub(crate) fn mark_reachable(&self, limit: u64, bitmaps: &mut Vec<Bitmap>) {
+        for (digits, numbers) in self.states_by_depth.iter().enumerate() {
+            if bitmaps.len() <= digits {
+                let last = bitmaps.last().cloned();
+                bitmaps.resize(digits + 1, last.unwrap_or_else(|| Bitmap::new(limit)));
+            }
+            for x in numbers.values().iter().filter_map(|x| x.to_int()) {
+                if x > 0 {
+                    for bitmap in &mut bitmaps[digits..] {
+                        bitmap.insert(x as u64);
+                    }
+                }
+            }
+        }
+    }
+
     #[inline]
-    pub fn get_solution(&self, x: &T) -> Option<&(Rc<Expression>, usize)> {
+    pub(crate) fn get_state(&self, x: &T) -> Option<(Rc<Expression>, usize)> {
         self.states.get(x)
     }
 
+    /// The number of known states and of candidate values tried so far, for [`SearchStats`].
+    ///
+    /// [`SearchStats`]: crate::SearchStats
+    #[inline]
+    pub(crate) fn counters(&self) -> (usize, u64) {
+        (self.states.len(), self.candidates)
+    }
+
+    /// A rough estimate of the bytes held by the states: what the state store holds, a depth
+    /// entry and one expression node each, and an entry and a node per queued extra state.
+    /// Subexpressions are shared between states, so they are not counted.
+    pub(crate) fn memory_estimate(&self) -> usize {
+        let per_state = std::mem::size_of::<T>()
+            + std::mem::size_of::<Rc<Expression>>()
+            + std::mem::size_of::<Expression>()
+            + 2 * std::mem::size_of::<usize>();
+        let per_extra_state =
+            std::mem::size_of::<(T, Rc<Expression>)>() + std::mem::size_of::<Expression>();
+        let extra_states: usize = self.extra_states_by_depth.iter().map(Vec::len).sum();
+        self.states.memory_estimate()
+            + self.states.len() * per_state
+            + extra_states * per_extra_state
+    }
+
+    /// Every known state with its digit count, in no particular order.
+    #[inline]
+    pub(crate) fn digit_counts(&self) -> impl Iterator<Item = (T, usize)> + '_ {
+        self.states.iter().map(|(x, _, digits)| (x, digits))
+    }
+
+    #[inline]
+    pub(super) fn expression(&self, index: StateIndex) -> &Rc<Expression> {
+        self.states_by_depth[index.depth].expression(index.position)
+    }
+
+    #[inline]
     pub fn try_insert(
         &mut self,
         x: T,
         digits: usize,
         expression_fn: impl FnOnce() -> Rc<Expression>,
     ) -> bool {
-        if !self.range_check(&x) || self.states.contains_key(&x) {
+        self.set_producer(Operation::Import, [None, None]);
+        self.try_insert_with(x, digits, |_| expression_fn())
+    }
+
+    /// Like [`try_insert`](Self::try_insert), but the expression is built from the solver's own
+    /// states, which are only looked up once the candidate is accepted.
+    pub(super) fn try_insert_with(
+        &mut self,
+        x: T,
+        digits: usize,
+        expression_fn: impl FnOnce(&Self) -> Rc<Expression>,
+    ) -> bool {
+        self.candidates += 1;
+        let rejection = if !self.residue_check(&x, digits) {
+            Some(RejectionReason::Pruned)
+        } else if !self.range_check(&x) {
+            Some(RejectionReason::RangeCheck)
+        } else if self
+            .seen_filter
+            .as_ref()
+            .is_some_and(|filter| !filter.may_contain(&x))
+        {
+            None
+        } else {
+            self.states.digits(&x).map(RejectionReason::Duplicate)
+        };
+        if let Some(reason) = rejection {
+            let watched = self
+                .tracer
+                .as_ref()
+                .is_some_and(|tracer| tracer.is_watched(&x));
+            let alternative = self.keep_all && reason == RejectionReason::Duplicate(digits);
+            if watched || alternative {
+                let expression = expression_fn(self);
+                if alternative {
+                    self.keep_alternative(x, expression.clone());
+                }
+                if let Some(tracer) = self.tracer.as_mut().filter(|_| watched) {
+                    tracer.record(x, digits, expression, reason);
+                }
+            }
             return false;
         }
-        let expression = expression_fn();
+        let expression = expression_fn(self);
+        let producer = self.search_trace.as_ref().map(|trace| {
+            trace.record(&x, digits);
+            trace.producer()
+        });
         let mut found = false;
-        if self.insert(x, digits, expression.clone()) {
+        if self.insert(x, digits, expression) {
             found = true;
         }
         let state = State {
             number: x,
             digits,
-            expression,
+            index: StateIndex {
+                depth: digits,
+                position: self.states_by_depth[digits].len() - 1,
+            },
         };
         if self.sqrt(&state) {
             found = true;
@@ -93,6 +524,12 @@ impl<T: Number> Solver<T> {
         if x.is_int() && self.factorial(&state) {
             found = true;
         }
+        if x.is_int() && self.subfactorial(&state) {
+            found = true;
+        }
+        if let Some((operation, operands)) = producer {
+            self.set_producer(operation, operands);
+        }
         found
     }
 
@@ -103,8 +540,32 @@ impl<T: Number> Solver<T> {
         self.extra_states_by_depth[digits].push((x, expression));
     }
 
+    /// Whether the search could build `expression` as a state of value `x`: `x` passes the range
+    /// check, and so does every subexpression that evaluates in the domain, modulo, gcd and lcm
+    /// appear only when enabled, factorials and subfactorials only of operands within the
+    /// limits, and negation not at all, since the search only negates the target.
+    pub(crate) fn builds(&self, x: &T, expression: &Expression) -> bool {
+        let operand = |x: &Expression| x.evaluate_rational().and_then(|x| x.to_int());
+        self.range_check(x)
+            && expression.iter_subexpressions().all(|node| {
+                let enabled = match node {
+                    Expression::Negate(_) => false,
+                    Expression::Modulo(..) => self.modulo,
+                    Expression::Gcd(..) | Expression::Lcm(..) => self.gcd_lcm,
+                    Expression::Factorial(x) => {
+                        operand(x).is_some_and(|n| n < self.limits.max_factorial)
+                    }
+                    Expression::Subfactorial(x) => {
+                        operand(x).is_some_and(|n| n >= 3 && n < self.limits.max_subfactorial)
+                    }
+                    _ => true,
+                };
+                enabled && node.evaluate::<T>().is_none_or(|x| self.range_check(&x))
+            })
+    }
+
     #[inline]
-    pub(crate) fn new_numbers(&self) -> NewNumberIterator<T> {
+    pub(crate) fn new_numbers(&self) -> NewNumberIterator<'_, T> {
         NewNumberIterator {
             solver: self,
             iter: self.new_numbers.iter(),
@@ -116,12 +577,44 @@ impl<T: Number> Solver<T> {
         self.new_numbers.clear();
     }
 
-    fn insert(&mut self, x: T, digits: usize, expression: Rc<Expression>) -> bool {
-        self.states.insert(x, (expression, digits));
+    /// Frees what the search no longer needs once depth `digits` is complete: the spare
+    /// capacity of the depth before, which nothing extends any more, the Bloom filter, rebuilt
+    /// at the start of every depth, and the value index, rebuilt on demand.
+    pub(super) fn compact(&mut self, digits: usize) {
+        if let Some(states) = self.states_by_depth.get_mut(digits - 1) {
+            states.shrink_to_fit();
+        }
+        self.seen_filter = None;
+        self.value_index = vec![];
+    }
+
+    fn rollback_depth(&mut self, depth: usize) {
+        if let Some(numbers) = self.states_by_depth.get_mut(depth) {
+            for x in numbers.drain_values() {
+                self.states.remove(&x);
+                self.alternatives.remove(&x);
+            }
+        }
+        self.depth_searched = self.depth_searched.min(depth - 1);
+        self.search_state = SearchState::None;
+        self.pruned_depth = None;
+        self.value_index.clear();
+    }
+
+    pub(super) fn insert(&mut self, x: T, digits: usize, expression: Rc<Expression>) -> bool {
+        self.states.insert(x, digits, expression.clone());
+        if self.progress_callback.is_some() {
+            self.report_insertion(x, digits, &expression);
+        }
+        self.value_index.clear();
+        if let Some(filter) = &mut self.seen_filter {
+            filter.insert(&x);
+        }
         if self.states_by_depth.len() <= digits {
-            self.states_by_depth.resize(digits + 1, vec![]);
+            self.states_by_depth
+                .resize_with(digits + 1, DepthStates::default);
         }
-        self.states_by_depth[digits].push(x);
+        self.states_by_depth[digits].push(x, expression);
         if self.progressive {
             self.new_numbers.push(x);
         }
@@ -135,11 +628,37 @@ pub(crate) struct NewNumberIterator<'a, T: Number> {
 }
 
 impl<'a, T: Number> Iterator for NewNumberIterator<'a, T> {
-    type Item = (&'a T, &'a Rc<Expression>, usize);
+    type Item = (&'a T, Rc<Expression>, usize);
 
     fn next(&mut self) -> Option<Self::Item> {
         let x = self.iter.next()?;
         let (expression, digits) = self.solver.states.get(x)?;
-        Some((x, expression, *digits))
+        Some((x, expression, digits))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Limits, Solver};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn exhausts_to_depth() {
+        let mut solver = Solver::<i64>::new(3, Limits::recommended_integral());
+        solver.exhaust_to_depth(3);
+        assert_eq!(solver.depth_searched(), 3);
+    }
+
+    #[test]
+    fn stops_exhausting_when_cancelled() {
+        let mut solver = Solver::<i64>::new(3, Limits::recommended_integral());
+        let token = Arc::new(AtomicBool::new(true));
+        solver.set_cancel_token(Some(token.clone()));
+        solver.exhaust_to_depth(3);
+        assert!(solver.depth_searched() < 3);
+        token.store(false, Ordering::Relaxed);
+        solver.exhaust_to_depth(3);
+        assert_eq!(solver.depth_searched(), 3);
     }
 }
@@ -0,0 +1,149 @@
+use super::solve_iter::UpperBound;
+use super::Solver;
+use crate::number_theory::{factorial_divide, integer_root};
+use crate::solution;
+use crate::{Domain, Expression, Solution};
+use std::rc::Rc;
+
+/// States up to this depth are the small pieces split off a value: the divisors of products
+/// and the terms added to or subtracted from it. Deeper pieces are only ever looked up.
+const SMALL_PIECE_DEPTH: usize = 3;
+
+/// How many times the large piece of a split is split in turn.
+const MAX_NESTING: usize = 2;
+
+/// The largest `m` of the quotients `m! / k!` tried.
+const MAX_FACTORIAL: i64 = 20;
+
+type Piece = (Rc<Expression>, usize);
+
+impl Solver<i64> {
+    /// Like [`solve`](Self::solve), alternating the breadth-first expansion with a top-down
+    /// search: before each depth, the target is split into `a * b`, `a + b`, `a - b`, `a^k` or
+    /// `m! / k!`, whose pieces are looked up in the tables built so far or split in turn.
+    ///
+    /// A split gives an upper bound, returned as soon as the expansion has ruled out every
+    /// shorter solution, or when the expansion reaches `max_depth` without one. For large
+    /// targets with structure, this often finds the solution depths before the expansion does.
+    pub fn solve_split(&mut self, target: i64, max_depth: Option<usize>) -> Option<Solution> {
+        if self.negation && target < 0 {
+            return self.solve_split(-target, max_depth).map(Solution::negate);
+        }
+        let start = solution::now();
+        let max_depth = max_depth.unwrap_or(usize::MAX);
+        // The best split so far, with the depth of the tables it was found from.
+        let mut best: Option<(Piece, usize)> = None;
+        let mut depth = self.depth_searched;
+        while depth < max_depth {
+            if target > 1 {
+                if let Some(split) = self.split(target, MAX_NESTING) {
+                    if best.as_ref().is_none_or(|(best, _)| split.1 < best.1) {
+                        best = Some((split, depth));
+                    }
+                }
+            }
+            if best
+                .as_ref()
+                .is_some_and(|((_, digits), _)| *digits <= depth + 1)
+            {
+                break;
+            }
+            depth += 1;
+            if let Some(solution) = self.solve(target, Some(depth)) {
+                return Some(solution);
+            }
+            if self.is_exhausted() || self.deadline_reached() || self.interrupted() {
+                break;
+            }
+        }
+        best.filter(|((_, digits), _)| *digits <= max_depth)
+            .map(|((expression, digits), depth)| {
+                Solution::new(
+                    expression,
+                    digits,
+                    Domain::Integral,
+                    start.map(|start| start.elapsed()),
+                )
+                .with_depth(depth)
+            })
+    }
+
+    /// The cheapest split of `x` found, or `x` itself if it is known.
+    fn split(&self, x: i64, nesting: usize) -> Option<Piece> {
+        if let Some(piece) = self.states.get(&x) {
+            return Some(piece);
+        }
+        if nesting == 0 || x <= 1 {
+            return None;
+        }
+        let mut best: Option<Piece> = None;
+        let mut consider = |candidate: Option<Piece>| {
+            if let Some(candidate) = candidate {
+                if best.as_ref().is_none_or(|best| candidate.1 < best.1) {
+                    best = Some(candidate);
+                }
+            }
+        };
+        let small_pieces = (1..=SMALL_PIECE_DEPTH.min(self.depth_searched)).flat_map(|d| {
+            self.states_by_depth[d]
+                .iter()
+                .map(move |(y, small)| (*y, small, d))
+        });
+        for (y, small, d) in small_pieces {
+            if y > 1 && x % y == 0 {
+                consider(self.split(x / y, nesting - 1).map(|(large, digits)| {
+                    (Expression::from_multiply(large, small.clone()), digits + d)
+                }));
+            }
+            if y < x {
+                consider(self.split(x - y, nesting - 1).map(|(large, digits)| {
+                    (Expression::from_add(large, small.clone()), digits + d)
+                }));
+            }
+            if let Some(sum) = x.checked_add(y) {
+                consider(self.split(sum, nesting - 1).map(|(large, digits)| {
+                    (Expression::from_subtract(large, small.clone()), digits + d)
+                }));
+            }
+        }
+        let mut exponent = 2;
+        while exponent < 63 && 1i64 << exponent <= x {
+            if let (Some(root), Some((power, d))) =
+                (integer_root(x, exponent), self.states.get(&exponent))
+            {
+                consider(
+                    self.split(root, nesting - 1)
+                        .map(|(root, digits)| (Expression::from_power(root, power), digits + d)),
+                );
+            }
+            exponent += 1;
+        }
+        for m in 3..=MAX_FACTORIAL {
+            for k in 2..(m - 1) {
+                if factorial_divide(m, k) != x {
+                    continue;
+                }
+                if let (Some((m, d1)), Some((k, d2))) = (self.states.get(&m), self.states.get(&k)) {
+                    consider(Some((
+                        Expression::from_divide(
+                            Expression::from_factorial(m),
+                            Expression::from_factorial(k),
+                        ),
+                        d1 + d2,
+                    )));
+                }
+            }
+        }
+        best
+    }
+}
+
+impl UpperBound<i64> for Solver<i64> {
+    fn upper_bound(&self, target: i64) -> Option<Piece> {
+        if target > 1 {
+            self.split(target, MAX_NESTING)
+        } else {
+            None
+        }
+    }
+}
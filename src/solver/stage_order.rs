@@ -0,0 +1,106 @@
+use super::{SearchState, Solver};
+use crate::Number;
+use serde::{Deserialize, Serialize};
+
+/// A stage of the search of one depth.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum Stage {
+    /// The number written with as many copies of `n` as the depth.
+    Concat,
+    /// The states queued for the depth with [`Solver::insert_extra`].
+    Extra,
+    /// Unary operations on the states of the depth before.
+    Unary,
+    /// Binary operations on the pairs of states whose depths add up to the depth.
+    Binary,
+}
+
+/// The order in which each depth runs its stages, and in which the binary stage pairs depths.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct StageOrder {
+    /// Every stage, each exactly once.
+    pub stages: [Stage; 4],
+    /// Pairs depths from the middle outwards: the shallower depth of a pair goes down to 1
+    /// instead of up from 1, so that the most balanced pairs come first. Pairs of the same
+    /// depth always come last.
+    pub balanced_first: bool,
+}
+
+impl StageOrder {
+    /// Concatenation, extra states, unary operations, then binary operations from the
+    /// shallowest states paired with the newest up.
+    pub const DEFAULT: Self = Self {
+        stages: [Stage::Concat, Stage::Extra, Stage::Unary, Stage::Binary],
+        balanced_first: false,
+    };
+
+    /// Binary operations before unary ones. Large targets are mostly reached by combining a
+    /// shallow state with one of the depth before, which this tries before the unary operations
+    /// of the final depth have run at all.
+    pub const TARGET_FIRST: Self = Self {
+        stages: [Stage::Concat, Stage::Extra, Stage::Binary, Stage::Unary],
+        balanced_first: false,
+    };
+
+    fn validate(&self) {
+        for stage in [Stage::Concat, Stage::Extra, Stage::Unary, Stage::Binary] {
+            assert!(
+                self.stages.contains(&stage),
+                "stage order {:?} lacks {stage:?}",
+                self.stages
+            );
+        }
+    }
+}
+
+impl Default for StageOrder {
+    #[inline]
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl<T: Number> Solver<T> {
+    /// Sets the order of the stages of each depth, which changes how soon a target within a depth
+    /// is found. Every order tries the same operations, but a state keeps the first expression
+    /// found for it, and quotients `a / d` with a single-digit `d` are only rewritten into
+    /// `(a ± d) / d` as that expression, so the states derived this way can differ slightly.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `order.stages` does not hold every stage.
+    #[inline]
+    pub fn set_stage_order(&mut self, order: StageOrder) {
+        order.validate();
+        self.stage_order = order;
+    }
+
+    /// The search state starting the stage after `stage`, or the first stage for `None`, or
+    /// [`SearchState::Finish`] after the last.
+    pub(super) fn next_stage(&self, stage: Option<Stage>) -> SearchState {
+        let next = stage.map_or(0, |stage| {
+            self.stage_order
+                .stages
+                .iter()
+                .position(|s| *s == stage)
+                .map_or(usize::MAX, |position| position + 1)
+        });
+        match self.stage_order.stages.get(next) {
+            Some(Stage::Concat) => SearchState::Concat,
+            Some(Stage::Extra) => SearchState::ExtraState(0),
+            Some(Stage::Unary) => SearchState::UnaryOperation(0),
+            Some(Stage::Binary) => SearchState::BinaryOperationOfDifferentDepth(0, (0, 0)),
+            None => SearchState::Finish,
+        }
+    }
+
+    /// The shallower depth of the `index`-th pair of different depths adding up to `digits`.
+    #[inline]
+    pub(super) fn pair_depth(&self, digits: usize, index: usize) -> usize {
+        if self.stage_order.balanced_first {
+            ((digits + 1) >> 1) - 1 - index
+        } else {
+            index + 1
+        }
+    }
+}
@@ -0,0 +1,479 @@
+use super::persistence::StateCodec;
+use crate::{Expression, Number};
+use rustc_hash::{FxHashMap, FxHasher};
+use std::cell::Cell;
+use std::fs::{self, File, OpenOptions};
+use std::hash::Hasher;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::marker::PhantomData;
+use std::mem::size_of;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Where a [`Solver`](super::Solver) keeps its map of states: the expression and digit count of
+/// every value reached. [`MemoryStateStore`] is the default, and [`FileStateStore`] keeps the
+/// map on disk. The solver still keeps the values and expressions of each depth in memory for
+/// its pair loops, so a store only moves the map out of memory, not the states themselves.
+pub trait StateStore<T: Number> {
+    fn get(&self, x: &T) -> Option<(Rc<Expression>, usize)>;
+
+    /// The digit count of `x`, without fetching its expression where the store can avoid it.
+    #[inline]
+    fn digits(&self, x: &T) -> Option<usize> {
+        self.get(x).map(|(_, digits)| digits)
+    }
+
+    #[inline]
+    fn contains(&self, x: &T) -> bool {
+        self.digits(x).is_some()
+    }
+
+    /// Keeps `x` with `digits` digits and `expression`, replacing what it had.
+    fn insert(&mut self, x: T, digits: usize, expression: Rc<Expression>);
+
+    fn remove(&mut self, x: &T);
+
+    fn len(&self) -> usize;
+
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn clear(&mut self);
+
+    /// Makes room for `additional` more states, in stores that allocate ahead.
+    #[inline]
+    fn reserve(&mut self, _additional: usize) {}
+
+    /// Every state with its expression and digit count, in no particular order.
+    fn iter(&self) -> Box<dyn Iterator<Item = (T, Rc<Expression>, usize)> + '_>;
+
+    /// A rough estimate of the bytes of memory the store holds, beyond what the solver counts
+    /// for each state it keeps by depth.
+    fn memory_estimate(&self) -> usize;
+
+    /// The first I/O error hit since the last call, in stores backed by storage that can fail.
+    /// The failed operations act as if the state were absent, so the solver stops its search.
+    #[inline]
+    fn take_error(&self) -> Option<io::Error> {
+        None
+    }
+
+    /// A store holding the same states, for cloning solvers.
+    fn boxed_clone(&self) -> Box<dyn StateStore<T>>;
+}
+
+impl<T: Number> Clone for Box<dyn StateStore<T>> {
+    #[inline]
+    fn clone(&self) -> Self {
+        self.boxed_clone()
+    }
+}
+
+/// Keeps the states in a hash map.
+#[derive(Clone)]
+pub struct MemoryStateStore<T> {
+    states: FxHashMap<T, (Rc<Expression>, usize)>,
+}
+
+impl<T> Default for MemoryStateStore<T> {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            states: FxHashMap::default(),
+        }
+    }
+}
+
+impl<T: Number> StateStore<T> for MemoryStateStore<T> {
+    #[inline]
+    fn get(&self, x: &T) -> Option<(Rc<Expression>, usize)> {
+        self.states.get(x).cloned()
+    }
+
+    #[inline]
+    fn digits(&self, x: &T) -> Option<usize> {
+        self.states.get(x).map(|(_, digits)| *digits)
+    }
+
+    #[inline]
+    fn insert(&mut self, x: T, digits: usize, expression: Rc<Expression>) {
+        self.states.insert(x, (expression, digits));
+    }
+
+    #[inline]
+    fn remove(&mut self, x: &T) {
+        self.states.remove(x);
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    #[inline]
+    fn clear(&mut self) {
+        self.states.clear();
+    }
+
+    #[inline]
+    fn reserve(&mut self, additional: usize) {
+        self.states.reserve(additional);
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = (T, Rc<Expression>, usize)> + '_> {
+        Box::new(
+            self.states
+                .iter()
+                .map(|(x, (expression, digits))| (*x, expression.clone(), *digits)),
+        )
+    }
+
+    /// An entry per state, with the spare slots of the map.
+    #[inline]
+    fn memory_estimate(&self) -> usize {
+        self.states.len() * size_of::<(T, (Rc<Expression>, usize))>() * 8 / 7
+    }
+
+    #[inline]
+    fn boxed_clone(&self) -> Box<dyn StateStore<T>> {
+        Box::new(self.clone())
+    }
+}
+
+/// The bytes of a record header: the link to the previous record of its bucket, the digit
+/// count, and the lengths of the encoded value and expression.
+const HEADER: u64 = 24;
+/// The digit count of a removed record.
+const REMOVED: u64 = u64::MAX;
+const MIN_BUCKETS: usize = 1 << 16;
+/// Records per bucket, on average, before the buckets double.
+const MAX_LOAD: usize = 4;
+const CORRUPT: &str = "corrupt state store";
+
+static NEXT_FILE: AtomicUsize = AtomicUsize::new(0);
+
+struct Header {
+    /// One more than the offset of the previous record of the bucket, or 0 for none.
+    next: u64,
+    digits: u64,
+    value_length: u64,
+    expression_length: u64,
+}
+
+impl Header {
+    #[inline]
+    fn size(&self) -> u64 {
+        HEADER + self.value_length + self.expression_length
+    }
+}
+
+/// Keeps the map of states in a file of its own. Each state is a record appended to the file and
+/// chained to the previous record of its hash bucket, and only the heads of the buckets stay in
+/// memory, 8 bytes for every few states. Lookups read the file through the page cache of the
+/// system, so they are much slower than in memory.
+///
+/// Removed states are only marked as such, and their space is reclaimed when the store is
+/// cleared. The file is deleted when the store is dropped.
+///
+/// An I/O error or a corrupt record fails the operation and is kept for
+/// [`take_error`](StateStore::take_error), which stops the search of the solver.
+pub struct FileStateStore<T> {
+    dir: PathBuf,
+    path: PathBuf,
+    file: File,
+    /// One more than the offset of the last record of each bucket, or 0 for none.
+    buckets: Vec<u64>,
+    end: u64,
+    len: usize,
+    /// The first error since the last [`take_error`](StateStore::take_error).
+    error: Cell<Option<io::Error>>,
+    marker: PhantomData<T>,
+}
+
+impl<T: StateCodec> FileStateStore<T> {
+    /// Creates an empty store in a new file of `dir`.
+    pub fn new(dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let dir = dir.into();
+        let path = Self::new_path(&dir);
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        Ok(Self {
+            dir,
+            path,
+            file,
+            buckets: vec![0; MIN_BUCKETS],
+            end: 0,
+            len: 0,
+            error: Cell::new(None),
+            marker: PhantomData,
+        })
+    }
+
+    fn new_path(dir: &Path) -> PathBuf {
+        dir.join(format!(
+            "tchisla-states-{}-{}",
+            process::id(),
+            NEXT_FILE.fetch_add(1, Ordering::Relaxed)
+        ))
+    }
+
+    #[inline]
+    fn bucket(&self, x: &T) -> usize {
+        let mut hasher = FxHasher::default();
+        x.hash(&mut hasher);
+        (hasher.finish() as usize) & (self.buckets.len() - 1)
+    }
+
+    /// Keeps `error` unless an earlier one is kept, and returns `None` for the failed operation.
+    fn fail<R>(&self, error: io::Error) -> Option<R> {
+        let first = self.error.take().unwrap_or(error);
+        self.error.set(Some(first));
+        None
+    }
+
+    fn read_at(&self, offset: u64, buffer: &mut [u8]) -> Option<()> {
+        let mut file = &self.file;
+        match file
+            .seek(SeekFrom::Start(offset))
+            .and_then(|_| file.read_exact(buffer))
+        {
+            Ok(()) => Some(()),
+            Err(error) => self.fail(error),
+        }
+    }
+
+    fn write_at(&self, offset: u64, bytes: &[u8]) -> Option<()> {
+        let mut file = &self.file;
+        match file
+            .seek(SeekFrom::Start(offset))
+            .and_then(|_| file.write_all(bytes))
+        {
+            Ok(()) => Some(()),
+            Err(error) => self.fail(error),
+        }
+    }
+
+    fn corrupt<R>(&self) -> Option<R> {
+        self.fail(io::Error::new(io::ErrorKind::InvalidData, CORRUPT))
+    }
+
+    fn header(&self, offset: u64) -> Option<Header> {
+        let mut bytes = [0; HEADER as usize];
+        self.read_at(offset, &mut bytes)?;
+        let field = |i: usize| u64::from_le_bytes(bytes[i * 8..(i + 1) * 8].try_into().unwrap());
+        let lengths = field(2);
+        let header = Header {
+            next: field(0),
+            digits: field(1),
+            value_length: lengths & 0xffff_ffff,
+            expression_length: lengths >> 32,
+        };
+        if offset + header.size() > self.end || header.next > offset {
+            return self.corrupt();
+        }
+        Some(header)
+    }
+
+    fn value(&self, offset: u64, header: &Header) -> Option<T> {
+        let mut bytes = vec![0; header.value_length as usize];
+        self.read_at(offset + HEADER, &mut bytes)?;
+        T::read(&mut bytes.iter().copied())
+            .ok()
+            .or_else(|| self.corrupt())
+    }
+
+    fn expression(&self, offset: u64, header: &Header) -> Option<Rc<Expression>> {
+        let mut bytes = vec![0; header.expression_length as usize];
+        self.read_at(offset + HEADER + header.value_length, &mut bytes)?;
+        Expression::decode(&bytes).ok().or_else(|| self.corrupt())
+    }
+
+    /// The offset and header of the record of `x`, unless removed or unreadable.
+    fn find(&self, x: &T) -> Option<(u64, Header)> {
+        let mut value = vec![];
+        x.write(&mut value);
+        let mut buffer = vec![0; value.len()];
+        let mut link = self.buckets[self.bucket(x)];
+        while link != 0 {
+            let offset = link - 1;
+            let header = self.header(offset)?;
+            if header.digits != REMOVED && header.value_length == value.len() as u64 {
+                self.read_at(offset + HEADER, &mut buffer)?;
+                if buffer == value {
+                    return Some((offset, header));
+                }
+            }
+            link = header.next;
+        }
+        None
+    }
+
+    /// Doubles the buckets and relinks every record still in the store into them, stopping at
+    /// the first record that cannot be read or relinked.
+    fn grow(&mut self) -> Option<()> {
+        self.buckets = vec![0; self.buckets.len() * 2];
+        let mut offset = 0;
+        while offset < self.end {
+            let header = self.header(offset)?;
+            if header.digits != REMOVED {
+                let bucket = self.bucket(&self.value(offset, &header)?);
+                self.write_at(offset, &self.buckets[bucket].to_le_bytes())?;
+                self.buckets[bucket] = offset + 1;
+            }
+            offset += header.size();
+        }
+        Some(())
+    }
+}
+
+impl<T: StateCodec> StateStore<T> for FileStateStore<T> {
+    fn get(&self, x: &T) -> Option<(Rc<Expression>, usize)> {
+        let (offset, header) = self.find(x)?;
+        Some((self.expression(offset, &header)?, header.digits as usize))
+    }
+
+    fn digits(&self, x: &T) -> Option<usize> {
+        self.find(x).map(|(_, header)| header.digits as usize)
+    }
+
+    fn insert(&mut self, x: T, digits: usize, expression: Rc<Expression>) {
+        self.remove(&x);
+        if self.len >= self.buckets.len() * MAX_LOAD && self.grow().is_none() {
+            return;
+        }
+        let bucket = self.bucket(&x);
+        let mut value = vec![];
+        x.write(&mut value);
+        let expression = expression.encode();
+        let lengths = (value.len() as u64) | ((expression.len() as u64) << 32);
+        let mut record = Vec::with_capacity(HEADER as usize + value.len() + expression.len());
+        record.extend(self.buckets[bucket].to_le_bytes());
+        record.extend((digits as u64).to_le_bytes());
+        record.extend(lengths.to_le_bytes());
+        record.extend(value);
+        record.extend(expression);
+        if self.write_at(self.end, &record).is_none() {
+            return;
+        }
+        self.buckets[bucket] = self.end + 1;
+        self.end += record.len() as u64;
+        self.len += 1;
+    }
+
+    fn remove(&mut self, x: &T) {
+        if let Some((offset, _)) = self.find(x) {
+            if self.write_at(offset + 8, &REMOVED.to_le_bytes()).is_some() {
+                self.len -= 1;
+            }
+        }
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn clear(&mut self) {
+        if let Err(error) = self.file.set_len(0) {
+            self.fail::<()>(error);
+        }
+        self.buckets = vec![0; MIN_BUCKETS];
+        self.end = 0;
+        self.len = 0;
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = (T, Rc<Expression>, usize)> + '_> {
+        Box::new(FileRecords {
+            store: self,
+            offset: 0,
+        })
+    }
+
+    /// The heads of the buckets, as allocated.
+    #[inline]
+    fn memory_estimate(&self) -> usize {
+        self.buckets.capacity() * size_of::<u64>()
+    }
+
+    #[inline]
+    fn take_error(&self) -> Option<io::Error> {
+        self.error.take()
+    }
+
+    /// Copies the file into a new one of the same directory, or into a [`MemoryStateStore`] if
+    /// the copy fails.
+    fn boxed_clone(&self) -> Box<dyn StateStore<T>> {
+        let path = Self::new_path(&self.dir);
+        match fs::copy(&self.path, &path)
+            .and_then(|_| OpenOptions::new().read(true).write(true).open(&path))
+        {
+            Ok(file) => Box::new(Self {
+                dir: self.dir.clone(),
+                path,
+                file,
+                buckets: self.buckets.clone(),
+                end: self.end,
+                len: self.len,
+                error: Cell::new(None),
+                marker: PhantomData,
+            }),
+            Err(_) => {
+                let _ = fs::remove_file(&path);
+                let mut store = MemoryStateStore::default();
+                for (x, expression, digits) in self.iter() {
+                    store.insert(x, digits, expression);
+                }
+                Box::new(store)
+            }
+        }
+    }
+}
+
+impl<T> Drop for FileStateStore<T> {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// The records of a [`FileStateStore`] still in the store, in the order of the file, up to the
+/// first that cannot be read.
+struct FileRecords<'a, T> {
+    store: &'a FileStateStore<T>,
+    offset: u64,
+}
+
+impl<T: StateCodec> Iterator for FileRecords<'_, T> {
+    type Item = (T, Rc<Expression>, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.offset < self.store.end {
+            let offset = self.offset;
+            let Some(header) = self.store.header(offset) else {
+                self.offset = self.store.end;
+                return None;
+            };
+            self.offset += header.size();
+            if header.digits != REMOVED {
+                let state = self.store.value(offset, &header).and_then(|x| {
+                    Some((
+                        x,
+                        self.store.expression(offset, &header)?,
+                        header.digits as usize,
+                    ))
+                });
+                if state.is_none() {
+                    self.offset = self.store.end;
+                }
+                return state;
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,92 @@
+use crate::{ExprHandle, Expression, Number};
+use rustc_hash::FxHashSet;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+const DEFAULT_CAPACITY: usize = 1024;
+
+/// Why a candidate for a traced value was not kept.
+///
+/// There is no reason for operations bailing out on overflow: they stop before their result
+/// exists as a value, so it cannot be matched against the traced ones. No traced value is lost
+/// that way either, since every result whose computation overflows also exceeds the limits.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RejectionReason {
+    /// The value exceeded the configured limits.
+    RangeCheck,
+    /// The value was already reachable, with the given number of digits.
+    Duplicate(usize),
+    /// Modular pruning ruled the value out at the final depth.
+    Pruned,
+}
+
+#[derive(Clone)]
+pub struct Rejection<T: Number> {
+    pub number: T,
+    pub digits: usize,
+    pub expression: ExprHandle,
+    pub reason: RejectionReason,
+}
+
+#[derive(Clone)]
+pub(super) struct Tracer<T: Number> {
+    values: FxHashSet<T>,
+    rejections: VecDeque<Rejection<T>>,
+    capacity: usize,
+}
+
+impl<T: Number> Tracer<T> {
+    pub(super) fn new() -> Self {
+        Self {
+            values: FxHashSet::default(),
+            rejections: VecDeque::new(),
+            capacity: DEFAULT_CAPACITY,
+        }
+    }
+
+    #[inline]
+    pub(super) fn watch(&mut self, x: T) {
+        self.values.insert(x);
+    }
+
+    #[inline]
+    pub(super) fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.rejections.len() > capacity {
+            self.rejections.pop_front();
+        }
+    }
+
+    #[inline]
+    pub(super) fn is_watched(&self, x: &T) -> bool {
+        self.values.contains(x)
+    }
+
+    pub(super) fn record(
+        &mut self,
+        number: T,
+        digits: usize,
+        expression: Rc<Expression>,
+        reason: RejectionReason,
+    ) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.rejections.len() == self.capacity {
+            self.rejections.pop_front();
+        }
+        self.rejections.push_back(Rejection {
+            number,
+            digits,
+            expression: ExprHandle::new(expression),
+            reason,
+        });
+    }
+
+    #[inline]
+    pub(super) fn rejections<'a>(&'a self, x: &'a T) -> impl Iterator<Item = &'a Rejection<T>> {
+        self.rejections
+            .iter()
+            .filter(move |rejection| rejection.number == *x)
+    }
+}
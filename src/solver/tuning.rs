@@ -0,0 +1,86 @@
+use super::Limits;
+use crate::Domain;
+
+/// Adjustments to the recommended limits of one domain for a particular `n`. Fields left as
+/// `None` keep the domain default.
+struct Tuning {
+    n: i64,
+    domain: Domain,
+    max_factorial: Option<i64>,
+    max_quadratic_power: Option<u8>,
+}
+
+/// Per-`n` tuning on top of the domain defaults. Entries for the same `n` and domain apply in
+/// order, and the two quadratic domains share their entries.
+const TUNINGS: &[Tuning] = &[
+    // Roots nested one level deeper pay off for 7 but mostly cost time for other n.
+    Tuning {
+        n: 7,
+        domain: Domain::RationalQuadratic,
+        max_factorial: None,
+        max_quadratic_power: Some(3),
+    },
+];
+
+impl Limits {
+    /// The recommended limits of `domain` for `n`, tuned per `n` where that pays off.
+    pub fn recommended(n: i64, domain: Domain) -> Self {
+        let mut limits = match domain {
+            Domain::Integral => Self {
+                max_digits: 48,
+                max_factorial: 20,
+                max_quadratic_power: 0,
+                max_radical_power: 1,
+                max_subfactorial: 0,
+            },
+            Domain::Rational | Domain::Custom => Self {
+                max_digits: 30,
+                max_factorial: 12,
+                max_quadratic_power: 0,
+                max_radical_power: 1,
+                max_subfactorial: 0,
+            },
+            Domain::IntegralQuadratic | Domain::RationalQuadratic => Self {
+                max_digits: 20,
+                max_factorial: 9,
+                max_quadratic_power: 2,
+                max_radical_power: 1,
+                max_subfactorial: 0,
+            },
+            Domain::Radical => Self {
+                max_digits: 20,
+                max_factorial: 9,
+                max_quadratic_power: 0,
+                max_radical_power: 6,
+                max_subfactorial: 0,
+            },
+        };
+        let domain = match domain {
+            Domain::IntegralQuadratic => Domain::RationalQuadratic,
+            domain => domain,
+        };
+        for tuning in TUNINGS {
+            if tuning.n == n && tuning.domain == domain {
+                if let Some(max_factorial) = tuning.max_factorial {
+                    limits.max_factorial = max_factorial;
+                }
+                if let Some(max_quadratic_power) = tuning.max_quadratic_power {
+                    limits.max_quadratic_power = max_quadratic_power;
+                }
+            }
+        }
+        limits
+    }
+
+    pub fn recommended_integral() -> Self {
+        Self::recommended(0, Domain::Integral)
+    }
+
+    pub fn recommended_rational() -> Self {
+        Self::recommended(0, Domain::Rational)
+    }
+
+    pub fn recommended_quadratic(n: i64) -> Self {
+        Self::recommended(n, Domain::RationalQuadratic)
+    }
+}
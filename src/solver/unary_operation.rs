@@ -1,16 +1,22 @@
-use super::{Solver, State};
-use crate::number_theory::{factorial, try_sqrt};
+use super::{Operation, Solver, State};
+use crate::number_theory::{factorial, subfactorial, try_sqrt};
+#[cfg(feature = "bigint")]
+use crate::BigNumber;
 use crate::{Expression, IntegralQuadratic, Number, Rational, RationalQuadratic};
 use num::traits::Inv;
+#[cfg(feature = "bigint")]
+use num::{BigInt, BigRational, One};
 use std::cmp::Ordering;
 use std::rc::Rc;
 
-fn is_single_digit(expression: &Expression) -> bool {
+/// Whether `expression` is written with one digit of `digit_set`, which may be a multi-digit `n`.
+fn is_single_digit(expression: &Expression, digit_set: &[i64]) -> bool {
     match expression {
-        Expression::Number(x) => *x < 10,
-        Expression::Negate(x) => is_single_digit(x),
-        Expression::Sqrt(x, _) => is_single_digit(x),
-        Expression::Factorial(x) => is_single_digit(x),
+        Expression::Number(x) => digit_set.binary_search(x).is_ok(),
+        Expression::Negate(x) => is_single_digit(x, digit_set),
+        Expression::Sqrt(x, _) => is_single_digit(x, digit_set),
+        Expression::Factorial(x) => is_single_digit(x, digit_set),
+        Expression::Subfactorial(x) => is_single_digit(x, digit_set),
         _ => false,
     }
 }
@@ -20,6 +26,7 @@ pub(super) trait UnaryOperation<T: Number> {
     fn concat(&mut self, digits: usize) -> bool;
     fn sqrt(&mut self, x: &State<T>) -> bool;
     fn factorial(&mut self, x: &State<T>) -> bool;
+    fn subfactorial(&mut self, x: &State<T>) -> bool;
     fn division_diff_one(
         &mut self,
         x: T,
@@ -31,12 +38,15 @@ pub(super) trait UnaryOperation<T: Number> {
 
 impl<T: Number> UnaryOperation<T> for Solver<T> {
     fn unary_operation(&mut self, x: State<T>) -> bool {
-        if self.n == 1 || !x.number.is_rational() || x.number.is_one() || !x.expression.is_divide()
+        if self.digit_set == [1]
+            || !x.number.is_rational()
+            || x.number.is_one()
+            || !self.expression(x.index).is_divide()
         {
             return false;
         }
-        let (numerator, denominator) = x.expression.to_divide().unwrap();
-        if is_single_digit(denominator) {
+        let (numerator, denominator) = self.expression(x.index).to_divide().unwrap();
+        if is_single_digit(denominator, &self.digit_set) {
             return self.division_diff_one(
                 x.number,
                 x.digits,
@@ -48,7 +58,7 @@ impl<T: Number> UnaryOperation<T> for Solver<T> {
         let mut rhs: Option<Rc<Expression>> = None;
         while let Some((p, q)) = lhs.to_multiply() {
             lhs = p;
-            if is_single_digit(q) {
+            if is_single_digit(q, &self.digit_set) {
                 return self.division_diff_one(
                     x.number,
                     x.digits,
@@ -73,22 +83,48 @@ impl<T: Number> UnaryOperation<T> for Solver<T> {
     }
 
     fn concat(&mut self, digits: usize) -> bool {
-        if digits as f64 * 10f64.log2() - 9f64.log2() > self.limits.max_digits as f64 {
+        if !self.concat_in_range(digits) {
             return false;
         }
-        let x = (10i64.pow(digits as u32) - 1) / 9 * self.n;
-        self.try_insert(x.into(), digits, || Expression::from_number(x))
+        let mut found = false;
+        for x in self.concatenations(digits) {
+            self.set_producer(Operation::Concat, [None, None]);
+            if self.try_insert_with(x.into(), digits, |_| Expression::from_number(x)) {
+                found = true;
+            }
+        }
+        found
     }
 
-    default fn sqrt(&mut self, _x: &State<T>) -> bool {
-        false
+    default fn sqrt(&mut self, x: &State<T>) -> bool {
+        self.set_producer(Operation::Sqrt, [Some(x.number), None]);
+        if let Some(y) = x.number.checked_sqrt() {
+            self.try_insert_with(y, x.digits, |solver| {
+                Expression::from_sqrt(solver.expression(x.index).clone(), 1)
+            })
+        } else {
+            false
+        }
     }
 
-    fn factorial(&mut self, x: &State<T>) -> bool {
+    default fn factorial(&mut self, x: &State<T>) -> bool {
+        self.set_producer(Operation::Factorial, [Some(x.number), None]);
         if let Some(n) = x.number.to_int() {
             if n < self.limits.max_factorial {
-                return self.try_insert(factorial(n).into(), x.digits, || {
-                    Expression::from_factorial(x.expression.clone())
+                return self.try_insert_with(factorial(n).into(), x.digits, |solver| {
+                    Expression::from_factorial(solver.expression(x.index).clone())
+                });
+            }
+        }
+        false
+    }
+
+    fn subfactorial(&mut self, x: &State<T>) -> bool {
+        self.set_producer(Operation::Subfactorial, [Some(x.number), None]);
+        if let Some(n) = x.number.to_int() {
+            if n >= 3 && n < self.limits.max_subfactorial {
+                return self.try_insert_with(subfactorial(n).into(), x.digits, |solver| {
+                    Expression::from_subfactorial(solver.expression(x.index).clone())
                 });
             }
         }
@@ -108,9 +144,10 @@ impl<T: Number> UnaryOperation<T> for Solver<T> {
 
 impl UnaryOperation<i64> for Solver<i64> {
     fn sqrt(&mut self, x: &State<i64>) -> bool {
+        self.set_producer(Operation::Sqrt, [Some(x.number), None]);
         if let Some(y) = try_sqrt(x.number) {
-            self.try_insert(y, x.digits, || {
-                Expression::from_sqrt(x.expression.clone(), 1)
+            self.try_insert_with(y, x.digits, |solver| {
+                Expression::from_sqrt(solver.expression(x.index).clone(), 1)
             })
         } else {
             false
@@ -124,9 +161,68 @@ impl UnaryOperation<i64> for Solver<i64> {
         numerator: Rc<Expression>,
         denominator: Rc<Expression>,
     ) -> bool {
+        self.set_producer(Operation::DivisionDiffOne, [Some(x), None]);
+        let mut found = false;
+        if x > 1
+            && self.try_insert_with(x - 1, digits, |_| {
+                Expression::from_divide(
+                    Expression::from_subtract(numerator.clone(), denominator.clone()),
+                    denominator.clone(),
+                )
+            })
+        {
+            found = true;
+        }
+        if self.try_insert_with(x + 1, digits, |_| {
+            Expression::from_divide(
+                Expression::from_add(numerator.clone(), denominator.clone()),
+                denominator.clone(),
+            )
+        }) {
+            found = true;
+        }
+        found
+    }
+}
+
+impl UnaryOperation<i128> for Solver<i128> {
+    fn sqrt(&mut self, x: &State<i128>) -> bool {
+        self.set_producer(Operation::Sqrt, [Some(x.number), None]);
+        if let Some(y) = x.number.checked_sqrt() {
+            self.try_insert_with(y, x.digits, |solver| {
+                Expression::from_sqrt(solver.expression(x.index).clone(), 1)
+            })
+        } else {
+            false
+        }
+    }
+
+    /// Factorials past `20!` overflow `i64`, so they are multiplied out in `i128`.
+    fn factorial(&mut self, x: &State<i128>) -> bool {
+        self.set_producer(Operation::Factorial, [Some(x.number), None]);
+        if x.number >= self.limits.max_factorial as i128 {
+            return false;
+        }
+        if let Some(result) = (2..=x.number).try_fold(1i128, |product, k| product.checked_mul(k)) {
+            self.try_insert_with(result, x.digits, |solver| {
+                Expression::from_factorial(solver.expression(x.index).clone())
+            })
+        } else {
+            false
+        }
+    }
+
+    fn division_diff_one(
+        &mut self,
+        x: i128,
+        digits: usize,
+        numerator: Rc<Expression>,
+        denominator: Rc<Expression>,
+    ) -> bool {
+        self.set_producer(Operation::DivisionDiffOne, [Some(x), None]);
         let mut found = false;
         if x > 1
-            && self.try_insert(x - 1, digits, || {
+            && self.try_insert_with(x - 1, digits, |_| {
                 Expression::from_divide(
                     Expression::from_subtract(numerator.clone(), denominator.clone()),
                     denominator.clone(),
@@ -135,7 +231,7 @@ impl UnaryOperation<i64> for Solver<i64> {
         {
             found = true;
         }
-        if self.try_insert(x + 1, digits, || {
+        if self.try_insert_with(x + 1, digits, |_| {
             Expression::from_divide(
                 Expression::from_add(numerator.clone(), denominator.clone()),
                 denominator.clone(),
@@ -149,10 +245,11 @@ impl UnaryOperation<i64> for Solver<i64> {
 
 impl UnaryOperation<Rational> for Solver<Rational> {
     fn sqrt(&mut self, x: &State<Rational>) -> bool {
+        self.set_producer(Operation::Sqrt, [Some(x.number), None]);
         if let Some(p) = try_sqrt(x.number.numerator()) {
             if let Some(q) = try_sqrt(x.number.denominator()) {
-                return self.try_insert(Rational::new_raw(p, q), x.digits, || {
-                    Expression::from_sqrt(x.expression.clone(), 1)
+                return self.try_insert_with(Rational::new_raw(p, q), x.digits, |solver| {
+                    Expression::from_sqrt(solver.expression(x.index).clone(), 1)
                 });
             }
         }
@@ -166,11 +263,12 @@ impl UnaryOperation<Rational> for Solver<Rational> {
         numerator: Rc<Expression>,
         denominator: Rc<Expression>,
     ) -> bool {
+        self.set_producer(Operation::DivisionDiffOne, [Some(x), None]);
         let mut found = false;
         match x.numerator().cmp(&x.denominator()) {
             Ordering::Less => {
                 let result = -(x - 1);
-                if self.try_insert(result, digits, || {
+                if self.try_insert_with(result, digits, |_| {
                     Expression::from_divide(
                         Expression::from_subtract(denominator.clone(), numerator.clone()),
                         denominator.clone(),
@@ -178,7 +276,7 @@ impl UnaryOperation<Rational> for Solver<Rational> {
                 }) {
                     found = true;
                 }
-                if self.try_insert(result.inv(), digits, || {
+                if self.try_insert_with(result.inv(), digits, |_| {
                     Expression::from_divide(
                         denominator.clone(),
                         Expression::from_subtract(denominator.clone(), numerator.clone()),
@@ -189,7 +287,7 @@ impl UnaryOperation<Rational> for Solver<Rational> {
             }
             Ordering::Greater => {
                 let result = x - 1;
-                if self.try_insert(result, digits, || {
+                if self.try_insert_with(result, digits, |_| {
                     Expression::from_divide(
                         Expression::from_subtract(numerator.clone(), denominator.clone()),
                         denominator.clone(),
@@ -197,7 +295,7 @@ impl UnaryOperation<Rational> for Solver<Rational> {
                 }) {
                     found = true;
                 }
-                if self.try_insert(result.inv(), digits, || {
+                if self.try_insert_with(result.inv(), digits, |_| {
                     Expression::from_divide(
                         denominator.clone(),
                         Expression::from_subtract(numerator.clone(), denominator.clone()),
@@ -209,7 +307,7 @@ impl UnaryOperation<Rational> for Solver<Rational> {
             _ => {}
         }
         let result = x + 1;
-        if self.try_insert(result, digits, || {
+        if self.try_insert_with(result, digits, |_| {
             Expression::from_divide(
                 Expression::from_add(numerator.clone(), denominator.clone()),
                 denominator.clone(),
@@ -217,7 +315,7 @@ impl UnaryOperation<Rational> for Solver<Rational> {
         }) {
             found = true;
         }
-        if self.try_insert(result.inv(), digits, || {
+        if self.try_insert_with(result.inv(), digits, |_| {
             Expression::from_divide(
                 denominator.clone(),
                 Expression::from_add(numerator.clone(), denominator.clone()),
@@ -231,10 +329,11 @@ impl UnaryOperation<Rational> for Solver<Rational> {
 
 impl UnaryOperation<IntegralQuadratic> for Solver<IntegralQuadratic> {
     fn sqrt(&mut self, x: &State<IntegralQuadratic>) -> bool {
+        self.set_producer(Operation::Sqrt, [Some(x.number), None]);
         if x.number.quadratic_power() < self.limits.max_quadratic_power {
             if let Some(result) = x.number.try_sqrt() {
-                return self.try_insert(result, x.digits, || {
-                    Expression::from_sqrt(x.expression.clone(), 1)
+                return self.try_insert_with(result, x.digits, |solver| {
+                    Expression::from_sqrt(solver.expression(x.index).clone(), 1)
                 });
             }
         }
@@ -248,9 +347,10 @@ impl UnaryOperation<IntegralQuadratic> for Solver<IntegralQuadratic> {
         numerator: Rc<Expression>,
         denominator: Rc<Expression>,
     ) -> bool {
+        self.set_producer(Operation::DivisionDiffOne, [Some(x), None]);
         let mut found = false;
         if x.integral_part() > 1
-            && self.try_insert(x - 1, digits, || {
+            && self.try_insert_with(x - 1, digits, |_| {
                 Expression::from_divide(
                     Expression::from_subtract(numerator.clone(), denominator.clone()),
                     denominator.clone(),
@@ -259,7 +359,7 @@ impl UnaryOperation<IntegralQuadratic> for Solver<IntegralQuadratic> {
         {
             found = true;
         }
-        if self.try_insert(x + 1, digits, || {
+        if self.try_insert_with(x + 1, digits, |_| {
             Expression::from_divide(
                 Expression::from_add(numerator.clone(), denominator.clone()),
                 denominator.clone(),
@@ -273,10 +373,11 @@ impl UnaryOperation<IntegralQuadratic> for Solver<IntegralQuadratic> {
 
 impl UnaryOperation<RationalQuadratic> for Solver<RationalQuadratic> {
     fn sqrt(&mut self, x: &State<RationalQuadratic>) -> bool {
+        self.set_producer(Operation::Sqrt, [Some(x.number), None]);
         if x.number.quadratic_power() < self.limits.max_quadratic_power {
             if let Some(result) = x.number.try_sqrt() {
-                self.try_insert(result, x.digits, || {
-                    Expression::from_sqrt(x.expression.clone(), 1)
+                self.try_insert_with(result, x.digits, |solver| {
+                    Expression::from_sqrt(solver.expression(x.index).clone(), 1)
                 })
             } else {
                 false
@@ -293,6 +394,7 @@ impl UnaryOperation<RationalQuadratic> for Solver<RationalQuadratic> {
         numerator: Rc<Expression>,
         denominator: Rc<Expression>,
     ) -> bool {
+        self.set_producer(Operation::DivisionDiffOne, [Some(x), None]);
         let mut found = false;
         match x
             .rational_part()
@@ -301,7 +403,7 @@ impl UnaryOperation<RationalQuadratic> for Solver<RationalQuadratic> {
         {
             Ordering::Less => {
                 let result = -(x - 1);
-                if self.try_insert(result, digits, || {
+                if self.try_insert_with(result, digits, |_| {
                     Expression::from_divide(
                         Expression::from_subtract(denominator.clone(), numerator.clone()),
                         denominator.clone(),
@@ -309,7 +411,7 @@ impl UnaryOperation<RationalQuadratic> for Solver<RationalQuadratic> {
                 }) {
                     found = true;
                 }
-                if self.try_insert(result.inv(), digits, || {
+                if self.try_insert_with(result.inv(), digits, |_| {
                     Expression::from_divide(
                         denominator.clone(),
                         Expression::from_subtract(denominator.clone(), numerator.clone()),
@@ -320,7 +422,7 @@ impl UnaryOperation<RationalQuadratic> for Solver<RationalQuadratic> {
             }
             Ordering::Greater => {
                 let result = x - 1;
-                if self.try_insert(result, digits, || {
+                if self.try_insert_with(result, digits, |_| {
                     Expression::from_divide(
                         Expression::from_subtract(numerator.clone(), denominator.clone()),
                         denominator.clone(),
@@ -328,7 +430,7 @@ impl UnaryOperation<RationalQuadratic> for Solver<RationalQuadratic> {
                 }) {
                     found = true;
                 }
-                if self.try_insert(result.inv(), digits, || {
+                if self.try_insert_with(result.inv(), digits, |_| {
                     Expression::from_divide(
                         denominator.clone(),
                         Expression::from_subtract(numerator.clone(), denominator.clone()),
@@ -340,7 +442,7 @@ impl UnaryOperation<RationalQuadratic> for Solver<RationalQuadratic> {
             _ => {}
         }
         let result = x + 1;
-        if self.try_insert(result, digits, || {
+        if self.try_insert_with(result, digits, |_| {
             Expression::from_divide(
                 Expression::from_add(numerator.clone(), denominator.clone()),
                 denominator.clone(),
@@ -348,7 +450,7 @@ impl UnaryOperation<RationalQuadratic> for Solver<RationalQuadratic> {
         }) {
             found = true;
         }
-        if self.try_insert(result.inv(), digits, || {
+        if self.try_insert_with(result.inv(), digits, |_| {
             Expression::from_divide(
                 denominator.clone(),
                 Expression::from_add(numerator.clone(), denominator.clone()),
@@ -359,3 +461,22 @@ impl UnaryOperation<RationalQuadratic> for Solver<RationalQuadratic> {
         found
     }
 }
+
+#[cfg(feature = "bigint")]
+impl UnaryOperation<BigNumber> for Solver<BigNumber> {
+    /// Factorials are multiplied out in `BigInt`, so `max_factorial` may go past `20`.
+    fn factorial(&mut self, x: &State<BigNumber>) -> bool {
+        self.set_producer(Operation::Factorial, [Some(x.number), None]);
+        match x.number.to_int() {
+            Some(n) if n < self.limits.max_factorial => {
+                let result = (2..=n).fold(BigInt::one(), |product, k| product * k);
+                self.try_insert_with(
+                    BigNumber::new(BigRational::from_integer(result)),
+                    x.digits,
+                    |solver| Expression::from_factorial(solver.expression(x.index).clone()),
+                )
+            }
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use super::Solver;
+use crate::solution;
+use crate::{Number, Solution};
+use std::ops::Range;
+
+impl<T: Number> Solver<T> {
+    /// Solutions for every known state whose value is within `epsilon` of `x`, in increasing
+    /// order of value.
+    ///
+    /// Lookups binary search an index of the states sorted by value, which is built on the first
+    /// lookup after the states change.
+    pub fn find_near(&mut self, x: f64, epsilon: f64) -> Vec<Solution> {
+        let near = self.states_near(x, epsilon);
+        self.value_index[near]
+            .iter()
+            .filter_map(|(_, number)| self.get_solution(number))
+            .collect()
+    }
+
+    /// Searches for approximations of `target` within `tolerance`, for targets no exact search
+    /// reaches, such as π. Depths are searched to completion one at a time until one yields an
+    /// approximation, or through `max_depth` if given, which also finds the closer
+    /// approximations of deeper depths.
+    ///
+    /// Returns every approximation found, best first: by error, then by digits.
+    pub fn solve_approximate(
+        &mut self,
+        target: f64,
+        tolerance: f64,
+        max_depth: Option<usize>,
+    ) -> Vec<Solution> {
+        let start = solution::now();
+        loop {
+            let done = match max_depth {
+                Some(max_depth) => self.depth_searched >= max_depth,
+                None => !self.states_near(target, tolerance).is_empty(),
+            };
+            if done || self.is_exhausted() {
+                break;
+            }
+            self.exhaust_to_depth(self.depth_searched + 1);
+            if self.interrupted() {
+                break;
+            }
+        }
+        let max_depth = max_depth.unwrap_or(usize::MAX);
+        let near = self.states_near(target, tolerance);
+        let mut approximations: Vec<_> = self.value_index[near]
+            .iter()
+            .filter_map(|(value, number)| {
+                let (expression, digits) = self.states.get(number)?;
+                (digits <= max_depth).then(|| ((value - target).abs(), expression, digits))
+            })
+            .collect();
+        approximations.sort_by(|(e1, _, d1), (e2, _, d2)| e1.total_cmp(e2).then(d1.cmp(d2)));
+        let elapsed = start.map(|start| start.elapsed());
+        approximations
+            .into_iter()
+            .map(|(_, expression, digits)| Solution::new(expression, digits, T::DOMAIN, elapsed))
+            .collect()
+    }
+
+    /// The positions in the value index of the states whose value is within `epsilon` of `x`.
+    fn states_near(&mut self, x: f64, epsilon: f64) -> Range<usize> {
+        if self.value_index.is_empty() {
+            self.rebuild_value_index();
+        }
+        let start = self
+            .value_index
+            .partition_point(|(value, _)| *value < x - epsilon);
+        let end = self
+            .value_index
+            .partition_point(|(value, _)| *value <= x + epsilon);
+        start..end.max(start)
+    }
+
+    fn rebuild_value_index(&mut self) {
+        self.value_index = self
+            .states
+            .iter()
+            .map(|(number, _, _)| (number.to_f64(), number))
+            .filter(|(value, _)| value.is_finite())
+            .collect();
+        self.value_index.sort_by(|(x, _), (y, _)| x.total_cmp(y));
+    }
+}
@@ -0,0 +1,28 @@
+#[cfg(feature = "bigint")]
+use crate::big_number::hold_store;
+#[cfg(feature = "bigint")]
+use crate::BigNumber;
+use crate::Number;
+use std::any::Any;
+use std::rc::Rc;
+
+/// The storage a solver keeps alive for the values of its domain, for domains whose values are
+/// handles into storage shared by the thread.
+pub(super) trait ValueStore {
+    fn hold_store() -> Option<Rc<dyn Any>>;
+}
+
+impl<T: Number> ValueStore for T {
+    #[inline]
+    default fn hold_store() -> Option<Rc<dyn Any>> {
+        None
+    }
+}
+
+#[cfg(feature = "bigint")]
+impl ValueStore for BigNumber {
+    #[inline]
+    fn hold_store() -> Option<Rc<dyn Any>> {
+        Some(hold_store())
+    }
+}
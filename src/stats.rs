@@ -0,0 +1,184 @@
+use crate::solution;
+use crate::{Domain, Number, ProgressiveSolver, Solution, Solver};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// Telemetry of one search call, measured against the solver's state before the call.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct SearchStats {
+    /// The depth searched to completion once the call returns.
+    pub depth_searched: usize,
+    /// States added during the call, across every domain.
+    pub new_states: usize,
+    /// States known once the call returns, across every domain.
+    pub total_states: usize,
+    /// Values produced by operations during the call, whether or not they were kept.
+    pub candidates: u64,
+    /// Wall time of the call, where a clock is available.
+    pub elapsed: Option<Duration>,
+    /// The depth at which the returned solution was found, if any.
+    pub solution_depth: Option<usize>,
+    /// Time from the start of the search to the returned solution, where a clock is available.
+    /// For a progressive search this spans every earlier call, unlike `elapsed`.
+    pub time_to_solution: Option<Duration>,
+}
+
+/// The passes of one depth of a progressive search, in order.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchPhase {
+    Integral,
+    /// The bounded integral search to the depth limit, which can find longer solutions early.
+    FullIntegral,
+    Rational,
+    RationalQuadratic,
+}
+
+impl SearchPhase {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SearchPhase::Integral => "integral",
+            SearchPhase::FullIntegral => "full_integral",
+            SearchPhase::Rational => "rational",
+            SearchPhase::RationalQuadratic => "rational_quadratic",
+        }
+    }
+}
+
+/// A snapshot of a progressive search, reported to
+/// [`on_progress`](ProgressiveSolver::on_progress) callbacks as each phase starts.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct Progress {
+    /// The depth being searched.
+    pub depth: usize,
+    pub phase: SearchPhase,
+    pub integral_states: usize,
+    /// States of the full-integral pass, including those it copied from the integral pass.
+    pub full_integral_states: usize,
+    pub rational_states: usize,
+    pub quadratic_states: usize,
+    /// A rough estimate of the bytes held by the states of every domain.
+    pub memory: usize,
+    /// Time since the search started, where a clock is available.
+    pub elapsed: Option<Duration>,
+}
+
+/// An event of a search, reported to
+/// [`set_progress_callback`](Solver::set_progress_callback) callbacks as it happens.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ProgressEvent {
+    /// The search of depth `depth` started in `domain`.
+    DepthStarted { domain: Domain, depth: usize },
+    /// The search of depth `depth` finished in `domain`, which first reached `states` states at
+    /// that depth.
+    DepthFinished {
+        domain: Domain,
+        depth: usize,
+        states: usize,
+    },
+    /// Another batch of states was inserted while searching depth `depth` in `domain`, which now
+    /// knows `states` states in all.
+    StatesInserted {
+        domain: Domain,
+        depth: usize,
+        states: usize,
+    },
+    /// The state closest to the target among those inserted since the solve call started, at a
+    /// distance of `error`, which is 0 once the target itself is reached.
+    BestCandidate { solution: Solution, error: f64 },
+}
+
+/// How the magnitudes of the states first reached at one depth spread over powers of ten.
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct MagnitudeHistogram {
+    pub depth: usize,
+    /// The number of states with `10^k <= |x| < 10^(k + 1)`, keyed by `k`. Fractions below 1
+    /// have negative keys.
+    pub buckets: BTreeMap<i32, usize>,
+}
+
+impl MagnitudeHistogram {
+    fn new(depth: usize, values: impl Iterator<Item = f64>) -> Self {
+        let mut buckets = BTreeMap::new();
+        for x in values {
+            *buckets.entry(x.abs().log10().floor() as i32).or_insert(0) += 1;
+        }
+        Self { depth, buckets }
+    }
+
+    /// The number of states of the depth.
+    pub fn total(&self) -> usize {
+        self.buckets.values().sum()
+    }
+
+    /// The number of states with at least `digits` digits before the decimal point.
+    pub fn at_least_digits(&self, digits: i32) -> usize {
+        self.buckets
+            .range(digits - 1..)
+            .map(|(_, count)| count)
+            .sum()
+    }
+}
+
+impl<T: Number> Solver<T> {
+    /// Like [`solve`](Self::solve), but also reports what the search cost.
+    pub fn solve_with_stats(
+        &mut self,
+        target: T,
+        max_depth: Option<usize>,
+    ) -> (Option<Solution>, SearchStats) {
+        let start = solution::now();
+        let (states, candidates) = self.counters();
+        let solution = self.solve(target, max_depth);
+        let (total_states, total_candidates) = self.counters();
+        let stats = SearchStats {
+            depth_searched: self.depth_searched(),
+            new_states: total_states.saturating_sub(states),
+            total_states,
+            candidates: total_candidates - candidates,
+            elapsed: start.map(|start| start.elapsed()),
+            solution_depth: solution.as_ref().map(Solution::depth),
+            time_to_solution: solution.as_ref().and_then(Solution::elapsed),
+        };
+        (solution, stats)
+    }
+
+    /// The magnitude histogram of every depth searched so far, from depth 1.
+    pub fn magnitude_histograms(&self) -> Vec<MagnitudeHistogram> {
+        (1..=self.depth_searched())
+            .map(|depth| {
+                MagnitudeHistogram::new(depth, self.states_at_depth(depth).map(|(x, _)| x.to_f64()))
+            })
+            .collect()
+    }
+}
+
+impl ProgressiveSolver {
+    /// Like [`run`](Self::run), but also reports what the search cost in every domain.
+    pub fn run_with_stats(&mut self) -> (Option<Solution>, SearchStats) {
+        let start = solution::now();
+        let (states, candidates) = self.counters();
+        let solution = self.run();
+        let (total_states, total_candidates) = self.counters();
+        let stats = SearchStats {
+            depth_searched: self.depth_searched(),
+            new_states: total_states.saturating_sub(states),
+            total_states,
+            candidates: total_candidates - candidates,
+            elapsed: start.map(|start| start.elapsed()),
+            solution_depth: solution.as_ref().map(Solution::depth),
+            time_to_solution: solution.as_ref().and_then(Solution::elapsed),
+        };
+        (solution, stats)
+    }
+    /// The magnitude histogram of every depth searched so far, across every domain, each
+    /// value counted once at the least depth any domain reaches it.
+    pub fn magnitude_histograms(&self) -> Vec<MagnitudeHistogram> {
+        (1..=self.depth_searched())
+            .map(|depth| {
+                MagnitudeHistogram::new(depth, self.states_at_depth(depth).map(|(x, _)| x.to_f64()))
+            })
+            .collect()
+    }
+}
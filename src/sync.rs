@@ -0,0 +1,111 @@
+use crate::{RecordDiff, RecordTable};
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+/// How long connecting, and then each read or write, may take before the fetch fails.
+const TIMEOUT: Duration = Duration::from_secs(30);
+/// The largest response fetched, headers included.
+const MAX_RESPONSE: u64 = 64 << 20;
+
+/// Why published records could not be fetched.
+#[derive(Debug)]
+pub enum FetchError {
+    /// The URL is not of the form `http://host[:port][/path]`. HTTPS is not supported.
+    Url,
+    Io(io::Error),
+    /// The server answered with a status other than 200, or with no valid response.
+    Status(Option<u16>),
+    /// The response is larger than 64 MiB.
+    TooLarge,
+    /// The body is not a record table.
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FetchError::Url => write!(f, "only http://host[:port][/path] URLs are supported"),
+            FetchError::Io(error) => write!(f, "{error}"),
+            FetchError::Status(Some(status)) => write!(f, "server answered with status {status}"),
+            FetchError::Status(None) => write!(f, "server sent an invalid response"),
+            FetchError::TooLarge => write!(f, "response is too large"),
+            FetchError::Json(error) => write!(f, "invalid records: {error}"),
+        }
+    }
+}
+
+impl From<io::Error> for FetchError {
+    #[inline]
+    fn from(error: io::Error) -> Self {
+        FetchError::Io(error)
+    }
+}
+
+/// Downloads a record table published as JSON at a plain-HTTP `url`. Connecting and each read
+/// or write time out after 30 seconds. The records are not verified; see
+/// [`RecordTable::merge`].
+pub fn fetch_records(url: &str) -> Result<RecordTable, FetchError> {
+    let rest = url.strip_prefix("http://").ok_or(FetchError::Url)?;
+    let (authority, path) = rest.find('/').map_or((rest, "/"), |i| rest.split_at(i));
+    let (host, port) = match authority.rsplit_once(':') {
+        Some((host, port)) => (host, port.parse().map_err(|_| FetchError::Url)?),
+        None => (authority, 80),
+    };
+    if host.is_empty() {
+        return Err(FetchError::Url);
+    }
+    let mut stream = connect((host, port))?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    // HTTP/1.0 keeps the body unchunked and has the server close the connection after it.
+    write!(
+        stream,
+        "GET {path} HTTP/1.0\r\nHost: {authority}\r\nAccept: application/json\r\n\r\n"
+    )?;
+    let mut response = vec![];
+    stream.take(MAX_RESPONSE + 1).read_to_end(&mut response)?;
+    if response.len() as u64 > MAX_RESPONSE {
+        return Err(FetchError::TooLarge);
+    }
+    let header_end = response
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .ok_or(FetchError::Status(None))?;
+    let status = std::str::from_utf8(&response[..header_end])
+        .ok()
+        .and_then(|header| header.split(' ').nth(1))
+        .and_then(|status| status.parse().ok())
+        .ok_or(FetchError::Status(None))?;
+    if status != 200 {
+        return Err(FetchError::Status(Some(status)));
+    }
+    serde_json::from_slice(&response[header_end + 4..]).map_err(FetchError::Json)
+}
+
+/// Connects to the first address of `address` that answers within the timeout.
+fn connect(address: impl ToSocketAddrs) -> io::Result<TcpStream> {
+    let mut last_error = None;
+    for address in address.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&address, TIMEOUT) {
+            Ok(stream) => return Ok(stream),
+            Err(error) => last_error = Some(error),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| io::Error::other("host has no address")))
+}
+
+impl RecordTable {
+    /// Merges the published records at `url` into the table, skipping those that do not solve
+    /// their problem as described at [`merge`](Self::merge). Returns how the table compared to
+    /// them beforehand: `better` and `added` list the problems where the table beats the
+    /// published records, `worse` and `missing` those it has now taken from them.
+    pub fn sync(&mut self, url: &str) -> Result<RecordDiff, FetchError> {
+        let mut published = fetch_records(url)?;
+        published.retain_valid();
+        let diff = self.diff(&published);
+        self.merge(published);
+        Ok(diff)
+    }
+}
@@ -1,13 +1,21 @@
 use crate::*;
 use serde::{Deserialize, Serialize};
-use std::rc::Rc;
 use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
 
 #[derive(Deserialize, Serialize)]
 struct Config {
     max_depth: usize,
     max_digits: usize,
     max_factorial: u32,
+    #[serde(default)]
+    max_subfactorial: u32,
+    #[serde(default)]
+    modulo: bool,
+    #[serde(default)]
+    gcd_lcm: bool,
+    #[serde(default)]
+    negation: bool,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -16,6 +24,14 @@ struct QuadraticConfig {
     max_digits: usize,
     max_factorial: u32,
     max_quadratic_power: u8,
+    #[serde(default)]
+    max_subfactorial: u32,
+    #[serde(default)]
+    modulo: bool,
+    #[serde(default)]
+    gcd_lcm: bool,
+    #[serde(default)]
+    negation: bool,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -28,27 +44,128 @@ struct ProgressiveConfig {
     quadratic_max_digits: usize,
     quadratic_max_factorial: u32,
     quadratic_max_quadratic_power: u8,
+    #[serde(default)]
+    integral_max_subfactorial: u32,
+    #[serde(default)]
+    rational_max_subfactorial: u32,
+    #[serde(default)]
+    quadratic_max_subfactorial: u32,
+    #[serde(default)]
+    modulo: bool,
+    #[serde(default)]
+    gcd_lcm: bool,
+    #[serde(default)]
+    negation: bool,
 }
 
-#[derive(Deserialize, Serialize)]
-struct Solution {
+impl ProgressiveConfig {
+    fn recommended(n: i64) -> Self {
+        let integral = Limits::recommended(n, Domain::Integral);
+        let rational = Limits::recommended(n, Domain::Rational);
+        let quadratic = Limits::recommended(n, Domain::RationalQuadratic);
+        Self {
+            max_depth: 0,
+            integral_max_digits: integral.max_digits,
+            integral_max_factorial: integral.max_factorial as u32,
+            rational_max_digits: rational.max_digits,
+            rational_max_factorial: rational.max_factorial as u32,
+            quadratic_max_digits: quadratic.max_digits,
+            quadratic_max_factorial: quadratic.max_factorial as u32,
+            quadratic_max_quadratic_power: quadratic.max_quadratic_power,
+            integral_max_subfactorial: integral.max_subfactorial as u32,
+            rational_max_subfactorial: rational.max_subfactorial as u32,
+            quadratic_max_subfactorial: quadratic.max_subfactorial as u32,
+            modulo: false,
+            gcd_lcm: false,
+            negation: false,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct SerializedSolution<E: Serialize> {
     digits: usize,
-    expression: String,
+    expression: E,
+    depth: usize,
+    /// Seconds from the start of the search, where a clock is available.
+    elapsed: Option<f64>,
+}
+
+fn _serialize_output(solution: Option<Solution>, format: Option<String>) -> JsValue {
+    let Some(solution) = solution else {
+        return JsValue::NULL;
+    };
+    let digits = solution.digits();
+    let depth = solution.depth();
+    let elapsed = solution.elapsed().map(|elapsed| elapsed.as_secs_f64());
+    let format = match format.as_deref() {
+        Some("ast") => {
+            return serde_wasm_bindgen::to_value(&SerializedSolution {
+                digits,
+                expression: solution.expression(),
+                depth,
+                elapsed,
+            })
+            .unwrap();
+        }
+        Some("plain") => Format::Ascii,
+        Some("unicode") => Format::Unicode,
+        Some("latex") | None => Format::Latex,
+        Some(format) => wasm_bindgen::throw_str(&format!("unknown format: {format}")),
+    };
+    serde_wasm_bindgen::to_value(&SerializedSolution {
+        digits,
+        expression: solution.format(format),
+        depth,
+        elapsed,
+    })
+    .unwrap()
+}
+
+const MAX_SAFE_INTEGER: f64 = 9007199254740991.0;
+
+/// Parses a positive target, or any nonzero one when `negation` is enabled.
+fn _parse_target(target: JsValue, negation: bool) -> i64 {
+    let parsed = if let Some(string) = target.as_string() {
+        string.trim().parse::<i64>().ok()
+    } else if target.is_bigint() {
+        i64::try_from(target.unchecked_into::<js_sys::BigInt>()).ok()
+    } else if let Some(x) = target.as_f64() {
+        if x.fract() == 0.0 && x.abs() <= MAX_SAFE_INTEGER {
+            Some(x as i64)
+        } else {
+            None
+        }
+    } else {
+        None
+    };
+    match parsed {
+        Some(target) if target > 0 || (negation && target < 0) => target,
+        _ if negation => wasm_bindgen::throw_str("target must be a nonzero 64-bit integer"),
+        _ => wasm_bindgen::throw_str("target must be a positive 64-bit integer"),
+    }
 }
 
-fn _serialize_output(solution: Option<(Rc<Expression>, usize)>) -> JsValue {
-    match solution {
-        Some((expression, digits)) => serde_wasm_bindgen::to_value(&Solution {
-            digits,
-            expression: expression.to_latex_string(),
-        })
-        .unwrap(),
-        None => JsValue::NULL,
+/// Passes on `limits`, throwing if solvers cannot search with them.
+fn _check_limits(limits: Limits) -> Limits {
+    if let Err(error) = limits.validate() {
+        wasm_bindgen::throw_str(&error);
     }
+    limits
+}
+
+#[wasm_bindgen(js_name = recommendedConfig)]
+pub fn _recommended_config(n: i32) -> JsValue {
+    serde_wasm_bindgen::to_value(&ProgressiveConfig::recommended(n as i64)).unwrap()
 }
 
 #[wasm_bindgen(js_name = solveIntegral)]
-pub fn _solve_integral(n: i32, target: i32, config: JsValue) -> JsValue {
+pub fn _solve_integral(
+    n: i32,
+    target: JsValue,
+    config: JsValue,
+    format: Option<String>,
+) -> JsValue {
     let config: Config = serde_wasm_bindgen::from_value(config).unwrap();
     let mut solver = Solver::new(
         n as i64,
@@ -56,20 +173,33 @@ pub fn _solve_integral(n: i32, target: i32, config: JsValue) -> JsValue {
             max_digits: config.max_digits,
             max_factorial: config.max_factorial as i64,
             max_quadratic_power: 0,
+            max_radical_power: 1,
+            max_subfactorial: config.max_subfactorial as i64,
         },
     );
-    _serialize_output(solver.solve(
-        target as i64,
-        if config.max_depth == 0 {
-            None
-        } else {
-            Some(config.max_depth)
-        },
-    ))
+    solver.set_modulo(config.modulo);
+    solver.set_gcd_lcm(config.gcd_lcm);
+    solver.set_negation(config.negation);
+    _serialize_output(
+        solver.solve(
+            _parse_target(target, config.negation),
+            if config.max_depth == 0 {
+                None
+            } else {
+                Some(config.max_depth)
+            },
+        ),
+        format,
+    )
 }
 
 #[wasm_bindgen(js_name = solveRational)]
-pub fn _solve_rational(n: i32, target: i32, config: JsValue) -> JsValue {
+pub fn _solve_rational(
+    n: i32,
+    target: JsValue,
+    config: JsValue,
+    format: Option<String>,
+) -> JsValue {
     let config: Config = serde_wasm_bindgen::from_value(config).unwrap();
     let mut solver = Solver::new(
         n as i64,
@@ -77,58 +207,92 @@ pub fn _solve_rational(n: i32, target: i32, config: JsValue) -> JsValue {
             max_digits: config.max_digits,
             max_factorial: config.max_factorial as i64,
             max_quadratic_power: 0,
+            max_radical_power: 1,
+            max_subfactorial: config.max_subfactorial as i64,
         },
     );
-    _serialize_output(solver.solve(
-        Rational::from(target as i64),
-        if config.max_depth == 0 {
-            None
-        } else {
-            Some(config.max_depth)
-        },
-    ))
+    solver.set_modulo(config.modulo);
+    solver.set_gcd_lcm(config.gcd_lcm);
+    solver.set_negation(config.negation);
+    _serialize_output(
+        solver.solve(
+            Rational::from(_parse_target(target, config.negation)),
+            if config.max_depth == 0 {
+                None
+            } else {
+                Some(config.max_depth)
+            },
+        ),
+        format,
+    )
 }
 
 #[wasm_bindgen(js_name = solveIntegralQuadratic)]
-pub fn _solve_integral_quadratic(n: i32, target: i32, config: JsValue) -> JsValue {
+pub fn _solve_integral_quadratic(
+    n: i32,
+    target: JsValue,
+    config: JsValue,
+    format: Option<String>,
+) -> JsValue {
     let config: QuadraticConfig = serde_wasm_bindgen::from_value(config).unwrap();
     let mut solver = Solver::new(
         n as i64,
-        Limits {
+        _check_limits(Limits {
             max_digits: config.max_digits,
             max_factorial: config.max_factorial as i64,
             max_quadratic_power: config.max_quadratic_power,
-        },
+            max_radical_power: 1,
+            max_subfactorial: config.max_subfactorial as i64,
+        }),
     );
-    _serialize_output(solver.solve(
-        IntegralQuadratic::from(target as i64),
-        if config.max_depth == 0 {
-            None
-        } else {
-            Some(config.max_depth)
-        },
-    ))
+    solver.set_modulo(config.modulo);
+    solver.set_gcd_lcm(config.gcd_lcm);
+    solver.set_negation(config.negation);
+    _serialize_output(
+        solver.solve(
+            IntegralQuadratic::from(_parse_target(target, config.negation)),
+            if config.max_depth == 0 {
+                None
+            } else {
+                Some(config.max_depth)
+            },
+        ),
+        format,
+    )
 }
 
 #[wasm_bindgen(js_name = solveRationalQuadratic)]
-pub fn _solve_rational_quadratic(n: i32, target: i32, config: JsValue) -> JsValue {
+pub fn _solve_rational_quadratic(
+    n: i32,
+    target: JsValue,
+    config: JsValue,
+    format: Option<String>,
+) -> JsValue {
     let config: QuadraticConfig = serde_wasm_bindgen::from_value(config).unwrap();
     let mut solver = Solver::new(
         n as i64,
-        Limits {
+        _check_limits(Limits {
             max_digits: config.max_digits,
             max_factorial: config.max_factorial as i64,
             max_quadratic_power: config.max_quadratic_power,
-        },
+            max_radical_power: 1,
+            max_subfactorial: config.max_subfactorial as i64,
+        }),
     );
-    _serialize_output(solver.solve(
-        RationalQuadratic::from(target as i64),
-        if config.max_depth == 0 {
-            None
-        } else {
-            Some(config.max_depth)
-        },
-    ))
+    solver.set_modulo(config.modulo);
+    solver.set_gcd_lcm(config.gcd_lcm);
+    solver.set_negation(config.negation);
+    _serialize_output(
+        solver.solve(
+            RationalQuadratic::from(_parse_target(target, config.negation)),
+            if config.max_depth == 0 {
+                None
+            } else {
+                Some(config.max_depth)
+            },
+        ),
+        format,
+    )
 }
 
 #[wasm_bindgen]
@@ -139,38 +303,66 @@ pub struct ProgressiveSolver {
 #[wasm_bindgen]
 impl ProgressiveSolver {
     #[wasm_bindgen(constructor)]
-    pub fn new(n: i32, target: i32, config: JsValue) -> Self {
+    pub fn new(n: i32, target: JsValue, config: JsValue) -> Self {
         let config: ProgressiveConfig = serde_wasm_bindgen::from_value(config).unwrap();
-        Self {
-            solver: crate::ProgressiveSolver::new(
-                n as i64,
-                target as i64,
-                if config.max_depth == 0 {
-                    None
-                } else {
-                    Some(config.max_depth)
-                },
-                Limits {
-                    max_digits: config.integral_max_digits,
-                    max_factorial: config.integral_max_factorial as i64,
-                    max_quadratic_power: 0,
-                },
-                Limits {
-                    max_digits: config.rational_max_digits,
-                    max_factorial: config.rational_max_factorial as i64,
-                    max_quadratic_power: 0,
-                },
-                Limits {
-                    max_digits: config.quadratic_max_digits,
-                    max_factorial: config.quadratic_max_factorial as i64,
-                    max_quadratic_power: config.quadratic_max_quadratic_power,
-                },
-            ),
-        }
+        let mut solver = crate::ProgressiveSolver::new(
+            n as i64,
+            _parse_target(target, config.negation),
+            if config.max_depth == 0 {
+                None
+            } else {
+                Some(config.max_depth)
+            },
+            Limits {
+                max_digits: config.integral_max_digits,
+                max_factorial: config.integral_max_factorial as i64,
+                max_quadratic_power: 0,
+                max_radical_power: 1,
+                max_subfactorial: config.integral_max_subfactorial as i64,
+            },
+            Limits {
+                max_digits: config.rational_max_digits,
+                max_factorial: config.rational_max_factorial as i64,
+                max_quadratic_power: 0,
+                max_radical_power: 1,
+                max_subfactorial: config.rational_max_subfactorial as i64,
+            },
+            _check_limits(Limits {
+                max_digits: config.quadratic_max_digits,
+                max_factorial: config.quadratic_max_factorial as i64,
+                max_quadratic_power: config.quadratic_max_quadratic_power,
+                max_radical_power: 1,
+                max_subfactorial: config.quadratic_max_subfactorial as i64,
+            }),
+        );
+        solver.set_modulo(config.modulo);
+        solver.set_gcd_lcm(config.gcd_lcm);
+        solver.set_negation(config.negation);
+        Self { solver }
     }
 
     #[wasm_bindgen(js_name = solveNext)]
-    pub fn solve_next(&mut self) -> JsValue {
-        _serialize_output(self.solver.solve_next())
+    pub fn solve_next(&mut self, format: Option<String>) -> JsValue {
+        _serialize_output(self.solver.solve_next(), format)
+    }
+
+    /// The states searched so far, to be restored with `importState` by a solver constructed
+    /// with the same `n` and config.
+    #[wasm_bindgen(js_name = exportState)]
+    pub fn export_state(&self) -> Vec<u8> {
+        self.solver.export_states()
+    }
+
+    /// How the magnitudes of the states of each searched depth spread over powers of ten.
+    #[wasm_bindgen(js_name = magnitudeHistograms)]
+    pub fn magnitude_histograms(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.solver.magnitude_histograms()).unwrap()
+    }
+
+    #[wasm_bindgen(js_name = importState)]
+    pub fn import_state(&mut self, bytes: &[u8]) {
+        if let Err(error) = self.solver.import_states(bytes) {
+            wasm_bindgen::throw_str(&error.to_string());
+        }
     }
 }